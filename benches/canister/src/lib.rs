@@ -0,0 +1,32 @@
+//! Canister exposing the `ic_web3_rs::bench` workloads.
+//!
+//! Build it with `cargo build --release --target wasm32-unknown-unknown -p ic-web3-bench-canister`
+//! from this directory, then run `tests/budgets.rs` with `POCKET_IC_BIN` pointing to the PocketIC
+//! server and `BENCH_CANISTER_WASM` to the built module.
+
+use candid::CandidType;
+use serde::Deserialize;
+
+/// Result of a single workload.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct BenchResult {
+    /// Workload name
+    pub name: String,
+    /// Number of iterations of the workload
+    pub iterations: u32,
+    /// Instructions executed by all iterations
+    pub instructions: u64,
+}
+
+#[cfg(target_arch = "wasm32")]
+#[ic_cdk_macros::query]
+fn run_benchmarks(iterations: u32) -> Vec<BenchResult> {
+    ic_web3_rs::bench::run_all(iterations)
+        .into_iter()
+        .map(|measurement| BenchResult {
+            name: measurement.name,
+            iterations: measurement.iterations,
+            instructions: measurement.instructions,
+        })
+        .collect()
+}
@@ -0,0 +1,59 @@
+//! Checks the benchmark canister against the instruction budgets of `ic_web3_rs::bench`.
+
+use candid::{decode_one, encode_one, Principal};
+use ic_web3_bench_canister::BenchResult;
+use ic_web3_rs::bench::{Budget, Measurement, DEFAULT_BUDGETS};
+use pocket_ic::{PocketIc, WasmResult};
+
+const ITERATIONS: u32 = 10;
+
+fn wasm() -> Option<Vec<u8>> {
+    let path = std::env::var("BENCH_CANISTER_WASM").ok()?;
+    Some(std::fs::read(path).expect("BENCH_CANISTER_WASM points to a readable file"))
+}
+
+#[test]
+fn workloads_stay_within_budget() {
+    let wasm = match wasm() {
+        Some(wasm) => wasm,
+        None => {
+            eprintln!("BENCH_CANISTER_WASM is not set, skipping");
+            return;
+        }
+    };
+    let pic = PocketIc::new();
+    let canister = pic.create_canister();
+    pic.add_cycles(canister, 2_000_000_000_000);
+    pic.install_canister(canister, wasm, vec![], None);
+
+    let reply = pic
+        .query_call(
+            canister,
+            Principal::anonymous(),
+            "run_benchmarks",
+            encode_one(ITERATIONS).unwrap(),
+        )
+        .expect("benchmark call succeeds");
+    let results: Vec<BenchResult> = match reply {
+        WasmResult::Reply(bytes) => decode_one(&bytes).unwrap(),
+        WasmResult::Reject(message) => panic!("benchmark call rejected: {}", message),
+    };
+
+    let mut failures = vec![];
+    for result in results {
+        let measurement = Measurement {
+            name: result.name,
+            iterations: result.iterations,
+            instructions: result.instructions,
+        };
+        println!("{}: {} instructions per iteration", measurement.name, measurement.per_iteration());
+        let budget: &Budget = DEFAULT_BUDGETS
+            .iter()
+            .find(|budget| budget.name == measurement.name)
+            .expect("every workload has a budget");
+        if let Err(err) = measurement.check(budget) {
+            failures.push(err);
+        }
+    }
+    assert!(failures.is_empty(), "budgets exceeded:\n{}", failures.join("\n"));
+}
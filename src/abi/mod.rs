@@ -0,0 +1,4 @@
+//! ABI helpers complementing `ethabi`.
+
+pub mod registry;
+pub mod selectors;
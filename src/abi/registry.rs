@@ -0,0 +1,191 @@
+//! Registry of known contracts, decoding the calldata of the transactions sent to them.
+
+use crate::{
+    abi::selectors::{self, Selector},
+    types::Address,
+};
+use ethabi::{Function, ParamType, Token};
+use std::collections::BTreeMap;
+
+/// Functions recognized in the calldata of any contract, without an ABI.
+const STANDARD_FUNCTIONS: &[(Selector, &str, &[(&str, ParamType)])] = &[
+    (
+        selectors::TRANSFER,
+        "transfer",
+        &[("to", ParamType::Address), ("amount", ParamType::Uint(256))],
+    ),
+    (
+        selectors::APPROVE,
+        "approve",
+        &[("spender", ParamType::Address), ("amount", ParamType::Uint(256))],
+    ),
+    (
+        selectors::TRANSFER_FROM,
+        "transferFrom",
+        &[
+            ("from", ParamType::Address),
+            ("to", ParamType::Address),
+            ("amount", ParamType::Uint(256)),
+        ],
+    ),
+    (
+        selectors::SAFE_TRANSFER_FROM,
+        "safeTransferFrom",
+        &[
+            ("from", ParamType::Address),
+            ("to", ParamType::Address),
+            ("tokenId", ParamType::Uint(256)),
+        ],
+    ),
+    (
+        selectors::SET_APPROVAL_FOR_ALL,
+        "setApprovalForAll",
+        &[("operator", ParamType::Address), ("approved", ParamType::Bool)],
+    ),
+];
+
+/// Argument of a decoded function call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedArg {
+    /// Parameter name, empty when the ABI does not name it
+    pub name: String,
+    /// Parameter type
+    pub kind: ParamType,
+    /// Decoded value
+    pub value: Token,
+}
+
+/// Function call decoded from calldata.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedCall {
+    /// Function name
+    pub name: String,
+    /// Function selector
+    pub selector: Selector,
+    /// Decoded arguments
+    pub args: Vec<DecodedArg>,
+}
+
+impl DecodedCall {
+    /// Canonical signature of the function, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self) -> String {
+        let kinds: Vec<String> = self.args.iter().map(|arg| arg.kind.to_string()).collect();
+        format!("{}({})", self.name, kinds.join(","))
+    }
+}
+
+/// Decode `data` as a call of `function`, `None` if the selector or the arguments do not match.
+pub fn decode_call(function: &Function, data: &[u8]) -> Option<DecodedCall> {
+    if data.len() < 4 || data[..4] != function.short_signature() {
+        return None;
+    }
+    let values = function.decode_input(&data[4..]).ok()?;
+    Some(DecodedCall {
+        name: function.name.clone(),
+        selector: function.short_signature(),
+        args: function
+            .inputs
+            .iter()
+            .zip(values)
+            .map(|(param, value)| DecodedArg {
+                name: param.name.clone(),
+                kind: param.kind.clone(),
+                value,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Clone, Debug, Default)]
+struct Entry {
+    label: Option<String>,
+    abi: Option<ethabi::Contract>,
+}
+
+/// ABIs and labels of known contracts.
+///
+/// Calldata sent to a registered contract is decoded against its ABI. Calldata sent to other addresses
+/// is matched by selector against the ABIs added with [`with_abi`](Self::with_abi), then against the
+/// token transfer and approval functions of ERC-20 and ERC-721.
+#[derive(Clone, Debug, Default)]
+pub struct AbiRegistry {
+    contracts: BTreeMap<Address, Entry>,
+    abis: Vec<ethabi::Contract>,
+}
+
+impl AbiRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the contract at `address` under `label`, decoding its calldata with `abi`.
+    pub fn with_contract(mut self, address: Address, label: impl Into<String>, abi: ethabi::Contract) -> Self {
+        self.contracts.insert(
+            address,
+            Entry {
+                label: Some(label.into()),
+                abi: Some(abi),
+            },
+        );
+        self
+    }
+
+    /// Label `address`, e.g. an exchange or a user's own address, without an ABI.
+    pub fn with_label(mut self, address: Address, label: impl Into<String>) -> Self {
+        self.contracts.entry(address).or_default().label = Some(label.into());
+        self
+    }
+
+    /// Decode the calldata of unregistered contracts against `abi`, e.g. a standard interface.
+    pub fn with_abi(mut self, abi: ethabi::Contract) -> Self {
+        self.abis.push(abi);
+        self
+    }
+
+    /// Label of `address`.
+    pub fn label(&self, address: &Address) -> Option<&str> {
+        self.contracts.get(address)?.label.as_deref()
+    }
+
+    /// ABI of the contract at `address`.
+    pub fn abi(&self, address: &Address) -> Option<&ethabi::Contract> {
+        self.contracts.get(address)?.abi.as_ref()
+    }
+
+    /// Decode calldata sent to `to`.
+    pub fn decode(&self, to: &Address, data: &[u8]) -> Option<DecodedCall> {
+        if data.len() < 4 {
+            return None;
+        }
+        if let Some(abi) = self.abi(to) {
+            return abi.functions().find_map(|function| decode_call(function, data));
+        }
+        self.abis
+            .iter()
+            .flat_map(|abi| abi.functions())
+            .find_map(|function| decode_call(function, data))
+            .or_else(|| decode_standard(data))
+    }
+}
+
+fn decode_standard(data: &[u8]) -> Option<DecodedCall> {
+    let (selector, name, params) = STANDARD_FUNCTIONS
+        .iter()
+        .find(|(selector, _, _)| data[..4] == selector[..])?;
+    let kinds: Vec<ParamType> = params.iter().map(|(_, kind)| kind.clone()).collect();
+    let values = ethabi::decode(&kinds, &data[4..]).ok()?;
+    Some(DecodedCall {
+        name: name.to_string(),
+        selector: *selector,
+        args: params
+            .iter()
+            .zip(values)
+            .map(|((name, kind), value)| DecodedArg {
+                name: name.to_string(),
+                kind: kind.clone(),
+                value,
+            })
+            .collect(),
+    })
+}
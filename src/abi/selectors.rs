@@ -0,0 +1,169 @@
+//! Precomputed selectors and calldata builders of the common ERC-20 and ERC-721 functions.
+//!
+//! The calldata is encoded by hand, without parsing an ABI or going through `ethabi`, for integrations
+//! which only need a handful of calls and care about instructions and wasm size.
+
+use crate::types::{Address, Bytes, U256};
+
+/// Four-byte function selector.
+pub type Selector = [u8; 4];
+
+/// `transfer(address,uint256)`
+pub const TRANSFER: Selector = [0xa9, 0x05, 0x9c, 0xbb];
+/// `approve(address,uint256)`
+pub const APPROVE: Selector = [0x09, 0x5e, 0xa7, 0xb3];
+/// `transferFrom(address,address,uint256)`
+pub const TRANSFER_FROM: Selector = [0x23, 0xb8, 0x72, 0xdd];
+/// `balanceOf(address)`
+pub const BALANCE_OF: Selector = [0x70, 0xa0, 0x82, 0x31];
+/// `allowance(address,address)`
+pub const ALLOWANCE: Selector = [0xdd, 0x62, 0xed, 0x3e];
+/// `totalSupply()`
+pub const TOTAL_SUPPLY: Selector = [0x18, 0x16, 0x0d, 0xdd];
+/// `decimals()`
+pub const DECIMALS: Selector = [0x31, 0x3c, 0xe5, 0x67];
+/// `symbol()`
+pub const SYMBOL: Selector = [0x95, 0xd8, 0x9b, 0x41];
+/// `name()`
+pub const NAME: Selector = [0x06, 0xfd, 0xde, 0x03];
+/// `ownerOf(uint256)`
+pub const OWNER_OF: Selector = [0x63, 0x52, 0x21, 0x1e];
+/// `safeTransferFrom(address,address,uint256)`
+pub const SAFE_TRANSFER_FROM: Selector = [0x42, 0x84, 0x2e, 0x0e];
+/// `setApprovalForAll(address,bool)`
+pub const SET_APPROVAL_FOR_ALL: Selector = [0xa2, 0x2c, 0xb4, 0x65];
+/// `getApproved(uint256)`
+pub const GET_APPROVED: Selector = [0x08, 0x18, 0x12, 0xfc];
+/// `isApprovedForAll(address,address)`
+pub const IS_APPROVED_FOR_ALL: Selector = [0xe9, 0x85, 0xe9, 0xc5];
+/// `tokenURI(uint256)`
+pub const TOKEN_URI: Selector = [0xc8, 0x7b, 0x56, 0xdd];
+/// `supportsInterface(bytes4)`
+pub const SUPPORTS_INTERFACE: Selector = [0x01, 0xff, 0xc9, 0xa7];
+
+/// A static ABI argument, encoded on a single 32-byte word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Word {
+    /// `address`
+    Address(Address),
+    /// `uint256`
+    Uint(U256),
+    /// `bool`
+    Bool(bool),
+    /// `bytes4`, e.g. an interface id
+    Bytes4([u8; 4]),
+}
+
+impl Word {
+    fn encode(&self) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        match self {
+            Word::Address(address) => word[12..].copy_from_slice(address.as_bytes()),
+            Word::Uint(value) => value.to_big_endian(&mut word),
+            Word::Bool(value) => word[31] = *value as u8,
+            Word::Bytes4(value) => word[..4].copy_from_slice(value),
+        }
+        word
+    }
+}
+
+/// Calldata of a call to `selector` with static arguments.
+pub fn calldata(selector: Selector, args: &[Word]) -> Bytes {
+    let mut data = Vec::with_capacity(4 + 32 * args.len());
+    data.extend_from_slice(&selector);
+    for arg in args {
+        data.extend_from_slice(&arg.encode());
+    }
+    Bytes(data)
+}
+
+/// ERC-20 `transfer(to, amount)`
+pub fn transfer(to: Address, amount: U256) -> Bytes {
+    calldata(TRANSFER, &[Word::Address(to), Word::Uint(amount)])
+}
+
+/// ERC-20 `approve(spender, amount)`
+pub fn approve(spender: Address, amount: U256) -> Bytes {
+    calldata(APPROVE, &[Word::Address(spender), Word::Uint(amount)])
+}
+
+/// ERC-20 `transferFrom(from, to, amount)`, or ERC-721 `transferFrom(from, to, tokenId)`
+pub fn transfer_from(from: Address, to: Address, amount: U256) -> Bytes {
+    calldata(
+        TRANSFER_FROM,
+        &[Word::Address(from), Word::Address(to), Word::Uint(amount)],
+    )
+}
+
+/// ERC-20 and ERC-721 `balanceOf(owner)`
+pub fn balance_of(owner: Address) -> Bytes {
+    calldata(BALANCE_OF, &[Word::Address(owner)])
+}
+
+/// ERC-20 `allowance(owner, spender)`
+pub fn allowance(owner: Address, spender: Address) -> Bytes {
+    calldata(ALLOWANCE, &[Word::Address(owner), Word::Address(spender)])
+}
+
+/// ERC-20 `totalSupply()`
+pub fn total_supply() -> Bytes {
+    calldata(TOTAL_SUPPLY, &[])
+}
+
+/// ERC-20 `decimals()`
+pub fn decimals() -> Bytes {
+    calldata(DECIMALS, &[])
+}
+
+/// ERC-721 `ownerOf(tokenId)`
+pub fn owner_of(token_id: U256) -> Bytes {
+    calldata(OWNER_OF, &[Word::Uint(token_id)])
+}
+
+/// ERC-721 `safeTransferFrom(from, to, tokenId)`
+pub fn safe_transfer_from(from: Address, to: Address, token_id: U256) -> Bytes {
+    calldata(
+        SAFE_TRANSFER_FROM,
+        &[Word::Address(from), Word::Address(to), Word::Uint(token_id)],
+    )
+}
+
+/// ERC-721 `setApprovalForAll(operator, approved)`
+pub fn set_approval_for_all(operator: Address, approved: bool) -> Bytes {
+    calldata(SET_APPROVAL_FOR_ALL, &[Word::Address(operator), Word::Bool(approved)])
+}
+
+/// ERC-721 `getApproved(tokenId)`
+pub fn get_approved(token_id: U256) -> Bytes {
+    calldata(GET_APPROVED, &[Word::Uint(token_id)])
+}
+
+/// ERC-721 `isApprovedForAll(owner, operator)`
+pub fn is_approved_for_all(owner: Address, operator: Address) -> Bytes {
+    calldata(IS_APPROVED_FOR_ALL, &[Word::Address(owner), Word::Address(operator)])
+}
+
+/// ERC-165 `supportsInterface(interfaceId)`
+pub fn supports_interface(interface_id: [u8; 4]) -> Bytes {
+    calldata(SUPPORTS_INTERFACE, &[Word::Bytes4(interface_id)])
+}
+
+/// Decode a `uint256` return value, e.g. of `balanceOf`.
+pub fn decode_uint(output: &[u8]) -> Option<U256> {
+    output.get(..32).map(U256::from_big_endian)
+}
+
+/// Decode an `address` return value, e.g. of `ownerOf`.
+pub fn decode_address(output: &[u8]) -> Option<Address> {
+    output.get(12..32).map(Address::from_slice)
+}
+
+/// Decode a `bool` return value, e.g. of `transfer`.
+///
+/// Empty outputs decode to `true`, as some ERC-20 tokens return nothing from `transfer` and `approve`.
+pub fn decode_bool(output: &[u8]) -> Option<bool> {
+    if output.is_empty() {
+        return Some(true);
+    }
+    decode_uint(output).map(|value| !value.is_zero())
+}
@@ -0,0 +1,172 @@
+//! Fee accounting of mined transactions.
+//!
+//! Splits the fees paid by transactions into the base fee burned by EIP-1559, the tip paid to the block
+//! producer, the blob fee of EIP-4844 transactions and the L1 data fee of rollups, e.g. for treasury
+//! canisters reconciling their spending:
+//!
+//! ```ignore
+//! let costs = accounting::transaction_costs(&web3.eth(), &receipts, options).await?;
+//! let summary = CostSummary::from_costs(&costs);
+//! let report = export::to_ndjson(&costs)?;
+//! ```
+//!
+//! The gas used reported by receipts is net of the refunds for cleared storage, so the fees computed
+//! here are the amounts actually charged.
+
+use crate::{
+    api::Eth,
+    error,
+    transports::ic_http_client::CallOptions,
+    types::{Address, BlockId, BlockNumber, TransactionReceipt, H256, U256, U64},
+    Transport,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Cost of a mined transaction, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxCost {
+    /// Transaction hash
+    pub transaction_hash: H256,
+    /// Number of the block of the transaction
+    pub block_number: Option<U64>,
+    /// Sender, paying the fees
+    pub from: Address,
+    /// Gas used, net of refunds
+    pub gas_used: U256,
+    /// Price paid per gas
+    pub effective_gas_price: U256,
+    /// Total fee paid, see [`TransactionReceipt::total_fee`]
+    pub fee: U256,
+    /// Part of the execution fee, `gas_used * effective_gas_price`, burned as base fee
+    pub base_fee_burned: U256,
+    /// Part of the execution fee paid to the block producer
+    pub tip: U256,
+    /// Part of the fee paid for the blobs of an EIP-4844 transaction
+    #[serde(default)]
+    pub blob_fee: U256,
+    /// Part of the fee paid for posting the transaction data to L1, on OP Stack rollups
+    #[serde(default)]
+    pub l1_fee: U256,
+}
+
+impl TxCost {
+    /// Cost of the transaction of `receipt`, mined in a block with the given base fee per gas.
+    ///
+    /// Without base fee, e.g. on networks without EIP-1559, the whole fee counts as tip. Returns `None`
+    /// for receipts without gas used or effective gas price, which old nodes do not return.
+    pub fn from_receipt(receipt: &TransactionReceipt, base_fee_per_gas: Option<U256>) -> Option<Self> {
+        let gas_used = receipt.gas_used?;
+        let effective_gas_price = receipt.effective_gas_price?;
+        let fee = receipt.total_fee()?;
+        let execution_fee = gas_used.saturating_mul(effective_gas_price);
+        let base_fee = base_fee_per_gas.unwrap_or_default().min(effective_gas_price);
+        let base_fee_burned = gas_used.saturating_mul(base_fee);
+        let blob_fee = match (receipt.blob_gas_used, receipt.blob_gas_price) {
+            (Some(used), Some(price)) => U256::from(used.as_u64()).saturating_mul(price),
+            _ => U256::zero(),
+        };
+        Some(TxCost {
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number,
+            from: receipt.from,
+            gas_used,
+            effective_gas_price,
+            fee,
+            base_fee_burned,
+            tip: execution_fee - base_fee_burned,
+            blob_fee,
+            l1_fee: receipt.l1_fee.unwrap_or_default(),
+        })
+    }
+}
+
+/// Totals of the costs of several transactions, in wei.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostSummary {
+    /// Number of transactions
+    pub transactions: usize,
+    /// Gas used
+    pub gas_used: U256,
+    /// Fees paid
+    pub fees: U256,
+    /// Fees burned as base fee
+    pub base_fee_burned: U256,
+    /// Fees paid to block producers
+    pub tips: U256,
+    /// Fees paid for blobs
+    #[serde(default)]
+    pub blob_fees: U256,
+    /// Fees paid for posting the transaction data to L1
+    #[serde(default)]
+    pub l1_fees: U256,
+}
+
+impl CostSummary {
+    /// Totals of `costs`.
+    pub fn from_costs(costs: &[TxCost]) -> Self {
+        let mut summary = CostSummary::default();
+        for cost in costs {
+            summary.add(cost);
+        }
+        summary
+    }
+
+    /// Add the cost of a transaction to the totals.
+    pub fn add(&mut self, cost: &TxCost) {
+        self.transactions += 1;
+        self.gas_used = self.gas_used.saturating_add(cost.gas_used);
+        self.fees = self.fees.saturating_add(cost.fee);
+        self.base_fee_burned = self.base_fee_burned.saturating_add(cost.base_fee_burned);
+        self.tips = self.tips.saturating_add(cost.tip);
+        self.blob_fees = self.blob_fees.saturating_add(cost.blob_fee);
+        self.l1_fees = self.l1_fees.saturating_add(cost.l1_fee);
+    }
+
+    /// Average price paid per gas, zero without gas used.
+    pub fn average_gas_price(&self) -> U256 {
+        if self.gas_used.is_zero() {
+            U256::zero()
+        } else {
+            self.fees / self.gas_used
+        }
+    }
+}
+
+/// Costs of the transactions of `receipts`, in order, fetching the base fee of each of their blocks once.
+///
+/// Fails for pending receipts, and for receipts without gas used or effective gas price.
+pub async fn transaction_costs<T: Transport>(
+    eth: &Eth<T>,
+    receipts: &[TransactionReceipt],
+    options: CallOptions,
+) -> error::Result<Vec<TxCost>> {
+    let mut base_fees = BTreeMap::new();
+    let mut costs = Vec::with_capacity(receipts.len());
+    for receipt in receipts {
+        let number = receipt.block_number.ok_or_else(|| {
+            error::Error::InvalidResponse(format!("receipt {:?} is pending", receipt.transaction_hash))
+        })?;
+        let base_fee = match base_fees.get(&number) {
+            Some(base_fee) => *base_fee,
+            None => {
+                let block = eth
+                    .block(BlockId::Number(BlockNumber::Number(number)), options.clone())
+                    .await?
+                    .ok_or_else(|| error::Error::InvalidResponse(format!("block {} not found", number)))?;
+                base_fees.insert(number, block.base_fee_per_gas);
+                block.base_fee_per_gas
+            }
+        };
+        let cost = TxCost::from_receipt(receipt, base_fee).ok_or_else(|| {
+            error::Error::InvalidResponse(format!(
+                "receipt {:?} without gas used or effective gas price",
+                receipt.transaction_hash
+            ))
+        })?;
+        costs.push(cost);
+    }
+    Ok(costs)
+}
@@ -1,7 +1,12 @@
 //! Partial implementation of the `Accounts` namespace.
 
-use crate::ic::{ic_raw_sign, recover_address, KeyInfo};
-use crate::{api::Namespace, signing, types::H256, Transport};
+use crate::ic::{get_eth_address, ic_raw_sign, recover_address, KeyInfo};
+use crate::{
+    api::Namespace,
+    error, signing,
+    types::{Address, Recovery, RecoveryMessage, H256},
+    Transport,
+};
 
 /// `Accounts` namespace
 #[derive(Debug, Clone)]
@@ -34,6 +39,29 @@ impl<T: Transport> Accounts<T> {
     {
         signing::hash_message(message)
     }
+
+    /// Recover the address of the signer of some data.
+    ///
+    /// Messages given as data are hashed according to EIP-191 first, while hashes are recovered from
+    /// as is. `v` may be 27 or 28, a y-parity of 0 or 1, or carry EIP-155 replay protection:
+    ///
+    /// ```ignore
+    /// let signer = web3.accounts().recover(Recovery::from_raw_signature(b"hello".to_vec(), signature)?)?;
+    /// ```
+    pub fn recover<R>(&self, recovery: R) -> error::Result<Address>
+    where
+        R: Into<Recovery>,
+    {
+        let recovery = recovery.into();
+        let message_hash = match recovery.message {
+            RecoveryMessage::Data(ref message) => signing::hash_message(message),
+            RecoveryMessage::Hash(hash) => hash,
+        };
+        let (signature, recovery_id) = recovery
+            .as_signature()
+            .ok_or(signing::RecoveryError::InvalidSignature)?;
+        Ok(signing::recover(message_hash.as_bytes(), &signature, recovery_id)?)
+    }
 }
 
 // #[cfg(feature = "signing")]
@@ -41,11 +69,15 @@ mod accounts_signing {
     use super::*;
     use crate::{
         api::Web3,
-        error,
-        signing::Signature,
+        chains::Chain,
+        debug, error,
+        gas::{Fees, MIN_REPLACEMENT_BUMP_PERCENT},
+        nonce::NonceManager,
+        signing::{Key, KeySigner, Signature, Signer},
+        transports::ic_http_client::CallOptions,
         types::{
-            AccessList, Address, Bytes, Recovery, RecoveryMessage, SignedData, SignedTransaction,
-            TransactionParameters, U256, U64,
+            hash_bytecode, AccessList, Address, Bytes, CallRequest, Eip712Meta, Recovery, RecoveryMessage, SignedData,
+            SignedTransaction, TransactionParameters, EIP712_TX_TYPE, MAX_TRANSACTION_DATA_SIZE, U256, U64,
         },
     };
     use rlp::RlpStream;
@@ -55,36 +87,99 @@ mod accounts_signing {
     const ACCESSLISTS_TX_ID: u64 = 1;
     const EIP1559_TX_ID: u64 = 2;
 
+    /// EIP-712 type hash of zkSync transactions.
+    const ZKSYNC_TX_TYPE: &str = "Transaction(uint256 txType,uint256 from,uint256 to,uint256 gasLimit,\
+        uint256 gasPerPubdataByteLimit,uint256 maxFeePerGas,uint256 maxPriorityFeePerGas,uint256 paymaster,\
+        uint256 nonce,uint256 value,bytes data,bytes32[] factoryDeps,bytes paymasterInput)";
+    const EIP712_DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId)";
+
     impl<T: Transport> Accounts<T> {
         /// Gets the parent `web3` namespace
         fn web3(&self) -> Web3<T> {
             Web3::new(self.transport.clone())
         }
-        
-        pub async fn sign_transaction(
+
+        /// Sign a transaction with `key`, the canister threshold ECDSA key or a local secret key.
+        ///
+        /// `from` is the hex address of the key, used to find the recovery id when the key source does not
+        /// return it.
+        pub async fn sign_transaction<K: Key>(
             &self,
             tx: TransactionParameters,
             from: String,
-            key_info: KeyInfo,
+            key: K,
+            chain_id: u64,
+        ) -> error::Result<SignedTransaction> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.sign_transaction_with(tx, &signer, chain_id).await
+        }
+
+        /// Sign a transaction with any [`Signer`].
+        ///
+        /// Its value is [settled](Key::settle_value) as sent once signed, the caller broadcasting it.
+        pub async fn sign_transaction_with<S: Signer>(
+            &self,
+            tx: TransactionParameters,
+            signer: S,
+            chain_id: u64,
+        ) -> error::Result<SignedTransaction> {
+            let value = tx.value;
+            let signed = self.sign_held(tx, &signer, chain_id).await?;
+            signer.settle_value(value, true);
+            Ok(signed)
+        }
+
+        /// Sign a transaction, leaving its authorized value to be settled by the caller unless signing
+        /// fails.
+        async fn sign_held<S: Signer>(
+            &self,
+            mut tx: TransactionParameters,
+            signer: S,
             chain_id: u64,
         ) -> error::Result<SignedTransaction> {
-            let gas_price = match tx.transaction_type {
-                Some(tx_type) if tx_type == U64::from(EIP1559_TX_ID) && tx.max_fee_per_gas.is_some() => {
-                    tx.max_fee_per_gas.unwrap()
+            tx.validate(MAX_TRANSACTION_DATA_SIZE)
+                .map_err(error::Error::InvalidTransaction)?;
+
+            if tx.transaction_type == Some(U64::from(EIP1559_TX_ID)) {
+                if let Some(chain) = Chain::from_id(chain_id).filter(|chain| !chain.supports_eip1559()) {
+                    debug::warn(&format!(
+                        "{} has no base fee, sending the EIP-1559 transaction as legacy",
+                        chain
+                    ));
+                    tx.downgrade_to_legacy();
+                } else if tx.max_fee_per_gas.is_none() && tx.gas_price.is_some() {
+                    debug::warn("EIP-1559 transaction without max fee per gas, sending it as legacy");
+                    tx.downgrade_to_legacy();
                 }
-                _ => tx.gas_price.unwrap(),
+            }
+
+            let has_eip1559_fees = matches!(
+                tx.transaction_type.map(|t| t.as_u64()),
+                Some(EIP1559_TX_ID) | Some(EIP712_TX_TYPE)
+            );
+            let gas_price = match tx.max_fee_per_gas {
+                Some(max_fee_per_gas) if has_eip1559_fees => max_fee_per_gas,
+                _ => tx.gas_price.or(tx.max_fee_per_gas).ok_or_else(|| {
+                    error::Error::InvalidTransaction("transaction has neither gas price nor max fee per gas".into())
+                })?,
             };
 
-            let max_priority_fee_per_gas = match tx.transaction_type {
-                Some(tx_type) if tx_type == U64::from(EIP1559_TX_ID) => {
-                    tx.max_priority_fee_per_gas.unwrap_or(gas_price)
-                }
-                _ => gas_price,
+            let max_priority_fee_per_gas = if has_eip1559_fees {
+                tx.max_priority_fee_per_gas.unwrap_or(gas_price)
+            } else {
+                gas_price
+            };
+
+            let sender = match tx.eip712_meta {
+                Some(_) => Some(signer_address(&signer).await?),
+                None => None,
             };
 
             let tx = Transaction {
                 to: tx.to,
-                nonce: tx.nonce.unwrap(),
+                nonce: tx
+                    .nonce
+                    .ok_or_else(|| error::Error::InvalidTransaction("transaction has no nonce".into()))?,
                 gas: tx.gas,
                 gas_price,
                 value: tx.value,
@@ -92,12 +187,332 @@ mod accounts_signing {
                 transaction_type: tx.transaction_type,
                 access_list: tx.access_list.unwrap_or_default(),
                 max_priority_fee_per_gas,
+                from: sender,
+                eip712_meta: tx.eip712_meta,
             };
 
-            let signed = tx.sign(from, key_info, chain_id).await;
-            Ok(signed)
+            tx.sign_held(signer, chain_id).await
+        }
+
+        /// Sign a message according to EIP-191 (`personal_sign`) with the canister key.
+        ///
+        /// The message is hashed with [`hash_message`](Accounts::hash_message) and signed with threshold
+        /// ECDSA. The (cached) address of `key_info` is used to find the recovery id, so that `v` is 27 or
+        /// 28 and the signature bytes are `r || s || v`, as returned by wallets.
+        pub async fn sign_message<S>(&self, message: S, key_info: KeyInfo) -> error::Result<SignedData>
+        where
+            S: AsRef<[u8]>,
+        {
+            let signer = crate::ic::signer(key_info).await.map_err(error::Error::Signing)?;
+            self.sign_message_with(message, &signer).await
+        }
+
+        /// Sign a message according to EIP-191 (`personal_sign`) with any [`Signer`], see
+        /// [`sign_message`](Self::sign_message).
+        pub async fn sign_message_with<M, S>(&self, message: M, signer: S) -> error::Result<SignedData>
+        where
+            M: AsRef<[u8]>,
+            S: Signer,
+        {
+            let message = message.as_ref().to_vec();
+            let message_hash = signing::hash_message(&message);
+
+            let (r, s, recovery_id) = signing::sign_hash(&signer, message_hash.0)
+                .await
+                .map_err(|e| error::Error::Signing(e.to_string()))?;
+            let v = 27 + recovery_id;
+
+            Ok(SignedData {
+                message,
+                message_hash,
+                v,
+                r,
+                s,
+                signature: Bytes([r.as_bytes(), s.as_bytes(), &[v]].concat()),
+            })
+        }
+
+        /// Sign a transaction and broadcast it with `eth_sendRawTransaction`.
+        ///
+        /// If `tx.nonce` is not set and a nonce manager is given, the nonce is reserved from the manager,
+        /// confirmed once the node accepted the transaction and released if it was refused. If the outcome
+        /// of the send is unknown, e.g. on a timeout, the nonce is [held](NonceManager::hold) instead.
+        pub async fn sign_and_send_transaction<K: Key>(
+            &self,
+            tx: TransactionParameters,
+            from: String,
+            key: K,
+            chain_id: u64,
+            nonce_manager: Option<&NonceManager>,
+            options: CallOptions,
+        ) -> error::Result<H256> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.sign_and_send_transaction_with(tx, &signer, chain_id, nonce_manager, options)
+                .await
+        }
+
+        /// Sign a transaction with any [`Signer`] and broadcast it, see
+        /// [`sign_and_send_transaction`](Self::sign_and_send_transaction).
+        pub async fn sign_and_send_transaction_with<S: Signer>(
+            &self,
+            mut tx: TransactionParameters,
+            signer: S,
+            chain_id: u64,
+            nonce_manager: Option<&NonceManager>,
+            options: CallOptions,
+        ) -> error::Result<H256> {
+            let eth = self.web3().eth();
+            let reserved = match (nonce_manager, tx.nonce) {
+                (Some(manager), None) => {
+                    let sender = signer_address(&signer).await?;
+                    let nonce = manager.reserve(&eth, sender, chain_id, options.clone()).await?;
+                    tx.nonce = Some(nonce);
+                    Some((manager, sender, nonce))
+                }
+                _ => None,
+            };
+
+            let value = tx.value;
+            let (result, sent) = match self.sign_held(tx, &signer, chain_id).await {
+                Ok(signed) => (eth.send_raw_transaction(signed.raw_transaction, options).await, true),
+                Err(err) => (Err(err), false),
+            };
+            if sent {
+                let broadcast = match &result {
+                    Ok(_) => true,
+                    Err(err) => err.is_outcome_unknown(),
+                };
+                signer.settle_value(value, broadcast);
+            }
+
+            if let Some((manager, sender, nonce)) = reserved {
+                match &result {
+                    Ok(_) => manager.confirm(sender, chain_id, nonce),
+                    Err(err) if sent && err.is_outcome_unknown() => manager.hold(sender, chain_id, nonce),
+                    Err(_) => manager.release(sender, chain_id, nonce),
+                }
+            }
+            result
+        }
+
+        /// Sign a batch of transactions with `key`, giving them sequential nonces.
+        ///
+        /// See [`sign_transactions_with`](Self::sign_transactions_with).
+        pub async fn sign_transactions<K: Key>(
+            &self,
+            txs: Vec<TransactionParameters>,
+            from: String,
+            key: K,
+            chain_id: u64,
+            nonce_manager: &NonceManager,
+            options: CallOptions,
+        ) -> error::Result<Vec<SignedTransaction>> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.sign_transactions_with(txs, &signer, chain_id, nonce_manager, options)
+                .await
+        }
+
+        /// Sign a batch of transactions with any [`Signer`], giving them sequential nonces.
+        ///
+        /// The transactions without nonce get the next nonces reserved from `nonce_manager`, in order, and
+        /// are signed one after the other. The signed transactions are returned in the same order, ready to
+        /// be broadcast; their nonces stay reserved until [`confirm`](NonceManager::confirm)ed or
+        /// [`release`](NonceManager::release)d. If any transaction fails to sign, all the nonces reserved
+        /// for the batch are released and the error is returned, so that no transaction of the batch gets
+        /// sent.
+        pub async fn sign_transactions_with<S: Signer>(
+            &self,
+            mut txs: Vec<TransactionParameters>,
+            signer: S,
+            chain_id: u64,
+            nonce_manager: &NonceManager,
+            options: CallOptions,
+        ) -> error::Result<Vec<SignedTransaction>> {
+            let eth = self.web3().eth();
+            let sender = signer_address(&signer).await?;
+            let mut reserved = Vec::new();
+            let mut result = Ok(());
+            for tx in txs.iter_mut().filter(|tx| tx.nonce.is_none()) {
+                match nonce_manager.reserve(&eth, sender, chain_id, options.clone()).await {
+                    Ok(nonce) => {
+                        tx.nonce = Some(nonce);
+                        reserved.push(nonce);
+                    }
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+
+            let mut signed = Vec::with_capacity(txs.len());
+            let mut values = Vec::with_capacity(txs.len());
+            if result.is_ok() {
+                for tx in txs {
+                    let value = tx.value;
+                    match self.sign_held(tx, &signer, chain_id).await {
+                        Ok(tx) => {
+                            signed.push(tx);
+                            values.push(value);
+                        }
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+            }
+            for value in values {
+                signer.settle_value(value, result.is_ok());
+            }
+
+            match result {
+                Ok(()) => Ok(signed),
+                Err(err) => {
+                    // Nothing of the batch was broadcast. Latest first, so that the manager rewinds instead
+                    // of resyncing.
+                    for nonce in reserved.into_iter().rev() {
+                        nonce_manager.release(sender, chain_id, nonce);
+                    }
+                    Err(err)
+                }
+            }
+        }
+
+        /// Sign `original` again with `new_fees`, to replace it while it is pending, e.g. stuck with fees
+        /// too low for the current base fee.
+        ///
+        /// `original` must be the parameters the pending transaction was signed with, nonce included.
+        /// Nodes only accept a replacement raising both the max fee and the tip by at least
+        /// [`MIN_REPLACEMENT_BUMP_PERCENT`](crate::gas::MIN_REPLACEMENT_BUMP_PERCENT) percent, so lower
+        /// `new_fees` are refused before signing; [`gas::bump`](crate::gas::bump) gives suitable ones.
+        pub async fn replace_transaction<K: Key>(
+            &self,
+            original: TransactionParameters,
+            new_fees: Fees,
+            from: String,
+            key: K,
+            chain_id: u64,
+        ) -> error::Result<SignedTransaction> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.replace_transaction_with(original, new_fees, &signer, chain_id)
+                .await
+        }
+
+        /// Sign a replacement of `original` with any [`Signer`], see
+        /// [`replace_transaction`](Self::replace_transaction).
+        pub async fn replace_transaction_with<S: Signer>(
+            &self,
+            mut original: TransactionParameters,
+            new_fees: Fees,
+            signer: S,
+            chain_id: u64,
+        ) -> error::Result<SignedTransaction> {
+            if original.nonce.is_none() {
+                return Err(error::Error::InvalidTransaction(
+                    "the replaced transaction has no nonce".into(),
+                ));
+            }
+            let fees = Fees::of(&original).ok_or_else(|| {
+                error::Error::InvalidTransaction("the replaced transaction has neither gas price nor max fee".into())
+            })?;
+            check_replacement(&fees, &new_fees)?;
+            set_fees(&mut original, new_fees);
+            self.sign_transaction_with(original, signer, chain_id).await
+        }
+
+        /// Sign a transaction cancelling the pending transaction with `nonce`, paid `original_fees`.
+        ///
+        /// The cancellation is a transfer of 0 from the key to itself with the same nonce, so that the
+        /// pending transaction can no longer be mined. The fee bump rule of
+        /// [`replace_transaction`](Self::replace_transaction) applies to `new_fees`.
+        ///
+        /// The transfer costs more than the base 21000 gas on some chains, e.g. rollups charging for the
+        /// L1 data or accounts with code, so its gas is estimated with the default call options of the
+        /// transport unless given as `gas`.
+        #[allow(clippy::too_many_arguments)]
+        pub async fn cancel_transaction<K: Key>(
+            &self,
+            nonce: U256,
+            original_fees: Fees,
+            new_fees: Fees,
+            gas: Option<U256>,
+            from: String,
+            key: K,
+            chain_id: u64,
+        ) -> error::Result<SignedTransaction> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.cancel_transaction_with(nonce, original_fees, new_fees, gas, &signer, chain_id)
+                .await
+        }
+
+        /// Sign a cancellation with any [`Signer`], see [`cancel_transaction`](Self::cancel_transaction).
+        pub async fn cancel_transaction_with<S: Signer>(
+            &self,
+            nonce: U256,
+            original_fees: Fees,
+            new_fees: Fees,
+            gas: Option<U256>,
+            signer: S,
+            chain_id: u64,
+        ) -> error::Result<SignedTransaction> {
+            check_replacement(&original_fees, &new_fees)?;
+            let sender = signer_address(&signer).await?;
+            let gas = match gas {
+                Some(gas) => gas,
+                None => {
+                    let request = CallRequest {
+                        from: Some(sender),
+                        to: Some(sender),
+                        value: Some(U256::zero()),
+                        ..Default::default()
+                    };
+                    let options = self.web3().transport().default_call_options();
+                    self.web3().eth().estimate_gas(request, None, options).await?
+                }
+            };
+            let mut tx = TransactionParameters {
+                nonce: Some(nonce),
+                to: Some(sender),
+                gas,
+                ..Default::default()
+            };
+            new_fees.apply(&mut tx);
+            self.sign_transaction_with(tx, signer, chain_id).await
+        }
+    }
+
+    /// Refuse `new` fees nodes would reject as underpriced for replacing a transaction paying `original`.
+    fn check_replacement(original: &Fees, new: &Fees) -> error::Result<()> {
+        if new.replaces(original) {
+            return Ok(());
         }
+        Err(error::Error::InvalidTransaction(format!(
+            "replacement fees {:?} do not exceed the fees {:?} of the pending transaction by {}%",
+            new, original, MIN_REPLACEMENT_BUMP_PERCENT
+        )))
     }
+
+    /// Set `fees` on `tx`, keeping the type of zkSync transactions.
+    fn set_fees(tx: &mut TransactionParameters, fees: Fees) {
+        if tx.transaction_type == Some(U64::from(EIP712_TX_TYPE)) {
+            tx.gas_price = None;
+            tx.max_fee_per_gas = Some(fees.max_fee_per_gas());
+            tx.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas());
+        } else {
+            fees.apply(tx);
+        }
+    }
+
+    fn parse_sender(from: &str) -> error::Result<Address> {
+        from.parse::<Address>()
+            .map_err(|_| error::Error::Decoder(format!("invalid sender address: {}", from)))
+    }
+
+    async fn signer_address<S: Signer>(signer: &S) -> error::Result<Address> {
+        signer.address().await.map_err(|e| error::Error::Signing(e.to_string()))
+    }
+
     /// A transaction used for RLP encoding, hashing and signing.
     #[derive(Debug)]
     pub struct Transaction {
@@ -110,6 +525,8 @@ mod accounts_signing {
         pub transaction_type: Option<U64>,
         pub access_list: AccessList,
         pub max_priority_fee_per_gas: U256,
+        pub from: Option<Address>,
+        pub eip712_meta: Option<Eip712Meta>,
     }
 
     impl Transaction {
@@ -192,6 +609,122 @@ mod accounts_signing {
             stream
         }
 
+        fn eip712_meta(&self) -> &Eip712Meta {
+            self.eip712_meta
+                .as_ref()
+                .expect("zkSync fields are checked before signing")
+        }
+
+        /// EIP-712 digest signed for zkSync transactions.
+        fn eip712_signing_hash(&self, chain_id: u64) -> [u8; 32] {
+            use ethabi::Token;
+
+            let meta = self.eip712_meta();
+            let address_word =
+                |address: Option<Address>| Token::Uint(U256::from_big_endian(address.unwrap_or_default().as_bytes()));
+            let factory_deps: Vec<u8> = meta
+                .factory_deps
+                .iter()
+                .flat_map(|dep| {
+                    hash_bytecode(&dep.0)
+                        .expect("factory dependencies are checked before signing")
+                        .0
+                })
+                .collect();
+            let paymaster = meta.paymaster_params.as_ref();
+
+            let struct_hash = signing::keccak256(&ethabi::encode(&[
+                Token::FixedBytes(signing::keccak256(ZKSYNC_TX_TYPE.as_bytes()).to_vec()),
+                Token::Uint(EIP712_TX_TYPE.into()),
+                address_word(self.from),
+                address_word(self.to),
+                Token::Uint(self.gas),
+                Token::Uint(meta.gas_per_pubdata),
+                Token::Uint(self.gas_price),
+                Token::Uint(self.max_priority_fee_per_gas),
+                address_word(paymaster.map(|params| params.paymaster)),
+                Token::Uint(self.nonce),
+                Token::Uint(self.value),
+                Token::FixedBytes(signing::keccak256(&self.data).to_vec()),
+                Token::FixedBytes(signing::keccak256(&factory_deps).to_vec()),
+                Token::FixedBytes(
+                    signing::keccak256(
+                        paymaster
+                            .map(|params| &params.paymaster_input.0[..])
+                            .unwrap_or_default(),
+                    )
+                    .to_vec(),
+                ),
+            ]));
+            let domain_separator = signing::keccak256(&ethabi::encode(&[
+                Token::FixedBytes(signing::keccak256(EIP712_DOMAIN_TYPE.as_bytes()).to_vec()),
+                Token::FixedBytes(signing::keccak256(b"zkSync").to_vec()),
+                Token::FixedBytes(signing::keccak256(b"2").to_vec()),
+                Token::Uint(chain_id.into()),
+            ]));
+            signing::keccak256(&[&[0x19, 0x01], &domain_separator[..], &struct_hash[..]].concat())
+        }
+
+        fn encode_eip712_payload(&self, chain_id: u64, signature: Option<&Signature>) -> RlpStream {
+            let meta = self.eip712_meta();
+            let mut stream = RlpStream::new();
+            stream.begin_list(16);
+
+            stream.append(&self.nonce);
+            stream.append(&self.max_priority_fee_per_gas);
+            stream.append(&self.gas_price);
+            stream.append(&self.gas);
+            if let Some(to) = self.to {
+                stream.append(&to);
+            } else {
+                stream.append(&"");
+            }
+            stream.append(&self.value);
+            stream.append(&self.data);
+
+            let serialized_signature = match signature {
+                Some(signature) => {
+                    self.rlp_append_signature(&mut stream, signature);
+                    [
+                        signature.r.as_bytes(),
+                        signature.s.as_bytes(),
+                        &[27 + signature.v as u8],
+                    ]
+                    .concat()
+                }
+                None => {
+                    stream.append(&chain_id);
+                    stream.append(&"");
+                    stream.append(&"");
+                    vec![]
+                }
+            };
+
+            stream.append(&chain_id);
+            stream.append(&self.from.unwrap_or_default());
+            stream.append(&meta.gas_per_pubdata);
+            stream.begin_list(meta.factory_deps.len());
+            for dep in meta.factory_deps.iter() {
+                stream.append(&dep.0);
+            }
+            match &meta.custom_signature {
+                Some(custom_signature) => stream.append(&custom_signature.0),
+                None => stream.append(&serialized_signature),
+            };
+            match &meta.paymaster_params {
+                Some(params) => {
+                    stream.begin_list(2);
+                    stream.append(&params.paymaster);
+                    stream.append(&params.paymaster_input.0);
+                }
+                None => {
+                    stream.begin_list(0);
+                }
+            }
+
+            stream
+        }
+
         fn rlp_append_signature(&self, stream: &mut RlpStream, signature: &Signature) {
             stream.append(&signature.v);
             stream.append(&U256::from_big_endian(signature.r.as_bytes()));
@@ -229,60 +762,73 @@ mod accounts_signing {
                     [&[tx_id], stream.as_raw()].concat()
                 }
 
+                Some(EIP712_TX_TYPE) => {
+                    let tx_id: u8 = EIP712_TX_TYPE as u8;
+                    let stream = self.encode_eip712_payload(chain_id, signature);
+                    [&[tx_id], stream.as_raw()].concat()
+                }
+
                 _ => {
                     panic!("Unsupported transaction type");
                 }
             }
         }
 
-        pub async fn sign(self, from: String, key_info: KeyInfo, chain_id: u64) -> SignedTransaction {
-            let adjust_v_value = matches!(self.transaction_type.map(|t| t.as_u64()), Some(LEGACY_TX_ID) | None);
-
-            let encoded = self.encode(chain_id, None);
+        /// Sign the transaction with `signer`.
+        ///
+        /// Its value is [settled](Key::settle_value) as sent once signed, the caller broadcasting it.
+        pub async fn sign<S: Signer>(self, signer: S, chain_id: u64) -> error::Result<SignedTransaction> {
+            let value = self.value;
+            let signed = self.sign_held(&signer, chain_id).await?;
+            signer.settle_value(value, true);
+            Ok(signed)
+        }
 
-            let hash = signing::keccak256(encoded.as_ref());
+        /// Sign the transaction with `signer`, leaving its authorized value to be settled by the caller
+        /// unless signing fails.
+        pub(crate) async fn sign_held<S: Signer>(self, signer: S, chain_id: u64) -> error::Result<SignedTransaction> {
+            let adjust_v_value = matches!(self.transaction_type.map(|t| t.as_u64()), Some(LEGACY_TX_ID) | None);
+            let is_eip712 = self.transaction_type == Some(EIP712_TX_TYPE.into());
 
-            let res = match ic_raw_sign(hash.to_vec(), key_info).await {
-                Ok(v) => v,
-                Err(e) => {
-                    panic!("{}", e);
-                }
+            let hash = if is_eip712 {
+                self.eip712_signing_hash(chain_id)
+            } else {
+                signing::keccak256(self.encode(chain_id, None).as_ref())
             };
 
-            let v = if from.contains(&recover_address(hash.clone().to_vec(), res.clone(), 0)) {
-                if adjust_v_value {
-                    2 * chain_id + 35 + 0
-                } else {
-                    0
-                }
+            signer
+                .authorize_value(self.value)
+                .map_err(|e| error::Error::Signing(e.to_string()))?;
+            let (r_arr, s_arr, rec_id) = signing::sign_hash(&signer, hash).await.map_err(|e| {
+                signer.settle_value(self.value, false);
+                error::Error::Signing(e.to_string())
+            })?;
+            let rec_id = rec_id as u64;
+            let v = if adjust_v_value {
+                2 * chain_id + 35 + rec_id
             } else {
-                if adjust_v_value {
-                    2 * chain_id + 35 + 1
-                } else {
-                    1
-                }
+                rec_id
             };
 
-            let r_arr = H256::from_slice(&res[0..32]);
-            let s_arr = H256::from_slice(&res[32..64]);
-            let sig = Signature {
-                v: v.clone(),
-                r: r_arr.clone().into(),
-                s: s_arr.clone().into(),
-            };
+            let sig = Signature { v, r: r_arr, s: s_arr };
 
             let signed = self.encode(chain_id, Some(&sig));
-            let transaction_hash = signing::keccak256(signed.as_ref()).into();
+            let transaction_hash = if is_eip712 {
+                // zkSync hashes the signed digest together with the hash of the signature
+                let signature = [r_arr.as_bytes(), s_arr.as_bytes(), &[27 + v as u8]].concat();
+                signing::keccak256(&[&hash[..], &signing::keccak256(&signature)[..]].concat()).into()
+            } else {
+                signing::keccak256(signed.as_ref()).into()
+            };
 
-            SignedTransaction {
+            Ok(SignedTransaction {
                 message_hash: hash.into(),
                 v,
-                r: r_arr.into(),
-                s: s_arr.into(),
+                r: r_arr,
+                s: s_arr,
                 raw_transaction: signed.into(),
                 transaction_hash,
-            }
+            })
         }
     }
 }
-
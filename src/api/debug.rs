@@ -0,0 +1,62 @@
+//! `Debug` namespace
+
+use crate::{
+    api::Namespace,
+    helpers::{self, CallFuture},
+    transports::ic_http_client::CallOptions,
+    types::{BlockId, BlockNumber, CallRequest, GethTrace, TraceOptions, H256},
+    Transport,
+};
+
+/// `Debug` namespace
+#[derive(Debug, Clone)]
+pub struct Debug<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for Debug<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Debug { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Debug<T> {
+    /// Replay a mined transaction and trace its execution with the configured tracer.
+    pub fn trace_transaction(
+        &self,
+        hash: H256,
+        trace_options: TraceOptions,
+        options: CallOptions,
+    ) -> CallFuture<GethTrace, T::Out> {
+        let hash = helpers::serialize(&hash);
+        let trace_options = helpers::serialize(&trace_options);
+        CallFuture::new(
+            self.transport
+                .execute("debug_traceTransaction", vec![hash, trace_options], options),
+        )
+    }
+
+    /// Execute a call on top of `block` (latest by default) and trace it with the configured tracer.
+    pub fn trace_call(
+        &self,
+        req: CallRequest,
+        block: Option<BlockId>,
+        trace_options: TraceOptions,
+        options: CallOptions,
+    ) -> CallFuture<GethTrace, T::Out> {
+        let req = helpers::serialize(&req);
+        let block = helpers::serialize(&block.unwrap_or_else(|| BlockNumber::Latest.into()));
+        let trace_options = helpers::serialize(&trace_options);
+        CallFuture::new(
+            self.transport
+                .execute("debug_traceCall", vec![req, block, trace_options], options),
+        )
+    }
+}
@@ -2,15 +2,21 @@
 
 use crate::{
     api::Namespace,
+    error,
     helpers::{self, CallFuture},
     transports::ic_http_client::CallOptions,
     types::{
-        Address, Block, BlockHeader, BlockId, BlockNumber, Bytes, CallRequest, FeeHistory, Filter, Index, Log, Proof,
-        Transaction, TransactionId, TransactionReceipt, TransactionRequest, Work, H256, H520, H64, U256,
-        U64,
+        AccountSnapshot, Address, BalancePoint, Block, BlockHeader, BlockId, BlockNumber, BlockRange, Bytes,
+        CallRequest, ChainSnapshot, FeeHistory, Filter, FilterBuilder, Index, Log, Proof, SimulatePayload,
+        SimulatedBlock, StateOverride, SyncState, Transaction, TransactionId, TransactionReceipt, TransactionRequest,
+        Work, H256, H520, H64, U256, U64,
     },
-    Transport,
+    BatchTransport, Transport,
 };
+use jsonrpc_core::Value;
+
+/// Maximum number of `eth_getBalance` calls of a batch sent by [`Eth::balance_series`].
+const BALANCE_SERIES_BATCH_SIZE: usize = 50;
 
 /// `Eth` namespace
 #[derive(Debug, Clone)]
@@ -50,6 +56,25 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_call", vec![req, block], options))
     }
 
+    /// Call a constant method of contract with the given state overrides applied on top of the state at
+    /// `block`.
+    ///
+    /// Defaults to the latest block when `block` is `None`, since the override set is passed as the
+    /// third positional parameter.
+    pub fn call_with_overrides(
+        &self,
+        req: CallRequest,
+        block: Option<BlockId>,
+        overrides: StateOverride,
+        options: CallOptions,
+    ) -> CallFuture<Bytes, T::Out> {
+        let req = helpers::serialize(&req);
+        let block = helpers::serialize(&block.unwrap_or_else(|| BlockNumber::Latest.into()));
+        let overrides = helpers::serialize(&overrides);
+
+        CallFuture::new(self.transport.execute("eth_call", vec![req, block, overrides], options))
+    }
+
     /// Get coinbase address
     pub fn coinbase(&self, options: CallOptions) -> CallFuture<Address, T::Out> {
         CallFuture::new(self.transport.execute("eth_coinbase", vec![], options))
@@ -74,10 +99,15 @@ impl<T: Transport> Eth<T> {
     }
 
     /// Call a contract without changing the state of the blockchain to estimate gas usage.
+    ///
+    /// `block` may be given either by number or by hash (EIP-1898), which allows estimating against
+    /// historical state, e.g. to check whether a transaction would have succeeded at a given block.
+    ///
+    /// When the call reverts, the reason can be recovered from the error with [`crate::revert::revert_reason`].
     pub fn estimate_gas(
         &self,
         req: CallRequest,
-        block: Option<BlockNumber>,
+        block: Option<BlockId>,
         options: CallOptions,
     ) -> CallFuture<U256, T::Out> {
         let req = helpers::serialize(&req);
@@ -90,11 +120,42 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_estimateGas", args, options))
     }
 
+    /// Estimate gas usage with the given state overrides applied on top of the state at `block`.
+    ///
+    /// Defaults to the latest block when `block` is `None`, since the override set is passed as the
+    /// third positional parameter.
+    pub fn estimate_gas_with_overrides(
+        &self,
+        req: CallRequest,
+        block: Option<BlockId>,
+        overrides: StateOverride,
+        options: CallOptions,
+    ) -> CallFuture<U256, T::Out> {
+        let req = helpers::serialize(&req);
+        let block = helpers::serialize(&block.unwrap_or_else(|| BlockNumber::Latest.into()));
+        let overrides = helpers::serialize(&overrides);
+
+        CallFuture::new(
+            self.transport
+                .execute("eth_estimateGas", vec![req, block, overrides], options),
+        )
+    }
+
     /// Get current recommended gas price
     pub fn gas_price(&self, options: CallOptions) -> CallFuture<U256, T::Out> {
         CallFuture::new(self.transport.execute("eth_gasPrice", vec![], options))
     }
 
+    /// Get the priority fee per gas the node suggests for EIP-1559 transactions to be included timely.
+    pub fn max_priority_fee_per_gas(&self, options: CallOptions) -> CallFuture<U256, T::Out> {
+        CallFuture::new(self.transport.execute("eth_maxPriorityFeePerGas", vec![], options))
+    }
+
+    /// Get the base fee per blob gas of the next block (EIP-4844).
+    pub fn blob_base_fee(&self, options: CallOptions) -> CallFuture<U256, T::Out> {
+        CallFuture::new(self.transport.execute("eth_blobBaseFee", vec![], options))
+    }
+
     /// Returns a collection of historical gas information. This can be used for evaluating the max_fee_per_gas
     /// and max_priority_fee_per_gas to send the future transactions.
     pub fn fee_history(
@@ -115,6 +176,38 @@ impl<T: Transport> Eth<T> {
         ))
     }
 
+    /// Simulate the calls of `payload` in a sequence of blocks built on top of `block`, with `eth_simulateV1`.
+    ///
+    /// Defaults to the latest block when `block` is `None`. The simulated blocks list the hashes of their
+    /// transactions, so `payload.return_full_transactions` must not be set, see
+    /// [`simulate_full`](Self::simulate_full).
+    pub fn simulate(
+        &self,
+        payload: SimulatePayload,
+        block: Option<BlockId>,
+        options: CallOptions,
+    ) -> CallFuture<Vec<SimulatedBlock>, T::Out> {
+        let payload = helpers::serialize(&payload);
+        let block = helpers::serialize(&block.unwrap_or_else(|| BlockNumber::Latest.into()));
+
+        CallFuture::new(self.transport.execute("eth_simulateV1", vec![payload, block], options))
+    }
+
+    /// Simulate the calls of `payload` like [`simulate`](Self::simulate), returning the simulated
+    /// transactions in the blocks.
+    pub fn simulate_full(
+        &self,
+        mut payload: SimulatePayload,
+        block: Option<BlockId>,
+        options: CallOptions,
+    ) -> CallFuture<Vec<SimulatedBlock<Transaction>>, T::Out> {
+        payload.return_full_transactions = true;
+        let payload = helpers::serialize(&payload);
+        let block = helpers::serialize(&block.unwrap_or_else(|| BlockNumber::Latest.into()));
+
+        CallFuture::new(self.transport.execute("eth_simulateV1", vec![payload, block], options))
+    }
+
     /// Get balance of given address
     pub fn balance(
         &self,
@@ -134,6 +227,20 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_getLogs", vec![filter], options))
     }
 
+    /// Get logs matching `filter` between blocks `from` and `to`, split into pages of bounded size.
+    ///
+    /// See [`LogPaginator`](crate::logs::LogPaginator) for how pages adapt to provider and response limits.
+    #[cfg(feature = "logs")]
+    pub fn paged_logs(
+        &self,
+        filter: FilterBuilder,
+        from: U64,
+        to: U64,
+        options: CallOptions,
+    ) -> crate::logs::LogPaginator<T> {
+        crate::logs::LogPaginator::new(self.clone(), filter, from, to, options)
+    }
+
     /// Get block details with transaction hashes.
     pub fn block(&self, block: BlockId, options: CallOptions) -> CallFuture<Option<Block<H256>>, T::Out> {
         let include_txs = helpers::serialize(&false);
@@ -219,6 +326,12 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_chainId", vec![], options))
     }
 
+    /// Get the sync state of the node, to check that it is not catching up with the chain before
+    /// trusting its state.
+    pub fn syncing(&self, options: CallOptions) -> CallFuture<SyncState, T::Out> {
+        CallFuture::new(self.transport.execute("eth_syncing", vec![], options))
+    }
+
     /// Get available user accounts. This method is only available in the browser. With MetaMask,
     /// this will cause the popup that prompts the user to allow or deny access to their accounts
     /// to your app.
@@ -295,6 +408,22 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_getTransactionReceipt", vec![hash], options))
     }
 
+    /// Get the receipts of all the transactions of a block, in one call instead of one per transaction.
+    ///
+    /// Returns `None` for unknown blocks.
+    pub fn block_receipts(
+        &self,
+        block: BlockId,
+        options: CallOptions,
+    ) -> CallFuture<Option<Vec<TransactionReceipt>>, T::Out> {
+        let block = match block {
+            BlockId::Hash(hash) => helpers::serialize(&hash),
+            BlockId::Number(num) => helpers::serialize(&num),
+        };
+
+        CallFuture::new(self.transport.execute("eth_getBlockReceipts", vec![block], options))
+    }
+
     /// Get uncle header by block ID and uncle index.
     ///
     /// This method is meant for TurboGeth compatiblity,
@@ -440,3 +569,188 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_getProof", vec![add, ks, blk], options))
     }
 }
+
+impl<T: Transport> Eth<T> {
+    /// Find the mined transaction `sender` sent with `nonce`, e.g. after losing the hash of a transaction
+    /// replaced by another one with the same nonce.
+    ///
+    /// Nodes supporting the Otterscan `ots_getTransactionBySenderAndNonce` method (Erigon, Reth) answer
+    /// directly. Otherwise the latest `max_blocks` blocks are fetched with their transactions, newest
+    /// first, which costs an outcall per block. `None` is returned when no mined transaction uses the
+    /// nonce yet, or when it was mined before the scanned blocks.
+    pub async fn transaction_by_sender_and_nonce(
+        &self,
+        sender: Address,
+        nonce: U256,
+        max_blocks: u64,
+        options: CallOptions,
+    ) -> error::Result<Option<Transaction>> {
+        let params = vec![helpers::serialize(&sender), helpers::serialize(&nonce)];
+        let result = self
+            .transport
+            .execute("ots_getTransactionBySenderAndNonce", params, options.clone())
+            .await;
+        match result {
+            Ok(hash) => {
+                return match helpers::decode::<Option<H256>>(hash)? {
+                    Some(hash) => self.transaction(TransactionId::Hash(hash), options).await,
+                    None => Ok(None),
+                };
+            }
+            Err(error::Error::Rpc(err)) if is_method_not_found(&err) => {}
+            Err(err) => return Err(err),
+        }
+
+        let mined = self.transaction_count(sender, None, options.clone()).await?;
+        if mined <= nonce {
+            return Ok(None);
+        }
+        let latest = self.block_number(options.clone()).await?.as_u64();
+        let oldest = latest.saturating_sub(max_blocks.saturating_sub(1));
+        for number in (oldest..=latest).rev() {
+            let block = BlockId::Number(BlockNumber::Number(number.into()));
+            let block = match self.block_with_txs(block, options.clone()).await? {
+                Some(block) => block,
+                None => continue,
+            };
+            let found = block
+                .transactions
+                .into_iter()
+                .find(|tx| tx.from == Some(sender) && tx.nonce == nonce);
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<T: BatchTransport> Eth<T> {
+    /// Chain id, latest block and gas price, fetched in a single batch request.
+    ///
+    /// Most workflows start with these calls; batching them costs a single outcall.
+    pub async fn chain_snapshot(&self, options: CallOptions) -> error::Result<ChainSnapshot> {
+        let latest = helpers::serialize(&BlockNumber::Latest);
+        let mut results = self
+            .batch(
+                vec![
+                    ("eth_chainId", vec![]),
+                    ("eth_getBlockByNumber", vec![latest, Value::Bool(false)]),
+                    ("eth_gasPrice", vec![]),
+                ],
+                options,
+            )
+            .await?
+            .into_iter();
+        let chain_id = helpers::decode(results.next().unwrap_or_default())?;
+        let block: Option<BlockHeader> = helpers::decode(results.next().unwrap_or_default())?;
+        let gas_price = helpers::decode(results.next().unwrap_or_default())?;
+        let block = block.ok_or_else(|| error::Error::InvalidResponse("latest block not found".into()))?;
+        Ok(ChainSnapshot {
+            chain_id,
+            block_number: block
+                .number
+                .ok_or_else(|| error::Error::InvalidResponse("latest block without number".into()))?,
+            block_hash: block
+                .hash
+                .ok_or_else(|| error::Error::InvalidResponse("latest block without hash".into()))?,
+            timestamp: block.timestamp,
+            base_fee_per_gas: block.base_fee_per_gas,
+            gas_price,
+        })
+    }
+
+    /// Balance, nonces and code presence of `address`, fetched in a single batch request.
+    pub async fn account_snapshot(&self, address: Address, options: CallOptions) -> error::Result<AccountSnapshot> {
+        let address = helpers::serialize(&address);
+        let latest = helpers::serialize(&BlockNumber::Latest);
+        let pending = helpers::serialize(&BlockNumber::Pending);
+        let mut results = self
+            .batch(
+                vec![
+                    ("eth_getBalance", vec![address.clone(), latest.clone()]),
+                    ("eth_getTransactionCount", vec![address.clone(), latest.clone()]),
+                    ("eth_getTransactionCount", vec![address.clone(), pending]),
+                    ("eth_getCode", vec![address, latest]),
+                ],
+                options,
+            )
+            .await?
+            .into_iter();
+        let balance = helpers::decode(results.next().unwrap_or_default())?;
+        let nonce = helpers::decode(results.next().unwrap_or_default())?;
+        let pending_nonce = helpers::decode(results.next().unwrap_or_default())?;
+        let code: Bytes = helpers::decode(results.next().unwrap_or_default())?;
+        Ok(AccountSnapshot {
+            balance,
+            nonce,
+            pending_nonce,
+            is_contract: !code.0.is_empty(),
+        })
+    }
+
+    /// Balances of `address` in `range`, every `stride` blocks, the last block of the range included.
+    ///
+    /// Long ranges are sampled with a wider stride, see [`BlockRange::sample`].
+    ///
+    /// The balances are fetched in batches of historical `eth_getBalance` calls. Blocks older than the
+    /// ones whose state a full node keeps, usually the latest 128, need an archive node: the call then
+    /// fails with [`Error::StatePruned`](error::Error::StatePruned) for the first pruned block.
+    pub async fn balance_series(
+        &self,
+        address: Address,
+        range: BlockRange,
+        stride: u64,
+        options: CallOptions,
+    ) -> error::Result<Vec<BalancePoint>> {
+        let address = helpers::serialize(&address);
+        let blocks = range.sample(stride);
+        let mut series = Vec::with_capacity(blocks.len());
+        for chunk in blocks.chunks(BALANCE_SERIES_BATCH_SIZE) {
+            let requests: Vec<_> = chunk
+                .iter()
+                .map(|block| {
+                    let block = helpers::serialize(&BlockNumber::Number(*block));
+                    self.transport.prepare("eth_getBalance", vec![address.clone(), block])
+                })
+                .collect();
+            let results = self.transport.send_batch(requests, options.clone()).await?;
+            for (block, result) in chunk.iter().zip(results) {
+                let balance = match result {
+                    Ok(balance) => helpers::decode(balance)?,
+                    Err(err) if err.is_pruned_state() => return Err(error::Error::StatePruned(*block)),
+                    Err(err) => return Err(err),
+                };
+                series.push(BalancePoint { block: *block, balance });
+            }
+        }
+        Ok(series)
+    }
+
+    /// Send `calls` as one batch, failing on the first failed call.
+    async fn batch(&self, calls: Vec<(&str, Vec<Value>)>, options: CallOptions) -> error::Result<Vec<Value>> {
+        let requests: Vec<_> = calls
+            .into_iter()
+            .map(|(method, params)| self.transport.prepare(method, params))
+            .collect();
+        self.transport
+            .send_batch(requests, options)
+            .await?
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Whether `err` signals that the node does not support the method called.
+fn is_method_not_found(err: &jsonrpc_core::Error) -> bool {
+    let message = err.message.to_lowercase();
+    err.code == jsonrpc_core::ErrorCode::MethodNotFound
+        || [
+            "method not found",
+            "does not exist",
+            "not supported",
+            "unsupported method",
+        ]
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
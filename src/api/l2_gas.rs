@@ -0,0 +1,167 @@
+//! `L2Gas` namespace, rollup-specific fee estimation
+
+use crate::{
+    api::{Eth, Namespace},
+    error,
+    helpers::CallFuture,
+    transports::ic_http_client::CallOptions,
+    types::{Address, ArbitrumGasEstimate, BlockId, Bytes, CallRequest, OptimismFee, RollupGasPrices, H160, U256},
+    Transport,
+};
+use ethabi::{ParamType, Token};
+
+/// OP Stack `GasPriceOracle` predeploy.
+pub const OP_GAS_PRICE_ORACLE: Address = H160([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0f,
+]);
+/// Arbitrum `NodeInterface` virtual contract, only reachable through `eth_call` and `eth_estimateGas`.
+pub const ARB_NODE_INTERFACE: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xc8,
+]);
+
+/// `getL1Fee(bytes)`
+const GET_L1_FEE: [u8; 4] = [0x49, 0x94, 0x8e, 0x0e];
+/// `getL1GasUsed(bytes)`
+const GET_L1_GAS_USED: [u8; 4] = [0xde, 0x26, 0xc4, 0xa1];
+/// `l1BaseFee()`
+const L1_BASE_FEE: [u8; 4] = [0x51, 0x9b, 0x4b, 0xd3];
+/// `gasEstimateComponents(address,bool,bytes)`
+const GAS_ESTIMATE_COMPONENTS: [u8; 4] = [0xc9, 0x4e, 0x6e, 0xeb];
+
+/// `L2Gas` namespace
+///
+/// Rollups charge for posting the transaction data to L1 on top of the L2 execution, which
+/// `eth_estimateGas` and `eth_gasPrice` do not fully reflect:
+///
+/// - OP Stack chains (Optimism, Base) charge an L1 data fee outside of the gas limit, deducted from the
+///   sender balance. It is quoted by the `GasPriceOracle` predeploy.
+/// - Arbitrum adds the L1 data cost to the gas limit, so that the estimate moves with the L1 base fee.
+///   The `NodeInterface` splits the estimate into its components.
+#[derive(Debug, Clone)]
+pub struct L2Gas<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for L2Gas<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        L2Gas { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> L2Gas<T> {
+    /// L1 and L2 gas prices of legacy Optimism nodes.
+    pub fn rollup_gas_prices(&self, options: CallOptions) -> CallFuture<RollupGasPrices, T::Out> {
+        CallFuture::new(self.transport.execute("rollup_gasPrices", vec![], options))
+    }
+
+    /// L1 data fee of an OP Stack transaction, `tx` being the RLP encoded transaction.
+    ///
+    /// Passing the signed transaction slightly overestimates the fee, by the size of the signature.
+    pub async fn op_l1_fee(&self, tx: Bytes, block: Option<BlockId>, options: CallOptions) -> error::Result<U256> {
+        self.op_call(GET_L1_FEE, &[Token::Bytes(tx.0)], block, options).await
+    }
+
+    /// L1 gas charged for the data of an OP Stack transaction, `tx` being the RLP encoded transaction.
+    pub async fn op_l1_gas_used(&self, tx: Bytes, block: Option<BlockId>, options: CallOptions) -> error::Result<U256> {
+        self.op_call(GET_L1_GAS_USED, &[Token::Bytes(tx.0)], block, options)
+            .await
+    }
+
+    /// L1 base fee known to an OP Stack chain.
+    pub async fn op_l1_base_fee(&self, block: Option<BlockId>, options: CallOptions) -> error::Result<U256> {
+        self.op_call(L1_BASE_FEE, &[], block, options).await
+    }
+
+    /// Total cost of an OP Stack transaction with gas limit `gas` paying `gas_price` per unit of gas,
+    /// `tx` being the RLP encoded transaction.
+    pub async fn op_total_fee(
+        &self,
+        tx: Bytes,
+        gas: U256,
+        gas_price: U256,
+        options: CallOptions,
+    ) -> error::Result<OptimismFee> {
+        let l1_fee = self.op_l1_fee(tx, None, options).await?;
+        Ok(OptimismFee {
+            l2_fee: gas.saturating_mul(gas_price),
+            l1_fee,
+        })
+    }
+
+    /// Gas estimate of the transaction `req` on Arbitrum, split into its L1 and L2 components.
+    ///
+    /// A transaction without `to` is estimated as a contract creation.
+    pub async fn arb_gas_estimate_components(
+        &self,
+        req: CallRequest,
+        block: Option<BlockId>,
+        options: CallOptions,
+    ) -> error::Result<ArbitrumGasEstimate> {
+        let args = [
+            Token::Address(req.to.unwrap_or_default()),
+            Token::Bool(req.to.is_none()),
+            Token::Bytes(req.data.map(|data| data.0).unwrap_or_default()),
+        ];
+        let call = CallRequest {
+            from: req.from,
+            to: Some(ARB_NODE_INTERFACE),
+            value: req.value,
+            data: Some(calldata(GAS_ESTIMATE_COMPONENTS, &args)),
+            ..Default::default()
+        };
+        let output = Eth::new(self.transport.clone()).call(call, block, options).await?;
+        let tokens = ethabi::decode(
+            &[
+                ParamType::Uint(64),
+                ParamType::Uint(64),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+            ],
+            &output.0,
+        )
+        .map_err(|err| error::Error::Decoder(format!("gasEstimateComponents output: {:?}", err)))?;
+        let uint = |index: usize| tokens[index].clone().into_uint().unwrap_or_default();
+        Ok(ArbitrumGasEstimate {
+            gas_estimate: uint(0),
+            gas_estimate_for_l1: uint(1),
+            base_fee: uint(2),
+            l1_base_fee_estimate: uint(3),
+        })
+    }
+
+    /// Call a `uint256` getter of the OP Stack `GasPriceOracle`.
+    async fn op_call(
+        &self,
+        selector: [u8; 4],
+        args: &[Token],
+        block: Option<BlockId>,
+        options: CallOptions,
+    ) -> error::Result<U256> {
+        let call = CallRequest {
+            to: Some(OP_GAS_PRICE_ORACLE),
+            data: Some(calldata(selector, args)),
+            ..Default::default()
+        };
+        let output = Eth::new(self.transport.clone()).call(call, block, options).await?;
+        ethabi::decode(&[ParamType::Uint(256)], &output.0)
+            .ok()
+            .and_then(|tokens| tokens.into_iter().next())
+            .and_then(Token::into_uint)
+            .ok_or_else(|| error::Error::Decoder(format!("GasPriceOracle output: {:?}", output)))
+    }
+}
+
+fn calldata(selector: [u8; 4], args: &[Token]) -> Bytes {
+    let mut data = selector.to_vec();
+    data.extend(ethabi::encode(args));
+    Bytes(data)
+}
@@ -1,15 +1,50 @@
 //! `Web3` implementation
 
 mod accounts;
+#[cfg(feature = "debug")]
+mod debug;
 mod eth;
+mod l2_gas;
+mod net;
+#[cfg(feature = "parity")]
+mod parity;
+#[cfg(feature = "parity")]
+mod parity_accounts;
+#[cfg(feature = "parity")]
+mod parity_set;
+mod read_only;
+#[cfg(feature = "traces")]
+mod traces;
+mod web3;
+#[cfg(feature = "zksync")]
+mod zks;
 
-pub use eth::Eth;
 pub use accounts::Accounts;
+#[cfg(feature = "debug")]
+pub use debug::Debug;
+pub use eth::Eth;
+pub use l2_gas::{L2Gas, ARB_NODE_INTERFACE, OP_GAS_PRICE_ORACLE};
+pub use net::Net;
+#[cfg(feature = "parity")]
+pub use parity::Parity;
+#[cfg(feature = "parity")]
+pub use parity_accounts::ParityAccounts;
+#[cfg(feature = "parity")]
+pub use parity_set::ParitySet;
+pub use read_only::{ReadOnlyEth, ReadOnlyWeb3};
+#[cfg(feature = "traces")]
+pub use traces::Traces;
+pub use web3::Web3 as Web3Api;
+#[cfg(feature = "zksync")]
+pub use zks::Zks;
 
 use crate::{
+    chains::Chain,
     error,
+    ic::KeyInfo,
     transports::ic_http_client::CallOptions,
     types::{Bytes, TransactionReceipt, TransactionRequest, U64},
+    wallet::Wallet,
     Error, RequestId, Transport,
 };
 use futures::Future;
@@ -29,12 +64,27 @@ pub trait Namespace<T: Transport>: Clone {
 #[derive(Debug, Clone)]
 pub struct Web3<T: Transport> {
     transport: T,
+    chain: Option<Chain>,
 }
 
 impl<T: Transport> Web3<T> {
     /// Create new `Web3` with given transport
     pub fn new(transport: T) -> Self {
-        Web3 { transport }
+        Web3 { transport, chain: None }
+    }
+
+    /// Create new `Web3` for a known network with given transport, e.g. one connected to
+    /// [`Chain::default_rpc_url`].
+    pub fn for_chain(chain: Chain, transport: T) -> Self {
+        Web3 {
+            transport,
+            chain: Some(chain),
+        }
+    }
+
+    /// Network this `Web3` was created for with [`Web3::for_chain`].
+    pub fn chain(&self) -> Option<Chain> {
+        self.chain
     }
 
     /// Borrows a transport.
@@ -47,6 +97,14 @@ impl<T: Transport> Web3<T> {
         self.transport.set_max_response_bytes(bytes)
     }
 
+    /// `Web3` limited to the calls reading the chain, see [`ReadOnlyWeb3`].
+    pub fn read_only(&self) -> ReadOnlyWeb3<T>
+    where
+        T::Out: Send + 'static,
+    {
+        self.clone().into()
+    }
+
     /// Access methods from custom namespace
     pub fn api<A: Namespace<T>>(&self) -> A {
         A::new(self.transport.clone())
@@ -57,6 +115,62 @@ impl<T: Transport> Web3<T> {
         self.api()
     }
 
+    /// Access methods from `net` namespace
+    pub fn net(&self) -> net::Net<T> {
+        self.api()
+    }
+
+    /// Access methods from `web3` namespace
+    pub fn web3(&self) -> web3::Web3<T> {
+        self.api()
+    }
+
+    /// Access the rollup-specific fee estimation of the `L2Gas` namespace
+    pub fn l2_gas(&self) -> l2_gas::L2Gas<T> {
+        self.api()
+    }
+
+    /// Access methods from `debug` namespace
+    #[cfg(feature = "debug")]
+    pub fn debug(&self) -> debug::Debug<T> {
+        self.api()
+    }
+
+    /// Access methods from `trace` namespace
+    #[cfg(feature = "traces")]
+    pub fn trace(&self) -> traces::Traces<T> {
+        self.api()
+    }
+
+    /// Access methods from `parity` namespace
+    #[cfg(feature = "parity")]
+    pub fn parity(&self) -> parity::Parity<T> {
+        self.api()
+    }
+
+    /// Access methods from `parity_accounts` namespace
+    #[cfg(feature = "parity")]
+    pub fn parity_accounts(&self) -> parity_accounts::ParityAccounts<T> {
+        self.api()
+    }
+
+    /// Access methods from `parity_set` namespace
+    #[cfg(feature = "parity")]
+    pub fn parity_set(&self) -> parity_set::ParitySet<T> {
+        self.api()
+    }
+
+    /// Access methods from the zkSync Era `zks` namespace
+    #[cfg(feature = "zksync")]
+    pub fn zks(&self) -> zks::Zks<T> {
+        self.api()
+    }
+
+    /// High-level [`Wallet`] of the canister key of `key_info` on `chain`, see [`crate::wallet`].
+    pub async fn wallet(&self, key_info: KeyInfo, chain: Chain) -> error::Result<Wallet<T>> {
+        Wallet::from_key(self.clone(), chain, key_info).await
+    }
+
     /// Call json rpc directly
     pub async fn json_rpc_call(&self, body: &str, options: CallOptions) -> error::Result<String> {
         let request: Call = serde_json::from_str(body).map_err(|_| Error::Decoder(body.to_string()))?;
@@ -0,0 +1,39 @@
+//! `Net` namespace
+
+use crate::{api::Namespace, helpers::CallFuture, transports::ic_http_client::CallOptions, types::U256, Transport};
+
+/// `Net` namespace
+#[derive(Debug, Clone)]
+pub struct Net<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for Net<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Net { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Net<T> {
+    /// Returns the network id.
+    pub fn version(&self, options: CallOptions) -> CallFuture<String, T::Out> {
+        CallFuture::new(self.transport.execute("net_version", vec![], options))
+    }
+
+    /// Returns number of peers connected to node.
+    pub fn peer_count(&self, options: CallOptions) -> CallFuture<U256, T::Out> {
+        CallFuture::new(self.transport.execute("net_peerCount", vec![], options))
+    }
+
+    /// Whether the node is listening for network connections
+    pub fn is_listening(&self, options: CallOptions) -> CallFuture<bool, T::Out> {
+        CallFuture::new(self.transport.execute("net_listening", vec![], options))
+    }
+}
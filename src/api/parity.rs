@@ -0,0 +1,86 @@
+//! `Parity` namespace
+
+use crate::{
+    api::Namespace,
+    helpers::{self, CallFuture},
+    transports::ic_http_client::CallOptions,
+    types::{
+        Address, BlockNumber, Bytes, CallRequest, ParityPeerType, ParityPendingTransactionFilter, Transaction,
+        TransactionRequest, U256,
+    },
+    Transport,
+};
+
+/// `Parity` namespace, served by OpenEthereum and compatible nodes
+#[derive(Debug, Clone)]
+pub struct Parity<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for Parity<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Parity { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Parity<T> {
+    /// Sequentially call multiple contract methods in one request without changing the state of the
+    /// blockchain.
+    pub fn call(
+        &self,
+        reqs: Vec<CallRequest>,
+        block: Option<BlockNumber>,
+        options: CallOptions,
+    ) -> CallFuture<Vec<Bytes>, T::Out> {
+        let reqs = helpers::serialize(&reqs);
+        let block = helpers::serialize(&block.unwrap_or(BlockNumber::Latest));
+
+        CallFuture::new(self.transport.execute("parity_call", vec![reqs, block], options))
+    }
+
+    /// Fill in the missing fields of a partial transaction request, e.g. to sign it externally.
+    pub fn compose_transaction(
+        &self,
+        req: TransactionRequest,
+        options: CallOptions,
+    ) -> CallFuture<Transaction, T::Out> {
+        let req = helpers::serialize(&req);
+
+        CallFuture::new(self.transport.execute("parity_composeTransaction", vec![req], options))
+    }
+
+    /// Get the pending transactions of the node's pool, at most `limit` of them and matching `filter`.
+    pub fn pending_transactions(
+        &self,
+        limit: Option<usize>,
+        filter: Option<ParityPendingTransactionFilter>,
+        options: CallOptions,
+    ) -> CallFuture<Vec<Transaction>, T::Out> {
+        let limit = helpers::serialize(&limit);
+        let filter = helpers::serialize(&filter);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_pendingTransactions", vec![limit, filter], options),
+        )
+    }
+
+    /// Get the next nonce of `address`, counting the transactions of the node's pool.
+    pub fn next_nonce(&self, address: Address, options: CallOptions) -> CallFuture<U256, T::Out> {
+        let address = helpers::serialize(&address);
+
+        CallFuture::new(self.transport.execute("parity_nextNonce", vec![address], options))
+    }
+
+    /// Get the peers of the node.
+    pub fn net_peers(&self, options: CallOptions) -> CallFuture<ParityPeerType, T::Out> {
+        CallFuture::new(self.transport.execute("parity_netPeers", vec![], options))
+    }
+}
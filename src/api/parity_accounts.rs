@@ -0,0 +1,96 @@
+//! `Parity_Accounts` namespace
+
+use crate::{
+    api::Namespace,
+    helpers::{self, CallFuture},
+    transports::ic_http_client::CallOptions,
+    types::{Address, H256},
+    Transport,
+};
+
+/// `Parity_Accounts` namespace, managing the accounts stored by the node
+#[derive(Debug, Clone)]
+pub struct ParityAccounts<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for ParityAccounts<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        ParityAccounts { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> ParityAccounts<T> {
+    /// Delete the account `address` from the node, returning whether it was deleted.
+    pub fn kill_account(&self, address: Address, password: &str, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let address = helpers::serialize(&address);
+        let password = helpers::serialize(&password);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_killAccount", vec![address, password], options),
+        )
+    }
+
+    /// Import the account of a recovery phrase, returning its address.
+    pub fn new_account_from_phrase(
+        &self,
+        phrase: &str,
+        password: &str,
+        options: CallOptions,
+    ) -> CallFuture<Address, T::Out> {
+        let phrase = helpers::serialize(&phrase);
+        let password = helpers::serialize(&password);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_newAccountFromPhrase", vec![phrase, password], options),
+        )
+    }
+
+    /// Import the account of a private key, returning its address.
+    pub fn new_account_from_secret(
+        &self,
+        secret: H256,
+        password: &str,
+        options: CallOptions,
+    ) -> CallFuture<Address, T::Out> {
+        let secret = helpers::serialize(&secret);
+        let password = helpers::serialize(&password);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_newAccountFromSecret", vec![secret, password], options),
+        )
+    }
+
+    /// Import the account of a JSON wallet file, returning its address.
+    pub fn new_account_from_wallet(
+        &self,
+        wallet: &str,
+        password: &str,
+        options: CallOptions,
+    ) -> CallFuture<Address, T::Out> {
+        let wallet = helpers::serialize(&wallet);
+        let password = helpers::serialize(&password);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_newAccountFromWallet", vec![wallet, password], options),
+        )
+    }
+
+    /// Remove `address` from the address book of the node, returning whether it was removed.
+    pub fn remove_address(&self, address: Address, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let address = helpers::serialize(&address);
+
+        CallFuture::new(self.transport.execute("parity_removeAddress", vec![address], options))
+    }
+}
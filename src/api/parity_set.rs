@@ -0,0 +1,164 @@
+//! `Parity_Set` namespace
+
+use crate::{
+    api::Namespace,
+    helpers::{self, CallFuture},
+    transports::ic_http_client::CallOptions,
+    types::{Address, Bytes, Transaction, H256, U256},
+    Transport,
+};
+
+/// `Parity_Set` namespace, configuring the node
+#[derive(Debug, Clone)]
+pub struct ParitySet<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for ParitySet<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        ParitySet { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> ParitySet<T> {
+    /// Accept connections from non-reserved peers.
+    pub fn accept_non_reserved_peers(&self, options: CallOptions) -> CallFuture<bool, T::Out> {
+        CallFuture::new(self.transport.execute("parity_acceptNonReservedPeers", vec![], options))
+    }
+
+    /// Add the peer with the given enode URL to the reserved peers.
+    pub fn add_reserved_peer(&self, enode: &str, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let enode = helpers::serialize(&enode);
+
+        CallFuture::new(self.transport.execute("parity_addReservedPeer", vec![enode], options))
+    }
+
+    /// Only accept connections from reserved peers.
+    pub fn drop_non_reserved_peers(&self, options: CallOptions) -> CallFuture<bool, T::Out> {
+        CallFuture::new(self.transport.execute("parity_dropNonReservedPeers", vec![], options))
+    }
+
+    /// Remove the peer with the given enode URL from the reserved peers.
+    pub fn remove_reserved_peer(&self, enode: &str, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let enode = helpers::serialize(&enode);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_removeReservedPeer", vec![enode], options),
+        )
+    }
+
+    /// Remove the transaction `hash` from the node's pool, returning it if it was pending.
+    pub fn remove_transaction(&self, hash: H256, options: CallOptions) -> CallFuture<Option<Transaction>, T::Out> {
+        let hash = helpers::serialize(&hash);
+
+        CallFuture::new(self.transport.execute("parity_removeTransaction", vec![hash], options))
+    }
+
+    /// Download the content at `url` and return its hash.
+    pub fn hash_content(&self, url: &str, options: CallOptions) -> CallFuture<H256, T::Out> {
+        let url = helpers::serialize(&url);
+
+        CallFuture::new(self.transport.execute("parity_hashContent", vec![url], options))
+    }
+
+    /// Set the beneficiary of the blocks mined by the node.
+    pub fn set_author(&self, author: Address, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let author = helpers::serialize(&author);
+
+        CallFuture::new(self.transport.execute("parity_setAuthor", vec![author], options))
+    }
+
+    /// Switch the node to the chain `chain`, e.g. `"foundation"`.
+    pub fn set_chain(&self, chain: &str, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let chain = helpers::serialize(&chain);
+
+        CallFuture::new(self.transport.execute("parity_setChain", vec![chain], options))
+    }
+
+    /// Set the account signing the blocks sealed by the node.
+    pub fn set_engine_signer(
+        &self,
+        address: Address,
+        password: &str,
+        options: CallOptions,
+    ) -> CallFuture<bool, T::Out> {
+        let address = helpers::serialize(&address);
+        let password = helpers::serialize(&password);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_setEngineSigner", vec![address, password], options),
+        )
+    }
+
+    /// Set the extra data of the blocks mined by the node.
+    pub fn set_extra_data(&self, data: Bytes, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let data = helpers::serialize(&data);
+
+        CallFuture::new(self.transport.execute("parity_setExtraData", vec![data], options))
+    }
+
+    /// Set the gas limit the node targets for the blocks it mines.
+    pub fn set_gas_ceil_target(&self, gas: U256, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let gas = helpers::serialize(&gas);
+
+        CallFuture::new(self.transport.execute("parity_setGasCeilTarget", vec![gas], options))
+    }
+
+    /// Set the minimum gas limit of the blocks mined by the node.
+    pub fn set_gas_floor_target(&self, gas: U256, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let gas = helpers::serialize(&gas);
+
+        CallFuture::new(self.transport.execute("parity_setGasFloorTarget", vec![gas], options))
+    }
+
+    /// Set the maximum gas of the transactions accepted in the node's pool.
+    pub fn set_max_transaction_gas(&self, gas: U256, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let gas = helpers::serialize(&gas);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_setMaxTransactionGas", vec![gas], options),
+        )
+    }
+
+    /// Set the minimum gas price of the transactions accepted in the node's pool.
+    pub fn set_min_gas_price(&self, gas_price: U256, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let gas_price = helpers::serialize(&gas_price);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_setMinGasPrice", vec![gas_price], options),
+        )
+    }
+
+    /// Set the operating mode of the node: `"active"`, `"passive"`, `"dark"` or `"offline"`.
+    pub fn set_mode(&self, mode: &str, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let mode = helpers::serialize(&mode);
+
+        CallFuture::new(self.transport.execute("parity_setMode", vec![mode], options))
+    }
+
+    /// Set the maximum number of transactions in the node's pool.
+    pub fn set_transactions_limit(&self, limit: usize, options: CallOptions) -> CallFuture<bool, T::Out> {
+        let limit = helpers::serialize(&limit);
+
+        CallFuture::new(
+            self.transport
+                .execute("parity_setTransactionsLimit", vec![limit], options),
+        )
+    }
+
+    /// Upgrade the node to the release it has downloaded, returning whether it did.
+    pub fn execute_upgrade(&self, options: CallOptions) -> CallFuture<bool, T::Out> {
+        CallFuture::new(self.transport.execute("parity_executeUpgrade", vec![], options))
+    }
+}
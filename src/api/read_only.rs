@@ -0,0 +1,269 @@
+//! Read-only `Web3`
+
+use crate::{
+    api::{l2_gas::L2Gas, net::Net, web3, Eth, Namespace, Web3},
+    chains::Chain,
+    error,
+    helpers::CallFuture,
+    transports::{ic_http_client::CallOptions, ReadOnlyTransport},
+    types::{
+        AccountSnapshot, Address, BalancePoint, Block, BlockHeader, BlockId, BlockNumber, BlockRange, Bytes,
+        CallRequest, ChainSnapshot, FeeHistory, Filter, Index, Log, Proof, SimulatePayload, SimulatedBlock,
+        StateOverride, SyncState, Transaction, TransactionId, TransactionReceipt, H256, U256, U64,
+    },
+    BatchTransport, Transport,
+};
+use futures::future::BoxFuture;
+use jsonrpc_core::Value;
+
+type Out = BoxFuture<'static, error::Result<Value>>;
+
+/// Forwards the read-only methods to the inner `Eth` namespace.
+macro_rules! forward {
+    ($($(#[$attr:meta])* fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self $(, $arg: $ty)*) -> $ret {
+                self.eth.$name($($arg),*)
+            }
+        )*
+    };
+}
+
+/// `Web3` limited to the calls reading the chain.
+///
+/// The type has no method sending transactions or signing, and the transport of the namespaces it hands
+/// out refuses the [state-changing](crate::transports::ic_http_client::is_state_changing) calls, so that
+/// components taking a `ReadOnlyWeb3` cannot broadcast anything, e.g. the parts of a canister that only
+/// monitor the chain:
+///
+/// ```ignore
+/// fn monitor(web3: ReadOnlyWeb3<ICHttp>) { ... }
+///
+/// monitor(web3.read_only());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReadOnlyWeb3<T: Transport> {
+    transport: ReadOnlyTransport<T>,
+    chain: Option<Chain>,
+}
+
+impl<T: Transport> From<Web3<T>> for ReadOnlyWeb3<T> {
+    fn from(web3: Web3<T>) -> Self {
+        ReadOnlyWeb3 {
+            chain: web3.chain(),
+            transport: ReadOnlyTransport::new(web3.transport().clone()),
+        }
+    }
+}
+
+impl<T: Transport> ReadOnlyWeb3<T>
+where
+    T::Out: Send + 'static,
+{
+    /// Create new `ReadOnlyWeb3` with given transport
+    pub fn new(transport: T) -> Self {
+        Web3::new(transport).into()
+    }
+
+    /// Network the `Web3` it was made from was created for, see [`Web3::for_chain`].
+    pub fn chain(&self) -> Option<Chain> {
+        self.chain
+    }
+
+    /// Access the read-only methods of the `eth` namespace
+    pub fn eth(&self) -> ReadOnlyEth<T> {
+        ReadOnlyEth {
+            eth: Eth::new(self.transport.clone()),
+        }
+    }
+
+    /// Access methods from `net` namespace
+    pub fn net(&self) -> Net<ReadOnlyTransport<T>> {
+        Net::new(self.transport.clone())
+    }
+
+    /// Access methods from `web3` namespace
+    pub fn web3(&self) -> web3::Web3<ReadOnlyTransport<T>> {
+        web3::Web3::new(self.transport.clone())
+    }
+
+    /// Access the rollup-specific fee estimation of the `L2Gas` namespace
+    pub fn l2_gas(&self) -> L2Gas<ReadOnlyTransport<T>> {
+        L2Gas::new(self.transport.clone())
+    }
+
+    /// Access methods from `debug` namespace
+    #[cfg(feature = "debug")]
+    pub fn debug(&self) -> super::Debug<ReadOnlyTransport<T>> {
+        super::Debug::new(self.transport.clone())
+    }
+
+    /// Access methods from `trace` namespace
+    #[cfg(feature = "traces")]
+    pub fn trace(&self) -> super::Traces<ReadOnlyTransport<T>> {
+        super::Traces::new(self.transport.clone())
+    }
+}
+
+/// Read-only methods of the `eth` namespace, see [`Eth`].
+#[derive(Debug, Clone)]
+pub struct ReadOnlyEth<T: Transport> {
+    eth: Eth<ReadOnlyTransport<T>>,
+}
+
+impl<T: Transport> ReadOnlyEth<T>
+where
+    T::Out: Send + 'static,
+{
+    forward! {
+        /// Get list of available accounts.
+        fn accounts(&self, options: CallOptions) -> CallFuture<Vec<Address>, Out>;
+        /// Get current block number
+        fn block_number(&self, options: CallOptions) -> CallFuture<U64, Out>;
+        /// Call a constant method of contract without changing the state of the blockchain.
+        fn call(&self, req: CallRequest, block: Option<BlockId>, options: CallOptions) -> CallFuture<Bytes, Out>;
+        /// Call a constant method of contract with the given state overrides, see [`Eth::call_with_overrides`].
+        fn call_with_overrides(
+            &self,
+            req: CallRequest,
+            block: Option<BlockId>,
+            overrides: StateOverride,
+            options: CallOptions
+        ) -> CallFuture<Bytes, Out>;
+        /// Call a contract without changing the state of the blockchain to estimate gas usage.
+        fn estimate_gas(&self, req: CallRequest, block: Option<BlockId>, options: CallOptions) -> CallFuture<U256, Out>;
+        /// Estimate gas usage with the given state overrides, see [`Eth::estimate_gas_with_overrides`].
+        fn estimate_gas_with_overrides(
+            &self,
+            req: CallRequest,
+            block: Option<BlockId>,
+            overrides: StateOverride,
+            options: CallOptions
+        ) -> CallFuture<U256, Out>;
+        /// Get current recommended gas price
+        fn gas_price(&self, options: CallOptions) -> CallFuture<U256, Out>;
+        /// Get the priority fee per gas the node suggests for EIP-1559 transactions to be included timely.
+        fn max_priority_fee_per_gas(&self, options: CallOptions) -> CallFuture<U256, Out>;
+        /// Get the base fee per blob gas of the next block (EIP-4844).
+        fn blob_base_fee(&self, options: CallOptions) -> CallFuture<U256, Out>;
+        /// Returns a collection of historical gas information, see [`Eth::fee_history`].
+        fn fee_history(
+            &self,
+            block_count: U256,
+            newest_block: BlockNumber,
+            reward_percentiles: Option<Vec<f64>>,
+            options: CallOptions
+        ) -> CallFuture<FeeHistory, Out>;
+        /// Simulate the calls of `payload` in a sequence of blocks, see [`Eth::simulate`].
+        fn simulate(
+            &self,
+            payload: SimulatePayload,
+            block: Option<BlockId>,
+            options: CallOptions
+        ) -> CallFuture<Vec<SimulatedBlock>, Out>;
+        /// Simulate the calls of `payload` returning the simulated transactions, see [`Eth::simulate_full`].
+        fn simulate_full(
+            &self,
+            payload: SimulatePayload,
+            block: Option<BlockId>,
+            options: CallOptions
+        ) -> CallFuture<Vec<SimulatedBlock<Transaction>>, Out>;
+        /// Get balance of given address
+        fn balance(&self, address: Address, block: Option<BlockNumber>, options: CallOptions) -> CallFuture<U256, Out>;
+        /// Get all logs matching a given filter object
+        fn logs(&self, filter: Filter, options: CallOptions) -> CallFuture<Vec<Log>, Out>;
+        /// Get block details with transaction hashes.
+        fn block(&self, block: BlockId, options: CallOptions) -> CallFuture<Option<Block<H256>>, Out>;
+        /// Get block details with full transaction objects.
+        fn block_with_txs(&self, block: BlockId, options: CallOptions) -> CallFuture<Option<Block<Transaction>>, Out>;
+        /// Get number of transactions in block
+        fn block_transaction_count(&self, block: BlockId, options: CallOptions) -> CallFuture<Option<U256>, Out>;
+        /// Get code under given address
+        fn code(&self, address: Address, block: Option<BlockNumber>, options: CallOptions) -> CallFuture<Bytes, Out>;
+        /// Get chain id
+        fn chain_id(&self, options: CallOptions) -> CallFuture<U256, Out>;
+        /// Get the sync state of the node.
+        fn syncing(&self, options: CallOptions) -> CallFuture<SyncState, Out>;
+        /// Get storage entry
+        fn storage(
+            &self,
+            address: Address,
+            idx: U256,
+            block: Option<BlockNumber>,
+            options: CallOptions
+        ) -> CallFuture<H256, Out>;
+        /// Get nonce
+        fn transaction_count(
+            &self,
+            address: Address,
+            block: Option<BlockNumber>,
+            options: CallOptions
+        ) -> CallFuture<U256, Out>;
+        /// Get transaction
+        fn transaction(&self, id: TransactionId, options: CallOptions) -> CallFuture<Option<Transaction>, Out>;
+        /// Get transaction receipt
+        fn transaction_receipt(&self, hash: H256, options: CallOptions) -> CallFuture<Option<TransactionReceipt>, Out>;
+        /// Get the receipts of all the transactions of a block, see [`Eth::block_receipts`].
+        fn block_receipts(
+            &self,
+            block: BlockId,
+            options: CallOptions
+        ) -> CallFuture<Option<Vec<TransactionReceipt>>, Out>;
+        /// Get uncle header by block ID and uncle index.
+        fn uncle_header(&self, block: BlockId, index: Index, options: CallOptions) -> CallFuture<Option<BlockHeader>, Out>;
+        /// Get uncle by block ID and uncle index -- transactions only has hashes.
+        fn uncle(&self, block: BlockId, index: Index, options: CallOptions) -> CallFuture<Option<Block<H256>>, Out>;
+        /// Get uncle count in block
+        fn uncle_count(&self, block: BlockId, options: CallOptions) -> CallFuture<Option<U256>, Out>;
+        /// Get ethereum protocol version
+        fn protocol_version(&self, options: CallOptions) -> CallFuture<String, Out>;
+        /// Returns the account- and storage-values of the specified account including the Merkle-proof.
+        fn proof(
+            &self,
+            address: Address,
+            keys: Vec<U256>,
+            block: Option<BlockNumber>,
+            options: CallOptions
+        ) -> CallFuture<Option<Proof>, Out>;
+    }
+
+    /// Get logs matching `filter` between blocks `from` and `to`, split into pages of bounded size.
+    #[cfg(feature = "logs")]
+    pub fn paged_logs(
+        &self,
+        filter: crate::types::FilterBuilder,
+        from: U64,
+        to: U64,
+        options: CallOptions,
+    ) -> crate::logs::LogPaginator<ReadOnlyTransport<T>> {
+        self.eth.paged_logs(filter, from, to, options)
+    }
+}
+
+impl<T: BatchTransport> ReadOnlyEth<T>
+where
+    T::Out: Send + 'static,
+    T::Batch: Send + 'static,
+{
+    /// Chain id, latest block and gas price, fetched in a single batch request.
+    pub async fn chain_snapshot(&self, options: CallOptions) -> error::Result<ChainSnapshot> {
+        self.eth.chain_snapshot(options).await
+    }
+
+    /// Balance, nonces and code presence of `address`, fetched in a single batch request.
+    pub async fn account_snapshot(&self, address: Address, options: CallOptions) -> error::Result<AccountSnapshot> {
+        self.eth.account_snapshot(address, options).await
+    }
+
+    /// Balances of `address` in `range`, every `stride` blocks, see [`Eth::balance_series`].
+    pub async fn balance_series(
+        &self,
+        address: Address,
+        range: BlockRange,
+        stride: u64,
+        options: CallOptions,
+    ) -> error::Result<Vec<BalancePoint>> {
+        self.eth.balance_series(address, range, stride, options).await
+    }
+}
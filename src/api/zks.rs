@@ -0,0 +1,118 @@
+//! `Zks` namespace of zkSync Era
+
+use crate::{
+    api::Namespace,
+    helpers::{self, CallFuture},
+    transports::ic_http_client::CallOptions,
+    types::{
+        Address, BridgeAddresses, CallRequest, Eip712CallRequest, Fee, L1BatchBlockRange, L2ToL1LogProof, H256, U256,
+        U64,
+    },
+    Transport,
+};
+
+/// `Zks` namespace
+#[derive(Debug, Clone)]
+pub struct Zks<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for Zks<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Zks { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Zks<T> {
+    /// Estimate the fee of a transaction, including the gas per pubdata limit.
+    ///
+    /// The request may carry zkSync fields (e.g. a paymaster), which affect the estimate.
+    pub fn estimate_fee(&self, req: Eip712CallRequest, options: CallOptions) -> CallFuture<Fee, T::Out> {
+        let req = helpers::serialize(&req);
+        CallFuture::new(self.transport.execute("zks_estimateFee", vec![req], options))
+    }
+
+    /// Estimate the gas of an L1 to L2 transaction.
+    pub fn estimate_gas_l1_to_l2(&self, req: CallRequest, options: CallOptions) -> CallFuture<U256, T::Out> {
+        let req = helpers::serialize(&req);
+        CallFuture::new(self.transport.execute("zks_estimateGasL1ToL2", vec![req], options))
+    }
+
+    /// Get the proof of an L2 to L1 message sent by `sender` in the L2 block `block`.
+    ///
+    /// `log_position` selects the message when the block contains several identical ones.
+    pub fn get_l2_to_l1_msg_proof(
+        &self,
+        block: u32,
+        sender: Address,
+        msg: H256,
+        log_position: Option<u64>,
+        options: CallOptions,
+    ) -> CallFuture<Option<L2ToL1LogProof>, T::Out> {
+        let mut params = vec![
+            helpers::serialize(&block),
+            helpers::serialize(&sender),
+            helpers::serialize(&msg),
+        ];
+        if let Some(log_position) = log_position {
+            params.push(helpers::serialize(&log_position));
+        }
+        CallFuture::new(self.transport.execute("zks_getL2ToL1MsgProof", params, options))
+    }
+
+    /// Get the proof of the L2 to L1 log number `index` emitted by transaction `hash`.
+    pub fn get_l2_to_l1_log_proof(
+        &self,
+        hash: H256,
+        index: Option<u64>,
+        options: CallOptions,
+    ) -> CallFuture<Option<L2ToL1LogProof>, T::Out> {
+        let mut params = vec![helpers::serialize(&hash)];
+        if let Some(index) = index {
+            params.push(helpers::serialize(&index));
+        }
+        CallFuture::new(self.transport.execute("zks_getL2ToL1LogProof", params, options))
+    }
+
+    /// Get the address of the main zkSync contract on L1.
+    pub fn main_contract(&self, options: CallOptions) -> CallFuture<Address, T::Out> {
+        CallFuture::new(self.transport.execute("zks_getMainContract", vec![], options))
+    }
+
+    /// Get the addresses of the default bridges.
+    pub fn bridge_contracts(&self, options: CallOptions) -> CallFuture<BridgeAddresses, T::Out> {
+        CallFuture::new(self.transport.execute("zks_getBridgeContracts", vec![], options))
+    }
+
+    /// Get the address of the testnet paymaster, `None` on mainnet.
+    pub fn testnet_paymaster(&self, options: CallOptions) -> CallFuture<Option<Address>, T::Out> {
+        CallFuture::new(self.transport.execute("zks_getTestnetPaymaster", vec![], options))
+    }
+
+    /// Get the chain id of the underlying L1.
+    pub fn l1_chain_id(&self, options: CallOptions) -> CallFuture<U64, T::Out> {
+        CallFuture::new(self.transport.execute("zks_L1ChainId", vec![], options))
+    }
+
+    /// Get the number of the latest L1 batch.
+    pub fn l1_batch_number(&self, options: CallOptions) -> CallFuture<U64, T::Out> {
+        CallFuture::new(self.transport.execute("zks_L1BatchNumber", vec![], options))
+    }
+
+    /// Get the range of L2 blocks included in the L1 batch `batch`.
+    pub fn l1_batch_block_range(
+        &self,
+        batch: u32,
+        options: CallOptions,
+    ) -> CallFuture<Option<L1BatchBlockRange>, T::Out> {
+        let batch = helpers::serialize(&batch);
+        CallFuture::new(self.transport.execute("zks_getL1BatchBlockRange", vec![batch], options))
+    }
+}
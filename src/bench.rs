@@ -0,0 +1,205 @@
+//! Instruction-count benchmarks of the hot paths.
+//!
+//! The workloads run inside a canister and measure the instructions they execute with the IC
+//! performance counter, so they can be compared against [`Budget`]s and fail when a change makes
+//! the decoding pipeline more expensive. `benches/canister` wraps them into a canister and drives it
+//! from PocketIC. Outside of a canister the counter is not available and every measurement reports
+//! zero instructions.
+
+use crate::{
+    contract::DecodedEvent,
+    ic, signing,
+    types::{Log, TransactionReceipt},
+};
+use libsecp256k1::{Message, SecretKey};
+
+/// Instructions executed by a named workload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    /// Workload name
+    pub name: String,
+    /// Number of iterations of the workload
+    pub iterations: u32,
+    /// Instructions executed by all iterations
+    pub instructions: u64,
+}
+
+impl Measurement {
+    /// Average instructions of a single iteration.
+    pub fn per_iteration(&self) -> u64 {
+        self.instructions / u64::from(self.iterations.max(1))
+    }
+
+    /// Check the measurement against `budget`.
+    pub fn check(&self, budget: &Budget) -> Result<(), String> {
+        if self.per_iteration() > budget.max_instructions {
+            return Err(format!(
+                "{}: {} instructions per iteration, over the budget of {}",
+                self.name,
+                self.per_iteration(),
+                budget.max_instructions
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Maximum instructions per iteration allowed for a workload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Budget {
+    /// Workload name
+    pub name: &'static str,
+    /// Maximum instructions of a single iteration
+    pub max_instructions: u64,
+}
+
+/// Budgets of the built-in workloads.
+pub const DEFAULT_BUDGETS: &[Budget] = &[
+    Budget {
+        name: "log_decoding",
+        max_instructions: 400_000,
+    },
+    Budget {
+        name: "receipt_parsing",
+        max_instructions: 1_500_000,
+    },
+    Budget {
+        name: "signing_round_trip",
+        max_instructions: 30_000_000,
+    },
+];
+
+fn instruction_counter() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        ic_cdk::api::performance_counter(0)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        0
+    }
+}
+
+/// Run `workload` `iterations` times and measure the instructions it executed.
+pub fn measure<F: FnMut()>(name: &str, iterations: u32, mut workload: F) -> Measurement {
+    let start = instruction_counter();
+    for _ in 0..iterations {
+        workload();
+    }
+    Measurement {
+        name: name.to_string(),
+        iterations,
+        instructions: instruction_counter() - start,
+    }
+}
+
+const TRANSFER_LOG: &str = r#"{
+    "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+    "topics": [
+        "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+        "0x00000000000000000000000028c6c06298d514db089934071355e5743bf21d60",
+        "0x000000000000000000000000a9d1e08c7793af67e9d92fe308d5697fb81d3e43"
+    ],
+    "data": "0x00000000000000000000000000000000000000000000000000000002540be400",
+    "blockHash": "0x1d59ff54b1eb26b013ce3cb5fc9dab3705b415a67127a003c3e61eb445bb8df2",
+    "blockNumber": "0x10d4f",
+    "transactionHash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+    "transactionIndex": "0x1",
+    "logIndex": "0x3",
+    "removed": false
+}"#;
+
+fn transfer_event() -> ethabi::Event {
+    use ethabi::{EventParam, ParamType};
+    let param = |name: &str, kind, indexed| EventParam {
+        name: name.to_string(),
+        kind,
+        indexed,
+    };
+    ethabi::Event {
+        name: "Transfer".to_string(),
+        inputs: vec![
+            param("from", ParamType::Address, true),
+            param("to", ParamType::Address, true),
+            param("value", ParamType::Uint(256), false),
+        ],
+        anonymous: false,
+    }
+}
+
+fn receipt_json(logs: usize) -> String {
+    let logs = vec![TRANSFER_LOG; logs].join(",");
+    format!(
+        r#"{{
+            "transactionHash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+            "transactionIndex": "0x1",
+            "blockHash": "0x1d59ff54b1eb26b013ce3cb5fc9dab3705b415a67127a003c3e61eb445bb8df2",
+            "blockNumber": "0x10d4f",
+            "from": "0x28c6c06298d514db089934071355e5743bf21d60",
+            "to": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            "cumulativeGasUsed": "0x1f6e4",
+            "gasUsed": "0xfd5c",
+            "contractAddress": null,
+            "logs": [{}],
+            "status": "0x1",
+            "logsBloom": "0x{}",
+            "type": "0x2",
+            "effectiveGasPrice": "0x3b9aca00"
+        }}"#,
+        logs,
+        "0".repeat(512)
+    )
+}
+
+/// Deserialize and ABI-decode an ERC-20 `Transfer` log.
+pub fn log_decoding(iterations: u32) -> Measurement {
+    let event = transfer_event();
+    measure("log_decoding", iterations, || {
+        let log: Log = serde_json::from_str(TRANSFER_LOG).expect("valid log fixture");
+        DecodedEvent::decode(&event, log).expect("valid transfer log");
+    })
+}
+
+/// Deserialize a receipt with four logs.
+pub fn receipt_parsing(iterations: u32) -> Measurement {
+    let receipt = receipt_json(4);
+    measure("receipt_parsing", iterations, || {
+        let _: TransactionReceipt = serde_json::from_str(&receipt).expect("valid receipt fixture");
+    })
+}
+
+/// Sign a hash with a fixed key and recover the signer address from the signature.
+pub fn signing_round_trip(iterations: u32) -> Measurement {
+    let key = SecretKey::parse(&[0x42; 32]).expect("valid secret key");
+    measure("signing_round_trip", iterations, || {
+        let hash = signing::keccak256(b"ic-web3 signing round trip");
+        let (signature, recovery_id) = libsecp256k1::sign(&Message::parse(&hash), &key);
+        ic::recover_address(hash.to_vec(), signature.serialize().to_vec(), recovery_id.serialize());
+    })
+}
+
+/// Run every built-in workload.
+pub fn run_all(iterations: u32) -> Vec<Measurement> {
+    vec![
+        log_decoding(iterations),
+        receipt_parsing(iterations),
+        signing_round_trip(iterations),
+    ]
+}
+
+/// Run every built-in workload and check it against [`DEFAULT_BUDGETS`].
+pub fn check_all(iterations: u32) -> Result<Vec<Measurement>, Vec<String>> {
+    let measurements = run_all(iterations);
+    let failures: Vec<String> = measurements
+        .iter()
+        .filter_map(|measurement| {
+            let budget = DEFAULT_BUDGETS.iter().find(|budget| budget.name == measurement.name)?;
+            measurement.check(budget).err()
+        })
+        .collect();
+    if failures.is_empty() {
+        Ok(measurements)
+    } else {
+        Err(failures)
+    }
+}
@@ -0,0 +1,476 @@
+//! Delivery of Ethereum events to canister methods.
+//!
+//! An [`EventBridge`] forwards decoded events, e.g. polled from an
+//! [`EventStream`](crate::contract::EventStream), to the canister methods registered for them. Each
+//! method receives a candid-encoded [`EventPayload`]:
+//!
+//! ```ignore
+//! let bridge = EventBridge::new()
+//!     .with_route("Deposit", ledger, "on_deposit")
+//!     .with_route("*", indexer, "on_event")
+//!     .with_store(store);
+//! for event in stream.poll().await? {
+//!     bridge.enqueue(&event.event);
+//! }
+//! bridge.flush().await;
+//! ```
+//!
+//! Failed deliveries are retried by the next flushes, e.g. from a timer started with
+//! [`EventBridge::start`], and moved to a dead-letter queue after too many attempts. Events are delivered
+//! at least once: a call which failed, or was in flight during an upgrade, may have run and is made
+//! again, so the methods should ignore the events they already received, e.g. by transaction hash and
+//! log index.
+
+use crate::{
+    contract::DecodedEvent,
+    debug,
+    runtime::{BusyFlag, Subsystem},
+    stable::{StableStore, StoreHandle},
+    types::U256,
+};
+use candid::{CandidType, Principal};
+use ethabi::Token;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Key prefix of the pending deliveries persisted into a [`StableStore`].
+const PENDING_PREFIX: &str = "bridge/pending/";
+/// Key prefix of the dead letters persisted into a [`StableStore`].
+const DEAD_PREFIX: &str = "bridge/dead/";
+
+/// Key of the next delivery identifier, under the pending prefix, kept so that identifiers of delivered
+/// or discarded deliveries are not reused.
+const NEXT_ID_KEY: &[u8] = b"next";
+
+/// Event name of the routes receiving every event.
+pub const ANY_EVENT: &str = "*";
+
+/// Value of an event argument, as sent to canisters.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventValue {
+    /// Checksum-less hex address, `0x`-prefixed
+    Address(String),
+    /// Unsigned integer, in decimal
+    Uint(String),
+    /// Signed integer, in decimal
+    Int(String),
+    /// Boolean
+    Bool(bool),
+    /// String
+    String(String),
+    /// Fixed or dynamic bytes, or the hash of an indexed dynamic value
+    Bytes(Vec<u8>),
+    /// Fixed or dynamic array
+    Array(Vec<EventValue>),
+    /// Struct
+    Tuple(Vec<EventValue>),
+}
+
+impl From<&Token> for EventValue {
+    fn from(token: &Token) -> Self {
+        match token {
+            Token::Address(address) => EventValue::Address(format!("{:?}", address)),
+            Token::Uint(value) => EventValue::Uint(value.to_string()),
+            Token::Int(value) if value.bit(255) => {
+                EventValue::Int(format!("-{}", (!*value).overflowing_add(U256::one()).0))
+            }
+            Token::Int(value) => EventValue::Int(value.to_string()),
+            Token::Bool(value) => EventValue::Bool(*value),
+            Token::String(value) => EventValue::String(value.clone()),
+            Token::Bytes(value) | Token::FixedBytes(value) => EventValue::Bytes(value.clone()),
+            Token::Array(tokens) | Token::FixedArray(tokens) => {
+                EventValue::Array(tokens.iter().map(EventValue::from).collect())
+            }
+            Token::Tuple(tokens) => EventValue::Tuple(tokens.iter().map(EventValue::from).collect()),
+        }
+    }
+}
+
+/// Named argument of an event.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventArg {
+    /// Parameter name
+    pub name: String,
+    /// Value
+    pub value: EventValue,
+}
+
+/// Event sent to the target canisters, as the only argument of their method.
+///
+/// Its candid type is:
+///
+/// ```text
+/// type EventValue = variant {
+///   Address : text; Uint : text; Int : text; Bool : bool; String : text;
+///   Bytes : blob; Array : vec EventValue; Tuple : vec EventValue;
+/// };
+/// type EventPayload = record {
+///   name : text; address : text; args : vec record { name : text; value : EventValue };
+///   block_number : opt nat64; transaction_hash : opt text; log_index : opt nat64;
+/// };
+/// ```
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventPayload {
+    /// Event name
+    pub name: String,
+    /// Address of the emitting contract, `0x`-prefixed
+    pub address: String,
+    /// Arguments, in ABI order
+    pub args: Vec<EventArg>,
+    /// Number of the block of the log
+    pub block_number: Option<u64>,
+    /// Hash of the transaction of the log, `0x`-prefixed
+    pub transaction_hash: Option<String>,
+    /// Index of the log in its block
+    pub log_index: Option<u64>,
+}
+
+impl From<&DecodedEvent> for EventPayload {
+    fn from(event: &DecodedEvent) -> Self {
+        EventPayload {
+            name: event.name.clone(),
+            address: format!("{:?}", event.log.address),
+            args: event
+                .params
+                .iter()
+                .map(|(name, value)| EventArg {
+                    name: name.clone(),
+                    value: value.into(),
+                })
+                .collect(),
+            block_number: event.log.block_number.map(|number| number.as_u64()),
+            transaction_hash: event.log.transaction_hash.map(|hash| format!("{:?}", hash)),
+            log_index: event.log.log_index.map(|index| index.low_u64()),
+        }
+    }
+}
+
+/// Canister method receiving events.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BridgeTarget {
+    /// Target canister
+    pub canister: Principal,
+    /// Method called with the [`EventPayload`]
+    pub method: String,
+}
+
+/// Event to deliver to a target.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Identifier, increasing in the order of the events
+    pub id: u64,
+    /// Called method
+    pub target: BridgeTarget,
+    /// Delivered event
+    pub payload: EventPayload,
+    /// Failed attempts at delivering the event
+    pub attempts: u32,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+}
+
+/// Outcome of a flush.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Deliveries which succeeded
+    pub delivered: usize,
+    /// Deliveries which failed and will be retried
+    pub failed: usize,
+    /// Deliveries which failed too many times and were moved to the dead-letter queue
+    pub dead_lettered: usize,
+    /// Deliveries postponed after an earlier delivery to the same target failed
+    pub postponed: usize,
+}
+
+#[derive(Debug, Default)]
+struct Queues {
+    next_id: u64,
+    pending: BTreeMap<u64, Delivery>,
+    dead: BTreeMap<u64, Delivery>,
+}
+
+/// Bridge calling canister methods with the events routed to them.
+///
+/// Deliveries to a target are made in the order of the events: when one fails, the following ones to
+/// the same target wait for the next flush. A delivery failing `max_attempts` times is moved to the
+/// dead-letter queue, where it stays until it is redelivered or discarded.
+///
+/// The bridge is cheap to clone; clones share the same queues.
+#[derive(Clone, Debug)]
+pub struct EventBridge {
+    routes: Vec<(String, BridgeTarget)>,
+    queues: Arc<Mutex<Queues>>,
+    flushing: BusyFlag,
+    store: Option<(StoreHandle, StoreHandle)>,
+    max_attempts: u32,
+}
+
+impl Default for EventBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBridge {
+    /// Create a bridge without routes.
+    pub fn new() -> Self {
+        EventBridge {
+            routes: vec![],
+            queues: Default::default(),
+            flushing: Default::default(),
+            store: None,
+            max_attempts: 5,
+        }
+    }
+
+    /// Deliver the events called `event`, or every event for [`ANY_EVENT`], to `method` of `canister`.
+    pub fn with_route(mut self, event: &str, canister: Principal, method: &str) -> Self {
+        self.routes.push((
+            event.into(),
+            BridgeTarget {
+                canister,
+                method: method.into(),
+            },
+        ));
+        self
+    }
+
+    /// Persist the queues into `store`, loading the deliveries queued there.
+    pub fn with_store(mut self, store: Arc<dyn StableStore>) -> Self {
+        let pending = StoreHandle::new(store.clone(), PENDING_PREFIX);
+        let dead = StoreHandle::new(store, DEAD_PREFIX);
+        let load = |handle: &StoreHandle| -> BTreeMap<u64, Delivery> {
+            handle
+                .scan::<Delivery>()
+                .into_iter()
+                .map(|(_, delivery)| (delivery.id, delivery))
+                .collect()
+        };
+        let queues = Queues {
+            pending: load(&pending),
+            dead: load(&dead),
+            next_id: 0,
+        };
+        let last = queues.pending.keys().chain(queues.dead.keys()).max();
+        let next_id = last.map(|id| id + 1).max(pending.get::<u64>(NEXT_ID_KEY)).unwrap_or(0);
+        self.queues = Arc::new(Mutex::new(Queues { next_id, ..queues }));
+        self.store = Some((pending, dead));
+        self
+    }
+
+    /// Set the number of failed attempts after which a delivery is dead-lettered.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    fn persist(&self, dead: bool, id: u64, delivery: Option<&Delivery>) {
+        if let Some((pending_store, dead_store)) = &self.store {
+            let store = if dead { dead_store } else { pending_store };
+            match delivery {
+                Some(delivery) => store.insert(&id.to_be_bytes(), delivery),
+                None => store.remove(&id.to_be_bytes()),
+            }
+        }
+    }
+
+    /// Targets of the event called `name`.
+    pub fn targets(&self, name: &str) -> Vec<BridgeTarget> {
+        let mut targets = vec![];
+        for (event, target) in &self.routes {
+            if (event == name || event == ANY_EVENT) && !targets.contains(target) {
+                targets.push(target.clone());
+            }
+        }
+        targets
+    }
+
+    /// Queue the delivery of `event` to its targets, returning the number of deliveries queued.
+    pub fn enqueue(&self, event: &DecodedEvent) -> usize {
+        let targets = self.targets(&event.name);
+        if targets.is_empty() {
+            return 0;
+        }
+        let payload = EventPayload::from(event);
+        let mut queues = self.queues.lock();
+        for target in &targets {
+            let id = queues.next_id;
+            queues.next_id += 1;
+            let delivery = Delivery {
+                id,
+                target: target.clone(),
+                payload: payload.clone(),
+                attempts: 0,
+                last_error: None,
+            };
+            self.persist(false, id, Some(&delivery));
+            queues.pending.insert(id, delivery);
+        }
+        if let Some((pending_store, _)) = &self.store {
+            pending_store.insert(NEXT_ID_KEY, &queues.next_id);
+        }
+        targets.len()
+    }
+
+    /// Queue `events` and deliver the pending events.
+    pub async fn deliver(&self, events: &[DecodedEvent]) -> FlushReport {
+        for event in events {
+            self.enqueue(event);
+        }
+        self.flush().await
+    }
+
+    /// Deliver the pending events, in order.
+    ///
+    /// A flush started while another one is running returns immediately, so that timers firing during
+    /// a slow flush do not deliver the same events twice.
+    pub async fn flush(&self) -> FlushReport {
+        let _guard = match self.flushing.enter() {
+            Some(guard) => guard,
+            None => return FlushReport::default(),
+        };
+        self.flush_pending().await
+    }
+
+    async fn flush_pending(&self) -> FlushReport {
+        let pending: Vec<Delivery> = self.queues.lock().pending.values().cloned().collect();
+        let mut report = FlushReport::default();
+        let mut blocked = HashSet::new();
+        for delivery in pending {
+            if blocked.contains(&delivery.target) {
+                report.postponed += 1;
+                continue;
+            }
+            let result: ic_cdk::api::call::CallResult<()> = ic_cdk::call(
+                delivery.target.canister,
+                &delivery.target.method,
+                (delivery.payload.clone(),),
+            )
+            .await;
+
+            let mut queues = self.queues.lock();
+            // The delivery may have been discarded while the call was in flight.
+            let current = match queues.pending.get_mut(&delivery.id) {
+                Some(current) => current,
+                None => continue,
+            };
+            match result {
+                Ok(()) => {
+                    queues.pending.remove(&delivery.id);
+                    self.persist(false, delivery.id, None);
+                    report.delivered += 1;
+                }
+                Err((code, message)) => {
+                    current.attempts += 1;
+                    current.last_error = Some(format!("{:?}: {}", code, message));
+                    blocked.insert(delivery.target.clone());
+                    if current.attempts >= self.max_attempts {
+                        debug::warn(&format!(
+                            "dead-lettering event {} to {}.{} after {} attempts: {:?}: {}",
+                            current.payload.name,
+                            delivery.target.canister,
+                            delivery.target.method,
+                            current.attempts,
+                            code,
+                            message
+                        ));
+                        let dead = queues.pending.remove(&delivery.id).expect("delivery is pending; qed");
+                        self.persist(false, delivery.id, None);
+                        self.persist(true, delivery.id, Some(&dead));
+                        queues.dead.insert(delivery.id, dead);
+                        report.dead_lettered += 1;
+                    } else {
+                        self.persist(false, delivery.id, Some(current));
+                        report.failed += 1;
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Deliveries waiting to be made, by identifier.
+    pub fn pending(&self) -> Vec<Delivery> {
+        self.queues.lock().pending.values().cloned().collect()
+    }
+
+    /// Deliveries of the dead-letter queue, by identifier.
+    pub fn dead_letters(&self) -> Vec<Delivery> {
+        self.queues.lock().dead.values().cloned().collect()
+    }
+
+    /// Move the dead letter `id` back to the pending deliveries, returning whether it was dead-lettered.
+    ///
+    /// Its attempts are reset, and it is delivered before the later events to the same target.
+    pub fn redeliver(&self, id: u64) -> bool {
+        let mut queues = self.queues.lock();
+        match queues.dead.remove(&id) {
+            Some(mut delivery) => {
+                delivery.attempts = 0;
+                self.persist(true, id, None);
+                self.persist(false, id, Some(&delivery));
+                queues.pending.insert(id, delivery);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop the delivery `id`, pending or dead-lettered, returning whether it was queued.
+    pub fn discard(&self, id: u64) -> bool {
+        let mut queues = self.queues.lock();
+        if queues.pending.remove(&id).is_some() {
+            self.persist(false, id, None);
+            true
+        } else if queues.dead.remove(&id).is_some() {
+            self.persist(true, id, None);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Flush the pending deliveries every `interval` from a canister timer, returning the timer to clear
+    /// to stop.
+    pub fn start(&self, interval: Duration) -> ic_cdk_timers::TimerId {
+        let bridge = self.clone();
+        ic_cdk_timers::set_timer_interval(interval, move || {
+            let bridge = bridge.clone();
+            ic_cdk::spawn(async move {
+                bridge.flush().await;
+            });
+        })
+    }
+}
+
+impl Subsystem for EventBridge {
+    fn name(&self) -> &'static str {
+        "event bridge"
+    }
+
+    fn start(&self, interval: Duration) -> Option<ic_cdk_timers::TimerId> {
+        Some(EventBridge::start(self, interval))
+    }
+
+    fn persist(&self) {
+        let queues = self.queues.lock();
+        for (id, delivery) in &queues.pending {
+            self.persist(false, *id, Some(delivery));
+        }
+        for (id, delivery) in &queues.dead {
+            self.persist(true, *id, Some(delivery));
+        }
+        if let Some((pending_store, _)) = &self.store {
+            pending_store.insert(NEXT_ID_KEY, &queues.next_id);
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.flushing.is_busy()
+    }
+}
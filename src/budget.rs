@@ -0,0 +1,166 @@
+//! Cycle budgets of planned outcall workflows.
+//!
+//! Large jobs, e.g. indexing the logs of a contract over months of blocks, may cost more cycles than a
+//! canister should spend without approval. An [`OutcallPlan`] lists the calls a job intends to make and
+//! prices them with the IC pricing model before anything is sent, so that governance-controlled canisters
+//! can pre-approve the spend:
+//!
+//! ```ignore
+//! let budget = OutcallPlan::new(RPC_URL)
+//!     .step(PlannedCall::new("eth_blockNumber", vec![]))
+//!     .step(PlannedCall::new("eth_getLogs", vec![filter]).with_response_bytes(1_500_000).repeated(200))
+//!     .estimate();
+//! if !budget.fits(ic_cdk::api::canister_balance128()) {
+//!     return Err("not enough cycles for the job".into());
+//! }
+//! ```
+//!
+//! The estimates follow [`ICHttpClient::estimate_cycles`], with the transform of
+//! [`CallOptions::default_for`](crate::transports::ic_http_client::CallOptions::default_for).
+
+use crate::{
+    helpers,
+    transforms::presets,
+    transports::{
+        ic_http_client::{json_headers, DEFAULT_SUBNET_SIZE},
+        ICHttpClient,
+    },
+};
+use candid::CandidType;
+use jsonrpc_core::types::{Request, Value};
+use serde::{Deserialize, Serialize};
+
+/// Calls of a method with the same parameters and expected response size, made a number of times.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedCall {
+    method: String,
+    params: Vec<Value>,
+    response_bytes: u64,
+    count: u64,
+}
+
+impl PlannedCall {
+    /// Plan a call of `method` with `params`, whose response is limited to the size of the method
+    /// [preset](crate::transforms::presets).
+    pub fn new(method: &str, params: Vec<Value>) -> Self {
+        PlannedCall {
+            method: method.to_string(),
+            params,
+            response_bytes: presets::for_method(method)
+                .map(|preset| preset.max_response_bytes)
+                .unwrap_or(presets::DEFAULT_MAX_RESPONSE_BYTES),
+            count: 1,
+        }
+    }
+
+    /// Limit the response to `bytes`, the size expected for the call.
+    pub fn with_response_bytes(mut self, bytes: u64) -> Self {
+        self.response_bytes = bytes;
+        self
+    }
+
+    /// Make the call `count` times, e.g. once per page of a paginated query.
+    pub fn repeated(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+}
+
+/// Estimated cost of a step of a plan.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StepBudget {
+    /// JSON-RPC method
+    pub method: String,
+    /// Number of outcalls
+    pub calls: u64,
+    /// Request size of each outcall, in bytes
+    pub request_bytes: u64,
+    /// Response size limit of each outcall, in bytes
+    pub response_bytes: u64,
+    /// Cycles of each outcall
+    pub cycles_per_call: u128,
+    /// Cycles of all the outcalls of the step
+    pub cycles: u128,
+}
+
+/// Estimated cost of a plan.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// Cycles of all the steps
+    pub total_cycles: u128,
+    /// Number of outcalls of all the steps
+    pub total_calls: u64,
+    /// Cost of each step, in the order of the plan
+    pub steps: Vec<StepBudget>,
+}
+
+impl Budget {
+    /// Whether `cycles` cover the whole plan.
+    pub fn fits(&self, cycles: u128) -> bool {
+        self.total_cycles <= cycles
+    }
+}
+
+/// Sequence of calls to an RPC endpoint, priced before they are made.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutcallPlan {
+    url: String,
+    subnet_size: u32,
+    steps: Vec<PlannedCall>,
+}
+
+impl OutcallPlan {
+    /// Plan calls to the endpoint at `url` from a subnet of [`DEFAULT_SUBNET_SIZE`] nodes.
+    pub fn new(url: &str) -> Self {
+        OutcallPlan {
+            url: url.to_string(),
+            subnet_size: DEFAULT_SUBNET_SIZE,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Price the outcalls for a subnet of `subnet_size` nodes.
+    pub fn with_subnet_size(mut self, subnet_size: u32) -> Self {
+        self.subnet_size = subnet_size;
+        self
+    }
+
+    /// Add `call` after the steps planned so far.
+    pub fn step(mut self, call: PlannedCall) -> Self {
+        self.steps.push(call);
+        self
+    }
+
+    /// Planned steps.
+    pub fn steps(&self) -> &[PlannedCall] {
+        &self.steps
+    }
+
+    /// Total and per-step cost of the plan.
+    pub fn estimate(&self) -> Budget {
+        let headers: usize = json_headers()
+            .iter()
+            .map(|header| header.name.len() + header.value.len())
+            .sum();
+        let mut budget = Budget::default();
+        for call in &self.steps {
+            let request = Request::Single(helpers::build_request(0, &call.method, call.params.clone()));
+            let body = helpers::encode_request(&request);
+            let transform = presets::TRANSFORM_METHOD.len() + call.method.len();
+            let request_bytes = (self.url.len() + headers + body.len() + transform) as u64;
+            let cycles_per_call = ICHttpClient::estimate_cycles(request_bytes, call.response_bytes, self.subnet_size);
+            let cycles = cycles_per_call.saturating_mul(call.count as u128);
+            budget.total_cycles = budget.total_cycles.saturating_add(cycles);
+            budget.total_calls = budget.total_calls.saturating_add(call.count);
+            budget.steps.push(StepBudget {
+                method: call.method.clone(),
+                calls: call.count,
+                request_bytes,
+                response_bytes: call.response_bytes,
+                cycles_per_call,
+                cycles,
+            });
+        }
+        budget
+    }
+}
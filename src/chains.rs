@@ -0,0 +1,186 @@
+//! Registry of common EVM networks.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A known EVM network.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Chain {
+    /// Ethereum mainnet
+    Mainnet,
+    /// Ethereum Sepolia testnet
+    Sepolia,
+    /// Arbitrum One
+    Arbitrum,
+    /// OP Mainnet
+    Optimism,
+    /// Base
+    Base,
+    /// Polygon PoS
+    Polygon,
+    /// BNB Smart Chain
+    Bsc,
+}
+
+/// Metadata of a [`Chain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainInfo {
+    /// EIP-155 chain id
+    pub chain_id: u64,
+    /// Human readable name
+    pub name: &'static str,
+    /// EIP-3770 short name, prefixing chain-specific addresses
+    pub short_name: &'static str,
+    /// Symbol of the native currency
+    pub currency: &'static str,
+    /// Whether the network prices transactions with an EIP-1559 base fee
+    pub eip1559: bool,
+    /// Whether the network is a testnet
+    pub testnet: bool,
+    /// Public JSON-RPC endpoints, rate limited and meant for development
+    pub rpc_urls: &'static [&'static str],
+}
+
+const MAINNET: ChainInfo = ChainInfo {
+    chain_id: 1,
+    name: "Ethereum",
+    short_name: "eth",
+    currency: "ETH",
+    eip1559: true,
+    testnet: false,
+    rpc_urls: &["https://ethereum-rpc.publicnode.com", "https://cloudflare-eth.com"],
+};
+
+const SEPOLIA: ChainInfo = ChainInfo {
+    chain_id: 11_155_111,
+    name: "Sepolia",
+    short_name: "sep",
+    currency: "ETH",
+    eip1559: true,
+    testnet: true,
+    rpc_urls: &["https://ethereum-sepolia-rpc.publicnode.com", "https://rpc.sepolia.org"],
+};
+
+const ARBITRUM: ChainInfo = ChainInfo {
+    chain_id: 42_161,
+    name: "Arbitrum One",
+    short_name: "arb1",
+    currency: "ETH",
+    eip1559: true,
+    testnet: false,
+    rpc_urls: &[
+        "https://arb1.arbitrum.io/rpc",
+        "https://arbitrum-one-rpc.publicnode.com",
+    ],
+};
+
+const OPTIMISM: ChainInfo = ChainInfo {
+    chain_id: 10,
+    name: "OP Mainnet",
+    short_name: "oeth",
+    currency: "ETH",
+    eip1559: true,
+    testnet: false,
+    rpc_urls: &["https://mainnet.optimism.io", "https://optimism-rpc.publicnode.com"],
+};
+
+const BASE: ChainInfo = ChainInfo {
+    chain_id: 8_453,
+    name: "Base",
+    short_name: "base",
+    currency: "ETH",
+    eip1559: true,
+    testnet: false,
+    rpc_urls: &["https://mainnet.base.org", "https://base-rpc.publicnode.com"],
+};
+
+const POLYGON: ChainInfo = ChainInfo {
+    chain_id: 137,
+    name: "Polygon",
+    short_name: "pol",
+    currency: "POL",
+    eip1559: true,
+    testnet: false,
+    rpc_urls: &["https://polygon-rpc.com", "https://polygon-bor-rpc.publicnode.com"],
+};
+
+const BSC: ChainInfo = ChainInfo {
+    chain_id: 56,
+    name: "BNB Smart Chain",
+    short_name: "bnb",
+    currency: "BNB",
+    eip1559: false,
+    testnet: false,
+    rpc_urls: &["https://bsc-dataseed.bnbchain.org", "https://bsc-rpc.publicnode.com"],
+};
+
+impl Chain {
+    /// Every known network.
+    pub const ALL: [Chain; 7] = [
+        Chain::Mainnet,
+        Chain::Sepolia,
+        Chain::Arbitrum,
+        Chain::Optimism,
+        Chain::Base,
+        Chain::Polygon,
+        Chain::Bsc,
+    ];
+
+    /// Metadata of the network.
+    pub const fn info(self) -> &'static ChainInfo {
+        match self {
+            Chain::Mainnet => &MAINNET,
+            Chain::Sepolia => &SEPOLIA,
+            Chain::Arbitrum => &ARBITRUM,
+            Chain::Optimism => &OPTIMISM,
+            Chain::Base => &BASE,
+            Chain::Polygon => &POLYGON,
+            Chain::Bsc => &BSC,
+        }
+    }
+
+    /// The known network with EIP-155 chain id `chain_id`.
+    pub fn from_id(chain_id: u64) -> Option<Chain> {
+        Self::ALL.iter().copied().find(|chain| chain.id() == chain_id)
+    }
+
+    /// The known network with EIP-3770 short name `short_name`, e.g. `oeth`.
+    pub fn from_short_name(short_name: &str) -> Option<Chain> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|chain| chain.short_name().eq_ignore_ascii_case(short_name))
+    }
+
+    /// EIP-155 chain id.
+    pub const fn id(self) -> u64 {
+        self.info().chain_id
+    }
+
+    /// EIP-3770 short name.
+    pub const fn short_name(self) -> &'static str {
+        self.info().short_name
+    }
+
+    /// Whether the network prices transactions with an EIP-1559 base fee.
+    pub const fn supports_eip1559(self) -> bool {
+        self.info().eip1559
+    }
+
+    /// Public JSON-RPC endpoints of the network.
+    pub const fn rpc_urls(self) -> &'static [&'static str] {
+        self.info().rpc_urls
+    }
+
+    /// First of the public JSON-RPC endpoints.
+    pub const fn default_rpc_url(self) -> &'static str {
+        self.info().rpc_urls[0]
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.info().name)
+    }
+}
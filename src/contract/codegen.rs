@@ -0,0 +1,384 @@
+//! Generation of typed contract bindings from an ABI, at build time.
+//!
+//! [`Bindings`] turns a JSON ABI into Rust source with a struct wrapping [`Contract`](super::Contract),
+//! one method per function and a struct per event, so that call parameters and return values are
+//! checked by the compiler instead of going through `query("balanceOf", ...)` and friends. The source is
+//! written from a build script and included into the crate with [`include_contract!`](crate::include_contract):
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     let out_dir = std::env::var("OUT_DIR").unwrap();
+//!     ic_web3_rs::contract::codegen::Bindings::new("Erc20", std::fs::read("abi/erc20.json").unwrap())
+//!         .write(format!("{}/erc20.rs", out_dir))
+//!         .unwrap();
+//!     println!("cargo:rerun-if-changed=abi/erc20.json");
+//! }
+//!
+//! // src/lib.rs
+//! ic_web3_rs::include_contract!("erc20");
+//!
+//! let token = erc20::Erc20::new(web3.eth(), address);
+//! let balance: U256 = token.balance_of(owner, Options::default(), None).await?;
+//! let tx_hash = token.transfer(to, amount, Options::default(), &signer, chain_id).await?;
+//! let transfers = token.transfer_events()?;
+//! ```
+//!
+//! View and pure functions are queried with `eth_call`, the other functions are sent as transactions
+//! signed with a [`Signer`](crate::signing::Signer). Functions are called by their full signature, so that
+//! overloads are told apart: the first overload of a function gets a method named after the function, the
+//! others after the function and their parameters, e.g. `safe_transfer_from_with_from_and_to_and_token_id_and_data`.
+
+use ethabi::{Contract as Abi, Function, ParamType, StateMutability};
+use std::{fmt::Write, io, path::Path};
+
+/// Keywords escaped as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct", "trait",
+    "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro", "override",
+    "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Keywords which cannot be raw identifiers, suffixed with `_` instead.
+const PATH_KEYWORDS: &[&str] = &["crate", "self", "super"];
+
+/// Methods of the generated struct that functions must not shadow.
+const RESERVED_METHODS: &[&str] = &["new", "contract", "address"];
+
+/// Generator of the typed bindings of a contract.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    name: String,
+    abi: Vec<u8>,
+    crate_path: String,
+}
+
+impl Bindings {
+    /// Bindings of the contract `name` (in CamelCase) described by the JSON `abi`.
+    pub fn new(name: impl Into<String>, abi: impl Into<Vec<u8>>) -> Self {
+        Bindings {
+            name: name.into(),
+            abi: abi.into(),
+            crate_path: "ic_web3_rs".into(),
+        }
+    }
+
+    /// Refer to this crate as `path` in the generated code, when it is renamed in `Cargo.toml`.
+    pub fn with_crate_path(mut self, path: impl Into<String>) -> Self {
+        self.crate_path = path.into();
+        self
+    }
+
+    /// Generate the bindings: a module named after the contract in snake case holding the contract
+    /// struct and the event structs.
+    pub fn generate(&self) -> ethabi::Result<String> {
+        let abi = Abi::load(&self.abi[..])?;
+        let krate = &self.crate_path;
+        let name = &self.name;
+        let abi_json = String::from_utf8_lossy(&self.abi);
+        let hashes = "#".repeat(raw_string_hashes(&abi_json));
+
+        let mut out = String::new();
+        let _ = writeln!(out, "/// Typed bindings of the `{}` contract.", name);
+        let _ = writeln!(out, "#[allow(dead_code, missing_docs, clippy::all)]");
+        let _ = writeln!(out, "pub mod {} {{", snake_case(name));
+        let _ = writeln!(out, "    use {}::{{", krate);
+        let _ = writeln!(out, "        api::Eth,");
+        let _ = writeln!(
+            out,
+            "        contract::{{tokens::Tokenizable, Contract, DecodedEvent, Error, EventStream, FromEvent, Options, Result}},"
+        );
+        let _ = writeln!(out, "        ethabi::Token,");
+        let _ = writeln!(out, "        signing::Signer,");
+        let _ = writeln!(out, "        types::{{Address, BlockId, H256, U256}},");
+        let _ = writeln!(out, "        Transport,");
+        let _ = writeln!(out, "    }};");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "    /// JSON ABI of the contract.");
+        let _ = writeln!(
+            out,
+            "    pub const ABI: &str = r{}\"{}\"{};",
+            hashes,
+            abi_json.trim(),
+            hashes
+        );
+        let _ = writeln!(out);
+        let _ = writeln!(out, "    /// Typed wrapper of the `{}` contract.", name);
+        let _ = writeln!(out, "    #[derive(Debug, Clone)]");
+        let _ = writeln!(out, "    pub struct {}<T: Transport> {{", name);
+        let _ = writeln!(out, "        contract: Contract<T>,");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "    impl<T: Transport> {}<T> {{", name);
+        let _ = writeln!(out, "        /// Bind to the contract deployed at `address`.");
+        let _ = writeln!(out, "        pub fn new(eth: Eth<T>, address: Address) -> Self {{");
+        let _ = writeln!(
+            out,
+            "            let contract = Contract::from_json(eth, address, ABI.as_bytes())"
+        );
+        let _ = writeln!(
+            out,
+            "                .expect(\"the ABI was parsed when generating the bindings\");"
+        );
+        let _ = writeln!(out, "            {} {{ contract }}", name);
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "        /// Underlying untyped contract.");
+        let _ = writeln!(out, "        pub fn contract(&self) -> &Contract<T> {{");
+        let _ = writeln!(out, "            &self.contract");
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "        /// Address of the contract.");
+        let _ = writeln!(out, "        pub fn address(&self) -> Address {{");
+        let _ = writeln!(out, "            self.contract.address()");
+        let _ = writeln!(out, "        }}");
+        for functions in abi.functions.values() {
+            for (index, function) in functions.iter().enumerate() {
+                write_function(&mut out, function, index, krate);
+            }
+        }
+        for events in abi.events.values() {
+            if let Some(event) = events.first() {
+                let method = method_name(&format!("{}_events", snake_case(&event.name)));
+                let _ = writeln!(out);
+                let _ = writeln!(out, "        /// Stream of the `{}` events.", event.name);
+                let _ = writeln!(
+                    out,
+                    "        pub fn {}(&self) -> Result<EventStream<T, {}>> {{",
+                    method,
+                    camel_case(&event.name)
+                );
+                let _ = writeln!(out, "            self.contract.event_stream({:?})", event.name);
+                let _ = writeln!(out, "        }}");
+            }
+        }
+        let _ = writeln!(out, "    }}");
+        for events in abi.events.values() {
+            if let Some(event) = events.first() {
+                let fields: Vec<(String, String)> = event
+                    .inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, param)| (param_name(&param.name, index), rust_type(&param.kind)))
+                    .collect();
+                let struct_name = camel_case(&event.name);
+                let _ = writeln!(out);
+                let _ = writeln!(out, "    /// `{}` event.", event.name);
+                let _ = writeln!(out, "    #[derive(Debug, Clone, PartialEq)]");
+                let _ = writeln!(out, "    pub struct {} {{", struct_name);
+                for (field, kind) in &fields {
+                    let _ = writeln!(out, "        pub {}: {},", field, kind);
+                }
+                let _ = writeln!(out, "    }}");
+                let _ = writeln!(out);
+                let _ = writeln!(out, "    impl FromEvent for {} {{", struct_name);
+                let _ = writeln!(out, "        fn from_event(event: DecodedEvent) -> Result<Self> {{");
+                let _ = writeln!(
+                    out,
+                    "            let mut params = event.params.into_iter().map(|(_, value)| value);"
+                );
+                let _ = writeln!(out, "            let mut next = || {{");
+                let _ = writeln!(out, "                params.next().ok_or_else(|| {{");
+                let _ = writeln!(
+                    out,
+                    "                    Error::InvalidOutputType(\"missing parameter of the {} event\".into())",
+                    event.name
+                );
+                let _ = writeln!(out, "                }})");
+                let _ = writeln!(out, "            }};");
+                let _ = writeln!(out, "            Ok({} {{", struct_name);
+                for (field, _) in &fields {
+                    let _ = writeln!(out, "                {}: Tokenizable::from_token(next()?)?,", field);
+                }
+                let _ = writeln!(out, "            }})");
+                let _ = writeln!(out, "        }}");
+                let _ = writeln!(out, "    }}");
+            }
+        }
+        let _ = writeln!(out, "}}");
+        Ok(out)
+    }
+
+    /// Generate the bindings into the file at `path`, typically under `OUT_DIR`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let source = self
+            .generate()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        std::fs::write(path, source)
+    }
+}
+
+fn write_function(out: &mut String, function: &Function, overload: usize, krate: &str) {
+    let params: Vec<(String, String)> = function
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(index, param)| (param_name(&param.name, index), rust_type(&param.kind)))
+        .collect();
+    let args: String = params
+        .iter()
+        .map(|(name, kind)| format!("{}: {}, ", name, kind))
+        .collect();
+    let kinds: Vec<String> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+    let signature = format!("{}({})", function.name, kinds.join(","));
+    let tokens: Vec<String> = params
+        .iter()
+        .map(|(name, _)| format!("{}.into_token()", name))
+        .collect();
+    let method = match overload {
+        0 => method_name(&snake_case(&function.name)),
+        _ if params.is_empty() => format!("{}_{}", snake_case(&function.name), overload),
+        _ => {
+            let names: Vec<&str> = params.iter().map(|(name, _)| name.trim_start_matches("r#")).collect();
+            format!("{}_with_{}", snake_case(&function.name), names.join("_and_"))
+        }
+    };
+    #[allow(deprecated)]
+    let is_view = matches!(function.state_mutability, StateMutability::View | StateMutability::Pure)
+        || function.constant == Some(true);
+
+    let _ = writeln!(out);
+    if is_view {
+        let output = match function.outputs.len() {
+            0 => "()".to_string(),
+            1 => rust_type(&function.outputs[0].kind),
+            _ => {
+                let kinds: Vec<String> = function.outputs.iter().map(|param| rust_type(&param.kind)).collect();
+                format!("({},)", kinds.join(", "))
+            }
+        };
+        let _ = writeln!(out, "        /// Query `{}`.", signature);
+        let _ = writeln!(
+            out,
+            "        pub async fn {}(&self, {}options: Options, block: Option<BlockId>) -> Result<{}> {{",
+            method, args, output
+        );
+        let _ = writeln!(out, "            let params: &[Token] = &[{}];", tokens.join(", "));
+        let _ = writeln!(
+            out,
+            "            self.contract.query({:?}, params, None, options, block).await",
+            signature
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "        /// Send a `{}` transaction signed by `signer`.",
+            signature
+        );
+        let _ = writeln!(
+            out,
+            "        pub async fn {}<S: Signer>(&self, {}options: Options, signer: S, chain_id: u64) -> {}::Result<H256> {{",
+            method, args, krate
+        );
+        let _ = writeln!(out, "            let params: &[Token] = &[{}];", tokens.join(", "));
+        let _ = writeln!(
+            out,
+            "            self.contract.signed_call_with({:?}, params, options, signer, chain_id).await",
+            signature
+        );
+    }
+    let _ = writeln!(out, "        }}");
+}
+
+/// Rust type of ABI values of type `kind`, falling back to raw tokens for types without a typed
+/// conversion.
+fn rust_type(kind: &ParamType) -> String {
+    match kind {
+        ParamType::Address => "Address".into(),
+        ParamType::Bool => "bool".into(),
+        ParamType::String => "String".into(),
+        ParamType::Bytes => "Vec<u8>".into(),
+        ParamType::Uint(_) => "U256".into(),
+        ParamType::Int(bits) if *bits <= 8 => "i8".into(),
+        ParamType::Int(bits) if *bits <= 16 => "i16".into(),
+        ParamType::Int(bits) if *bits <= 32 => "i32".into(),
+        ParamType::Int(bits) if *bits <= 64 => "i64".into(),
+        ParamType::Int(bits) if *bits <= 128 => "i128".into(),
+        ParamType::FixedBytes(32) => "H256".into(),
+        ParamType::FixedBytes(len) if has_fixed_impl(*len) => format!("[u8; {}]", len),
+        ParamType::Array(inner) => format!("Vec<{}>", rust_type(inner)),
+        ParamType::FixedArray(inner, len) if has_fixed_impl(*len) => format!("[{}; {}]", rust_type(inner), len),
+        ParamType::Tuple(fields) if (1..=16).contains(&fields.len()) => {
+            let fields: Vec<String> = fields.iter().map(rust_type).collect();
+            format!("({},)", fields.join(", "))
+        }
+        _ => "Token".into(),
+    }
+}
+
+/// Whether the fixed size arrays of `len` elements are `Tokenizable`.
+fn has_fixed_impl(len: usize) -> bool {
+    (1..=16).contains(&len) || [32, 64, 128, 256, 512, 1024].contains(&len)
+}
+
+/// Smallest number of `#` delimiting a raw string literal holding `text`.
+fn raw_string_hashes(text: &str) -> usize {
+    (1..)
+        .find(|count| !text.contains(&format!("\"{}", "#".repeat(*count))))
+        .unwrap_or(1)
+}
+
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous != '_' && (previous.is_lowercase() || previous.is_ascii_digit() || next_is_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn escape(name: String) -> String {
+    if PATH_KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+fn method_name(name: &str) -> String {
+    if RESERVED_METHODS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        escape(name.to_string())
+    }
+}
+
+fn param_name(name: &str, index: usize) -> String {
+    let name = snake_case(name.trim_start_matches('_'));
+    if name.is_empty() {
+        format!("arg{}", index)
+    } else {
+        escape(name)
+    }
+}
+
+/// Include the bindings generated by [`Bindings::write`] into `$OUT_DIR/<name>.rs` from a build script.
+#[macro_export]
+macro_rules! include_contract {
+    ($name:literal) => {
+        include!(concat!(env!("OUT_DIR"), "/", $name, ".rs"));
+    };
+}
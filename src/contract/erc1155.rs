@@ -0,0 +1,122 @@
+//! Typed ERC-1155 (multi token) contract wrapper.
+
+use crate::{
+    api::Eth,
+    contract::{tokens::Tokenize, Contract, Options, Result},
+    ic::KeyInfo,
+    types::{Address, H256, U256},
+    Transport,
+};
+
+const ERC1155_ABI: &[u8] = include_bytes!("res/erc1155.json");
+
+/// ERC-1155 contract interface
+#[derive(Debug, Clone)]
+pub struct Erc1155<T: Transport> {
+    contract: Contract<T>,
+}
+
+impl<T: Transport> Erc1155<T> {
+    /// Creates new ERC-1155 interface for the token deployed at `address`.
+    pub fn new(eth: Eth<T>, address: Address) -> Self {
+        let contract = Contract::from_json(eth, address, ERC1155_ABI).expect("ERC-1155 ABI is valid; qed");
+        Erc1155 { contract }
+    }
+
+    /// Underlying untyped contract interface.
+    pub fn contract(&self) -> &Contract<T> {
+        &self.contract
+    }
+
+    /// Returns contract address
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    /// Amount of `id` tokens owned by `account`.
+    pub async fn balance_of(&self, account: Address, id: U256, options: Options) -> Result<U256> {
+        self.contract
+            .query("balanceOf", (account, id), None, options, None)
+            .await
+    }
+
+    /// Balances of several (account, id) pairs in a single call.
+    pub async fn balance_of_batch(
+        &self,
+        accounts: Vec<Address>,
+        ids: Vec<U256>,
+        options: Options,
+    ) -> Result<Vec<U256>> {
+        self.contract
+            .query("balanceOfBatch", (accounts, ids), None, options, None)
+            .await
+    }
+
+    /// Metadata URI template of the `id` token.
+    pub async fn uri(&self, id: U256, options: Options) -> Result<String> {
+        self.contract.query("uri", id, None, options, None).await
+    }
+
+    /// Whether `operator` may manage all tokens of `account`.
+    pub async fn is_approved_for_all(&self, account: Address, operator: Address, options: Options) -> Result<bool> {
+        self.contract
+            .query("isApprovedForAll", (account, operator), None, options, None)
+            .await
+    }
+
+    /// Sign and submit a `safeTransferFrom(from, to, id, amount, data)` transaction.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn safe_transfer_from(
+        &self,
+        from: Address,
+        to: Address,
+        id: U256,
+        amount: U256,
+        data: Vec<u8>,
+        options: Options,
+        sender: String,
+        key_info: KeyInfo,
+        chain_id: u64,
+    ) -> crate::Result<H256> {
+        let params = (from, to, id, amount, data).into_tokens();
+        self.contract
+            .signed_call("safeTransferFrom", &params, options, sender, key_info, chain_id)
+            .await
+    }
+
+    /// Sign and submit a `safeBatchTransferFrom(from, to, ids, amounts, data)` transaction.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn safe_batch_transfer_from(
+        &self,
+        from: Address,
+        to: Address,
+        ids: Vec<U256>,
+        amounts: Vec<U256>,
+        data: Vec<u8>,
+        options: Options,
+        sender: String,
+        key_info: KeyInfo,
+        chain_id: u64,
+    ) -> crate::Result<H256> {
+        let params = (from, to, ids, amounts, data).into_tokens();
+        self.contract
+            .signed_call("safeBatchTransferFrom", &params, options, sender, key_info, chain_id)
+            .await
+    }
+
+    /// Sign and submit a `setApprovalForAll(operator, approved)` transaction.
+    pub async fn set_approval_for_all(
+        &self,
+        operator: Address,
+        approved: bool,
+        options: Options,
+        sender: String,
+        key_info: KeyInfo,
+        chain_id: u64,
+    ) -> crate::Result<H256> {
+        let params = (operator, approved).into_tokens();
+        self.contract
+            .signed_call("setApprovalForAll", &params, options, sender, key_info, chain_id)
+            .await
+    }
+}
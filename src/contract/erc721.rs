@@ -0,0 +1,112 @@
+//! Typed ERC-721 (non-fungible token) contract wrapper.
+
+use crate::{
+    api::Eth,
+    contract::{tokens::Tokenize, Contract, Options, Result},
+    ic::KeyInfo,
+    types::{Address, H256, U256},
+    Transport,
+};
+
+const ERC721_ABI: &[u8] = include_bytes!("res/erc721.json");
+
+/// ERC-721 contract interface
+#[derive(Debug, Clone)]
+pub struct Erc721<T: Transport> {
+    contract: Contract<T>,
+}
+
+impl<T: Transport> Erc721<T> {
+    /// Creates new ERC-721 interface for the token deployed at `address`.
+    pub fn new(eth: Eth<T>, address: Address) -> Self {
+        let contract = Contract::from_json(eth, address, ERC721_ABI).expect("ERC-721 ABI is valid; qed");
+        Erc721 { contract }
+    }
+
+    /// Underlying untyped contract interface.
+    pub fn contract(&self) -> &Contract<T> {
+        &self.contract
+    }
+
+    /// Returns contract address
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    /// Number of tokens owned by `owner`.
+    pub async fn balance_of(&self, owner: Address, options: Options) -> Result<U256> {
+        self.contract.query("balanceOf", owner, None, options, None).await
+    }
+
+    /// Owner of the `token_id` token.
+    pub async fn owner_of(&self, token_id: U256, options: Options) -> Result<Address> {
+        self.contract.query("ownerOf", token_id, None, options, None).await
+    }
+
+    /// Metadata URI of the `token_id` token.
+    pub async fn token_uri(&self, token_id: U256, options: Options) -> Result<String> {
+        self.contract.query("tokenURI", token_id, None, options, None).await
+    }
+
+    /// Account approved to transfer the `token_id` token.
+    pub async fn get_approved(&self, token_id: U256, options: Options) -> Result<Address> {
+        self.contract.query("getApproved", token_id, None, options, None).await
+    }
+
+    /// Whether `operator` may manage all tokens of `owner`.
+    pub async fn is_approved_for_all(&self, owner: Address, operator: Address, options: Options) -> Result<bool> {
+        self.contract
+            .query("isApprovedForAll", (owner, operator), None, options, None)
+            .await
+    }
+
+    /// Sign and submit a `safeTransferFrom(from, to, tokenId)` transaction.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn safe_transfer_from(
+        &self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        options: Options,
+        sender: String,
+        key_info: KeyInfo,
+        chain_id: u64,
+    ) -> crate::Result<H256> {
+        let params = (from, to, token_id).into_tokens();
+        self.contract
+            .signed_call("safeTransferFrom", &params, options, sender, key_info, chain_id)
+            .await
+    }
+
+    /// Sign and submit an `approve(to, tokenId)` transaction.
+    pub async fn approve(
+        &self,
+        to: Address,
+        token_id: U256,
+        options: Options,
+        sender: String,
+        key_info: KeyInfo,
+        chain_id: u64,
+    ) -> crate::Result<H256> {
+        let params = (to, token_id).into_tokens();
+        self.contract
+            .signed_call("approve", &params, options, sender, key_info, chain_id)
+            .await
+    }
+
+    /// Sign and submit a `setApprovalForAll(operator, approved)` transaction.
+    pub async fn set_approval_for_all(
+        &self,
+        operator: Address,
+        approved: bool,
+        options: Options,
+        sender: String,
+        key_info: KeyInfo,
+        chain_id: u64,
+    ) -> crate::Result<H256> {
+        let params = (operator, approved).into_tokens();
+        self.contract
+            .signed_call("setApprovalForAll", &params, options, sender, key_info, chain_id)
+            .await
+    }
+}
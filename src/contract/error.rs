@@ -1,6 +1,10 @@
 //! Contract call/query error.
 
-use crate::error::Error as ApiError;
+use crate::{
+    error::{Error as ApiError, ErrorCode, ErrorInfo},
+    revert::RevertReason,
+};
+use candid::CandidType;
 use derive_more::{Display, From};
 use ethabi::Error as EthError;
 
@@ -21,6 +25,19 @@ pub enum Error {
     //Deployment(crate::contract::deploy::Error),
     /// Contract does not support this interface.
     InterfaceUnsupported,
+    /// Inconsistent call options
+    #[display(fmt = "Invalid options: {}", _0)]
+    #[from(ignore)]
+    InvalidOptions(String),
+    /// The call reverted, with the reason decoded against the contract ABI
+    #[display(fmt = "Reverted: {}", reason)]
+    #[from(ignore)]
+    Revert {
+        /// Decoded revert reason
+        reason: Box<RevertReason>,
+        /// JSON-RPC error carrying the revert data
+        error: ApiError,
+    },
 }
 
 impl std::error::Error for Error {
@@ -31,10 +48,72 @@ impl std::error::Error for Error {
             Error::Api(ref e) => Some(e),
             //Error::Deployment(ref e) => Some(e),
             Error::InterfaceUnsupported => None,
+            Error::InvalidOptions(_) => None,
+            Error::Revert { ref error, .. } => Some(error),
         }
     }
 }
 
+impl Error {
+    /// Flattened kind of the error, API errors are reported with the code of the underlying error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::InvalidOutputType(_) => ErrorCode::InvalidOutputType,
+            Error::Abi(_) => ErrorCode::Abi,
+            Error::Api(e) => e.code(),
+            Error::InterfaceUnsupported => ErrorCode::InterfaceUnsupported,
+            Error::InvalidOptions(_) => ErrorCode::InvalidOptions,
+            Error::Revert { .. } => ErrorCode::Reverted,
+        }
+    }
+
+    /// Revert reason of a reverted call, `None` for other errors.
+    pub fn revert_reason(&self) -> Option<&RevertReason> {
+        match self {
+            Error::Revert { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Error> for ErrorInfo {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::Api(e) => e.into(),
+            Error::Revert { error, .. } => ErrorInfo {
+                code: err.code(),
+                message: err.to_string(),
+                ..error.into()
+            },
+            _ => ErrorInfo::new(err.code(), err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "candid-errors")]
+impl CandidType for Error {
+    fn _ty() -> candid::types::Type {
+        ErrorInfo::ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> std::result::Result<(), S::Error>
+    where
+        S: candid::types::Serializer,
+    {
+        ErrorInfo::from(self).idl_serialize(serializer)
+    }
+}
+
+#[cfg(feature = "candid-errors")]
+impl serde::Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ErrorInfo::from(self).serialize(serializer)
+    }
+}
+
 pub mod deploy {
     use crate::{error::Error as ApiError, types::H256};
     use derive_more::{Display, From};
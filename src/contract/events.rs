@@ -0,0 +1,299 @@
+//! Typed decoding and streaming of contract events.
+
+use crate::{
+    api::Eth,
+    contract::{
+        tokens::{Detokenize, Tokenizable},
+        Error, Result,
+    },
+    polling::{self, PollConfig},
+    transports::ic_http_client::CallOptions,
+    types::{Address, BlockId, BlockNumber, FilterBuilder, Log, Topic, H256, U64},
+    Transport,
+};
+use ethabi::Token;
+use futures::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// An event log decoded against the contract ABI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedEvent {
+    /// Event name
+    pub name: String,
+    /// Parameters in ABI order, indexed and non-indexed alike
+    pub params: Vec<(String, Token)>,
+    /// The raw log
+    pub log: Log,
+}
+
+impl DecodedEvent {
+    /// Decode `log` as an occurrence of `event`.
+    pub fn decode(event: &ethabi::Event, log: Log) -> Result<Self> {
+        let parsed = event.parse_log(ethabi::RawLog {
+            topics: log.topics.clone(),
+            data: log.data.0.clone(),
+        })?;
+        Ok(DecodedEvent {
+            name: event.name.clone(),
+            params: parsed
+                .params
+                .into_iter()
+                .map(|param| (param.name, param.value))
+                .collect(),
+            log,
+        })
+    }
+
+    /// Raw value of the parameter `name`.
+    pub fn token(&self, name: &str) -> Option<&Token> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Value of the parameter `name` converted to `T`.
+    pub fn get<T: Tokenizable>(&self, name: &str) -> Result<T> {
+        let token = self
+            .token(name)
+            .ok_or_else(|| Error::InvalidOutputType(format!("missing event parameter {}", name)))?;
+        T::from_token(token.clone())
+    }
+
+    /// Decode all parameters, in ABI order, into `R` (e.g. a tuple).
+    pub fn into_tokens<R: Detokenize>(self) -> Result<R> {
+        R::from_tokens(self.params.into_iter().map(|(_, value)| value).collect())
+    }
+}
+
+/// Conversion of a decoded event into a user type.
+///
+/// ```ignore
+/// struct Transfer { from: Address, to: Address, value: U256 }
+///
+/// impl FromEvent for Transfer {
+///     fn from_event(event: DecodedEvent) -> Result<Self> {
+///         Ok(Transfer { from: event.get("from")?, to: event.get("to")?, value: event.get("value")? })
+///     }
+/// }
+/// ```
+pub trait FromEvent: Sized {
+    /// Build `Self` from the decoded event.
+    fn from_event(event: DecodedEvent) -> Result<Self>;
+}
+
+impl FromEvent for DecodedEvent {
+    fn from_event(event: DecodedEvent) -> Result<Self> {
+        Ok(event)
+    }
+}
+
+impl FromEvent for Vec<Token> {
+    fn from_event(event: DecodedEvent) -> Result<Self> {
+        Ok(event.params.into_iter().map(|(_, value)| value).collect())
+    }
+}
+
+/// A typed event with the log it was decoded from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventLog<E> {
+    /// Decoded event
+    pub event: E,
+    /// Raw log, carrying the block and transaction the event was emitted in
+    pub log: Log,
+}
+
+/// Position of an event stream, persisted by the caller to resume after an upgrade.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCursor {
+    /// First block not scanned yet
+    pub next_block: Option<U64>,
+    /// Number and hash of the last scanned block, used to detect reorgs
+    pub last_block: Option<(U64, H256)>,
+}
+
+/// Stream of the occurrences of a single contract event.
+///
+/// Every poll scans the blocks between the cursor and the head (minus `confirmations`) in pages of at
+/// most `max_block_range` blocks. Before scanning, the hash of the last scanned block is compared with
+/// the one stored in the cursor: on mismatch the chain reorganized, and the stream rewinds by
+/// `reorg_depth` blocks, so the events of the rewound blocks are delivered again. Consumers should
+/// therefore be idempotent, e.g. by deduplicating on transaction hash and log index.
+#[derive(Debug, Clone)]
+pub struct EventStream<T: Transport, E> {
+    eth: Eth<T>,
+    address: Address,
+    event: ethabi::Event,
+    topics: Vec<(usize, Vec<Topic>)>,
+    options: CallOptions,
+    cursor: EventCursor,
+    max_block_range: u64,
+    confirmations: u64,
+    reorg_depth: u64,
+    caught_up: bool,
+    _event: PhantomData<E>,
+}
+
+impl<T: Transport, E: FromEvent> EventStream<T, E> {
+    pub(crate) fn new(eth: Eth<T>, address: Address, event: ethabi::Event, options: CallOptions) -> Self {
+        EventStream {
+            eth,
+            address,
+            event,
+            topics: vec![],
+            options,
+            cursor: EventCursor::default(),
+            max_block_range: 1_000,
+            confirmations: 0,
+            reorg_depth: 64,
+            caught_up: false,
+            _event: PhantomData,
+        }
+    }
+
+    /// Start scanning at block `number` instead of at the current head.
+    pub fn from_block(mut self, number: U64) -> Self {
+        self.cursor = EventCursor {
+            next_block: Some(number),
+            last_block: None,
+        };
+        self
+    }
+
+    /// Resume from a cursor returned by [`cursor`](Self::cursor).
+    pub fn with_cursor(mut self, cursor: EventCursor) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Only deliver events whose indexed parameter at `position` (1 to 3) is one of `values`.
+    pub fn topic<I>(mut self, position: usize, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Topic>,
+    {
+        assert!((1..4).contains(&position), "indexed parameters are at topics 1 to 3");
+        self.topics.retain(|(p, _)| *p != position);
+        self.topics
+            .push((position, values.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Set the maximum number of blocks queried by a single `eth_getLogs` call.
+    pub fn with_max_block_range(mut self, max_block_range: u64) -> Self {
+        self.max_block_range = max_block_range.max(1);
+        self
+    }
+
+    /// Only scan blocks with at least `confirmations` blocks on top of them.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Set the number of blocks rewound when a reorg is detected.
+    pub fn with_reorg_depth(mut self, reorg_depth: u64) -> Self {
+        self.reorg_depth = reorg_depth;
+        self
+    }
+
+    /// Current position of the stream.
+    pub fn cursor(&self) -> &EventCursor {
+        &self.cursor
+    }
+
+    /// Scan the next page of blocks and return the events found, in chain order.
+    ///
+    /// On error the cursor is kept, so the same blocks are scanned again on the next poll.
+    pub async fn poll(&mut self) -> Result<Vec<EventLog<E>>> {
+        let head = self.eth.block_number(self.options.clone()).await?;
+        self.caught_up = true;
+        if head.as_u64() < self.confirmations {
+            return Ok(vec![]);
+        }
+        let safe_head = head - self.confirmations;
+
+        let mut from = self.cursor.next_block.unwrap_or(safe_head);
+        if let Some((number, hash)) = self.cursor.last_block {
+            if self.block_hash(number).await? != Some(hash) {
+                from = number.saturating_sub(self.reorg_depth.into());
+            }
+        }
+        if from > safe_head {
+            return Ok(vec![]);
+        }
+        let to = std::cmp::min(safe_head, from + self.max_block_range - 1);
+
+        let mut filter = FilterBuilder::default()
+            .address(vec![self.address])
+            .from_block(BlockNumber::Number(from))
+            .to_block(BlockNumber::Number(to))
+            .topic(0, Some(Topic(self.event.signature())));
+        for (position, values) in &self.topics {
+            filter = filter.topic(*position, values.iter().copied());
+        }
+        let logs = self.eth.logs(filter.build(), self.options.clone()).await?;
+        let to_hash = self
+            .block_hash(to)
+            .await?
+            .ok_or_else(|| crate::Error::InvalidResponse(format!("block {} not found", to)))?;
+
+        let events = logs
+            .into_iter()
+            .filter(|log| !log.is_removed())
+            .map(|log| {
+                let event = E::from_event(DecodedEvent::decode(&self.event, log.clone())?)?;
+                Ok(EventLog { event, log })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.cursor = EventCursor {
+            next_block: Some(to + 1),
+            last_block: Some((to, to_hash)),
+        };
+        self.caught_up = to == safe_head;
+        Ok(events)
+    }
+
+    async fn block_hash(&self, number: U64) -> crate::Result<Option<H256>> {
+        let block = self
+            .eth
+            .block(BlockId::Number(BlockNumber::Number(number)), self.options.clone())
+            .await?;
+        Ok(block.and_then(|block| block.hash))
+    }
+
+    /// Turn into a `Stream` polling the chain according to `config`.
+    ///
+    /// `seed` makes the poll jitter differ between canisters, see [`PollConfig`]. The stream ends once
+    /// `config.max_attempts` polls were made or on the first error. Polls are not delayed while the
+    /// stream is catching up with the head.
+    pub fn into_stream(self, config: PollConfig, seed: Vec<u8>) -> impl Stream<Item = Result<EventLog<E>>> {
+        let state = (self, Vec::<EventLog<E>>::new().into_iter(), 0u32, false);
+        stream::unfold(state, move |(mut this, mut pending, mut attempt, mut failed)| {
+            let seed = seed.clone();
+            async move {
+                loop {
+                    if let Some(event) = pending.next() {
+                        return Some((Ok(event), (this, pending, attempt, failed)));
+                    }
+                    if failed || !config.can_retry(attempt) {
+                        return None;
+                    }
+                    if attempt > 0 && this.caught_up {
+                        polling::sleep(config.delay(attempt, &seed)).await;
+                    }
+                    attempt += 1;
+                    match this.poll().await {
+                        Ok(events) => pending = events.into_iter(),
+                        Err(err) => {
+                            failed = true;
+                            return Some((Err(err), (this, pending, attempt, failed)));
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
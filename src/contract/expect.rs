@@ -0,0 +1,180 @@
+//! Verification of the events emitted by a transaction.
+
+use crate::{
+    contract::{filter::encode_topic, tokens::Tokenizable, Contract, DecodedEvent},
+    types::{Address, TransactionReceipt, H256, U64},
+    Transport,
+};
+use ethabi::{EventParam, ParamType, Token};
+use ethereum_types::BloomInput;
+use std::fmt;
+
+/// Expected values of the arguments of an event, by parameter name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventMatcher {
+    args: Vec<(String, Token)>,
+}
+
+impl EventMatcher {
+    /// Match any occurrence of the event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match occurrences whose argument `name` is `value`.
+    ///
+    /// Indexed strings and bytes, of which logs only hold the hash, match the hash of `value`.
+    pub fn arg<V: Tokenizable>(mut self, name: &str, value: V) -> Self {
+        self.args.retain(|(arg, _)| arg != name);
+        self.args.push((name.into(), value.into_token()));
+        self
+    }
+}
+
+/// Reason why a receipt does not hold the expected event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventMismatch {
+    /// The ABI of the contract has no such event
+    UnknownEvent(String),
+    /// The event has no parameter with the name of an expected argument
+    UnknownArgument(String),
+    /// The transaction reverted, so it emitted no event
+    Reverted,
+    /// The logs bloom rules out the event, without decoding the logs
+    NotInBloom,
+    /// The contract emitted no occurrence of the event
+    NotEmitted,
+    /// The contract emitted the event, with other arguments
+    ArgumentMismatch {
+        /// Name of the first mismatching argument of the first occurrence
+        name: String,
+        /// Expected value
+        expected: Token,
+        /// Emitted value
+        actual: Token,
+        /// Number of occurrences of the event, none of which matches
+        occurrences: usize,
+    },
+}
+
+impl fmt::Display for EventMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventMismatch::UnknownEvent(event) => write!(f, "unknown event `{}`", event),
+            EventMismatch::UnknownArgument(name) => write!(f, "the event has no argument `{}`", name),
+            EventMismatch::Reverted => f.write_str("the transaction reverted"),
+            EventMismatch::NotInBloom => f.write_str("the event is not in the logs bloom"),
+            EventMismatch::NotEmitted => f.write_str("the event was not emitted"),
+            EventMismatch::ArgumentMismatch {
+                name,
+                expected,
+                actual,
+                occurrences,
+            } => write!(
+                f,
+                "argument `{}` is {} instead of {} ({} occurrences of the event)",
+                name, actual, expected, occurrences
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EventMismatch {}
+
+/// Find the occurrence of the event `event` of `contract` matching `matcher` in the logs of `receipt`.
+///
+/// The logs bloom is checked first for the contract address, the event signature and the expected
+/// indexed arguments, so that receipts which cannot hold the event are rejected without decoding their
+/// logs. Use it to assert the side effects of a transaction before updating the canister state.
+pub fn expect_event<T: Transport>(
+    receipt: &TransactionReceipt,
+    contract: &Contract<T>,
+    event: &str,
+    matcher: &EventMatcher,
+) -> Result<DecodedEvent, EventMismatch> {
+    let event = contract
+        .abi()
+        .event(event)
+        .map_err(|_| EventMismatch::UnknownEvent(event.into()))?;
+    for (name, _) in &matcher.args {
+        if !event.inputs.iter().any(|param| &param.name == name) {
+            return Err(EventMismatch::UnknownArgument(name.clone()));
+        }
+    }
+    if receipt.status == Some(U64::zero()) {
+        return Err(EventMismatch::Reverted);
+    }
+    if !in_bloom(receipt, contract.address(), event, matcher) {
+        return Err(EventMismatch::NotInBloom);
+    }
+
+    let mut occurrences = 0;
+    let mut first_mismatch = None;
+    for log in receipt.logs.iter().filter(|log| log.address == contract.address()) {
+        let decoded = match DecodedEvent::decode(event, log.clone()) {
+            Ok(decoded) => decoded,
+            Err(_) => continue,
+        };
+        occurrences += 1;
+        let mismatch = matcher.args.iter().find_map(|(name, expected)| {
+            let param = event.inputs.iter().find(|param| &param.name == name)?;
+            let actual = decoded.token(name)?;
+            if matches(param, expected, actual) {
+                None
+            } else {
+                Some((name.clone(), expected.clone(), actual.clone()))
+            }
+        });
+        match mismatch {
+            None => return Ok(decoded),
+            Some(mismatch) => {
+                first_mismatch.get_or_insert(mismatch);
+            }
+        }
+    }
+    match first_mismatch {
+        Some((name, expected, actual)) => Err(EventMismatch::ArgumentMismatch {
+            name,
+            expected,
+            actual,
+            occurrences,
+        }),
+        None => Err(EventMismatch::NotEmitted),
+    }
+}
+
+/// Whether the logs bloom of `receipt` may hold the event, `true` when the bloom is missing.
+fn in_bloom(receipt: &TransactionReceipt, address: Address, event: &ethabi::Event, matcher: &EventMatcher) -> bool {
+    let bloom = &receipt.logs_bloom;
+    if bloom.is_zero() {
+        return true;
+    }
+    let mut inputs = vec![address.as_bytes().to_vec()];
+    if !event.anonymous {
+        inputs.push(event.signature().as_bytes().to_vec());
+    }
+    for param in event.inputs.iter().filter(|param| param.indexed) {
+        let expected = matcher.args.iter().find(|(name, _)| name == &param.name);
+        if let Some(Ok(topic)) = expected.map(|(_, value)| encode_topic(&param.kind, value.clone())) {
+            inputs.push(topic.0.as_bytes().to_vec());
+        }
+    }
+    inputs.iter().all(|input| bloom.contains_input(BloomInput::Raw(input)))
+}
+
+/// Whether the emitted `actual` value of `param` is the `expected` one.
+fn matches(param: &EventParam, expected: &Token, actual: &Token) -> bool {
+    let hashed = matches!(
+        param.kind,
+        ParamType::String | ParamType::Bytes | ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_)
+    );
+    match (expected, actual) {
+        // Indexed dynamic values are only logged hashed.
+        (expected, Token::FixedBytes(hash)) if param.indexed && hashed => {
+            encode_topic(&param.kind, expected.clone()).is_ok_and(|topic| topic.0 == H256::from_slice(hash))
+        }
+        // Integers converted from Rust values carry their signedness, not the one of the parameter.
+        (Token::Uint(expected), Token::Int(actual)) | (Token::Int(expected), Token::Uint(actual)) => expected == actual,
+        (expected, actual) => expected == actual,
+    }
+}
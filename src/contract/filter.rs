@@ -0,0 +1,222 @@
+//! Log filters built from typed event arguments.
+
+use crate::{
+    contract::{tokens::Tokenizable, Error, Result},
+    types::{Address, BlockNumber, Filter, FilterBuilder, Topic, H256},
+};
+use ethabi::{ParamType, Token};
+
+/// Builder of a log filter matching the occurrences of an event with given indexed arguments.
+///
+/// Values are encoded into topics according to the type of the indexed parameter in the ABI: static
+/// values are left-padded (and sign-extended for negative integers), while strings, bytes, arrays and
+/// structs are hashed as the EVM does. `argN` refers to the N-th indexed parameter of the event, i.e.
+/// to topic N + 1 (topic N for anonymous events).
+///
+/// ```ignore
+/// // Transfer(address indexed from, address indexed to, uint256 indexed tokenId)
+/// let filter = contract
+///     .event_filter("Transfer")?
+///     .arg0(owner)
+///     .arg2(U256::from(1))
+///     .from_block(BlockNumber::Number(start.into()))
+///     .build()?;
+/// let logs = web3.eth().logs(filter, options).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventFilterBuilder {
+    event: ethabi::Event,
+    filter: FilterBuilder,
+    error: Option<String>,
+}
+
+impl EventFilterBuilder {
+    /// Filter the occurrences of `event`, with any arguments.
+    pub fn new(event: &ethabi::Event) -> Self {
+        let mut filter = FilterBuilder::default();
+        if !event.anonymous {
+            filter = filter.topic(0, Some(event.signature()));
+        }
+        EventFilterBuilder {
+            event: event.clone(),
+            filter,
+            error: None,
+        }
+    }
+
+    /// Only match logs whose first indexed argument is `value`.
+    pub fn arg0<V: Tokenizable>(self, value: V) -> Self {
+        self.arg(0, value)
+    }
+
+    /// Only match logs whose second indexed argument is `value`.
+    pub fn arg1<V: Tokenizable>(self, value: V) -> Self {
+        self.arg(1, value)
+    }
+
+    /// Only match logs whose third indexed argument is `value`.
+    pub fn arg2<V: Tokenizable>(self, value: V) -> Self {
+        self.arg(2, value)
+    }
+
+    /// Only match logs whose fourth indexed argument is `value`, for anonymous events.
+    pub fn arg3<V: Tokenizable>(self, value: V) -> Self {
+        self.arg(3, value)
+    }
+
+    /// Only match logs whose `index`-th indexed argument is `value`.
+    pub fn arg<V: Tokenizable>(self, index: usize, value: V) -> Self {
+        self.arg_any_of(index, Some(value))
+    }
+
+    /// Only match logs whose `index`-th indexed argument is one of `values`.
+    pub fn arg_any_of<I>(self, index: usize, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Tokenizable,
+    {
+        let param = self
+            .event
+            .inputs
+            .iter()
+            .filter(|param| param.indexed)
+            .nth(index)
+            .cloned();
+        match param {
+            Some(param) => self.set(index, &param.name, &param.kind, values),
+            None => {
+                let message = format!("event `{}` has no indexed argument {}", self.event.name, index);
+                self.fail(message)
+            }
+        }
+    }
+
+    /// Only match logs whose indexed argument called `name` is `value`.
+    pub fn named<V: Tokenizable>(self, name: &str, value: V) -> Self {
+        let param = self
+            .event
+            .inputs
+            .iter()
+            .filter(|param| param.indexed)
+            .enumerate()
+            .find(|(_, param)| param.name == name)
+            .map(|(index, param)| (index, param.kind.clone()));
+        match param {
+            Some((index, kind)) => self.set(index, name, &kind, Some(value)),
+            None => {
+                let message = format!("event `{}` has no indexed argument `{}`", self.event.name, name);
+                self.fail(message)
+            }
+        }
+    }
+
+    /// Only match logs emitted by one of `addresses`.
+    pub fn address(mut self, addresses: Vec<Address>) -> Self {
+        self.filter = self.filter.address(addresses);
+        self
+    }
+
+    /// Sets the first block scanned.
+    pub fn from_block(mut self, block: BlockNumber) -> Self {
+        self.filter = self.filter.from_block(block);
+        self
+    }
+
+    /// Sets the last block scanned.
+    pub fn to_block(mut self, block: BlockNumber) -> Self {
+        self.filter = self.filter.to_block(block);
+        self
+    }
+
+    /// Only match logs of the block with the given hash.
+    pub fn block_hash(mut self, hash: H256) -> Self {
+        self.filter = self.filter.block_hash(hash);
+        self
+    }
+
+    /// Returns the filter builder, failing if an argument does not match the ABI of the event.
+    pub fn into_filter_builder(self) -> Result<FilterBuilder> {
+        match self.error {
+            Some(error) => Err(Error::InvalidOptions(error)),
+            None => Ok(self.filter),
+        }
+    }
+
+    /// Returns the filter, failing if an argument does not match the ABI of the event.
+    pub fn build(self) -> Result<Filter> {
+        Ok(self.into_filter_builder()?.build())
+    }
+
+    fn set<I>(mut self, index: usize, name: &str, kind: &ParamType, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Tokenizable,
+    {
+        if self.error.is_some() {
+            return self;
+        }
+        let topics: std::result::Result<Vec<Topic>, String> = values
+            .into_iter()
+            .map(|value| encode_topic(kind, value.into_token()))
+            .collect();
+        match topics {
+            Ok(topics) => {
+                let position = if self.event.anonymous { index } else { index + 1 };
+                self.filter = self.filter.topic(position, topics);
+                self
+            }
+            Err(error) => {
+                let message = format!("argument `{}` of event `{}`: {}", name, self.event.name, error);
+                self.fail(message)
+            }
+        }
+    }
+
+    fn fail(mut self, error: String) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+}
+
+/// Topic of the value `token` of an indexed parameter of type `kind`.
+pub(crate) fn encode_topic(kind: &ParamType, token: Token) -> std::result::Result<Topic, String> {
+    // Integers converted from Rust values carry their signedness, not the one of the parameter.
+    let token = match (kind, token) {
+        (ParamType::Int(_), Token::Uint(value)) => Token::Int(value),
+        (ParamType::Uint(_), Token::Int(value)) => Token::Uint(value),
+        (_, token) => token,
+    };
+    if !token.type_check(kind) {
+        return Err(format!("expected a value of type `{}`, got {:?}", kind, token));
+    }
+    match token {
+        Token::String(value) => Ok(Topic::from_hashed(value.as_bytes())),
+        Token::Bytes(value) => Ok(Topic::from_hashed(&value)),
+        token @ (Token::Array(_) | Token::FixedArray(_) | Token::Tuple(_)) => {
+            let mut data = vec![];
+            encode_in_place(&token, &mut data);
+            Ok(Topic::from_hashed(&data))
+        }
+        token => Ok(Topic(H256::from_slice(&ethabi::encode(&[token])))),
+    }
+}
+
+/// Encoding of a complex indexed value before hashing: its elements padded to 32 bytes and
+/// concatenated, without offsets nor lengths.
+fn encode_in_place(token: &Token, out: &mut Vec<u8>) {
+    match token {
+        Token::String(value) => pad(value.as_bytes(), out),
+        Token::Bytes(value) => pad(value, out),
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+            for token in tokens {
+                encode_in_place(token, out);
+            }
+        }
+        token => out.extend(ethabi::encode(std::slice::from_ref(token))),
+    }
+}
+
+fn pad(data: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(data);
+    out.resize(out.len() + (32 - data.len() % 32) % 32, 0);
+}
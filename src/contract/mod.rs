@@ -5,19 +5,40 @@ use crate::{
     contract::tokens::{Detokenize, Tokenize},
     futures::Future,
     ic::KeyInfo,
+    nonce::NonceManager,
+    revert::{self, RevertReason},
     transports::ic_http_client::CallOptions,
     types::{
-        AccessList, Address, BlockId, Bytes, CallRequest, FilterBuilder, TransactionCondition, TransactionParameters,
-        TransactionReceipt, TransactionRequest, H256, U256, U64,
+        AccessList, Address, BlockId, BlockNumber, Bytes, CallRequest, Eip712Meta, FilterBuilder, Log, PaymasterParams,
+        StateOverride, TransactionCondition, TransactionParameters, TransactionReceipt, TransactionRequest,
+        EIP712_TX_TYPE, H256, U256, U64,
     },
     Transport,
 };
+use ethabi::Token;
 use std::{collections::HashMap, hash::Hash, time};
 
+pub mod codegen;
+pub mod erc1155;
+pub mod erc721;
 mod error;
+pub mod events;
+mod expect;
+mod filter;
+mod pinned;
+mod read_as;
 pub mod tokens;
 
-pub use crate::contract::error::Error;
+pub use crate::contract::{
+    erc1155::Erc1155,
+    erc721::Erc721,
+    error::Error,
+    events::{DecodedEvent, EventCursor, EventLog, EventStream, FromEvent},
+    expect::{expect_event, EventMatcher, EventMismatch},
+    filter::EventFilterBuilder,
+    pinned::{is_missing_state, BlockPin, QueryPolicy},
+    read_as::{resolve_proxy, ReadAs, EIP1967_IMPLEMENTATION_SLOT},
+};
 
 /// Contract `Result` type.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -43,7 +64,10 @@ pub struct Options {
     pub max_fee_per_gas: Option<U256>,
     /// miner bribe
     pub max_priority_fee_per_gas: Option<U256>,
+    /// Outcall options (None for the transport defaults)
     pub call_options: Option<CallOptions>,
+    /// zkSync fields, e.g. a paymaster, for EIP-712 (type `0x71`) transactions
+    pub eip712_meta: Option<Eip712Meta>,
 }
 
 impl Options {
@@ -56,6 +80,147 @@ impl Options {
         func(&mut options);
         options
     }
+
+    /// Returns a builder validating the options on `build`.
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder::default()
+    }
+
+    /// Returns a copy of these options with every field set in `overrides` replaced.
+    pub fn merge(&self, overrides: &Options) -> Options {
+        Options {
+            gas: overrides.gas.or(self.gas),
+            gas_price: overrides.gas_price.or(self.gas_price),
+            value: overrides.value.or(self.value),
+            nonce: overrides.nonce.or(self.nonce),
+            condition: overrides.condition.clone().or_else(|| self.condition.clone()),
+            transaction_type: overrides.transaction_type.or(self.transaction_type),
+            access_list: overrides.access_list.clone().or_else(|| self.access_list.clone()),
+            max_fee_per_gas: overrides.max_fee_per_gas.or(self.max_fee_per_gas),
+            max_priority_fee_per_gas: overrides.max_priority_fee_per_gas.or(self.max_priority_fee_per_gas),
+            call_options: overrides.call_options.clone().or_else(|| self.call_options.clone()),
+            eip712_meta: overrides.eip712_meta.clone().or_else(|| self.eip712_meta.clone()),
+        }
+    }
+
+    /// Checks that the fee fields are consistent with the transaction type.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: &str| Err(Error::InvalidOptions(msg.to_string()));
+        let has_eip1559_fees = self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some();
+        match self.transaction_type.map(|t| t.as_u64()) {
+            None | Some(0) if self.access_list.is_some() => invalid("access list requires transaction type 1 or 2"),
+            None | Some(0) | Some(1) if has_eip1559_fees => invalid("EIP-1559 fee fields require transaction type 2"),
+            Some(t) if t != EIP712_TX_TYPE && self.eip712_meta.is_some() => {
+                invalid("zkSync fields require transaction type 0x71")
+            }
+            Some(EIP712_TX_TYPE) if self.access_list.is_some() => {
+                invalid("access list is not allowed for transaction type 0x71")
+            }
+            None if self.eip712_meta.is_some() => invalid("zkSync fields require transaction type 0x71"),
+            Some(2) | Some(EIP712_TX_TYPE) => {
+                if self.gas_price.is_some() {
+                    return invalid("gas price is not allowed for EIP-1559 transactions, use max fee per gas");
+                }
+                match (self.max_fee_per_gas, self.max_priority_fee_per_gas) {
+                    (Some(max_fee), Some(priority_fee)) if priority_fee > max_fee => {
+                        invalid("max priority fee per gas exceeds max fee per gas")
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Builder for contract call options.
+#[derive(Default, Debug, Clone)]
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    /// Set fixed gas limit
+    pub fn gas(mut self, gas: U256) -> Self {
+        self.options.gas = Some(gas);
+        self
+    }
+
+    /// Set fixed gas price
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.options.gas_price = Some(gas_price);
+        self
+    }
+
+    /// Set value to transfer
+    pub fn value(mut self, value: U256) -> Self {
+        self.options.value = Some(value);
+        self
+    }
+
+    /// Set fixed transaction nonce
+    pub fn nonce(mut self, nonce: U256) -> Self {
+        self.options.nonce = Some(nonce);
+        self
+    }
+
+    /// Set a condition to satisfy before including transaction
+    pub fn condition(mut self, condition: TransactionCondition) -> Self {
+        self.options.condition = Some(condition);
+        self
+    }
+
+    /// Set transaction type, 1 for AccessList transaction, 2 for EIP-1559
+    pub fn transaction_type(mut self, transaction_type: U64) -> Self {
+        self.options.transaction_type = Some(transaction_type);
+        self
+    }
+
+    /// Set access list
+    pub fn access_list(mut self, access_list: AccessList) -> Self {
+        self.options.access_list = Some(access_list);
+        self
+    }
+
+    /// Set max fee per gas
+    pub fn max_fee_per_gas(mut self, max_fee_per_gas: U256) -> Self {
+        self.options.max_fee_per_gas = Some(max_fee_per_gas);
+        self
+    }
+
+    /// Set miner bribe
+    pub fn max_priority_fee_per_gas(mut self, max_priority_fee_per_gas: U256) -> Self {
+        self.options.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+        self
+    }
+
+    /// Set outcall options
+    pub fn call_options(mut self, call_options: CallOptions) -> Self {
+        self.options.call_options = Some(call_options);
+        self
+    }
+
+    /// Set zkSync fields of EIP-712 transactions
+    pub fn eip712_meta(mut self, eip712_meta: Eip712Meta) -> Self {
+        self.options.eip712_meta = Some(eip712_meta);
+        self
+    }
+
+    /// Pay the fees through a paymaster, turning the transaction into a zkSync EIP-712 transaction
+    pub fn paymaster(mut self, paymaster_params: PaymasterParams) -> Self {
+        self.options.transaction_type = Some(EIP712_TX_TYPE.into());
+        self.options
+            .eip712_meta
+            .get_or_insert_with(Eip712Meta::default)
+            .paymaster_params = Some(paymaster_params);
+        self
+    }
+
+    /// Validate and build the options
+    pub fn build(&self) -> Result<Options> {
+        self.options.validate()?;
+        Ok(self.options.clone())
+    }
 }
 
 /// Ethereum Contract Interface
@@ -64,6 +229,7 @@ pub struct Contract<T: Transport> {
     address: Address,
     eth: Eth<T>,
     abi: ethabi::Contract,
+    nonce_manager: Option<NonceManager>,
 }
 
 impl<T: Transport> Contract<T> {}
@@ -71,7 +237,18 @@ impl<T: Transport> Contract<T> {}
 impl<T: Transport> Contract<T> {
     /// Creates new Contract Interface given blockchain address and ABI
     pub fn new(eth: Eth<T>, address: Address, abi: ethabi::Contract) -> Self {
-        Contract { address, eth, abi }
+        Contract {
+            address,
+            eth,
+            abi,
+            nonce_manager: None,
+        }
+    }
+
+    /// Reserve the nonces of signed calls from the given manager.
+    pub fn with_nonce_manager(mut self, nonce_manager: NonceManager) -> Self {
+        self.nonce_manager = Some(nonce_manager);
+        self
     }
 
     /// Creates new Contract Interface given blockchain address and JSON containing ABI
@@ -90,12 +267,64 @@ impl<T: Transport> Contract<T> {
         self.address
     }
 
-    /// Execute a contract function
+    /// Resolve the function `func` called with `params`.
+    ///
+    /// `func` is either a function name, a full signature such as `transfer(address,uint256)` or a hex
+    /// encoded selector such as `0xa9059cbb`. A name resolves to the first function of that name whose
+    /// inputs match the types of `params`, failing if none does, the signature or the selector picking a
+    /// specific overload instead.
+    pub fn function(&self, func: &str, params: &[Token]) -> Result<&ethabi::Function> {
+        let func = func.trim();
+        if let Some(selector) = func.strip_prefix("0x") {
+            let selector = hex::decode(selector).map_err(|_| ethabi::Error::InvalidName(func.into()))?;
+            return self
+                .abi
+                .functions()
+                .find(|function| function.short_signature()[..] == selector[..])
+                .ok_or_else(|| ethabi::Error::InvalidName(func.into()).into());
+        }
+        if let Some(position) = func.find('(') {
+            let signature: String = func.chars().filter(|c| !c.is_whitespace()).collect();
+            return self
+                .abi
+                .functions_by_name(&func[..position])?
+                .iter()
+                .find(|function| function_signature(function) == signature)
+                .ok_or_else(|| ethabi::Error::InvalidName(func.into()).into());
+        }
+        self.abi
+            .functions_by_name(func)?
+            .iter()
+            .find(|function| inputs_match(function, params))
+            .ok_or_else(|| ethabi::Error::InvalidName(func.into()).into())
+    }
+
+    /// Returns the given outcall options or the defaults of the underlying transport.
+    fn call_options(&self, call_options: Option<CallOptions>) -> CallOptions {
+        call_options.unwrap_or_else(|| self.eth.transport().default_call_options())
+    }
+
+    /// Turns revert errors into [`Error::Revert`], decoding custom errors against the ABI.
+    fn api_error(&self, error: crate::error::Error) -> Error {
+        match &error {
+            crate::error::Error::Rpc(rpc) => match revert::revert_data(rpc) {
+                Some(data) => Error::Revert {
+                    reason: Box::new(RevertReason::decode_with_abi(&data.0, &self.abi)),
+                    error,
+                },
+                None => Error::Api(error),
+            },
+            _ => Error::Api(error),
+        }
+    }
+
+    /// Execute a contract function, `func` being resolved by [`function`](Self::function).
     pub async fn call<P>(&self, func: &str, params: P, from: Address, options: Options) -> Result<H256>
     where
         P: Tokenize,
     {
-        let data = self.abi.function(func)?.encode_input(&params.into_tokens())?;
+        let params = params.into_tokens();
+        let data = self.function(func, &params)?.encode_input(&params)?;
         let Options {
             gas,
             gas_price,
@@ -107,6 +336,7 @@ impl<T: Transport> Contract<T> {
             max_fee_per_gas,
             max_priority_fee_per_gas,
             call_options,
+            eip712_meta: _,
         } = options;
         self.eth
             .send_transaction(
@@ -124,10 +354,10 @@ impl<T: Transport> Contract<T> {
                     max_fee_per_gas,
                     max_priority_fee_per_gas,
                 },
-                call_options.unwrap_or_default(),
+                self.call_options(call_options),
             )
             .await
-            .map_err(Error::from)
+            .map_err(|err| self.api_error(err))
     }
 
     /// Estimate gas required for this function call.
@@ -135,7 +365,8 @@ impl<T: Transport> Contract<T> {
     where
         P: Tokenize,
     {
-        let data = self.abi.function(func)?.encode_input(&params.into_tokens())?;
+        let params = params.into_tokens();
+        let data = self.function(func, &params)?.encode_input(&params)?;
         self.eth
             .estimate_gas(
                 CallRequest {
@@ -151,10 +382,10 @@ impl<T: Transport> Contract<T> {
                     max_priority_fee_per_gas: options.max_priority_fee_per_gas,
                 },
                 None,
-                options.call_options.unwrap_or_default(),
+                self.call_options(options.call_options),
             )
             .await
-            .map_err(Into::into)
+            .map_err(|err| self.api_error(err))
     }
     async fn _estimate_gas(
         &self,
@@ -180,10 +411,9 @@ impl<T: Transport> Contract<T> {
                 call_options,
             )
             .await
-            .map_err(Into::into)
     }
 
-    /// Call constant function
+    /// Call constant function, `func` being resolved by [`function`](Self::function).
     pub fn query<R, A, B, P>(
         &self,
         func: &str,
@@ -198,13 +428,14 @@ impl<T: Transport> Contract<T> {
         B: Into<Option<BlockId>>,
         P: Tokenize,
     {
+        let params = params.into_tokens();
         let result = self
-            .abi
-            .function(func)
+            .function(func, &params)
             .and_then(|function| {
                 function
-                    .encode_input(&params.into_tokens())
+                    .encode_input(&params)
                     .map(|call| (call, function))
+                    .map_err(Error::from)
             })
             .map(|(call, function)| {
                 let call_future = self.eth.call(
@@ -221,20 +452,159 @@ impl<T: Transport> Contract<T> {
                         max_priority_fee_per_gas: options.max_priority_fee_per_gas,
                     },
                     block.into(),
-                    options.call_options.unwrap_or_default(),
+                    self.call_options(options.call_options),
                 );
                 (call_future, function)
             });
         // NOTE for the batch transport to work correctly, we must call `transport.execute` without ever polling the future,
         // hence it cannot be a fully `async` function.
-        async {
+        async move {
             let (call_future, function) = result?;
-            let bytes = call_future.await?;
+            let bytes = call_future.await.map_err(|err| self.api_error(err))?;
             let output = function.decode_output(&bytes.0)?;
             R::from_tokens(output)
         }
     }
 
+    /// Call constant function as the wallet of `read_as`, with its state overrides applied.
+    pub async fn query_as<R, P>(
+        &self,
+        func: &str,
+        params: P,
+        read_as: &ReadAs,
+        options: Options,
+        block: Option<BlockId>,
+    ) -> Result<R>
+    where
+        R: Detokenize,
+        P: Tokenize,
+    {
+        self.query_with_overrides(func, params, read_as.wallet(), options, block, read_as.overrides())
+            .await
+    }
+
+    /// Call constant function against a state modified by `overrides`, e.g. to check what it would
+    /// return with a balance or an allowance the caller does not have yet.
+    pub async fn query_with_overrides<R, A, P>(
+        &self,
+        func: &str,
+        params: P,
+        from: A,
+        options: Options,
+        block: Option<BlockId>,
+        overrides: StateOverride,
+    ) -> Result<R>
+    where
+        R: Detokenize,
+        A: Into<Option<Address>>,
+        P: Tokenize,
+    {
+        let params = params.into_tokens();
+        let function = self.function(func, &params)?;
+        let data = function.encode_input(&params)?;
+        let bytes = self
+            .eth
+            .call_with_overrides(
+                CallRequest {
+                    from: from.into(),
+                    to: Some(self.address),
+                    gas: options.gas,
+                    gas_price: options.gas_price,
+                    value: options.value,
+                    data: Some(Bytes(data)),
+                    transaction_type: options.transaction_type,
+                    access_list: options.access_list,
+                    max_fee_per_gas: options.max_fee_per_gas,
+                    max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+                },
+                block,
+                overrides,
+                self.call_options(options.call_options),
+            )
+            .await
+            .map_err(|err| self.api_error(err))?;
+        R::from_tokens(function.decode_output(&bytes.0)?)
+    }
+
+    /// Pin a sequence of queries to the current head, see [`BlockPin`].
+    pub async fn pin(&self, policy: QueryPolicy) -> Result<BlockPin> {
+        let options = self.eth.transport().default_call_options();
+        Ok(BlockPin::latest(&self.eth, policy, options).await?)
+    }
+
+    /// Call constant function at the block of `pin`.
+    ///
+    /// When the node serving the call does not have the pinned block state (see [`is_missing_state`]),
+    /// the pin falls back to an older block according to its [`QueryPolicy`] and the call is retried.
+    pub async fn query_pinned<R, A, P>(
+        &self,
+        func: &str,
+        params: P,
+        from: A,
+        options: Options,
+        pin: &BlockPin,
+    ) -> Result<R>
+    where
+        R: Detokenize,
+        A: Into<Option<Address>>,
+        P: Tokenize,
+    {
+        let params = params.into_tokens();
+        let from = from.into();
+        let mut retries = 0;
+        loop {
+            let block = pin.block();
+            let result = self
+                .query(
+                    func,
+                    &params[..],
+                    from,
+                    options.clone(),
+                    BlockId::Number(BlockNumber::Number(block)),
+                )
+                .await;
+            match result {
+                Err(Error::Api(err))
+                    if is_missing_state(&err) && retries < pin.policy().retries && pin.fall_back(block) =>
+                {
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Stream the occurrences of the event `event`, decoded into `E`.
+    ///
+    /// The stream starts at the current head unless a start block or a cursor is set, see [`EventStream`].
+    pub fn event_stream<E: FromEvent>(&self, event: &str) -> Result<EventStream<T, E>> {
+        let event = self.abi.event(event)?.clone();
+        let options = self.eth.transport().default_call_options();
+        Ok(EventStream::new(self.eth.clone(), self.address, event, options))
+    }
+
+    /// Build a filter of the occurrences of the event `event` emitted by this contract, see
+    /// [`EventFilterBuilder`].
+    pub fn event_filter(&self, event: &str) -> Result<EventFilterBuilder> {
+        Ok(EventFilterBuilder::new(self.abi.event(event)?).address(vec![self.address]))
+    }
+
+    /// Find the occurrence of the event `event` matching `matcher` in the logs of `receipt`, see
+    /// [`expect_event`].
+    pub fn expect_event(
+        &self,
+        receipt: &TransactionReceipt,
+        event: &str,
+        matcher: &EventMatcher,
+    ) -> std::result::Result<DecodedEvent, EventMismatch> {
+        expect_event(receipt, self, event, matcher)
+    }
+
+    /// Decode a log emitted by this contract as the event `event`.
+    pub fn decode_event<E: FromEvent>(&self, event: &str, log: Log) -> Result<E> {
+        E::from_event(DecodedEvent::decode(self.abi.event(event)?, log)?)
+    }
+
     /// Find events matching the topics.
     pub async fn events<A, B, C, R>(
         &self,
@@ -289,6 +659,25 @@ impl<T: Transport> Contract<T> {
     }
 }
 
+/// Canonical signature of `function`, e.g. `transfer(address,uint256)`.
+fn function_signature(function: &ethabi::Function) -> String {
+    let kinds: Vec<String> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+    format!("{}({})", function.name, kinds.join(","))
+}
+
+/// Whether `params` have the types of the inputs of `function`, integers matching regardless of their sign.
+fn inputs_match(function: &ethabi::Function, params: &[Token]) -> bool {
+    function.inputs.len() == params.len()
+        && function
+            .inputs
+            .iter()
+            .zip(params)
+            .all(|(param, token)| match (&param.kind, token) {
+                (ethabi::ParamType::Int(_), Token::Uint(_)) | (ethabi::ParamType::Uint(_), Token::Int(_)) => true,
+                (kind, token) => token.type_check(kind),
+            })
+}
+
 // #[cfg(feature = "signing")]
 mod contract_signing {
     use std::str::FromStr;
@@ -298,74 +687,132 @@ mod contract_signing {
     use super::*;
     use crate::{
         api::Accounts,
+        signing::{Key, KeySigner, Signer},
         types::{SignedTransaction, TransactionParameters},
     };
 
     impl<T: Transport> Contract<T> {
-        pub async fn sign(
+        /// Builds the transaction parameters of a contract call, estimating gas when no fixed gas is given.
+        async fn build_transaction(
             &self,
             func: &str,
             params: &[Token],
-            options: Options,
-            from: String,
-            key_info: KeyInfo,
-            chain_id: u64,
-        ) -> crate::Result<SignedTransaction> {
+            options: &Options,
+            from: Address,
+        ) -> crate::Result<TransactionParameters> {
             let fn_data = self
-                .abi
-                .function(func)
-                .and_then(|function| function.encode_input(&params))
+                .function(func, params)
+                .and_then(|function| function.encode_input(params).map_err(Error::from))
                 // TODO [ToDr] SendTransactionWithConfirmation should support custom error type (so that we can return
                 // `contract::Error` instead of more generic `Error`.
                 .map_err(|err| crate::error::Error::Decoder(format!("{:?}", err)))?;
-            let accounts = Accounts::new(self.eth.transport().clone());
             let mut tx = TransactionParameters {
                 nonce: options.nonce,
                 to: Some(self.address),
                 gas_price: options.gas_price,
                 data: Bytes(fn_data),
                 transaction_type: options.transaction_type,
-                access_list: options.access_list,
+                access_list: options.access_list.clone(),
                 max_fee_per_gas: options.max_fee_per_gas,
                 max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+                eip712_meta: options.eip712_meta.clone(),
                 ..Default::default()
             };
             if let Some(gas) = options.gas {
                 tx.gas = gas;
             } else {
                 tx.gas = self
-                    ._estimate_gas(
-                        Address::from_str(&from.to_string().as_str()).unwrap(),
-                        &tx,
-                        options.call_options.unwrap_or_default(),
-                    )
+                    ._estimate_gas(from, &tx, self.call_options(options.call_options.clone()))
                     .await?;
             }
             if let Some(value) = options.value {
                 tx.value = value;
             }
-            accounts.sign_transaction(tx, from, key_info, chain_id).await
+            Ok(tx)
+        }
+
+        /// Sign a contract call transaction with `key`, `from` being the hex address of the key.
+        pub async fn sign<K: Key>(
+            &self,
+            func: &str,
+            params: &[Token],
+            options: Options,
+            from: String,
+            key: K,
+            chain_id: u64,
+        ) -> crate::Result<SignedTransaction> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.sign_with(func, params, options, &signer, chain_id).await
+        }
+
+        /// Sign a contract call transaction with any [`Signer`].
+        pub async fn sign_with<S: Signer>(
+            &self,
+            func: &str,
+            params: &[Token],
+            options: Options,
+            signer: S,
+            chain_id: u64,
+        ) -> crate::Result<SignedTransaction> {
+            let from = signer_address(&signer).await?;
+            let tx = self.build_transaction(func, params, &options, from).await?;
+            let accounts = Accounts::new(self.eth.transport().clone());
+            accounts.sign_transaction_with(tx, signer, chain_id).await
         }
 
         /// Submit contract call transaction to the transaction pool.
         ///
+        /// When a nonce manager is attached to the contract and `options.nonce` is not set, the nonce is
+        /// reserved from the manager.
+        ///
         /// Note this function DOES NOT wait for any confirmations, so there is no guarantees that the call is actually executed.
         /// If you'd rather wait for block inclusion, please use [`signed_call_with_confirmations`] instead.
-        pub async fn signed_call(
+        pub async fn signed_call<K: Key>(
             &self,
             func: &str,
             params: &[Token],
             options: Options,
             from: String,
-            key_info: KeyInfo,
+            key: K,
+            chain_id: u64,
+        ) -> crate::Result<H256> {
+            let signer = KeySigner::new(key, parse_sender(&from)?);
+            self.signed_call_with(func, params, options, &signer, chain_id).await
+        }
+
+        /// Submit a contract call transaction signed with any [`Signer`], see
+        /// [`signed_call`](Self::signed_call).
+        pub async fn signed_call_with<S: Signer>(
+            &self,
+            func: &str,
+            params: &[Token],
+            options: Options,
+            signer: S,
             chain_id: u64,
         ) -> crate::Result<H256> {
-            let signed = self
-                .sign(func, params, options.clone(), from, key_info, chain_id)
-                .await?;
-            self.eth
-                .send_raw_transaction(signed.raw_transaction, options.call_options.unwrap_or_default())
+            let from = signer_address(&signer).await?;
+            let tx = self.build_transaction(func, params, &options, from).await?;
+            let accounts = Accounts::new(self.eth.transport().clone());
+            accounts
+                .sign_and_send_transaction_with(
+                    tx,
+                    signer,
+                    chain_id,
+                    self.nonce_manager.as_ref(),
+                    self.call_options(options.call_options),
+                )
                 .await
         }
     }
+
+    fn parse_sender(from: &str) -> crate::Result<Address> {
+        Address::from_str(from).map_err(|_| crate::error::Error::Decoder(format!("invalid sender address: {}", from)))
+    }
+
+    async fn signer_address<S: Signer>(signer: &S) -> crate::Result<Address> {
+        signer
+            .address()
+            .await
+            .map_err(|e| crate::error::Error::Signing(e.to_string()))
+    }
 }
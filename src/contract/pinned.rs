@@ -0,0 +1,105 @@
+//! Contract queries pinned to a block.
+
+use crate::{
+    api::Eth,
+    error::{Error, TransportError},
+    transports::ic_http_client::CallOptions,
+    types::U64,
+    Transport,
+};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Whether `err` signals that the node serving the call does not have the requested block state.
+///
+/// Same as [`Error::is_missing_state`].
+pub fn is_missing_state(err: &Error) -> bool {
+    err.is_missing_state()
+}
+
+/// How pinned queries fall back when the pinned block is not available.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryPolicy {
+    /// Number of blocks the pin is moved back on every fallback
+    pub step: u64,
+    /// Maximum number of blocks the pin may end up behind the block it was created at
+    pub max_lag: u64,
+    /// Maximum number of fallbacks of a single query
+    pub retries: u32,
+}
+
+impl Default for QueryPolicy {
+    fn default() -> Self {
+        QueryPolicy {
+            step: 1,
+            max_lag: 5,
+            retries: 3,
+        }
+    }
+}
+
+/// A block number shared by a sequence of queries, so they all read the same chain state.
+///
+/// Providers behind a load balancer may route a query to a node which has not imported the pinned block
+/// yet, failing with "header not found" or "missing trie node". Pinned queries then move the pin back by
+/// [`QueryPolicy::step`] blocks and retry, as long as the pin stays within [`QueryPolicy::max_lag`] of
+/// the block it was created at. Since the pin is shared, later queries of the sequence also read the
+/// older block; results obtained before a fallback can be detected by comparing [`block`](Self::block)
+/// before and after.
+///
+/// The pin is cheap to clone; clones share the same block.
+#[derive(Clone, Debug)]
+pub struct BlockPin {
+    origin: U64,
+    block: Arc<Mutex<U64>>,
+    policy: QueryPolicy,
+}
+
+impl BlockPin {
+    /// Pin queries to block `number`.
+    pub fn new(number: U64, policy: QueryPolicy) -> Self {
+        BlockPin {
+            origin: number,
+            block: Arc::new(Mutex::new(number)),
+            policy,
+        }
+    }
+
+    /// Pin queries to the current head, fetched once.
+    pub async fn latest<T: Transport>(eth: &Eth<T>, policy: QueryPolicy, options: CallOptions) -> crate::Result<Self> {
+        let number = eth.block_number(options).await?;
+        Ok(Self::new(number, policy))
+    }
+
+    /// Block the queries currently read.
+    pub fn block(&self) -> U64 {
+        *self.block.lock()
+    }
+
+    /// Block the pin was created at.
+    pub fn origin(&self) -> U64 {
+        self.origin
+    }
+
+    /// Fallback policy of the pin.
+    pub fn policy(&self) -> QueryPolicy {
+        self.policy
+    }
+
+    /// Move the pin back from `failed`, returns whether it moved.
+    ///
+    /// When another query already moved the pin past `failed`, the pin is kept and the caller retries at
+    /// the current block.
+    pub(crate) fn fall_back(&self, failed: U64) -> bool {
+        let mut block = self.block.lock();
+        if *block < failed {
+            return true;
+        }
+        let oldest = self.origin.saturating_sub(self.policy.max_lag.into());
+        if failed <= oldest {
+            return false;
+        }
+        *block = std::cmp::max(failed.saturating_sub(self.policy.step.max(1).into()), oldest);
+        true
+    }
+}
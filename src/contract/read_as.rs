@@ -0,0 +1,130 @@
+//! Contract queries seen from a smart contract wallet.
+
+use crate::{
+    api::Eth,
+    transports::ic_http_client::CallOptions,
+    types::{AccountOverride, Address, BlockNumber, Bytes, StateOverride, H256, U256},
+    Transport,
+};
+
+/// EIP-1967 storage slot holding the implementation of a proxy,
+/// `keccak256("eip1967.proxy.implementation") - 1`.
+pub const EIP1967_IMPLEMENTATION_SLOT: H256 = H256([
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d, 0xca, 0x3e, 0x20,
+    0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+]);
+
+/// Implementation of the EIP-1967 proxy at `address`, `None` if it is not such a proxy.
+pub async fn resolve_proxy<T: Transport>(
+    eth: &Eth<T>,
+    address: Address,
+    block: Option<BlockNumber>,
+    options: CallOptions,
+) -> crate::Result<Option<Address>> {
+    let slot = U256::from_big_endian(EIP1967_IMPLEMENTATION_SLOT.as_bytes());
+    let value = eth.storage(address, slot, block, options).await?;
+    let implementation = Address::from_slice(&value.as_bytes()[12..]);
+    Ok(Some(implementation).filter(|implementation| !implementation.is_zero()))
+}
+
+/// Query mode impersonating a smart contract wallet, see
+/// [`Contract::query_as`](crate::contract::Contract::query_as).
+///
+/// Queries are sent with the wallet as `from`, so contracts checking `msg.sender` answer as they would
+/// to the wallet. Wallets which are not deployed yet, e.g. counterfactual account abstraction wallets,
+/// have no code, which breaks contracts calling back into the sender (ERC-1271 signature checks, token
+/// receiver hooks). Given the wallet code, and for proxy wallets the implementation they delegate to,
+/// the mode injects them with state overrides when the wallet turns out not to be deployed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadAs {
+    wallet: Address,
+    code: Option<Bytes>,
+    implementation: Option<Address>,
+    overrides: StateOverride,
+    deployed: Option<bool>,
+}
+
+impl ReadAs {
+    /// Read as `wallet`.
+    pub fn new(wallet: Address) -> Self {
+        ReadAs {
+            wallet,
+            code: None,
+            implementation: None,
+            overrides: StateOverride::new(),
+            deployed: None,
+        }
+    }
+
+    /// Code injected at the wallet address when it is not deployed.
+    pub fn with_code(mut self, code: Bytes) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Implementation written into the EIP-1967 slot of the wallet when it is not deployed, for wallets
+    /// whose code is a proxy.
+    pub fn with_implementation(mut self, implementation: Address) -> Self {
+        self.implementation = Some(implementation);
+        self
+    }
+
+    /// Additional override, e.g. a token balance of the wallet.
+    pub fn with_override(mut self, address: Address, account: AccountOverride) -> Self {
+        self.overrides.insert(address, account);
+        self
+    }
+
+    /// Check whether the wallet is deployed at `block` and, if it is, which implementation it delegates
+    /// to when it is an EIP-1967 proxy.
+    ///
+    /// Without resolution the wallet is assumed not to be deployed, so the code given with
+    /// [`with_code`](Self::with_code) is always injected.
+    pub async fn resolve<T: Transport>(
+        mut self,
+        eth: &Eth<T>,
+        block: Option<BlockNumber>,
+        options: CallOptions,
+    ) -> crate::Result<Self> {
+        let code = eth.code(self.wallet, block, options.clone()).await?;
+        let deployed = !code.0.is_empty();
+        if deployed {
+            self.implementation = resolve_proxy(eth, self.wallet, block, options).await?;
+        }
+        self.deployed = Some(deployed);
+        Ok(self)
+    }
+
+    /// Impersonated wallet.
+    pub fn wallet(&self) -> Address {
+        self.wallet
+    }
+
+    /// Whether the wallet is deployed, `None` before [`resolve`](Self::resolve).
+    pub fn is_deployed(&self) -> Option<bool> {
+        self.deployed
+    }
+
+    /// Implementation the wallet delegates to, as resolved from the chain for deployed wallets or as given
+    /// for the others.
+    pub fn implementation(&self) -> Option<Address> {
+        self.implementation
+    }
+
+    /// State overrides sent with the queries.
+    pub fn overrides(&self) -> StateOverride {
+        let mut overrides = self.overrides.clone();
+        if self.deployed == Some(true) || self.code.is_none() {
+            return overrides;
+        }
+        let account = overrides.entry(self.wallet).or_default();
+        account.code = self.code.clone();
+        if let Some(implementation) = self.implementation {
+            account
+                .state_diff
+                .get_or_insert_with(Default::default)
+                .insert(EIP1967_IMPLEMENTATION_SLOT, H256::from(implementation));
+        }
+        overrides
+    }
+}
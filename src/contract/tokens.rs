@@ -15,75 +15,50 @@ pub trait Detokenize {
         Self: Sized;
 }
 
+/// Single values are converted from the only token, several values from a tuple of the tokens, so that
+/// a Rust tuple matches both the outputs of a function and a single struct output.
 impl<T: Tokenizable> Detokenize for T {
     fn from_tokens(mut tokens: Vec<Token>) -> Result<Self, Error> {
         if tokens.len() != 1 {
-            Err(Error::InvalidOutputType(format!(
-                "Expected single element, got a list: {:?}",
-                tokens
-            )))
-        } else {
-            Self::from_token(tokens.drain(..).next().expect("At least one element in vector; qed"))
+            return Self::from_token(Token::Tuple(tokens));
         }
+        let token = tokens.pop().expect("One element in vector; qed");
+        // A single output, a struct as well, decoded into a tuple of one element.
+        Self::from_token(token.clone()).or_else(|err| Self::from_token(Token::Tuple(vec![token])).map_err(|_| err))
     }
 }
 
-macro_rules! impl_output {
-  ($num: expr, $( $ty: ident , )+) => {
-    impl<$($ty, )+> Detokenize for ($($ty,)+) where
-      $(
-        $ty: Tokenizable,
-      )+
-    {
-      fn from_tokens(mut tokens: Vec<Token>) -> Result<Self, Error> {
-        if tokens.len() != $num {
-          return Err(Error::InvalidOutputType(format!(
-            "Expected {} elements, got a list of {}: {:?}",
-            $num,
-            tokens.len(),
-            tokens
-          )));
+impl Detokenize for () {
+    fn from_tokens(tokens: Vec<Token>) -> Result<Self, Error> {
+        if tokens.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidOutputType(format!(
+                "Expected no elements, got a list: {:?}",
+                tokens
+            )))
         }
-        let mut it = tokens.drain(..);
-        Ok(($(
-          $ty::from_token(it.next().expect("All elements are in vector; qed"))?,
-        )+))
-      }
     }
-  }
 }
 
-impl_output!(1, A,);
-impl_output!(2, A, B,);
-impl_output!(3, A, B, C,);
-impl_output!(4, A, B, C, D,);
-impl_output!(5, A, B, C, D, E,);
-impl_output!(6, A, B, C, D, E, F,);
-impl_output!(7, A, B, C, D, E, F, G,);
-impl_output!(8, A, B, C, D, E, F, G, H,);
-impl_output!(9, A, B, C, D, E, F, G, H, I,);
-impl_output!(10, A, B, C, D, E, F, G, H, I, J,);
-impl_output!(11, A, B, C, D, E, F, G, H, I, J, K,);
-impl_output!(12, A, B, C, D, E, F, G, H, I, J, K, L,);
-impl_output!(13, A, B, C, D, E, F, G, H, I, J, K, L, M,);
-impl_output!(14, A, B, C, D, E, F, G, H, I, J, K, L, M, N,);
-impl_output!(15, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O,);
-impl_output!(16, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P,);
 /// Tokens conversion trait
 pub trait Tokenize {
     /// Convert to list of tokens
     fn into_tokens(self) -> Vec<Token>;
 }
 
-impl<'a> Tokenize for &'a [Token] {
+impl Tokenize for &[Token] {
     fn into_tokens(self) -> Vec<Token> {
         self.to_vec()
     }
 }
 
+/// Rust tuples are flattened into their elements, so that a tuple holds the parameters of a function. A
+/// single struct parameter is passed as a tuple of one element, e.g. `(params,)`, or as a `Token::Tuple`,
+/// which is never flattened.
 impl<T: Tokenizable> Tokenize for T {
     fn into_tokens(self) -> Vec<Token> {
-        vec![self.into_token()]
+        self.into_params()
     }
 }
 
@@ -93,39 +68,6 @@ impl Tokenize for () {
     }
 }
 
-macro_rules! impl_tokens {
-  ($( $ty: ident : $no: tt, )+) => {
-    impl<$($ty, )+> Tokenize for ($($ty,)+) where
-      $(
-        $ty: Tokenizable,
-      )+
-    {
-      fn into_tokens(self) -> Vec<Token> {
-        vec![
-          $( self.$no.into_token(), )+
-        ]
-      }
-    }
-  }
-}
-
-impl_tokens!(A:0, );
-impl_tokens!(A:0, B:1, );
-impl_tokens!(A:0, B:1, C:2, );
-impl_tokens!(A:0, B:1, C:2, D:3, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, );
-impl_tokens!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, );
-
 /// Simplified output type for single value.
 pub trait Tokenizable {
     /// Converts a `Token` into expected type.
@@ -134,6 +76,15 @@ pub trait Tokenizable {
         Self: Sized;
     /// Converts a specified type back into token.
     fn into_token(self) -> Token;
+
+    /// Converts the value into function parameters, the value itself unless it is a Rust tuple.
+    #[doc(hidden)]
+    fn into_params(self) -> Vec<Token>
+    where
+        Self: Sized,
+    {
+        vec![self.into_token()]
+    }
 }
 
 impl Tokenizable for Token {
@@ -246,8 +197,8 @@ macro_rules! int_tokenizable {
                 let data = if self < 0 {
                     // NOTE: Rust does sign extension when converting from a
                     // signed integer to an unsigned integer, so:
-                    // `-1u8 as u128 == u128::max_value()`
-                    U256::from(self as u128) | U256([0, 0, u64::max_value(), u64::max_value()])
+                    // `-1u8 as u128 == u128::MAX`
+                    U256::from(self as u128) | U256([0, 0, u64::MAX, u64::MAX])
                 } else {
                     self.into()
                 };
@@ -280,6 +231,71 @@ impl Tokenizable for bool {
     }
 }
 
+macro_rules! impl_tuple {
+  ($num: expr, $( $ty: ident : $no: tt, )+) => {
+    /// Solidity struct or tuple, as a `Token::Tuple` of its fields.
+    impl<$($ty, )+> Tokenizable for ($($ty,)+) where
+      $(
+        $ty: Tokenizable,
+      )+
+    {
+      fn from_token(token: Token) -> Result<Self, Error> {
+        match token {
+          Token::Tuple(tokens) => {
+            if tokens.len() != $num {
+              return Err(Error::InvalidOutputType(format!(
+                "Expected a tuple of {} elements, got a tuple of {}: {:?}",
+                $num,
+                tokens.len(),
+                tokens
+              )));
+            }
+            let mut it = tokens.into_iter();
+            Ok(($(
+              $ty::from_token(it.next().expect("All elements are in vector; qed"))?,
+            )+))
+          }
+          other => Err(Error::InvalidOutputType(format!("Expected `Tuple`, got {:?}", other))),
+        }
+      }
+
+      fn into_token(self) -> Token {
+        Token::Tuple(self.into_params())
+      }
+
+      fn into_params(self) -> Vec<Token> {
+        vec![
+          $( self.$no.into_token(), )+
+        ]
+      }
+    }
+
+    impl<$($ty, )+> TokenizableItem for ($($ty,)+) where
+      $(
+        $ty: Tokenizable,
+      )+
+    {
+    }
+  }
+}
+
+impl_tuple!(1, A:0, );
+impl_tuple!(2, A:0, B:1, );
+impl_tuple!(3, A:0, B:1, C:2, );
+impl_tuple!(4, A:0, B:1, C:2, D:3, );
+impl_tuple!(5, A:0, B:1, C:2, D:3, E:4, );
+impl_tuple!(6, A:0, B:1, C:2, D:3, E:4, F:5, );
+impl_tuple!(7, A:0, B:1, C:2, D:3, E:4, F:5, G:6, );
+impl_tuple!(8, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, );
+impl_tuple!(9, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, );
+impl_tuple!(10, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, );
+impl_tuple!(11, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, );
+impl_tuple!(12, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, );
+impl_tuple!(13, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, );
+impl_tuple!(14, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, );
+impl_tuple!(15, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, );
+impl_tuple!(16, A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8, J:9, K:10, L:11, M:12, N:13, O:14, P:15, );
+
 /// Marker trait for `Tokenizable` types that are can tokenized to and from a
 /// `Token::Array` and `Token:FixedArray`.
 pub trait TokenizableItem: Tokenizable {}
@@ -437,3 +453,189 @@ impl_fixed_types!(128);
 impl_fixed_types!(256);
 impl_fixed_types!(512);
 impl_fixed_types!(1024);
+
+#[cfg(test)]
+mod tests {
+    use super::{Detokenize, Tokenizable, Tokenize};
+    use crate::types::{Address, H256, U256};
+    use ethabi::{Contract, Token};
+
+    // ISwapRouter.exactInputSingle(ExactInputSingleParams)
+    type ExactInputSingleParams = (Address, Address, u32, Address, U256, U256, U256, U256);
+
+    // Seaport OfferItem, ConsiderationItem and OrderComponents
+    type OfferItem = (u8, Address, U256, U256, U256);
+    type ConsiderationItem = (u8, Address, U256, U256, U256, Address);
+    type OrderComponents = (
+        Address,
+        Address,
+        Vec<OfferItem>,
+        Vec<ConsiderationItem>,
+        u8,
+        U256,
+        U256,
+        H256,
+        U256,
+        H256,
+        U256,
+    );
+
+    const ABI: &str = r#"[
+        {
+            "type": "function",
+            "name": "exactInputSingle",
+            "stateMutability": "payable",
+            "inputs": [{
+                "name": "params",
+                "type": "tuple",
+                "components": [
+                    { "name": "tokenIn", "type": "address" },
+                    { "name": "tokenOut", "type": "address" },
+                    { "name": "fee", "type": "uint24" },
+                    { "name": "recipient", "type": "address" },
+                    { "name": "deadline", "type": "uint256" },
+                    { "name": "amountIn", "type": "uint256" },
+                    { "name": "amountOutMinimum", "type": "uint256" },
+                    { "name": "sqrtPriceLimitX96", "type": "uint160" }
+                ]
+            }],
+            "outputs": [{ "name": "amountOut", "type": "uint256" }]
+        },
+        {
+            "type": "function",
+            "name": "getOrderHash",
+            "stateMutability": "view",
+            "inputs": [{
+                "name": "order",
+                "type": "tuple",
+                "components": [
+                    { "name": "offerer", "type": "address" },
+                    { "name": "zone", "type": "address" },
+                    { "name": "offer", "type": "tuple[]", "components": [
+                        { "name": "itemType", "type": "uint8" },
+                        { "name": "token", "type": "address" },
+                        { "name": "identifierOrCriteria", "type": "uint256" },
+                        { "name": "startAmount", "type": "uint256" },
+                        { "name": "endAmount", "type": "uint256" }
+                    ]},
+                    { "name": "consideration", "type": "tuple[]", "components": [
+                        { "name": "itemType", "type": "uint8" },
+                        { "name": "token", "type": "address" },
+                        { "name": "identifierOrCriteria", "type": "uint256" },
+                        { "name": "startAmount", "type": "uint256" },
+                        { "name": "endAmount", "type": "uint256" },
+                        { "name": "recipient", "type": "address" }
+                    ]},
+                    { "name": "orderType", "type": "uint8" },
+                    { "name": "startTime", "type": "uint256" },
+                    { "name": "endTime", "type": "uint256" },
+                    { "name": "zoneHash", "type": "bytes32" },
+                    { "name": "salt", "type": "uint256" },
+                    { "name": "conduitKey", "type": "bytes32" },
+                    { "name": "counter", "type": "uint256" }
+                ]
+            }],
+            "outputs": [{ "name": "orderHash", "type": "bytes32" }]
+        }
+    ]"#;
+
+    fn abi() -> Contract {
+        Contract::load(ABI.as_bytes()).unwrap()
+    }
+
+    fn exact_input_single() -> ExactInputSingleParams {
+        (
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            3000,
+            Address::from_low_u64_be(3),
+            U256::from(1_700_000_000u64),
+            U256::exp10(18),
+            U256::from(12345),
+            U256::zero(),
+        )
+    }
+
+    fn order_components() -> OrderComponents {
+        (
+            Address::from_low_u64_be(1),
+            Address::zero(),
+            vec![(2, Address::from_low_u64_be(4), U256::from(7), U256::one(), U256::one())],
+            vec![
+                (
+                    0,
+                    Address::zero(),
+                    U256::zero(),
+                    U256::exp10(17),
+                    U256::exp10(17),
+                    Address::from_low_u64_be(1),
+                ),
+                (
+                    0,
+                    Address::zero(),
+                    U256::zero(),
+                    U256::exp10(15),
+                    U256::exp10(15),
+                    Address::from_low_u64_be(5),
+                ),
+            ],
+            0,
+            U256::from(1_700_000_000u64),
+            U256::from(1_800_000_000u64),
+            H256::zero(),
+            U256::from(42),
+            H256::repeat_byte(0xab),
+            U256::zero(),
+        )
+    }
+
+    #[test]
+    fn uniswap_v3_struct_round_trip() {
+        let abi = abi();
+        let function = abi.function("exactInputSingle").unwrap();
+        assert_eq!(function.short_signature(), [0x41, 0x4b, 0xf3, 0x89]);
+
+        let params = exact_input_single();
+        let tokens = (params,).into_tokens();
+        assert_eq!(tokens.len(), 1);
+        let data = function.encode_input(&tokens).unwrap();
+
+        let decoded = function.decode_input(&data[4..]).unwrap();
+        assert_eq!(ExactInputSingleParams::from_tokens(decoded.clone()).unwrap(), params);
+        assert_eq!(<(ExactInputSingleParams,)>::from_tokens(decoded).unwrap(), (params,));
+    }
+
+    #[test]
+    fn seaport_nested_struct_round_trip() {
+        let abi = abi();
+        let function = abi.function("getOrderHash").unwrap();
+        assert_eq!(function.short_signature(), [0x79, 0xdf, 0x72, 0xbd]);
+
+        let order = order_components();
+        let data = function.encode_input(&(order.clone(),).into_tokens()).unwrap();
+
+        let decoded = function.decode_input(&data[4..]).unwrap();
+        assert_eq!(OrderComponents::from_tokens(decoded).unwrap(), order);
+    }
+
+    #[test]
+    fn raw_tuple_token_is_one_parameter() {
+        let abi = abi();
+        let function = abi.function("exactInputSingle").unwrap();
+        let token = exact_input_single().into_token();
+
+        let tokens = token.clone().into_tokens();
+        assert_eq!(tokens, vec![token.clone()]);
+        assert_eq!(
+            function.encode_input(&tokens).unwrap(),
+            function.encode_input(&(exact_input_single(),).into_tokens()).unwrap()
+        );
+        assert_eq!((&[token][..]).into_tokens().len(), 1);
+    }
+
+    #[test]
+    fn rust_tuple_is_flattened_into_parameters() {
+        let tokens = (Address::zero(), U256::one()).into_tokens();
+        assert_eq!(tokens, vec![Token::Address(Address::zero()), Token::Uint(U256::one())]);
+    }
+}
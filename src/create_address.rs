@@ -0,0 +1,46 @@
+//! Addresses of contracts deployed with `CREATE` and `CREATE2`.
+//!
+//! Both opcodes derive the address of the new contract from data known before the deployment, so that
+//! canisters can predict it, e.g. to fund a counterfactual smart account before deploying it:
+//!
+//! ```ignore
+//! let account = compute_create2_address(factory, salt, H256(keccak256(&init_code)));
+//! wallet.send_eth(account, deposit).await?;
+//! ```
+
+use crate::{
+    signing::keccak256,
+    types::{Address, H256, U256},
+};
+use rlp::RlpStream;
+
+/// Address of the contract deployed with `CREATE` by `sender` in its transaction or contract creation
+/// with `nonce`: the last 20 bytes of `keccak256(rlp([sender, nonce]))`.
+///
+/// For externally owned accounts `nonce` is the nonce of the deployment transaction; for contracts it
+/// is their number of contract creations plus one (EIP-161).
+pub fn compute_create_address(sender: Address, nonce: U256) -> Address {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&sender);
+    stream.append(&nonce);
+    Address::from_slice(&keccak256(&stream.out())[12..])
+}
+
+/// Address of the contract deployed with `CREATE2` by `deployer` with `salt`, given the hash of its
+/// init code: the last 20 bytes of `keccak256(0xff ++ deployer ++ salt ++ init_code_hash)` (EIP-1014).
+pub fn compute_create2_address(deployer: Address, salt: H256, init_code_hash: H256) -> Address {
+    let preimage = [
+        &[0xff][..],
+        deployer.as_bytes(),
+        salt.as_bytes(),
+        init_code_hash.as_bytes(),
+    ]
+    .concat();
+    Address::from_slice(&keccak256(&preimage)[12..])
+}
+
+/// Address of the contract deployed with `CREATE2` by `deployer` with `salt` and `init_code`, i.e. its
+/// creation bytecode followed by the ABI-encoded constructor arguments.
+pub fn compute_create2_address_from_code(deployer: Address, salt: H256, init_code: &[u8]) -> Address {
+    compute_create2_address(deployer, salt, H256(keccak256(init_code)))
+}
@@ -0,0 +1,152 @@
+//! Tracing of the JSON-RPC requests sent by the crate.
+//!
+//! Every request sent through [`ICHttp`](crate::transports::ICHttp) is recorded in a bounded in-memory
+//! ring buffer, so that a canister can expose its recent outcalls, e.g. from an ops query method:
+//!
+//! ```ignore
+//! #[query]
+//! fn recent_requests() -> Vec<ic_web3_rs::debug::RequestRecord> {
+//!     ic_web3_rs::debug::recent_requests(20)
+//! }
+//! ```
+//!
+//! The buffer lives in the heap of the canister and is lost on upgrade.
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::VecDeque};
+
+/// Number of records kept by default.
+pub const DEFAULT_CAPACITY: usize = 64;
+/// Maximum length of the recorded params and error messages, longer ones are truncated.
+pub const MAX_TEXT_LEN: usize = 256;
+
+/// Outcome of a request.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RequestStatus {
+    /// The node returned a result
+    Success,
+    /// The node returned a JSON-RPC error with this code
+    Rpc(i64),
+    /// The HTTP response had this non-2xx status
+    Http(u16),
+    /// The outcall or the decoding of the response failed
+    Failed(String),
+}
+
+/// A request sent by the crate.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RequestRecord {
+    /// JSON-RPC method
+    pub method: String,
+    /// JSON encoded params, truncated to [`MAX_TEXT_LEN`] bytes
+    pub params: String,
+    /// Outcome
+    pub status: RequestStatus,
+    /// Time the request was sent, in nanoseconds since the epoch
+    pub timestamp: u64,
+    /// Time until the response was received, in nanoseconds
+    pub latency: u64,
+    /// Size of the response body in bytes, zero when no response was received
+    pub response_bytes: u64,
+}
+
+struct RingBuffer {
+    capacity: usize,
+    records: VecDeque<RequestRecord>,
+}
+
+thread_local! {
+    static REQUESTS: RefCell<RingBuffer> = const {
+        RefCell::new(RingBuffer {
+            capacity: DEFAULT_CAPACITY,
+            records: VecDeque::new(),
+        })
+    };
+}
+
+/// The last `n` requests, the most recent first.
+pub fn recent_requests(n: usize) -> Vec<RequestRecord> {
+    REQUESTS.with(|buffer| buffer.borrow().records.iter().rev().take(n).cloned().collect())
+}
+
+/// Keep the last `capacity` requests, zero disabling the tracing.
+pub fn set_capacity(capacity: usize) {
+    REQUESTS.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.capacity = capacity;
+        let excess = buffer.records.len().saturating_sub(capacity);
+        buffer.records.drain(..excess);
+    });
+}
+
+/// Forget the recorded requests.
+pub fn clear() {
+    REQUESTS.with(|buffer| buffer.borrow_mut().records.clear());
+}
+
+/// Record a request.
+pub fn record(record: RequestRecord) {
+    REQUESTS.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        if buffer.capacity == 0 {
+            return;
+        }
+        while buffer.records.len() >= buffer.capacity {
+            buffer.records.pop_front();
+        }
+        buffer.records.push_back(record);
+    });
+}
+
+/// Truncate `text` to [`MAX_TEXT_LEN`] bytes on a character boundary.
+pub(crate) fn truncate(mut text: String) -> String {
+    if text.len() > MAX_TEXT_LEN {
+        let mut end = MAX_TEXT_LEN;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push('…');
+    }
+    text
+}
+
+/// Current time in nanoseconds since the epoch, from the IC in canisters.
+pub(crate) fn now() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        ic_cdk::api::time()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default()
+    }
+}
+
+/// Print a line to the canister log.
+///
+/// Outside canisters, e.g. in native tests, the line goes to stderr with the `debug` feature and is
+/// dropped without it.
+pub(crate) fn print(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        ic_cdk::api::print(message);
+    }
+    #[cfg(all(not(target_arch = "wasm32"), feature = "debug"))]
+    {
+        eprintln!("{}", message);
+    }
+    #[cfg(all(not(target_arch = "wasm32"), not(feature = "debug")))]
+    {
+        let _ = message;
+    }
+}
+
+/// Print a warning to the canister log, see [`print`].
+pub(crate) fn warn(message: &str) {
+    print(&format!("warning: {}", message));
+}
@@ -1,6 +1,9 @@
 //! Web3 Error
-use crate::rpc::error::Error as RPCError;
+use crate::rpc::{self, error::Error as RPCError};
+use candid::CandidType;
 use derive_more::{Display, From};
+use ic_cdk::api::call::RejectionCode;
+use serde::{Deserialize, Serialize};
 use serde_json::Error as SerdeError;
 use std::io::Error as IoError;
 
@@ -16,6 +19,159 @@ pub enum TransportError {
     /// Arbitrary, developer-readable description of the occurred error.
     #[display(fmt = "{}", _0)]
     Message(String),
+    /// The request was refused before being sent, e.g. a call the provider does not support.
+    #[display(fmt = "not sent: {}", _0)]
+    NotSent(String),
+    /// Not enough providers returned the same response.
+    #[display(fmt = "{}", _0)]
+    Quorum(QuorumFailure),
+    /// The response was rejected by the `ResponseVerifier` of the transport.
+    #[display(fmt = "uncertified response: {}", _0)]
+    Uncertified(String),
+    /// The canister cycle balance is below the threshold of a `CyclesGuardTransport`.
+    #[display(fmt = "cycle balance {} below the threshold of {}", balance, threshold)]
+    LowCycles {
+        /// Cycle balance of the canister
+        balance: u128,
+        /// Minimum balance required to issue outcalls
+        threshold: u128,
+    },
+    /// The cycles attached to an outcall do not cover its cost.
+    #[display(fmt = "{} cycles attached to an outcall costing {}", attached, required)]
+    InsufficientCycles {
+        /// Cycles set in the call options
+        attached: u128,
+        /// Estimated cost of the outcall
+        required: u128,
+    },
+    /// The management canister rejected the outcall, for a reason without its own variant.
+    #[display(
+        fmt = "The http_request resulted into error. RejectionCode: {:?}, Error: {}",
+        code,
+        message
+    )]
+    Rejected {
+        /// Reject code of the management canister
+        code: RejectionCode,
+        /// Reject message of the management canister
+        message: String,
+    },
+    /// The response exceeds the response size limit of the outcall.
+    #[display(fmt = "response too large: {}", message)]
+    ResponseTooLarge {
+        /// Size limit given in the reject message, in bytes
+        limit: Option<u64>,
+        /// Reject message of the management canister
+        message: String,
+    },
+    /// The provider did not answer in time.
+    #[display(fmt = "outcall timed out: {}", _0)]
+    Timeout(String),
+    /// The replicas received different responses, e.g. for lack of a transform making them identical.
+    #[display(fmt = "no consensus on the outcall response: {}", _0)]
+    ConsensusFailure(String),
+}
+
+impl TransportError {
+    /// Structured error of an outcall rejected by the management canister with `code` and `message`.
+    ///
+    /// The reject messages are not part of the IC interface, so they are matched loosely; messages which
+    /// are not recognized give [`TransportError::Rejected`].
+    pub fn from_reject(code: RejectionCode, message: &str) -> Self {
+        let lowercase = message.to_lowercase();
+        let contains_any = |fragments: &[&str]| fragments.iter().any(|fragment| lowercase.contains(fragment));
+        if contains_any(&["consensus"]) {
+            return TransportError::ConsensusFailure(message.to_string());
+        }
+        if contains_any(&["timeout", "timed out", "deadline"]) {
+            return TransportError::Timeout(message.to_string());
+        }
+        if lowercase.contains("cycles") && contains_any(&["required", "not enough", "insufficient"]) {
+            if let [attached, required, ..] = numbers(&lowercase)[..] {
+                return TransportError::InsufficientCycles {
+                    attached: attached as u128,
+                    required: required as u128,
+                };
+            }
+        }
+        // e.g. `Http body exceeds size limit of 2000000 bytes.`
+        if code == RejectionCode::SysFatal
+            && contains_any(&["exceeds size limit", "exceeds specified response size limit"])
+        {
+            let limit = lowercase
+                .find("limit")
+                .and_then(|position| numbers(&lowercase[position..]).first().copied());
+            return TransportError::ResponseTooLarge {
+                limit,
+                message: message.to_string(),
+            };
+        }
+        TransportError::Rejected {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Fragments of the errors providers return when they rate limit the canister.
+const RATE_LIMIT_MESSAGES: &[&str] = &[
+    "rate limit",
+    "rate-limit",
+    "ratelimit",
+    "rate exceeded",
+    "too many requests",
+    "request limit",
+    "request count exceeded",
+    "per second",
+];
+
+/// Whether `message` is the error of a provider rate limiting the canister.
+pub(crate) fn is_rate_limit_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    RATE_LIMIT_MESSAGES.iter().any(|fragment| message.contains(fragment))
+}
+
+/// Fragments of the errors nodes return for the state of blocks they have pruned.
+const PRUNED_STATE_MESSAGES: &[&str] = &[
+    "missing trie node",
+    "required historical state unavailable",
+    "historical state",
+    "state is not available",
+    "state not available",
+    "pruned",
+];
+
+/// Fragments of the errors load-balanced providers return when the node serving a call has not seen the
+/// requested block yet.
+const UNKNOWN_BLOCK_MESSAGES: &[&str] = &["header not found", "unknown block", "block not found"];
+
+/// Numbers of `text`, in order.
+fn numbers(text: &str) -> Vec<u64> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter_map(|number| number.parse().ok())
+        .collect()
+}
+
+/// Responses of a call sent to several providers which did not reach the required quorum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuorumFailure {
+    /// Number of matching responses required
+    pub quorum: usize,
+    /// Distinct responses (or error descriptions), each with the indices of the providers that returned it
+    pub responses: Vec<(std::result::Result<rpc::Value, String>, Vec<usize>)>,
+}
+
+impl std::fmt::Display for QuorumFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "quorum of {} not reached:", self.quorum)?;
+        for (response, providers) in &self.responses {
+            match response {
+                Ok(value) => write!(f, " {:?} returned {};", providers, value)?,
+                Err(err) => write!(f, " {:?} failed with {};", providers, err)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Errors which can occur when attempting to generate resource uri.
@@ -44,6 +200,18 @@ pub enum Error {
     /// recovery error
     #[display(fmt = "Recovery error: {}", _0)]
     Recovery(crate::signing::RecoveryError),
+    /// transaction rejected by local validation before signing
+    #[display(fmt = "Invalid transaction: {}", _0)]
+    #[from(ignore)]
+    InvalidTransaction(String),
+    /// signing with the canister key failed
+    #[display(fmt = "Signing error: {}", _0)]
+    #[from(ignore)]
+    Signing(String),
+    /// the node no longer has the state of a historical block, an archive node is needed
+    #[display(fmt = "State of block {} is not available, it was pruned", _0)]
+    #[from(ignore)]
+    StatePruned(crate::types::U64),
     /// web3 internal error
     #[display(fmt = "Internal Web3 error")]
     Internal,
@@ -53,7 +221,14 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use self::Error::*;
         match *self {
-            Unreachable | Decoder(_) | InvalidResponse(_) | Transport { .. } | Internal => None,
+            Unreachable
+            | Decoder(_)
+            | InvalidResponse(_)
+            | Transport { .. }
+            | InvalidTransaction(_)
+            | Signing(_)
+            | StatePruned(_)
+            | Internal => None,
             Rpc(ref e) => Some(e),
             Io(ref e) => Some(e),
             Recovery(ref e) => Some(e),
@@ -78,7 +253,202 @@ impl Clone for Error {
             Rpc(e) => Rpc(e.clone()),
             Io(e) => Io(IoError::from(e.kind())),
             Recovery(e) => Recovery(e.clone()),
+            InvalidTransaction(s) => InvalidTransaction(s.clone()),
+            Signing(s) => Signing(s.clone()),
+            StatePruned(block) => StatePruned(*block),
             Internal => Internal,
         }
     }
 }
+
+/// Flattened error kind, meant for callers that branch on the kind of failure rather than on messages.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// The server is unreachable
+    Unreachable,
+    /// A response could not be decoded
+    Decoder,
+    /// The server returned an invalid response
+    InvalidResponse,
+    /// The HTTP outcall failed
+    Transport,
+    /// The server answered with a non-success HTTP status
+    HttpStatus,
+    /// Providers did not agree on a response
+    QuorumNotReached,
+    /// The outcall was refused as the canister is low on cycles
+    LowCycles,
+    /// The cycles attached to the outcall do not cover its cost
+    InsufficientCycles,
+    /// The management canister rejected the outcall
+    OutcallRejected,
+    /// The response exceeds the size limit of the outcall
+    ResponseTooLarge,
+    /// The outcall timed out
+    Timeout,
+    /// The replicas did not agree on the outcall response
+    ConsensusFailure,
+    /// The response failed certification
+    Uncertified,
+    /// The node returned a JSON-RPC error
+    Rpc,
+    /// IO error
+    Io,
+    /// Signer recovery failed
+    Recovery,
+    /// The transaction was rejected before signing
+    InvalidTransaction,
+    /// Signing with the canister key failed
+    Signing,
+    /// The state of a historical block was pruned by the node
+    StatePruned,
+    /// Internal error
+    Internal,
+    /// A contract call returned an unexpected output type
+    InvalidOutputType,
+    /// ABI encoding or decoding failed
+    Abi,
+    /// The contract does not support the interface
+    InterfaceUnsupported,
+    /// Inconsistent contract call options
+    InvalidOptions,
+    /// A contract call reverted
+    Reverted,
+}
+
+/// Flattened representation of an error which can be returned across canister boundaries.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ErrorInfo {
+    /// Kind of the error
+    pub code: ErrorCode,
+    /// Human readable description
+    pub message: String,
+    /// JSON-RPC error code, for `ErrorCode::Rpc`
+    pub rpc_code: Option<i64>,
+    /// JSON encoded JSON-RPC error data, for `ErrorCode::Rpc`
+    pub rpc_data: Option<String>,
+    /// HTTP status, for `ErrorCode::HttpStatus`
+    pub http_status: Option<u16>,
+}
+
+impl ErrorInfo {
+    /// Create error info with the given code and message only.
+    pub fn new(code: ErrorCode, message: String) -> Self {
+        ErrorInfo {
+            code,
+            message,
+            rpc_code: None,
+            rpc_data: None,
+            http_status: None,
+        }
+    }
+}
+
+impl Error {
+    /// Flattened kind of the error.
+    pub fn code(&self) -> ErrorCode {
+        use self::Error::*;
+        match self {
+            Unreachable => ErrorCode::Unreachable,
+            Decoder(_) => ErrorCode::Decoder,
+            InvalidResponse(_) => ErrorCode::InvalidResponse,
+            Transport(TransportError::Code(_)) => ErrorCode::HttpStatus,
+            Transport(TransportError::Quorum(_)) => ErrorCode::QuorumNotReached,
+            Transport(TransportError::LowCycles { .. }) => ErrorCode::LowCycles,
+            Transport(TransportError::InsufficientCycles { .. }) => ErrorCode::InsufficientCycles,
+            Transport(TransportError::Uncertified(_)) => ErrorCode::Uncertified,
+            Transport(TransportError::Rejected { .. }) => ErrorCode::OutcallRejected,
+            Transport(TransportError::ResponseTooLarge { .. }) => ErrorCode::ResponseTooLarge,
+            Transport(TransportError::Timeout(_)) => ErrorCode::Timeout,
+            Transport(TransportError::ConsensusFailure(_)) => ErrorCode::ConsensusFailure,
+            Transport(_) => ErrorCode::Transport,
+            Rpc(_) => ErrorCode::Rpc,
+            Io(_) => ErrorCode::Io,
+            Recovery(_) => ErrorCode::Recovery,
+            InvalidTransaction(_) => ErrorCode::InvalidTransaction,
+            Signing(_) => ErrorCode::Signing,
+            StatePruned(_) => ErrorCode::StatePruned,
+            Internal => ErrorCode::Internal,
+        }
+    }
+
+    /// Whether the node pruned the state of the requested block.
+    pub fn is_pruned_state(&self) -> bool {
+        matches!(self, Error::StatePruned(_)) || self.node_message_contains(PRUNED_STATE_MESSAGES)
+    }
+
+    /// Whether the node serving the call does not have the state of the requested block, as it has not
+    /// seen the block yet or has already pruned its state.
+    pub fn is_missing_state(&self) -> bool {
+        self.is_pruned_state() || self.node_message_contains(UNKNOWN_BLOCK_MESSAGES)
+    }
+
+    /// Whether the error message of the node contains one of `fragments`.
+    fn node_message_contains(&self, fragments: &[&str]) -> bool {
+        let message = match self {
+            Error::Rpc(err) => err.message.to_lowercase(),
+            Error::Transport(TransportError::Message(message)) => message.to_lowercase(),
+            _ => return false,
+        };
+        fragments.iter().any(|fragment| message.contains(fragment))
+    }
+
+    /// Whether a request failing with this error may still have been processed by the node, e.g. a
+    /// transaction broadcast although its outcall timed out.
+    ///
+    /// Only errors raised before the request was sent, or answers of the node itself, are known to leave
+    /// the node untouched.
+    pub fn is_outcome_unknown(&self) -> bool {
+        use self::Error::*;
+        !matches!(
+            self,
+            Rpc(_)
+                | Recovery(_)
+                | InvalidTransaction(_)
+                | Signing(_)
+                | StatePruned(_)
+                | Transport(TransportError::NotSent(_))
+                | Transport(TransportError::LowCycles { .. })
+                | Transport(TransportError::InsufficientCycles { .. })
+        )
+    }
+}
+
+impl From<&Error> for ErrorInfo {
+    fn from(err: &Error) -> Self {
+        let mut info = ErrorInfo::new(err.code(), err.to_string());
+        match err {
+            Error::Rpc(rpc) => {
+                info.rpc_code = Some(rpc.code.code());
+                info.rpc_data = rpc.data.as_ref().map(|data| data.to_string());
+            }
+            Error::Transport(TransportError::Code(status)) => info.http_status = Some(*status),
+            _ => {}
+        }
+        info
+    }
+}
+
+#[cfg(feature = "candid-errors")]
+impl CandidType for Error {
+    fn _ty() -> candid::types::Type {
+        ErrorInfo::ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> std::result::Result<(), S::Error>
+    where
+        S: candid::types::Serializer,
+    {
+        ErrorInfo::from(self).idl_serialize(serializer)
+    }
+}
+
+#[cfg(feature = "candid-errors")]
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ErrorInfo::from(self).serialize(serializer)
+    }
+}
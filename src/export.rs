@@ -0,0 +1,271 @@
+//! Export of blocks, receipts, logs and transaction costs as NDJSON or CSV.
+//!
+//! Meant for canisters serving raw chain data to analytics pipelines, e.g. from an HTTP endpoint or
+//! to other canisters. Both formats have a stable field order: NDJSON lines follow the JSON-RPC
+//! representation of the types, field by field in declaration order, and CSV rows the columns of
+//! [`CsvRecord::HEADER`].
+//!
+//! Responses of canisters are limited in size, so exports are split into chunks of bounded size, each
+//! of them being a complete document:
+//!
+//! ```ignore
+//! for chunk in export::csv_chunks(&logs, 1_000_000) {
+//!     send_chunk(chunk).await?;
+//! }
+//! ```
+
+use crate::{
+    accounting::TxCost,
+    error,
+    types::{Block, Bytes, Log, TransactionReceipt, H256, U256, U64},
+};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// A type exported as CSV rows.
+pub trait CsvRecord {
+    /// Column names, in order.
+    const HEADER: &'static [&'static str];
+
+    /// Values of the columns of [`HEADER`](Self::HEADER), unescaped, empty for missing values.
+    fn row(&self) -> Vec<String>;
+}
+
+fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+fn hash(value: &H256) -> String {
+    format!("{:?}", value)
+}
+
+fn opt_hash(value: &Option<H256>) -> String {
+    value.as_ref().map(hash).unwrap_or_default()
+}
+
+fn bytes(value: &Bytes) -> String {
+    format!("0x{}", hex::encode(&value.0))
+}
+
+fn opt_u64(value: &Option<U64>) -> String {
+    value.map(|value| value.as_u64().to_string()).unwrap_or_default()
+}
+
+fn opt_u256(value: &Option<U256>) -> String {
+    opt(value)
+}
+
+impl<TX> CsvRecord for Block<TX> {
+    const HEADER: &'static [&'static str] = &[
+        "number",
+        "hash",
+        "parent_hash",
+        "timestamp",
+        "miner",
+        "gas_used",
+        "gas_limit",
+        "base_fee_per_gas",
+        "transaction_count",
+        "state_root",
+        "transactions_root",
+        "receipts_root",
+        "extra_data",
+        "size",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            opt_u64(&self.number),
+            opt_hash(&self.hash),
+            hash(&self.parent_hash),
+            self.timestamp.to_string(),
+            format!("{:?}", self.author),
+            self.gas_used.to_string(),
+            self.gas_limit.to_string(),
+            opt_u256(&self.base_fee_per_gas),
+            self.transactions.len().to_string(),
+            hash(&self.state_root),
+            hash(&self.transactions_root),
+            hash(&self.receipts_root),
+            bytes(&self.extra_data),
+            opt_u256(&self.size),
+        ]
+    }
+}
+
+impl CsvRecord for TransactionReceipt {
+    const HEADER: &'static [&'static str] = &[
+        "block_number",
+        "block_hash",
+        "transaction_hash",
+        "transaction_index",
+        "from",
+        "to",
+        "contract_address",
+        "status",
+        "type",
+        "gas_used",
+        "cumulative_gas_used",
+        "effective_gas_price",
+        "log_count",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            opt_u64(&self.block_number),
+            opt_hash(&self.block_hash),
+            hash(&self.transaction_hash),
+            self.transaction_index.as_u64().to_string(),
+            format!("{:?}", self.from),
+            self.to.map(|to| format!("{:?}", to)).unwrap_or_default(),
+            self.contract_address
+                .map(|address| format!("{:?}", address))
+                .unwrap_or_default(),
+            opt_u64(&self.status),
+            opt_u64(&self.transaction_type),
+            opt_u256(&self.gas_used),
+            self.cumulative_gas_used.to_string(),
+            opt_u256(&self.effective_gas_price),
+            self.logs.len().to_string(),
+        ]
+    }
+}
+
+impl CsvRecord for TxCost {
+    const HEADER: &'static [&'static str] = &[
+        "block_number",
+        "transaction_hash",
+        "from",
+        "gas_used",
+        "effective_gas_price",
+        "fee",
+        "base_fee_burned",
+        "tip",
+        "blob_fee",
+        "l1_fee",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            opt_u64(&self.block_number),
+            hash(&self.transaction_hash),
+            format!("{:?}", self.from),
+            self.gas_used.to_string(),
+            self.effective_gas_price.to_string(),
+            self.fee.to_string(),
+            self.base_fee_burned.to_string(),
+            self.tip.to_string(),
+            self.blob_fee.to_string(),
+            self.l1_fee.to_string(),
+        ]
+    }
+}
+
+impl CsvRecord for Log {
+    const HEADER: &'static [&'static str] = &[
+        "block_number",
+        "block_hash",
+        "transaction_hash",
+        "transaction_index",
+        "log_index",
+        "address",
+        "topic0",
+        "topic1",
+        "topic2",
+        "topic3",
+        "data",
+        "removed",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        let topic = |index: usize| self.topics.get(index).map(hash).unwrap_or_default();
+        vec![
+            opt_u64(&self.block_number),
+            opt_hash(&self.block_hash),
+            opt_hash(&self.transaction_hash),
+            self.transaction_index
+                .map(|index| index.as_u64().to_string())
+                .unwrap_or_default(),
+            opt_u256(&self.log_index),
+            format!("{:?}", self.address),
+            topic(0),
+            topic(1),
+            topic(2),
+            topic(3),
+            bytes(&self.data),
+            self.is_removed().to_string(),
+        ]
+    }
+}
+
+/// Append a CSV line with `fields`, quoting them when needed (RFC 4180).
+fn write_csv_line<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\n', '\r']) {
+            let _ = write!(out, "\"{}\"", field.replace('"', "\"\""));
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+/// CSV document with a header line and a row per item.
+pub fn to_csv<R: CsvRecord>(items: &[R]) -> String {
+    csv_chunks(items, usize::MAX).pop().unwrap_or_else(|| {
+        let mut out = String::new();
+        write_csv_line(&mut out, R::HEADER);
+        out
+    })
+}
+
+/// CSV documents of about `max_bytes` each, all starting with the header line.
+///
+/// A chunk only goes over `max_bytes` when it holds a single row larger than that.
+pub fn csv_chunks<R: CsvRecord>(items: &[R], max_bytes: usize) -> Vec<String> {
+    let mut header = String::new();
+    write_csv_line(&mut header, R::HEADER);
+    let mut chunks = vec![];
+    let mut chunk = header.clone();
+    for item in items {
+        let mut line = String::new();
+        write_csv_line(&mut line, &item.row());
+        if chunk.len() > header.len() && chunk.len() + line.len() > max_bytes {
+            chunks.push(std::mem::replace(&mut chunk, header.clone()));
+        }
+        chunk.push_str(&line);
+    }
+    if chunk.len() > header.len() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// NDJSON document with the JSON representation of an item per line.
+pub fn to_ndjson<T: Serialize>(items: &[T]) -> error::Result<String> {
+    Ok(ndjson_chunks(items, usize::MAX)?.pop().unwrap_or_default())
+}
+
+/// NDJSON documents of about `max_bytes` each.
+///
+/// A chunk only goes over `max_bytes` when it holds a single line larger than that.
+pub fn ndjson_chunks<T: Serialize>(items: &[T], max_bytes: usize) -> error::Result<Vec<String>> {
+    let mut chunks = vec![];
+    let mut chunk = String::new();
+    for item in items {
+        let line = serde_json::to_string(item)?;
+        if !chunk.is_empty() && chunk.len() + line.len() + 1 > max_bytes {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        chunk.push_str(&line);
+        chunk.push('\n');
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
@@ -0,0 +1,405 @@
+//! Fee suggestions and resubmission fee bumping for outgoing transactions.
+
+use crate::{
+    api::Eth,
+    debug, error,
+    transports::ic_http_client::CallOptions,
+    types::{BlockNumber, TransactionParameters, EIP712_TX_TYPE, U256, U64},
+    Transport,
+};
+
+/// Fees of a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fees {
+    /// Legacy (type 0) gas price
+    Legacy {
+        /// Price paid per unit of gas
+        gas_price: U256,
+    },
+    /// EIP-1559 (type 2) fees
+    Eip1559 {
+        /// Maximum total price paid per unit of gas
+        max_fee_per_gas: U256,
+        /// Maximum tip paid to the block producer per unit of gas
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl Fees {
+    /// Maximum price paid per unit of gas.
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            Fees::Legacy { gas_price } => *gas_price,
+            Fees::Eip1559 { max_fee_per_gas, .. } => *max_fee_per_gas,
+        }
+    }
+
+    /// Maximum tip paid to the block producer per unit of gas, the whole gas price for legacy fees.
+    pub fn max_priority_fee_per_gas(&self) -> U256 {
+        match self {
+            Fees::Legacy { gas_price } => *gas_price,
+            Fees::Eip1559 {
+                max_priority_fee_per_gas,
+                ..
+            } => *max_priority_fee_per_gas,
+        }
+    }
+
+    /// Fees `tx` is signed with, `None` if it has neither gas price nor max fee per gas.
+    pub fn of(tx: &TransactionParameters) -> Option<Fees> {
+        let has_eip1559_fees = matches!(tx.transaction_type.map(|t| t.as_u64()), Some(2) | Some(EIP712_TX_TYPE));
+        match tx.max_fee_per_gas {
+            Some(max_fee_per_gas) if has_eip1559_fees => Some(Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or(max_fee_per_gas),
+            }),
+            _ => tx
+                .gas_price
+                .or(tx.max_fee_per_gas)
+                .map(|gas_price| Fees::Legacy { gas_price }),
+        }
+    }
+
+    /// Whether nodes accept these fees for a transaction replacing a pending one paying `original`.
+    ///
+    /// Both the max fee and the tip must grow by at least [`MIN_REPLACEMENT_BUMP_PERCENT`], a legacy gas
+    /// price counting as both.
+    pub fn replaces(&self, original: &Fees) -> bool {
+        is_valid_replacement(original.max_fee_per_gas(), self.max_fee_per_gas())
+            && is_valid_replacement(original.max_priority_fee_per_gas(), self.max_priority_fee_per_gas())
+    }
+
+    /// Set the fees and the matching transaction type on `tx`.
+    pub fn apply(&self, tx: &mut TransactionParameters) {
+        match *self {
+            Fees::Legacy { gas_price } => {
+                tx.transaction_type = None;
+                tx.gas_price = Some(gas_price);
+                tx.max_fee_per_gas = None;
+                tx.max_priority_fee_per_gas = None;
+            }
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                tx.transaction_type = Some(U64::from(2));
+                tx.gas_price = None;
+                tx.max_fee_per_gas = Some(max_fee_per_gas);
+                tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            }
+        }
+    }
+}
+
+/// Suggests transaction fees from `eth_feeHistory`, or `eth_gasPrice` on networks without EIP-1559.
+///
+/// The EIP-1559 priority fee is the average of the `priority_fee_percentile` rewards of the last
+/// `blocks` blocks, and the max fee leaves room for the base fee to grow `base_fee_multiplier` times
+/// before the transaction is included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GasOracle {
+    /// Number of past blocks considered
+    pub blocks: u64,
+    /// Percentile of the priority fees paid in past blocks
+    pub priority_fee_percentile: f64,
+    /// Growth of the base fee tolerated by the max fee
+    pub base_fee_multiplier: u64,
+}
+
+impl Default for GasOracle {
+    fn default() -> Self {
+        Self {
+            blocks: 5,
+            priority_fee_percentile: 50.0,
+            base_fee_multiplier: 2,
+        }
+    }
+}
+
+impl GasOracle {
+    /// Create a gas oracle with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of past blocks considered.
+    pub fn with_blocks(mut self, blocks: u64) -> Self {
+        self.blocks = blocks.max(1);
+        self
+    }
+
+    /// Set the percentile of the priority fees paid in past blocks.
+    pub fn with_priority_fee_percentile(mut self, percentile: f64) -> Self {
+        self.priority_fee_percentile = percentile.clamp(0.0, 100.0);
+        self
+    }
+
+    /// Set the growth of the base fee tolerated by the max fee.
+    pub fn with_base_fee_multiplier(mut self, multiplier: u64) -> Self {
+        self.base_fee_multiplier = multiplier;
+        self
+    }
+
+    /// Suggest fees for a transaction sent now, EIP-1559 ones when `eip1559` is set.
+    ///
+    /// Networks without a base fee are detected from `eth_feeHistory` failing or reporting no base
+    /// fee, in which case a legacy gas price is suggested instead, with a warning.
+    pub async fn fees<T: Transport>(&self, eth: &Eth<T>, eip1559: bool, options: CallOptions) -> error::Result<Fees> {
+        if eip1559 {
+            match self.eip1559_fees(eth, options.clone()).await {
+                Ok(Some(fees)) => return Ok(fees),
+                Ok(None) => debug::warn("no base fee in the fee history, falling back to a legacy gas price"),
+                Err(error::Error::Rpc(err)) => debug::warn(&format!(
+                    "eth_feeHistory failed ({}), falling back to a legacy gas price",
+                    err.message
+                )),
+                Err(err) => return Err(err),
+            }
+        }
+        let gas_price = eth.gas_price(options).await?;
+        Ok(Fees::Legacy { gas_price })
+    }
+
+    /// EIP-1559 fees from the fee history, `None` if the network has no base fee.
+    async fn eip1559_fees<T: Transport>(&self, eth: &Eth<T>, options: CallOptions) -> error::Result<Option<Fees>> {
+        let history = eth
+            .fee_history(
+                self.blocks.into(),
+                BlockNumber::Latest,
+                Some(vec![self.priority_fee_percentile]),
+                options,
+            )
+            .await?;
+        // The last base fee is the one of the next block, zero before EIP-1559.
+        let base_fee = match history.base_fee_per_gas.last() {
+            Some(base_fee) if !base_fee.is_zero() => *base_fee,
+            _ => return Ok(None),
+        };
+        let rewards: Vec<U256> = history
+            .reward
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|rewards| rewards.first().copied())
+            .collect();
+        let max_priority_fee_per_gas = if rewards.is_empty() {
+            U256::zero()
+        } else {
+            rewards.iter().fold(U256::zero(), |sum, reward| sum + reward) / rewards.len()
+        };
+        Ok(Some(Fees::Eip1559 {
+            max_fee_per_gas: base_fee * self.base_fee_multiplier + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+        }))
+    }
+}
+
+/// Minimum increase, in percent, of the fees of a transaction replacing a pending one with the same nonce,
+/// under which nodes reject the replacement as underpriced.
+pub const MIN_REPLACEMENT_BUMP_PERCENT: u64 = 10;
+
+/// Lowest fee replacing a pending transaction paying `fee`, i.e. `fee` increased by
+/// [`MIN_REPLACEMENT_BUMP_PERCENT`] and rounded up.
+pub fn min_replacement_fee(fee: U256) -> U256 {
+    increase(fee, MIN_REPLACEMENT_BUMP_PERCENT)
+}
+
+/// Whether nodes accept `new` as the fee of a transaction replacing a pending one paying `old`.
+pub fn is_valid_replacement(old: U256, new: U256) -> bool {
+    new >= min_replacement_fee(old)
+}
+
+/// Fee of the `attempt`-th resubmission of a transaction first sent with `fee`, with the default schedule.
+///
+/// Each attempt bumps the previous fee by the replacement minimum, so attempt 0 is `fee` and attempt 2 is
+/// about `fee * 1.21`.
+pub fn bump(fee: U256, attempt: u32) -> U256 {
+    BumpSchedule::default().bump(fee, attempt)
+}
+
+/// Growth of the fee across resubmissions of a transaction.
+#[derive(Clone, Copy, Debug)]
+pub enum BumpCurve {
+    /// Add `percent` of the initial fee at each attempt
+    Linear {
+        /// Increase per attempt, in percent of the initial fee
+        percent: u64,
+    },
+    /// Multiply the previous fee by `1 + percent / 100` at each attempt
+    Geometric {
+        /// Increase per attempt, in percent of the previous fee
+        percent: u64,
+    },
+    /// Compute the fee of an attempt from the initial fee and the attempt number
+    Custom(fn(U256, u32) -> U256),
+}
+
+impl BumpCurve {
+    fn fee(&self, initial: U256, attempt: u32) -> U256 {
+        match *self {
+            BumpCurve::Linear { percent } => {
+                let step = initial.saturating_mul(percent.into()) / 100;
+                initial.saturating_add(step.saturating_mul(attempt.into()))
+            }
+            BumpCurve::Geometric { percent } => (0..attempt).fold(initial, |fee, _| increase(fee, percent)),
+            BumpCurve::Custom(curve) => curve(initial, attempt),
+        }
+    }
+}
+
+/// Deterministic schedule of the fees of the resubmissions of a transaction.
+///
+/// Whatever the curve, the fee of every attempt is at least the minimum replacement of the previous one,
+/// so that nodes never reject a resubmission as underpriced. The cap takes precedence over that minimum:
+/// once reached, the fee stops growing and further resubmissions are rejected by the nodes still holding
+/// the previous one.
+#[derive(Clone, Copy, Debug)]
+pub struct BumpSchedule {
+    /// Growth of the fee
+    pub curve: BumpCurve,
+    /// Maximum fee
+    pub cap: Option<U256>,
+}
+
+impl Default for BumpSchedule {
+    fn default() -> Self {
+        Self {
+            curve: BumpCurve::Geometric {
+                percent: MIN_REPLACEMENT_BUMP_PERCENT,
+            },
+            cap: None,
+        }
+    }
+}
+
+impl BumpSchedule {
+    /// Create a schedule bumping the fee by the replacement minimum at each attempt, without a cap.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the growth of the fee.
+    pub fn with_curve(mut self, curve: BumpCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Set the maximum fee.
+    pub fn with_cap(mut self, cap: U256) -> Self {
+        self.cap = Some(cap);
+        self
+    }
+
+    /// Fee of the `attempt`-th resubmission of a transaction first sent with `fee`, attempt 0 being the
+    /// first submission.
+    pub fn bump(&self, fee: U256, attempt: u32) -> U256 {
+        let mut previous = fee;
+        for attempt in 1..=attempt {
+            previous = self.curve.fee(fee, attempt).max(min_replacement_fee(previous));
+        }
+        match self.cap {
+            Some(cap) => previous.min(cap),
+            None => previous,
+        }
+    }
+
+    /// Fees of the `attempt`-th resubmission of a transaction first sent with `fees`.
+    ///
+    /// Both EIP-1559 fees are bumped, as nodes require both of them to grow for a replacement.
+    pub fn bump_fees(&self, fees: Fees, attempt: u32) -> Fees {
+        match fees {
+            Fees::Legacy { gas_price } => Fees::Legacy {
+                gas_price: self.bump(gas_price, attempt),
+            },
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                let max_fee_per_gas = self.bump(max_fee_per_gas, attempt);
+                Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas: self.bump(max_priority_fee_per_gas, attempt).min(max_fee_per_gas),
+                }
+            }
+        }
+    }
+}
+
+/// `fee` increased by `percent`, rounded up.
+fn increase(fee: U256, percent: u64) -> U256 {
+    let increase = fee.saturating_mul(percent.into()).saturating_add(99.into()) / 100;
+    fee.saturating_add(increase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacement_minimum_is_ten_percent_rounded_up() {
+        assert_eq!(min_replacement_fee(100.into()), 110.into());
+        assert_eq!(min_replacement_fee(101.into()), 112.into());
+        assert_eq!(min_replacement_fee(1.into()), 2.into());
+        assert_eq!(min_replacement_fee(0.into()), 0.into());
+        assert!(!is_valid_replacement(100.into(), 109.into()));
+        assert!(is_valid_replacement(100.into(), 110.into()));
+    }
+
+    #[test]
+    fn default_bump_compounds_the_replacement_minimum() {
+        assert_eq!(bump(100.into(), 0), 100.into());
+        assert_eq!(bump(100.into(), 1), 110.into());
+        assert_eq!(bump(100.into(), 2), 121.into());
+        assert_eq!(bump(1.into(), 3), 4.into());
+        assert_eq!(bump(U256::MAX, 1), U256::MAX);
+    }
+
+    #[test]
+    fn curves_never_go_below_the_replacement_minimum() {
+        let slow = BumpSchedule::new().with_curve(BumpCurve::Linear { percent: 5 });
+        assert_eq!(slow.bump(1_000.into(), 1), 1_100.into());
+        assert_eq!(slow.bump(1_000.into(), 2), 1_210.into());
+
+        let fast = BumpSchedule::new().with_curve(BumpCurve::Linear { percent: 20 });
+        assert_eq!(fast.bump(1_000.into(), 1), 1_200.into());
+        assert_eq!(fast.bump(1_000.into(), 2), 1_400.into());
+
+        let doubling = BumpSchedule::new().with_curve(BumpCurve::Custom(|fee, attempt| fee << attempt));
+        assert_eq!(doubling.bump(1_000.into(), 3), 8_000.into());
+    }
+
+    #[test]
+    fn cap_takes_precedence() {
+        let schedule = BumpSchedule::new().with_cap(115.into());
+        assert_eq!(schedule.bump(100.into(), 1), 110.into());
+        assert_eq!(schedule.bump(100.into(), 2), 115.into());
+        assert_eq!(schedule.bump(100.into(), 10), 115.into());
+    }
+
+    #[test]
+    fn bumped_fees_replace_the_original() {
+        let original = Fees::Eip1559 {
+            max_fee_per_gas: 100.into(),
+            max_priority_fee_per_gas: 3.into(),
+        };
+        let bumped = BumpSchedule::new().bump_fees(original, 1);
+        assert_eq!(
+            bumped,
+            Fees::Eip1559 {
+                max_fee_per_gas: 110.into(),
+                max_priority_fee_per_gas: 4.into(),
+            }
+        );
+        assert!(bumped.replaces(&original));
+        assert!(!original.replaces(&original));
+
+        let capped = BumpSchedule::new().with_cap(105.into()).bump_fees(
+            Fees::Eip1559 {
+                max_fee_per_gas: 100.into(),
+                max_priority_fee_per_gas: 100.into(),
+            },
+            1,
+        );
+        assert_eq!(capped.max_fee_per_gas(), 105.into());
+        assert_eq!(capped.max_priority_fee_per_gas(), 105.into());
+    }
+}
@@ -68,25 +68,61 @@ pub fn build_request(id: usize, method: &str, params: Vec<rpc::Value>) -> rpc::C
     })
 }
 
-/// Parse bytes slice into JSON-RPC response.
-/// It looks for arbitrary_precision feature as a temporary workaround for https://github.com/tomusdrw/rust-web3/issues/460.
+/// Encode a JSON-RPC request into the body sent by the HTTP transports.
+pub fn encode_request(request: &rpc::Request) -> Vec<u8> {
+    serde_json::to_vec(request).expect("JSON-RPC requests never fail to serialize.")
+}
+
+/// Encode a `method` call into the exact bytes the HTTP transports send, for requests sent through other
+/// channels, e.g. a gateway canister.
+///
+/// The request is compact JSON with the fields in the order `jsonrpc`, `method`, `params`, `id`, and
+/// the id is always 0, so the same call always encodes to the same bytes:
+///
+/// ```ignore
+/// let tx = Bytes(vec![0x02, 0xf8]);
+/// let body = helpers::encode_rpc_request("eth_sendRawTransaction", vec![helpers::serialize(&tx)]);
+/// assert_eq!(body, br#"{"jsonrpc":"2.0","method":"eth_sendRawTransaction","params":["0x02f8"],"id":0}"#);
+/// ```
+pub fn encode_rpc_request(method: &str, params: Vec<rpc::Value>) -> Vec<u8> {
+    encode_request(&rpc::Request::Single(build_request(0, method, params)))
+}
+
+/// Parse bytes slice into JSON-RPC response, see [`from_slice`].
 pub fn to_response_from_slice(response: &[u8]) -> error::Result<rpc::Response> {
-    arbitrary_precision_deserialize_workaround(response).map_err(|e| Error::InvalidResponse(format!("{:?}", e)))
+    from_slice(response).map_err(|e| Error::InvalidResponse(format!("{:?}", e)))
 }
 
-/// Deserialize bytes into T.
-/// It looks for arbitrary_precision feature as a temporary workaround for https://github.com/tomusdrw/rust-web3/issues/460.
-pub fn arbitrary_precision_deserialize_workaround<T>(bytes: &[u8]) -> Result<T, serde_json::Error>
+/// Deserialize a JSON document into `T`, with the same results whether the `arbitrary_precision`
+/// feature is enabled or not.
+///
+/// Under the feature, deserializing straight from the bytes breaks untagged and flattened types (see
+/// https://github.com/tomusdrw/rust-web3/issues/460), so the document is first parsed into a
+/// `serde_json::Value`, which keeps numbers exactly, then converted into `T`. Without it the bytes are
+/// deserialized directly, sparing the intermediate value. Use [`JsonNumber`](crate::types::JsonNumber) for
+/// results holding plain JSON numbers which may not fit an `f64`.
+pub fn from_slice<T>(bytes: &[u8]) -> Result<T, serde_json::Error>
 where
     T: DeserializeOwned,
 {
-    if cfg!(feature = "arbitrary_precision") {
+    #[cfg(feature = "arbitrary_precision")]
+    {
         serde_json::from_value(serde_json::from_slice(bytes)?)
-    } else {
+    }
+    #[cfg(not(feature = "arbitrary_precision"))]
+    {
         serde_json::from_slice(bytes)
     }
 }
 
+/// Deserialize bytes into T, see [`from_slice`].
+pub fn arbitrary_precision_deserialize_workaround<T>(bytes: &[u8]) -> Result<T, serde_json::Error>
+where
+    T: DeserializeOwned,
+{
+    from_slice(bytes)
+}
+
 /// Parse bytes slice into JSON-RPC notification.
 pub fn to_notification_from_slice(notification: &[u8]) -> error::Result<rpc::Notification> {
     serde_json::from_slice(notification).map_err(|e| error::Error::InvalidResponse(format!("{:?}", e)))
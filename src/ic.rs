@@ -1,13 +1,19 @@
 //! IC's threshold ECDSA related functions
 
 use crate::signing;
-use crate::types::{Address, Recovery};
+use crate::types::{Address, Recovery, H256};
 use candid::{CandidType, Principal};
+use futures::future::BoxFuture;
 use libsecp256k1::{recover, Message, PublicKey, PublicKeyFormat, RecoveryId, Signature};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{cell::RefCell, collections::BTreeMap, str::FromStr};
 
 const ECDSA_SIGN_CYCLES: u64 = 10_000_000_000;
+
+thread_local! {
+    // Addresses derived by `get_eth_address`, by key name and derivation path.
+    static ADDRESSES: RefCell<BTreeMap<(String, Vec<Vec<u8>>), Address>> = const { RefCell::new(BTreeMap::new()) };
+}
 // pub type Address = [u8; 20];
 
 // #[derive(CandidType, Serialize, Debug, Clone)]
@@ -25,6 +31,29 @@ pub struct KeyInfo {
     pub ecdsa_sign_cycles: Option<u64>,
 }
 
+impl signing::Key for KeyInfo {
+    fn sign_hash(&self, hash: [u8; 32]) -> BoxFuture<'_, Result<signing::HashSignature, signing::SigningError>> {
+        let key_info = self.clone();
+        Box::pin(async move {
+            let signature = ic_raw_sign(hash.to_vec(), key_info)
+                .await
+                .map_err(signing::SigningError::Key)?;
+            Ok(signing::HashSignature {
+                r: H256::from_slice(&signature[0..32]),
+                s: H256::from_slice(&signature[32..64]),
+                recovery_id: None,
+            })
+        })
+    }
+}
+
+/// Signer of the canister threshold ECDSA key of `key_info`, its address being derived from the
+/// public key fetched from the management canister once, see [`get_eth_address`].
+pub async fn signer(key_info: KeyInfo) -> Result<signing::KeySigner<KeyInfo>, String> {
+    let address = get_eth_address(&key_info, None).await?;
+    Ok(signing::KeySigner::new(key_info, address))
+}
+
 /// get public key from ic,
 /// derivation_path: 4-byte big-endian encoding of an unsigned integer less than 2^31
 pub async fn get_public_key(
@@ -76,15 +105,33 @@ pub async fn get_eth_addr(
     } else {
         vec![ic_cdk::id().as_slice().to_vec()]
     };
-    
+
     match get_public_key(canister_id, path, name).await {
-        Ok(pubkey) => {
-            pubkey_to_address(&pubkey)
-        }
-        Err(e) => {
-            Err(e)
-        }
+        Ok(pubkey) => pubkey_to_address(&pubkey),
+        Err(e) => Err(e),
+    }
+}
+
+/// Ethereum address of the canister's threshold ECDSA key `key_info.key_name` at `derivation_path`,
+/// defaulting to `key_info.derivation_path`.
+///
+/// The public key is fetched from the management canister on the first call only, the derived address
+/// being cached in the heap of the canister for the following ones.
+pub async fn get_eth_address(key_info: &KeyInfo, derivation_path: Option<Vec<Vec<u8>>>) -> Result<Address, String> {
+    let derivation_path = derivation_path.unwrap_or_else(|| key_info.derivation_path.clone());
+    let cache_key = (key_info.key_name.clone(), derivation_path);
+    if let Some(address) = ADDRESSES.with(|addresses| addresses.borrow().get(&cache_key).copied()) {
+        return Ok(address);
     }
+    let public_key = get_public_key(None, cache_key.1.clone(), cache_key.0.clone()).await?;
+    let address = pubkey_to_address(&public_key)?;
+    ADDRESSES.with(|addresses| addresses.borrow_mut().insert(cache_key, address));
+    Ok(address)
+}
+
+/// Forget the addresses cached by [`get_eth_address`].
+pub fn clear_eth_address_cache() {
+    ADDRESSES.with(|addresses| addresses.borrow_mut().clear());
 }
 
 /// use ic's threshold ecdsa to sign a message
@@ -138,7 +185,6 @@ pub fn verify(addr: String, message: Vec<u8>, signature: Vec<u8>) -> bool {
         .unwrap()
         .as_signature()
         .unwrap();
-    
 
     recover_address(message, sig.to_vec(), rec_id as u8) == addr
 }
@@ -0,0 +1,331 @@
+//! Payment invoices paid in the native currency to per-invoice deposit addresses.
+//!
+//! Each [`Invoice`] gets its own deposit address, derived from the canister threshold ECDSA key with
+//! the derivation path of the invoice, so that payments are attributed without any data from the
+//! payer. [`Invoices::refresh`] then follows the balances of the open invoices:
+//!
+//! ```ignore
+//! let invoices = Invoices::new(web3, key_info).with_confirmations(12).with_store(store);
+//! let invoice = invoices.create(price, Duration::from_secs(3600)).await?;
+//! // show `invoice.address` to the payer, then from a timer:
+//! for invoice in invoices.refresh().await? {
+//!     if invoice.status == InvoiceStatus::Paid {
+//!         fulfill(invoice.id);
+//!     }
+//! }
+//! ```
+//!
+//! The funds stay on the deposit addresses; they are swept by a wallet of the key returned by
+//! [`Invoices::deposit_key`].
+
+use crate::{
+    debug, error, ic,
+    ic::KeyInfo,
+    runtime::{BusyFlag, Subsystem},
+    stable::{StableStore, StoreHandle},
+    types::{Address, BlockNumber, U256, U64},
+    Transport, Web3,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+/// Key prefix of the invoices persisted into a [`StableStore`].
+const STORE_PREFIX: &str = "invoices/";
+
+/// Key of the next invoice identifier, kept so that identifiers of removed invoices are not reused.
+const NEXT_ID_KEY: &[u8] = b"next";
+
+/// Component appended to the derivation path of the key for the deposit addresses of invoices.
+const DERIVATION_TAG: &[u8] = b"invoice";
+
+/// Derivation path of the deposit address of invoice `id`, below the path `base` of the key.
+pub fn invoice_derivation_path(base: &[Vec<u8>], id: u64) -> Vec<Vec<u8>> {
+    let mut path = base.to_vec();
+    path.push(DERIVATION_TAG.to_vec());
+    path.push(id.to_be_bytes().to_vec());
+    path
+}
+
+/// State of an invoice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvoiceStatus {
+    /// Waiting for the payment
+    Open,
+    /// The payment is on chain, without enough confirmations yet
+    Seen,
+    /// The payment has enough confirmations
+    Paid,
+    /// The invoice expired before the payment was seen
+    Expired,
+}
+
+impl InvoiceStatus {
+    /// Whether the invoice is still followed by [`Invoices::refresh`].
+    pub fn is_open(&self) -> bool {
+        matches!(self, InvoiceStatus::Open | InvoiceStatus::Seen)
+    }
+}
+
+/// Invoice paid to its own deposit address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invoice {
+    /// Identifier returned by [`Invoices::create`]
+    pub id: u64,
+    /// Deposit address
+    pub address: Address,
+    /// Amount due, in wei
+    pub amount: U256,
+    /// Creation time, in nanoseconds since the epoch
+    pub created_at: u64,
+    /// Expiry time, in nanoseconds since the epoch
+    pub expires_at: u64,
+    /// State
+    pub status: InvoiceStatus,
+    /// Balance of the deposit address at the last refresh, in wei
+    pub received: U256,
+    /// Block from which the payment has enough confirmations, once paid
+    pub paid_at_block: Option<U64>,
+}
+
+/// Invoices of a canister, with their deposit addresses derived from one threshold ECDSA key.
+///
+/// An invoice is paid once the balance of its deposit address covers its amount with the required
+/// number of confirmations. A payment seen before the expiry counts even if it is confirmed after it,
+/// while invoices still unpaid at the first refresh after their expiry are expired: refreshing
+/// regularly keeps this close to the expiry time.
+///
+/// The invoices are cheap to clone; clones share the same invoices.
+#[derive(Clone, Debug)]
+pub struct Invoices<T: Transport> {
+    web3: Web3<T>,
+    key_info: KeyInfo,
+    entries: Arc<Mutex<BTreeMap<u64, Invoice>>>,
+    next_id: Arc<Mutex<u64>>,
+    refreshing: BusyFlag,
+    store: Option<StoreHandle>,
+    confirmations: u64,
+}
+
+impl<T: Transport> Invoices<T> {
+    /// Create invoices paid to addresses derived from the key of `key_info`, followed through `web3`.
+    pub fn new(web3: Web3<T>, key_info: KeyInfo) -> Self {
+        Invoices {
+            web3,
+            key_info,
+            entries: Default::default(),
+            next_id: Default::default(),
+            refreshing: Default::default(),
+            store: None,
+            confirmations: 1,
+        }
+    }
+
+    /// Persist the invoices into `store`, loading the invoices created there.
+    pub fn with_store(mut self, store: Arc<dyn StableStore>) -> Self {
+        let store = StoreHandle::new(store, STORE_PREFIX);
+        let entries: BTreeMap<u64, Invoice> = store
+            .scan::<Invoice>()
+            .into_iter()
+            .map(|(_, invoice)| (invoice.id, invoice))
+            .collect();
+        let next_id = entries
+            .keys()
+            .next_back()
+            .map(|id| id + 1)
+            .max(store.get::<u64>(NEXT_ID_KEY))
+            .unwrap_or(0);
+        self.entries = Arc::new(Mutex::new(entries));
+        self.next_id = Arc::new(Mutex::new(next_id));
+        self.store = Some(store);
+        self
+    }
+
+    /// Require `confirmations` blocks, the one of the payment included, before an invoice is paid.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    fn persist(&self, invoice: &Invoice) {
+        if let Some(store) = &self.store {
+            store.insert(&invoice.id.to_be_bytes(), invoice);
+        }
+    }
+
+    /// Key of the deposit address of invoice `id`, e.g. to sweep its funds with a
+    /// [`Wallet`](crate::wallet::Wallet).
+    pub fn deposit_key(&self, id: u64) -> KeyInfo {
+        KeyInfo {
+            derivation_path: invoice_derivation_path(&self.key_info.derivation_path, id),
+            ..self.key_info.clone()
+        }
+    }
+
+    /// Create an invoice of `amount` wei expiring after `expiry`, fetching its deposit address from the
+    /// management canister.
+    pub async fn create(&self, amount: U256, expiry: Duration) -> error::Result<Invoice> {
+        let id = {
+            let mut next_id = self.next_id.lock();
+            *next_id += 1;
+            if let Some(store) = &self.store {
+                store.insert(NEXT_ID_KEY, &*next_id);
+            }
+            *next_id - 1
+        };
+        let key = self.deposit_key(id);
+        let address = ic::get_eth_address(&key, None).await.map_err(error::Error::Signing)?;
+        let now = debug::now();
+        let invoice = Invoice {
+            id,
+            address,
+            amount,
+            created_at: now,
+            expires_at: now.saturating_add(expiry.as_nanos() as u64),
+            status: InvoiceStatus::Open,
+            received: U256::zero(),
+            paid_at_block: None,
+        };
+        self.persist(&invoice);
+        self.entries.lock().insert(id, invoice.clone());
+        Ok(invoice)
+    }
+
+    /// Invoice `id`.
+    pub fn get(&self, id: u64) -> Option<Invoice> {
+        self.entries.lock().get(&id).cloned()
+    }
+
+    /// All the invoices, by identifier.
+    pub fn invoices(&self) -> Vec<Invoice> {
+        self.entries.lock().values().cloned().collect()
+    }
+
+    /// Forget invoice `id`, e.g. once fulfilled, returning whether it existed.
+    ///
+    /// Its identifier, and so its deposit address, is never reused.
+    pub fn remove(&self, id: u64) -> bool {
+        let removed = self.entries.lock().remove(&id).is_some();
+        if removed {
+            if let Some(store) = &self.store {
+                store.remove(&id.to_be_bytes());
+            }
+        }
+        removed
+    }
+
+    /// Update the open invoices from the balances of their deposit addresses, returning the invoices
+    /// whose status changed.
+    ///
+    /// A refresh started while another one is running returns immediately.
+    pub async fn refresh(&self) -> error::Result<Vec<Invoice>> {
+        let _guard = match self.refreshing.enter() {
+            Some(guard) => guard,
+            None => return Ok(vec![]),
+        };
+        self.refresh_open().await
+    }
+
+    async fn refresh_open(&self) -> error::Result<Vec<Invoice>> {
+        let open: Vec<Invoice> = self
+            .entries
+            .lock()
+            .values()
+            .filter(|invoice| invoice.status.is_open())
+            .cloned()
+            .collect();
+        if open.is_empty() {
+            return Ok(vec![]);
+        }
+        let eth = self.web3.eth();
+        let options = self.web3.transport().default_call_options();
+        let head = eth.block_number(options.clone()).await?;
+        let confirmed_block = head.saturating_sub(U64::from(self.confirmations - 1));
+
+        let mut changed = vec![];
+        for invoice in open {
+            let received = eth.balance(invoice.address, None, options.clone()).await?;
+            let confirmed = if self.confirmations == 1 {
+                received
+            } else if received >= invoice.amount {
+                let block = Some(BlockNumber::Number(confirmed_block));
+                eth.balance(invoice.address, block, options.clone()).await?
+            } else {
+                U256::zero()
+            };
+            let status = if confirmed >= invoice.amount {
+                InvoiceStatus::Paid
+            } else if received >= invoice.amount {
+                InvoiceStatus::Seen
+            } else if invoice.status == InvoiceStatus::Open && debug::now() >= invoice.expires_at {
+                InvoiceStatus::Expired
+            } else {
+                invoice.status
+            };
+
+            let mut entries = self.entries.lock();
+            // The invoice may have been removed during the balance calls.
+            let current = match entries.get_mut(&invoice.id) {
+                Some(current) => current,
+                None => continue,
+            };
+            let status_changed = current.status != status;
+            if status_changed || current.received != received {
+                current.status = status;
+                current.received = received;
+                if status == InvoiceStatus::Paid {
+                    current.paid_at_block = Some(confirmed_block);
+                }
+                self.persist(current);
+                if status_changed {
+                    changed.push(current.clone());
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+impl<T> Invoices<T>
+where
+    T: Transport + 'static,
+{
+    /// Refresh the invoices every `interval` from a canister timer, returning the timer to clear to stop.
+    pub fn start(&self, interval: Duration) -> ic_cdk_timers::TimerId {
+        let invoices = self.clone();
+        ic_cdk_timers::set_timer_interval(interval, move || {
+            let invoices = invoices.clone();
+            ic_cdk::spawn(async move {
+                if let Err(err) = invoices.refresh().await {
+                    debug::warn(&format!("invoices refresh failed: {}", err));
+                }
+            });
+        })
+    }
+}
+
+impl<T> Subsystem for Invoices<T>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    fn name(&self) -> &'static str {
+        "invoices"
+    }
+
+    fn start(&self, interval: Duration) -> Option<ic_cdk_timers::TimerId> {
+        Some(Invoices::start(self, interval))
+    }
+
+    fn persist(&self) {
+        if let Some(store) = &self.store {
+            for invoice in self.entries.lock().values() {
+                store.insert(&invoice.id.to_be_bytes(), invoice);
+            }
+            store.insert(NEXT_ID_KEY, &*self.next_id.lock());
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.refreshing.is_busy()
+    }
+}
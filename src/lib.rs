@@ -24,6 +24,10 @@ pub extern crate futures;
 pub use futures::executor::{block_on, block_on_stream};
 
 pub use ethabi;
+#[doc(hidden)]
+pub use ic_cdk;
+#[doc(hidden)]
+pub use ic_cdk_macros;
 use transports::ic_http_client::CallOptions;
 
 // it needs to be before other modules
@@ -31,14 +35,45 @@ use transports::ic_http_client::CallOptions;
 #[macro_use]
 pub mod helpers;
 
+pub mod abi;
+pub mod accounting;
 pub mod api;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "contract")]
+pub mod bridge;
+pub mod budget;
+pub mod chains;
+#[cfg(feature = "contract")]
 pub mod contract;
+pub mod create_address;
+pub mod debug;
 pub mod error;
+pub mod export;
+pub mod gas;
 pub mod ic;
+pub mod invoices;
+#[cfg(feature = "logs")]
+pub mod logs;
+pub mod nonce;
+pub mod payment_uri;
+pub mod polling;
+pub mod preview;
+pub mod quota;
+pub mod revert;
+pub mod runtime;
+pub mod scheduler;
 pub mod signing;
+pub mod stable;
+pub mod streams;
+pub mod timestamp;
+pub mod tip;
+pub mod tracker;
 pub mod transforms;
 pub mod transports;
 pub mod types;
+pub mod units;
+pub mod wallet;
 // pub mod tx_helpers;
 
 pub use crate::{
@@ -70,11 +105,21 @@ pub trait Transport: std::fmt::Debug + Clone {
 
     /// set the max response bytes, do nothing by default
     fn set_max_response_bytes(&mut self, bytes: u64) {}
+
+    /// Outcall options used when the caller does not provide any.
+    fn default_call_options(&self) -> CallOptions {
+        CallOptions::default()
+    }
+
+    /// Limits the provider puts on `eth_getLogs` queries, none by default.
+    fn log_limits(&self) -> transports::LogLimits {
+        transports::LogLimits::default()
+    }
 }
 
 impl<X, T> Transport for X
 where
-    T: Transport + ?Sized,
+    T: Transport,
     X: std::ops::Deref<Target = T>,
     X: std::fmt::Debug,
     X: Clone,
@@ -88,4 +133,40 @@ where
     fn send(&self, id: RequestId, request: rpc::Call, options: CallOptions) -> Self::Out {
         (**self).send(id, request, options)
     }
+
+    fn default_call_options(&self) -> CallOptions {
+        (**self).default_call_options()
+    }
+
+    fn log_limits(&self) -> transports::LogLimits {
+        (**self).log_limits()
+    }
+}
+
+/// A transport implementation supporting batch requests.
+pub trait BatchTransport: Transport {
+    /// The type of future this transport returns when a batch is sent.
+    type Batch: futures::Future<Output = error::Result<Vec<error::Result<rpc::Value>>>>;
+
+    /// Send prepared RPC calls in a single request, returning their results in the same order.
+    fn send_batch<I>(&self, requests: I, options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>;
+}
+
+impl<X, T> BatchTransport for X
+where
+    T: BatchTransport,
+    X: std::ops::Deref<Target = T>,
+    X: std::fmt::Debug,
+    X: Clone,
+{
+    type Batch = T::Batch;
+
+    fn send_batch<I>(&self, requests: I, options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        (**self).send_batch(requests, options)
+    }
 }
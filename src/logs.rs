@@ -0,0 +1,267 @@
+//! Pagination of large `eth_getLogs` queries.
+
+use crate::{
+    api::{Eth, Namespace},
+    error::{self, is_rate_limit_message, Error, TransportError},
+    transports::{ic_http_client::CallOptions, LogLimits},
+    types::{BlockNumber, FilterBuilder, Log, U64},
+    Transport,
+};
+use futures::{stream, Stream};
+
+/// Fragments of the error messages returned when a log query covers too many blocks or logs.
+const TOO_LARGE_MESSAGES: &[&str] = &[
+    // IC outcall rejected because the response exceeds `max_response_bytes`
+    "exceeds size limit",
+    "body exceeds",
+    "response too large",
+    "response size",
+    "more than",
+    "too many",
+    "range too large",
+    "range is too large",
+    "block range",
+    "limit exceeded",
+    "exceed maximum",
+];
+
+/// Whether `err` signals that a log query returned or would return too much data.
+///
+/// This covers the IC rejecting responses above `max_response_bytes` as well as the various errors
+/// providers return for too wide block ranges or too many results, but not their rate limits, e.g.
+/// `too many requests`, which splitting the query would only make worse.
+pub fn is_response_too_large(err: &Error) -> bool {
+    if let Error::Transport(TransportError::ResponseTooLarge { .. }) = err {
+        return true;
+    }
+    error_message(err).is_some_and(|message| {
+        let lowercase = message.to_lowercase();
+        TOO_LARGE_MESSAGES.iter().any(|fragment| lowercase.contains(fragment)) && !is_rate_limit_message(message)
+    })
+}
+
+fn error_message(err: &Error) -> Option<&str> {
+    match err {
+        Error::Transport(TransportError::Message(message)) => Some(message),
+        Error::Rpc(err) => Some(&err.message),
+        _ => None,
+    }
+}
+
+/// Number of blocks of the range suggested by a "range too large" error, if any.
+///
+/// Alchemy and Infura answer too large queries with a range that would succeed, e.g.
+/// `query returned more than 10000 results. Try with this block range [0x10d4f, 0x10d8f].`
+pub fn suggested_block_range(err: &Error) -> Option<u64> {
+    let message = error_message(err)?;
+    let start = message.find("[0x")?;
+    let end = start + message[start..].find(']')?;
+    let mut bounds = message[start + 1..end].split(',').map(|bound| {
+        let bound = bound.trim();
+        u64::from_str_radix(bound.strip_prefix("0x").unwrap_or(bound), 16).ok()
+    });
+    let (from, to) = (bounds.next()??, bounds.next()??);
+    (to >= from).then(|| to - from + 1)
+}
+
+/// Splits a log query over a block range into several `eth_getLogs` calls.
+///
+/// Pages start at `max_chunk_size` blocks, taken from the transport [`LogLimits`] when the provider has
+/// a block range limit. When a page fails with a "response too large" error, either one of the generic
+/// errors of [`is_response_too_large`] or one of the provider specific ones, it is retried with the
+/// range suggested by the error (see [`suggested_block_range`]) or else with half the blocks, down to
+/// a single block, and `max_chunk_size` is lowered below the failed size so later pages don't hit the
+/// limit again. After a successful page the size grows back, doubling up to `max_chunk_size`, unless
+/// the page already returned more than half the provider result limit.
+#[derive(Debug, Clone)]
+pub struct LogPaginator<T: Transport> {
+    eth: Eth<T>,
+    filter: FilterBuilder,
+    options: CallOptions,
+    next: U64,
+    to: U64,
+    chunk_size: u64,
+    max_chunk_size: u64,
+    limits: LogLimits,
+}
+
+impl<T: Transport> LogPaginator<T> {
+    /// Paginate the logs matching `filter` between blocks `from` and `to`, both inclusive.
+    ///
+    /// The block range set on `filter`, if any, is replaced by the page ranges.
+    pub fn new(eth: Eth<T>, filter: FilterBuilder, from: U64, to: U64, options: CallOptions) -> Self {
+        let limits = eth.transport().log_limits();
+        LogPaginator {
+            eth,
+            filter,
+            options,
+            next: from,
+            to,
+            chunk_size: 2_000,
+            max_chunk_size: 2_000,
+            limits: LogLimits::default(),
+        }
+        .with_limits(limits)
+    }
+
+    /// Use the given provider limits instead of the ones of the transport.
+    pub fn with_limits(mut self, limits: LogLimits) -> Self {
+        if let Some(max_block_range) = limits.max_block_range {
+            self = self.with_max_chunk_size(max_block_range);
+        }
+        self.limits = limits;
+        self
+    }
+
+    /// Set the maximum number of blocks covered by a single page.
+    pub fn with_max_chunk_size(mut self, max_chunk_size: u64) -> Self {
+        self.max_chunk_size = max_chunk_size.max(1);
+        self.chunk_size = self.max_chunk_size;
+        self
+    }
+
+    /// Maximum number of blocks per page, lowered whenever the provider rejects a page as too large.
+    pub fn max_chunk_size(&self) -> u64 {
+        self.max_chunk_size
+    }
+
+    /// Current number of blocks per page.
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    /// First block not fetched yet.
+    pub fn next_block(&self) -> U64 {
+        self.next
+    }
+
+    /// Whether the whole range was fetched.
+    pub fn is_done(&self) -> bool {
+        self.next > self.to
+    }
+
+    /// Fetch the next page, `None` once the whole range was fetched.
+    ///
+    /// On error the position is kept, so the same page is requested again on the next call.
+    pub async fn next_page(&mut self) -> error::Result<Option<Vec<Log>>> {
+        if self.is_done() {
+            return Ok(None);
+        }
+        loop {
+            let to = std::cmp::min(self.to, self.next + self.chunk_size - 1);
+            let filter = self
+                .filter
+                .clone()
+                .from_block(BlockNumber::Number(self.next))
+                .to_block(BlockNumber::Number(to))
+                .build();
+            match self.eth.logs(filter, self.options.clone()).await {
+                Ok(logs) => {
+                    self.next = to + 1;
+                    let dense = self
+                        .limits
+                        .max_results
+                        .is_some_and(|max_results| logs.len() as u64 > max_results / 2);
+                    if !dense {
+                        self.chunk_size = std::cmp::min(self.chunk_size.saturating_mul(2), self.max_chunk_size);
+                    }
+                    return Ok(Some(logs));
+                }
+                Err(err) if self.chunk_size > 1 && self.is_too_large(&err) => {
+                    let failed = to.as_u64() - self.next.as_u64() + 1;
+                    self.max_chunk_size = std::cmp::min(self.max_chunk_size, failed - 1).max(1);
+                    self.chunk_size = suggested_block_range(&err)
+                        .filter(|suggested| *suggested < failed)
+                        .unwrap_or(failed / 2)
+                        .clamp(1, self.max_chunk_size);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn is_too_large(&self, err: &Error) -> bool {
+        is_response_too_large(err) || error_message(err).is_some_and(|message| self.limits.is_range_error(message))
+    }
+
+    /// Fetch all the remaining pages and return the logs in chain order.
+    pub async fn collect_all(mut self) -> error::Result<Vec<Log>> {
+        let mut logs = vec![];
+        while let Some(page) = self.next_page().await? {
+            logs.extend(page);
+        }
+        Ok(logs)
+    }
+
+    /// Turn into a `Stream` of pages, ending after the last page or on the first error.
+    pub fn into_stream(self) -> impl Stream<Item = error::Result<Vec<Log>>> {
+        stream::unfold(Some(self), |this| async move {
+            let mut this = this?;
+            match this.next_page().await {
+                Ok(Some(page)) => Some((Ok(page), Some(this))),
+                Ok(None) => None,
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_response_too_large;
+    use crate::{
+        error::{Error, TransportError},
+        rpc,
+    };
+    use ic_cdk::api::call::RejectionCode;
+
+    fn rpc_error(code: i64, message: &str) -> Error {
+        Error::Rpc(rpc::Error {
+            code: rpc::ErrorCode::ServerError(code),
+            message: message.into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn too_large_queries() {
+        let rejected = TransportError::from_reject(
+            RejectionCode::SysFatal,
+            "Http body exceeds size limit of 2000000 bytes.",
+        );
+        assert!(matches!(
+            rejected,
+            TransportError::ResponseTooLarge {
+                limit: Some(2_000_000),
+                ..
+            }
+        ));
+        assert!(is_response_too_large(&Error::Transport(rejected)));
+        assert!(is_response_too_large(&rpc_error(
+            -32005,
+            "query returned more than 10000 results. Try with this block range [0x10d4f, 0x10d8f]."
+        )));
+        assert!(is_response_too_large(&rpc_error(-32602, "block range too large")));
+    }
+
+    #[test]
+    fn rate_limits_are_not_too_large() {
+        assert!(!is_response_too_large(&rpc_error(429, "Too many requests, slow down")));
+        assert!(!is_response_too_large(&rpc_error(
+            -32005,
+            "daily request count exceeded, request rate limited"
+        )));
+        assert!(!is_response_too_large(&rpc_error(
+            429,
+            "Your app has exceeded its compute units per second capacity"
+        )));
+    }
+
+    #[test]
+    fn size_limits_only_from_sys_fatal_rejections() {
+        let rejected = TransportError::from_reject(RejectionCode::SysTransient, "Http body exceeds size limit");
+        assert!(matches!(rejected, TransportError::Rejected { .. }));
+        let rejected = TransportError::from_reject(RejectionCode::SysFatal, "Request exceeds the allowed rate");
+        assert!(matches!(rejected, TransportError::Rejected { .. }));
+    }
+}
@@ -0,0 +1,245 @@
+//! Nonce management for canister-controlled addresses.
+
+use crate::{
+    api::Eth,
+    error,
+    runtime::Subsystem,
+    stable::{MemoryStore, StableStore, StateSnapshot, StoreHandle},
+    transports::ic_http_client::CallOptions,
+    types::{Address, BlockNumber, U256},
+    Transport,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+/// Key prefix of the states persisted into a [`StableStore`].
+const STORE_PREFIX: &str = "nonce/";
+
+/// Nonce bookkeeping of a single (address, chain id) pair.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceState {
+    /// Next nonce to hand out
+    pub next: U256,
+    /// Nonces handed out whose transaction was not broadcast yet
+    pub reserved: BTreeSet<U256>,
+    /// Nonces of broadcast transactions not yet accounted for by the provider transaction count
+    pub submitted: BTreeSet<U256>,
+    /// Set when a reserved nonce was released out of order or held, forcing a resync on next reservation
+    pub needs_resync: bool,
+}
+
+/// Hands out nonces for concurrently sent transactions.
+///
+/// A canister sending several transactions from its tECDSA address without waiting for each of them
+/// to be mined would otherwise read the same `eth_getTransactionCount` for all of them. The manager
+/// reserves nonces locally per (address, chain id) and only queries the provider (with the `pending`
+/// tag) the first time an address is used or after a gap has been detected.
+///
+/// The manager is cheap to clone; clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct NonceManager {
+    state: Arc<Mutex<BTreeMap<(Address, u64), NonceState>>>,
+    store: Option<StoreHandle>,
+}
+
+impl NonceManager {
+    /// Create an empty nonce manager.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a nonce manager persisting its state into `store`, loading the state stored there.
+    pub fn with_store(store: Arc<dyn StableStore>) -> Self {
+        let store = StoreHandle::new(store, STORE_PREFIX);
+        NonceManager {
+            state: Arc::new(Mutex::new(load(&store))),
+            store: Some(store),
+        }
+    }
+
+    fn persist(&self, address: Address, chain_id: u64, state: Option<&NonceState>) {
+        if let Some(store) = &self.store {
+            let key = store_key(address, chain_id);
+            match state {
+                Some(state) => store.insert(&key, state),
+                None => store.remove(&key),
+            }
+        }
+    }
+
+    /// Snapshot of the whole state, e.g. to save it in `pre_upgrade` without a [`StableStore`].
+    pub fn snapshot(&self) -> StateSnapshot {
+        let snapshot = Arc::new(MemoryStore::new());
+        let store = StoreHandle::new(snapshot.clone(), STORE_PREFIX);
+        for ((address, chain_id), state) in self.state.lock().iter() {
+            store.insert(&store_key(*address, *chain_id), state);
+        }
+        snapshot.snapshot()
+    }
+
+    /// Replace the whole state with the one of `snapshot`, writing it through to the store if any.
+    pub fn restore(&self, snapshot: StateSnapshot) {
+        let restored = load(&StoreHandle::new(
+            Arc::new(MemoryStore::from_snapshot(snapshot)),
+            STORE_PREFIX,
+        ));
+        let mut state = self.state.lock();
+        for (address, chain_id) in state.keys() {
+            if !restored.contains_key(&(*address, *chain_id)) {
+                self.persist(*address, *chain_id, None);
+            }
+        }
+        for ((address, chain_id), entry) in restored.iter() {
+            self.persist(*address, *chain_id, Some(entry));
+        }
+        *state = restored;
+    }
+
+    /// Reserve the next nonce of `address` on `chain_id`.
+    ///
+    /// Every reserved nonce must eventually be either [`confirm`](Self::confirm)ed once the transaction
+    /// was accepted by the node or [`release`](Self::release)d if it was never broadcast.
+    pub async fn reserve<T: Transport>(
+        &self,
+        eth: &Eth<T>,
+        address: Address,
+        chain_id: u64,
+        options: CallOptions,
+    ) -> error::Result<U256> {
+        let synced = self
+            .state
+            .lock()
+            .get(&(address, chain_id))
+            .is_some_and(|state| !state.needs_resync);
+        if !synced {
+            self.resync(eth, address, chain_id, options).await?;
+        }
+
+        let mut state = self.state.lock();
+        let entry = state.entry((address, chain_id)).or_default();
+        let mut nonce = entry.next;
+        while entry.reserved.contains(&nonce) || entry.submitted.contains(&nonce) {
+            nonce += U256::one();
+        }
+        entry.next = nonce + 1;
+        entry.reserved.insert(nonce);
+        self.persist(address, chain_id, Some(entry));
+        Ok(nonce)
+    }
+
+    /// Mark `nonce` as used by a transaction accepted by the node.
+    pub fn confirm(&self, address: Address, chain_id: u64, nonce: U256) {
+        if let Some(entry) = self.state.lock().get_mut(&(address, chain_id)) {
+            if entry.reserved.remove(&nonce) {
+                entry.submitted.insert(nonce);
+                self.persist(address, chain_id, Some(entry));
+            }
+        }
+    }
+
+    /// Give back a reserved `nonce` whose transaction was never broadcast.
+    ///
+    /// Releasing the most recently reserved nonce simply rewinds the counter. Releasing an older one
+    /// leaves a gap that would block every later transaction, so the next reservation resyncs with
+    /// the provider instead.
+    pub fn release(&self, address: Address, chain_id: u64, nonce: U256) {
+        if let Some(entry) = self.state.lock().get_mut(&(address, chain_id)) {
+            if !entry.reserved.remove(&nonce) {
+                return;
+            }
+            if nonce + 1 == entry.next {
+                entry.next = nonce;
+            } else {
+                entry.needs_resync = true;
+            }
+            self.persist(address, chain_id, Some(entry));
+        }
+    }
+
+    /// Keep `nonce` reserved after a send whose outcome is unknown, e.g. timed out, and resync on the next
+    /// reservation.
+    ///
+    /// The resync forgets the nonce once the provider counts the transaction. Until then it is not handed
+    /// out again, so that a transaction which did reach the node is not replaced; it must be
+    /// [`release`](Self::release)d once the transaction is known not to have been broadcast.
+    pub fn hold(&self, address: Address, chain_id: u64, nonce: U256) {
+        if let Some(entry) = self.state.lock().get_mut(&(address, chain_id)) {
+            if entry.reserved.contains(&nonce) {
+                entry.needs_resync = true;
+                self.persist(address, chain_id, Some(entry));
+            }
+        }
+    }
+
+    /// Re-read the pending transaction count of `address` from the provider.
+    ///
+    /// Nonces below the provider count are forgotten as they have been mined or queued. Those at or
+    /// above it are kept and skipped by later reservations, so gaps get filled first while nonces still
+    /// in flight are never handed out twice.
+    pub async fn resync<T: Transport>(
+        &self,
+        eth: &Eth<T>,
+        address: Address,
+        chain_id: u64,
+        options: CallOptions,
+    ) -> error::Result<U256> {
+        let count = eth
+            .transaction_count(address, Some(BlockNumber::Pending), options)
+            .await?;
+
+        let mut state = self.state.lock();
+        let entry = state.entry((address, chain_id)).or_default();
+        entry.reserved = entry.reserved.split_off(&count);
+        entry.submitted = entry.submitted.split_off(&count);
+        entry.next = count;
+        entry.needs_resync = false;
+        self.persist(address, chain_id, Some(entry));
+        Ok(entry.next)
+    }
+
+    /// Snapshot of the state tracked for `address` on `chain_id`.
+    pub fn state(&self, address: Address, chain_id: u64) -> Option<NonceState> {
+        self.state.lock().get(&(address, chain_id)).cloned()
+    }
+
+    /// Forget everything tracked for `address` on `chain_id`.
+    pub fn reset(&self, address: Address, chain_id: u64) {
+        self.state.lock().remove(&(address, chain_id));
+        self.persist(address, chain_id, None);
+    }
+}
+
+/// Key of the state of `address` on `chain_id` in the store.
+fn store_key(address: Address, chain_id: u64) -> Vec<u8> {
+    [address.as_bytes(), &chain_id.to_be_bytes()].concat()
+}
+
+/// States persisted into `store`, by address and chain id.
+fn load(store: &StoreHandle) -> BTreeMap<(Address, u64), NonceState> {
+    store
+        .scan::<NonceState>()
+        .into_iter()
+        .filter(|(key, _)| key.len() == 28)
+        .map(|(key, state)| {
+            let mut chain_id = [0u8; 8];
+            chain_id.copy_from_slice(&key[20..]);
+            ((Address::from_slice(&key[..20]), u64::from_be_bytes(chain_id)), state)
+        })
+        .collect()
+}
+
+impl Subsystem for NonceManager {
+    fn name(&self) -> &'static str {
+        "nonce manager"
+    }
+
+    fn persist(&self) {
+        for ((address, chain_id), state) in self.state.lock().iter() {
+            self.persist(*address, *chain_id, Some(state));
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! Chain-specific addresses (EIP-3770) and payment request URIs (ERC-681).
+//!
+//! Wallets share addresses prefixed with the short name of their network, e.g. `oeth:0x…`, and
+//! payment requests as `ethereum:` URIs, e.g. shown as QR codes. Payment canisters parse them into a
+//! [`ChainAddress`] or a [`PaymentRequest`], and format their own requests the same way:
+//!
+//! ```ignore
+//! let address: ChainAddress = "oeth:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse()?;
+//! assert_eq!(address.chain, Chain::Optimism);
+//!
+//! let request: PaymentRequest = "ethereum:0xA0b8…eB48@1/transfer?address=0x5aAe…BeAed&uint256=1.5e6".parse()?;
+//! let tx = request.to_transaction()?;
+//!
+//! let invoice = PaymentRequest::transfer(deposit_address, amount).on_chain(Chain::Base.id());
+//! show_qr_code(&invoice.to_string());
+//! ```
+//!
+//! ENS names are not resolved, so requests must target an address.
+
+use crate::{
+    chains::Chain,
+    preview::checksum,
+    types::{Address, Bytes, TransactionParameters, U256},
+    units::{parse_units, UnitsError},
+};
+use ethabi::{
+    param_type::{ParamType, Reader},
+    token::{LenientTokenizer, Token, Tokenizer},
+};
+use std::{fmt, str::FromStr};
+
+/// Scheme of ERC-681 URIs.
+const SCHEME: &str = "ethereum:";
+
+/// Prefix of the target of ERC-681 payment requests, optional.
+const PAY_PREFIX: &str = "pay-";
+
+/// Error while parsing an address or a payment request.
+#[derive(Debug, derive_more::Display, PartialEq, Eq, Clone)]
+pub enum PaymentUriError {
+    /// The address is not 20 hex-encoded bytes
+    #[display(fmt = "invalid address: {}", _0)]
+    InvalidAddress(String),
+    /// The address is mixed-case with an invalid EIP-55 checksum
+    #[display(fmt = "invalid address checksum: {}", _0)]
+    InvalidChecksum(String),
+    /// The short name of the network is not known
+    #[display(fmt = "unknown network: {}", _0)]
+    UnknownChain(String),
+    /// The string is not an `ethereum:` URI, or is malformed
+    #[display(fmt = "invalid payment request: {}", _0)]
+    InvalidUri(String),
+    /// A parameter of the request is invalid
+    #[display(fmt = "invalid parameter {}: {}", _0, _1)]
+    InvalidParameter(String, String),
+}
+impl std::error::Error for PaymentUriError {}
+
+/// Parse a `0x`-prefixed address, checking its EIP-55 checksum if it is mixed-case.
+pub fn parse_address(address: &str) -> Result<Address, PaymentUriError> {
+    let hex = address
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40)
+        .ok_or_else(|| PaymentUriError::InvalidAddress(address.to_string()))?;
+    let parsed: Address = hex
+        .parse()
+        .map_err(|_| PaymentUriError::InvalidAddress(address.to_string()))?;
+    let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && checksum(&parsed) != address {
+        return Err(PaymentUriError::InvalidChecksum(address.to_string()));
+    }
+    Ok(parsed)
+}
+
+/// Address on a given network, written `shortName:address` (EIP-3770).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChainAddress {
+    /// Network of the address
+    pub chain: Chain,
+    /// Address
+    pub address: Address,
+}
+
+impl ChainAddress {
+    /// `address` on `chain`.
+    pub fn new(chain: Chain, address: Address) -> Self {
+        ChainAddress { chain, address }
+    }
+}
+
+impl FromStr for ChainAddress {
+    type Err = PaymentUriError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (short_name, address) = value
+            .split_once(':')
+            .ok_or_else(|| PaymentUriError::InvalidAddress(value.to_string()))?;
+        let chain =
+            Chain::from_short_name(short_name).ok_or_else(|| PaymentUriError::UnknownChain(short_name.to_string()))?;
+        Ok(ChainAddress {
+            chain,
+            address: parse_address(address)?,
+        })
+    }
+}
+
+impl fmt::Display for ChainAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.chain.short_name(), checksum(&self.address))
+    }
+}
+
+/// Payment request, written as an `ethereum:` URI (ERC-681).
+///
+/// Plain transfers only have a value, while contract calls, e.g. ERC-20 transfers, name the function
+/// and its arguments as `type=value` parameters, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PaymentRequest {
+    /// Recipient of the transfer, or contract called
+    pub target: Address,
+    /// EIP-155 chain id of the network, the current one of the wallet if `None`
+    pub chain_id: Option<u64>,
+    /// Name of the function called
+    pub function: Option<String>,
+    /// Arguments of the function, as ABI types and values
+    pub args: Vec<(String, String)>,
+    /// Value transferred, in wei
+    pub value: Option<U256>,
+    /// Gas limit
+    pub gas_limit: Option<U256>,
+    /// Gas price
+    pub gas_price: Option<U256>,
+}
+
+impl PaymentRequest {
+    /// Request for a transfer of `value` wei to `to`.
+    pub fn transfer(to: Address, value: U256) -> Self {
+        PaymentRequest {
+            target: to,
+            value: Some(value),
+            ..Default::default()
+        }
+    }
+
+    /// Request for an ERC-20 transfer of `amount` tokens of `token`, in their smallest unit, to `to`.
+    pub fn token_transfer(token: Address, to: Address, amount: U256) -> Self {
+        PaymentRequest {
+            target: token,
+            function: Some("transfer".to_string()),
+            args: vec![
+                ("address".to_string(), checksum(&to)),
+                ("uint256".to_string(), amount.to_string()),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Request the payment on the network with EIP-155 chain id `chain_id`.
+    pub fn on_chain(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Recipient and amount of an ERC-20 transfer request, `None` for other requests.
+    pub fn erc20_transfer(&self) -> Option<(Address, U256)> {
+        match (self.function.as_deref(), self.args.as_slice()) {
+            (Some("transfer"), [(to_type, to), (amount_type, amount)])
+                if to_type == "address" && (amount_type == "uint256" || amount_type == "uint") =>
+            {
+                Some((parse_address(to).ok()?, parse_number(amount).ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Calldata of the requested function call, empty for plain transfers.
+    pub fn calldata(&self) -> Result<Bytes, PaymentUriError> {
+        let function = match &self.function {
+            Some(function) => function,
+            None => return Ok(Bytes::default()),
+        };
+        let mut types = Vec::with_capacity(self.args.len());
+        let mut tokens = Vec::with_capacity(self.args.len());
+        for (kind, value) in &self.args {
+            let invalid = |err: String| PaymentUriError::InvalidParameter(kind.clone(), err);
+            let param_type = Reader::read(kind).map_err(|err| invalid(err.to_string()))?;
+            let token = match param_type {
+                ParamType::Uint(_) => Token::Uint(parse_number(value).map_err(|err| invalid(err.to_string()))?),
+                ParamType::Int(_) => Token::Int(parse_signed(value).map_err(|err| invalid(err.to_string()))?),
+                ParamType::Address => Token::Address(parse_address(value)?),
+                _ => LenientTokenizer::tokenize(&param_type, value).map_err(|err| invalid(err.to_string()))?,
+            };
+            types.push(param_type);
+            tokens.push(token);
+        }
+        let selector = ethabi::short_signature(function, &types);
+        Ok(Bytes([&selector[..], &ethabi::encode(&tokens)].concat()))
+    }
+
+    /// Transaction paying the request, without nonce and with the default gas limit unless requested.
+    pub fn to_transaction(&self) -> Result<TransactionParameters, PaymentUriError> {
+        let mut tx = TransactionParameters {
+            to: Some(self.target),
+            value: self.value.unwrap_or_default(),
+            data: self.calldata()?,
+            chain_id: self.chain_id,
+            gas_price: self.gas_price,
+            ..Default::default()
+        };
+        if let Some(gas_limit) = self.gas_limit {
+            tx.gas = gas_limit;
+        }
+        Ok(tx)
+    }
+}
+
+/// Parse an ERC-681 number, e.g. `2.014e18`, as an unsigned integer.
+fn parse_number(number: &str) -> Result<U256, UnitsError> {
+    let number = number.strip_prefix('+').unwrap_or(number);
+    match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            let exponent = exponent
+                .parse()
+                .map_err(|_| UnitsError::InvalidNumber(number.to_string()))?;
+            parse_units(mantissa, exponent)
+        }
+        None => parse_units(number, 0),
+    }
+}
+
+/// Parse an ERC-681 number as a signed integer, in two's complement.
+fn parse_signed(number: &str) -> Result<U256, UnitsError> {
+    match number.strip_prefix('-') {
+        Some(magnitude) => {
+            let magnitude = parse_number(magnitude)?;
+            if magnitude > U256::one() << 255 {
+                return Err(UnitsError::Overflow);
+            }
+            Ok((!magnitude).overflowing_add(U256::one()).0)
+        }
+        None => {
+            let value = parse_number(number)?;
+            if value.bit(255) {
+                return Err(UnitsError::Overflow);
+            }
+            Ok(value)
+        }
+    }
+}
+
+/// Decode the `%XX` escapes of a URI component.
+fn percent_decode(value: &str) -> Result<String, PaymentUriError> {
+    let invalid = || PaymentUriError::InvalidUri(format!("invalid escape in {}", value));
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = value.get(index + 1..index + 3).ok_or_else(invalid)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+/// Escape the characters of `value` which cannot appear in a URI query value.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'[' | b']' | b',' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+impl FromStr for PaymentRequest {
+    type Err = PaymentUriError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let rest = uri
+            .get(..SCHEME.len())
+            .filter(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+            .map(|_| &uri[SCHEME.len()..])
+            .ok_or_else(|| PaymentUriError::InvalidUri(uri.to_string()))?;
+        let rest = rest.strip_prefix(PAY_PREFIX).unwrap_or(rest);
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (target, function) = match path.split_once('/') {
+            Some((target, function)) => (target, Some(function)),
+            None => (path, None),
+        };
+        let (target, chain_id) = match target.split_once('@') {
+            Some((target, chain_id)) => {
+                let chain_id = chain_id
+                    .parse()
+                    .map_err(|_| PaymentUriError::InvalidParameter("chain_id".to_string(), chain_id.to_string()))?;
+                (target, Some(chain_id))
+            }
+            None => (target, None),
+        };
+
+        let mut request = PaymentRequest {
+            target: parse_address(target)?,
+            chain_id,
+            function: function.filter(|function| !function.is_empty()).map(str::to_string),
+            ..Default::default()
+        };
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            let (key, value) = parameter
+                .split_once('=')
+                .ok_or_else(|| PaymentUriError::InvalidUri(format!("parameter without value: {}", parameter)))?;
+            let value = percent_decode(value)?;
+            let number = || {
+                parse_number(&value).map_err(|err| PaymentUriError::InvalidParameter(key.to_string(), err.to_string()))
+            };
+            match key {
+                "value" => request.value = Some(number()?),
+                "gas" | "gasLimit" => request.gas_limit = Some(number()?),
+                "gasPrice" => request.gas_price = Some(number()?),
+                _ if request.function.is_some() => request.args.push((key.to_string(), value)),
+                _ => {
+                    return Err(PaymentUriError::InvalidParameter(
+                        key.to_string(),
+                        "arguments require a function".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(request)
+    }
+}
+
+impl fmt::Display for PaymentRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", SCHEME, checksum(&self.target))?;
+        if let Some(chain_id) = self.chain_id {
+            write!(f, "@{}", chain_id)?;
+        }
+        if let Some(function) = &self.function {
+            write!(f, "/{}", function)?;
+        }
+        let parameters = self
+            .args
+            .iter()
+            .map(|(kind, value)| format!("{}={}", kind, percent_encode(value)))
+            .chain(self.value.map(|value| format!("value={}", value)))
+            .chain(self.gas_limit.map(|gas| format!("gasLimit={}", gas)))
+            .chain(self.gas_price.map(|price| format!("gasPrice={}", price)));
+        for (index, parameter) in parameters.enumerate() {
+            write!(f, "{}{}", if index == 0 { '?' } else { '&' }, parameter)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+//! Shared configuration of the polling subsystems.
+
+use crate::signing;
+use futures::channel::oneshot;
+use std::time::Duration;
+
+/// Polling schedule used by confirmation, event polling and transaction tracking.
+///
+/// Many canisters polling the same provider at the same fixed interval produce synchronized outcall
+/// bursts. `jitter` spreads the polls by adding a pseudo-random delay in `[0, jitter]` to every interval.
+/// The delay is derived from a caller supplied seed (e.g. the canister id) rather than from randomness,
+/// so all replicas of a canister compute the same schedule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PollConfig {
+    /// Base delay between two polls
+    pub interval: Duration,
+    /// Upper bound of the random delay added to `interval`
+    pub jitter: Duration,
+    /// Maximum number of polls, `None` for unbounded polling
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        PollConfig {
+            interval: Duration::from_secs(12),
+            jitter: Duration::from_secs(2),
+            max_attempts: None,
+        }
+    }
+}
+
+impl PollConfig {
+    /// Create a new config polling every `interval` without jitter nor attempt limit.
+    pub fn new(interval: Duration) -> Self {
+        PollConfig {
+            interval,
+            jitter: Duration::ZERO,
+            max_attempts: None,
+        }
+    }
+
+    /// Set the maximum random delay added to every interval.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the maximum number of polls.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Whether another poll is allowed after `attempt` polls were made.
+    pub fn can_retry(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt < max)
+    }
+
+    /// Delay to wait before poll number `attempt`, jittered deterministically from `seed`.
+    pub fn delay(&self, attempt: u32, seed: &[u8]) -> Duration {
+        let jitter_nanos = self.jitter.as_nanos() as u64;
+        if jitter_nanos == 0 {
+            return self.interval;
+        }
+        let hash = signing::keccak256(&[seed, &attempt.to_be_bytes()].concat());
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&hash[..8]);
+        let jitter = u64::from_be_bytes(word) % (jitter_nanos + 1);
+        self.interval + Duration::from_nanos(jitter)
+    }
+}
+
+/// Suspend the current call for `duration`, using a one-off canister timer to wake it up.
+pub async fn sleep(duration: Duration) {
+    let (sender, receiver) = oneshot::channel();
+    ic_cdk_timers::set_timer(duration, move || {
+        let _ = sender.send(());
+    });
+    let _ = receiver.await;
+}
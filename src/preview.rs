@@ -0,0 +1,201 @@
+//! Human-readable previews of transactions, for wallet frontends to show before approval.
+//!
+//! ```ignore
+//! let registry = AbiRegistry::new().with_contract(usdc, "USDC", erc20_abi);
+//! let preview = preview::preview(&tx, &registry);
+//! // transfer(to: 0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B, amount: 1000000) on USDC, value 0 ETH, max fee 0.0021 ETH
+//! println!("{}", preview);
+//! ```
+
+use crate::{
+    abi::registry::AbiRegistry,
+    chains::Chain,
+    signing,
+    types::{Address, TransactionParameters, U256},
+    units::format_units,
+};
+use candid::CandidType;
+use ethabi::Token;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Decimals of the native currency of EVM networks.
+const NATIVE_DECIMALS: u32 = 18;
+
+/// What a transaction does.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Transfer of the native currency, without calldata
+    Transfer,
+    /// Creation of a contract
+    Deploy,
+    /// Call of a decoded contract function
+    Call {
+        /// Function name
+        name: String,
+        /// Canonical function signature
+        signature: String,
+    },
+    /// Call whose calldata could not be decoded
+    Unknown {
+        /// Hex encoded selector, `None` for calldata shorter than a selector
+        selector: Option<String>,
+    },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Transfer => f.write_str("transfer"),
+            Action::Deploy => f.write_str("deploy contract"),
+            Action::Call { name, .. } => f.write_str(name),
+            Action::Unknown {
+                selector: Some(selector),
+            } => write!(f, "unknown call {}", selector),
+            Action::Unknown { selector: None } => f.write_str("unknown call"),
+        }
+    }
+}
+
+/// Decoded argument of a previewed call.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PreviewArg {
+    /// Parameter name, empty when the ABI does not name it
+    pub name: String,
+    /// Solidity type
+    pub kind: String,
+    /// Formatted value: checksummed addresses, decimal integers and hex bytes
+    pub value: String,
+}
+
+/// Display-ready summary of a transaction.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TxPreview {
+    /// What the transaction does
+    pub action: Action,
+    /// Checksummed recipient address, `None` for contract creations
+    pub target: Option<String>,
+    /// Label of the recipient in the registry
+    pub target_label: Option<String>,
+    /// Decoded arguments of the call
+    pub args: Vec<PreviewArg>,
+    /// Transferred value, in units of the native currency
+    pub value: String,
+    /// Maximum fee paid for the gas limit, in units of the native currency, `None` if no fee is set
+    pub max_fee: Option<String>,
+    /// Symbol of the native currency
+    pub currency: String,
+}
+
+impl fmt::Display for TxPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.action)?;
+        if matches!(self.action, Action::Call { .. }) {
+            let args: Vec<String> = self
+                .args
+                .iter()
+                .map(|arg| {
+                    if arg.name.is_empty() {
+                        arg.value.clone()
+                    } else {
+                        format!("{}: {}", arg.name, arg.value)
+                    }
+                })
+                .collect();
+            write!(f, "({})", args.join(", "))?;
+        }
+        match (&self.target_label, &self.target) {
+            (Some(label), _) => write!(f, " on {}", label)?,
+            (None, Some(target)) => write!(f, " to {}", target)?,
+            (None, None) => {}
+        }
+        write!(f, ", value {} {}", self.value, self.currency)?;
+        if let Some(max_fee) = &self.max_fee {
+            write!(f, ", max fee {} {}", max_fee, self.currency)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summarize `tx`, decoding its calldata and labelling its recipient with `registry`.
+///
+/// The currency is the one of `tx.chain_id` when it is a known [`Chain`], ETH otherwise.
+pub fn preview(tx: &TransactionParameters, registry: &AbiRegistry) -> TxPreview {
+    let data = &tx.data.0;
+    let decoded = tx.to.and_then(|to| registry.decode(&to, data));
+    let action = match (&tx.to, &decoded) {
+        (None, _) => Action::Deploy,
+        (Some(_), Some(call)) => Action::Call {
+            name: call.name.clone(),
+            signature: call.signature(),
+        },
+        (Some(_), None) if data.is_empty() => Action::Transfer,
+        (Some(_), None) => Action::Unknown {
+            selector: data.get(..4).map(|selector| format!("0x{}", hex::encode(selector))),
+        },
+    };
+    let args = decoded
+        .map(|call| {
+            call.args
+                .into_iter()
+                .map(|arg| PreviewArg {
+                    name: arg.name,
+                    kind: arg.kind.to_string(),
+                    value: format_token(&arg.value),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let max_fee_per_gas = tx.max_fee_per_gas.or(tx.gas_price);
+    let currency = tx
+        .chain_id
+        .and_then(Chain::from_id)
+        .map_or("ETH", |chain| chain.info().currency);
+    TxPreview {
+        action,
+        target: tx.to.as_ref().map(checksum),
+        target_label: tx.to.and_then(|to| registry.label(&to)).map(str::to_string),
+        args,
+        value: format_units(tx.value, NATIVE_DECIMALS),
+        max_fee: max_fee_per_gas.map(|price| format_units(tx.gas.saturating_mul(price), NATIVE_DECIMALS)),
+        currency: currency.to_string(),
+    }
+}
+
+/// EIP-55 mixed-case checksum encoding of `address`.
+pub(crate) fn checksum(address: &Address) -> String {
+    let hex = hex::encode(address.as_bytes());
+    let hash = signing::keccak256(hex.as_bytes());
+    let checksummed: String = hex
+        .chars()
+        .enumerate()
+        .map(|(index, c)| {
+            let nibble = if index % 2 == 0 {
+                hash[index / 2] >> 4
+            } else {
+                hash[index / 2] & 0x0f
+            };
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+fn format_token(token: &Token) -> String {
+    let join = |tokens: &[Token]| tokens.iter().map(format_token).collect::<Vec<_>>().join(", ");
+    match token {
+        Token::Address(address) => checksum(address),
+        Token::Uint(value) => value.to_string(),
+        Token::Int(value) if value.bit(255) => format!("-{}", (!*value).overflowing_add(U256::one()).0),
+        Token::Int(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => value.clone(),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::Array(tokens) | Token::FixedArray(tokens) => format!("[{}]", join(tokens)),
+        Token::Tuple(tokens) => format!("({})", join(tokens)),
+    }
+}
@@ -0,0 +1,351 @@
+//! Usage quotas of the canister keys per derivation path.
+//!
+//! Wallet canisters serving several users typically derive a key per user from a shared threshold
+//! ECDSA key. Signatures are a shared, rate-limited resource of the subnet, so a single user must not be
+//! able to exhaust it, nor to move unlimited value out of their address if the canister is compromised
+//! at the application level. [`KeyQuotas`] limits the number of signatures and the total value of the
+//! sent transactions per derivation path and time window, and [`QuotaKey`] enforces the quota of a
+//! path on every signature made with it, transactions and messages alike:
+//!
+//! ```ignore
+//! let quotas = KeyQuotas::with_store(store)
+//!     .with_default_quota(Quota::per_day().max_signatures(100).max_value(U256::exp10(18)));
+//! let key = QuotaKey::new(key_info.clone(), key_info.derivation_path.clone(), quotas.clone());
+//! accounts.sign_and_send_transaction(tx, from, key, chain_id, None, options).await?;
+//! // or, for a wallet
+//! let wallet = web3.wallet(key_info, chain).await?.with_quotas(quotas);
+//! ```
+
+use crate::{
+    signing::{HashSignature, Key, SigningError},
+    stable::{StableStore, StoreHandle},
+    types::U256,
+};
+use futures::future::{self, BoxFuture};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Key prefix of the usages persisted into a [`StableStore`].
+const STORE_PREFIX: &str = "quota/";
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Limits of a derivation path over a time window.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quota {
+    /// Length of the window in nanoseconds
+    pub window: u64,
+    /// Maximum number of signatures per window
+    pub max_signatures: Option<u64>,
+    /// Maximum total value of the signed transactions per window, in wei
+    pub max_value: Option<U256>,
+}
+
+impl Quota {
+    /// Unlimited quota over windows of `seconds`.
+    pub fn per_seconds(seconds: u64) -> Self {
+        Quota {
+            window: seconds.saturating_mul(NANOS_PER_SECOND),
+            max_signatures: None,
+            max_value: None,
+        }
+    }
+
+    /// Unlimited quota over hourly windows.
+    pub fn per_hour() -> Self {
+        Self::per_seconds(3_600)
+    }
+
+    /// Unlimited quota over daily windows.
+    pub fn per_day() -> Self {
+        Self::per_seconds(86_400)
+    }
+
+    /// Limit the number of signatures per window.
+    pub fn max_signatures(mut self, max_signatures: u64) -> Self {
+        self.max_signatures = Some(max_signatures);
+        self
+    }
+
+    /// Limit the total value of the signed transactions per window.
+    pub fn max_value(mut self, max_value: U256) -> Self {
+        self.max_value = Some(max_value);
+        self
+    }
+}
+
+/// Usage of a derivation path in the current window.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    /// Start of the window, in nanoseconds since the epoch
+    pub window_start: u64,
+    /// Signatures made in the window
+    pub signatures: u64,
+    /// Total value of the transactions sent in the window, in wei
+    pub value: U256,
+    /// Value of the transactions being signed or sent, held against the quota until they are settled.
+    ///
+    /// Neither persisted nor carried into the next window, as the transactions of a canister which
+    /// trapped or was upgraded during their calls are never settled.
+    #[serde(skip)]
+    pub pending: U256,
+}
+
+/// Reason a quota rejected a signature.
+#[derive(Clone, Debug, PartialEq, Eq, derive_more::Display)]
+pub enum QuotaExceeded {
+    /// The window already holds the maximum number of signatures
+    #[display(fmt = "signature quota of {} per window exhausted", _0)]
+    Signatures(u64),
+    /// The transaction would go over the maximum value of the window
+    #[display(fmt = "value quota of {} wei per window exhausted, {} wei left", max, left)]
+    Value {
+        /// Maximum value per window
+        max: U256,
+        /// Value left in the window
+        left: U256,
+    },
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+#[derive(Debug, Default)]
+struct State {
+    quotas: BTreeMap<Vec<Vec<u8>>, Quota>,
+    usages: BTreeMap<Vec<Vec<u8>>, QuotaUsage>,
+}
+
+/// Quotas and usage counters of the derivation paths.
+///
+/// Paths without a quota of their own are subject to the default quota, if any. The counters are
+/// written through to the [`StableStore`] given to [`with_store`](Self::with_store), so that they
+/// survive upgrades; the quotas themselves are configuration and must be set again after an upgrade.
+///
+/// The quotas are cheap to clone; clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct KeyQuotas {
+    state: Arc<Mutex<State>>,
+    default_quota: Option<Quota>,
+    store: Option<StoreHandle>,
+}
+
+impl KeyQuotas {
+    /// Create quotas without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create quotas persisting the usage counters into `store`.
+    pub fn with_store(store: Arc<dyn StableStore>) -> Self {
+        KeyQuotas {
+            store: Some(StoreHandle::new(store, STORE_PREFIX)),
+            ..Default::default()
+        }
+    }
+
+    /// Apply `quota` to the paths without a quota of their own.
+    pub fn with_default_quota(mut self, quota: Quota) -> Self {
+        self.default_quota = Some(quota);
+        self
+    }
+
+    /// Set the quota of `path`.
+    pub fn set_quota(&self, path: &[Vec<u8>], quota: Quota) {
+        self.state.lock().quotas.insert(path.to_vec(), quota);
+    }
+
+    /// Remove the quota of `path`, which falls back to the default quota.
+    pub fn remove_quota(&self, path: &[Vec<u8>]) {
+        self.state.lock().quotas.remove(path);
+    }
+
+    /// Quota applying to `path`.
+    pub fn quota(&self, path: &[Vec<u8>]) -> Option<Quota> {
+        self.state
+            .lock()
+            .quotas
+            .get(path)
+            .or(self.default_quota.as_ref())
+            .cloned()
+    }
+
+    /// Usage of `path` in the window containing `now`.
+    pub fn usage(&self, path: &[Vec<u8>], now: u64) -> QuotaUsage {
+        let quota = self.quota(path);
+        let mut state = self.state.lock();
+        self.current_usage(&mut state, path, quota.as_ref(), now).clone()
+    }
+
+    /// Reset the usage counters of `path`.
+    pub fn reset(&self, path: &[Vec<u8>]) {
+        self.state.lock().usages.remove(path);
+        if let Some(store) = &self.store {
+            store.remove(&store_key(path));
+        }
+    }
+
+    /// Record a signature of a transaction transferring `value` by `path` at `now`, unless it would
+    /// exceed the quota of the path.
+    pub fn charge(&self, path: &[Vec<u8>], signatures: u64, value: U256, now: u64) -> Result<(), QuotaExceeded> {
+        self.update(path, now, |quota, usage| {
+            check(quota, usage, signatures, value)?;
+            usage.signatures += signatures;
+            usage.value = usage.value.saturating_add(value);
+            Ok(())
+        })
+    }
+
+    /// Hold `value` against the quota of `path` at `now` while its transaction is signed and sent, unless
+    /// it would exceed the quota. The value must then be [`settle`](Self::settle)d.
+    pub fn reserve(&self, path: &[Vec<u8>], value: U256, now: u64) -> Result<(), QuotaExceeded> {
+        self.update(path, now, |quota, usage| {
+            check(quota, usage, 0, value)?;
+            usage.pending = usage.pending.saturating_add(value);
+            Ok(())
+        })
+    }
+
+    /// Release the `value` [reserved](Self::reserve) by `path`, charging it if its transaction was `sent`.
+    pub fn settle(&self, path: &[Vec<u8>], value: U256, sent: bool, now: u64) {
+        let _ = self.update(path, now, |_, usage| {
+            usage.pending = usage.pending.saturating_sub(value);
+            if sent {
+                usage.value = usage.value.saturating_add(value);
+            }
+            Ok(())
+        });
+    }
+
+    /// Apply `f` to the usage of `path` in the window containing `now`, persisting it if `f` succeeds.
+    fn update<F>(&self, path: &[Vec<u8>], now: u64, f: F) -> Result<(), QuotaExceeded>
+    where
+        F: FnOnce(Option<&Quota>, &mut QuotaUsage) -> Result<(), QuotaExceeded>,
+    {
+        let quota = self.quota(path);
+        let mut state = self.state.lock();
+        let usage = self.current_usage(&mut state, path, quota.as_ref(), now);
+        f(quota.as_ref(), usage)?;
+        let usage = usage.clone();
+        if let Some(store) = &self.store {
+            store.insert(&store_key(path), &usage);
+        }
+        Ok(())
+    }
+
+    /// Usage of `path` in the window containing `now`, starting a new window if the last one is over.
+    fn current_usage<'a>(
+        &self,
+        state: &'a mut State,
+        path: &[Vec<u8>],
+        quota: Option<&Quota>,
+        now: u64,
+    ) -> &'a mut QuotaUsage {
+        if !state.usages.contains_key(path) {
+            let stored = self
+                .store
+                .as_ref()
+                .and_then(|store| store.get(&store_key(path)))
+                .unwrap_or_default();
+            state.usages.insert(path.to_vec(), stored);
+        }
+        let usage = state.usages.get_mut(path).expect("inserted above");
+        let window = quota.map_or(u64::MAX, |quota| quota.window.max(1));
+        if now.saturating_sub(usage.window_start) >= window || now < usage.window_start {
+            *usage = QuotaUsage {
+                window_start: now - now % window,
+                ..Default::default()
+            };
+        }
+        usage
+    }
+}
+
+/// Check that `signatures` more signatures and a transaction of `value` fit in `quota`, the pending value
+/// included.
+fn check(quota: Option<&Quota>, usage: &QuotaUsage, signatures: u64, value: U256) -> Result<(), QuotaExceeded> {
+    let quota = match quota {
+        Some(quota) => quota,
+        None => return Ok(()),
+    };
+    if let Some(max) = quota.max_signatures {
+        if usage.signatures.saturating_add(signatures) > max {
+            return Err(QuotaExceeded::Signatures(max));
+        }
+    }
+    if let Some(max) = quota.max_value {
+        let used = usage.value.saturating_add(usage.pending);
+        if used.saturating_add(value) > max {
+            return Err(QuotaExceeded::Value {
+                max,
+                left: max.saturating_sub(used),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Store key of a path, the components prefixed with their length so that distinct paths never
+/// collide.
+fn store_key(path: &[Vec<u8>]) -> Vec<u8> {
+    let mut key = vec![];
+    for component in path {
+        key.extend_from_slice(&(component.len() as u32).to_be_bytes());
+        key.extend_from_slice(component);
+    }
+    key
+}
+
+/// A [`Key`] charging its signatures and the value of the transactions it signs to the quota of its
+/// derivation path.
+///
+/// Every signature made with the key counts, whatever signs it. The value of a transaction is held when it
+/// is authorized and only charged once the transaction was sent, or signed when the caller broadcasts it
+/// itself: a transaction whose signing or sending fails does not count towards the quota.
+#[derive(Clone, Debug)]
+pub struct QuotaKey<K> {
+    key: K,
+    path: Vec<Vec<u8>>,
+    quotas: KeyQuotas,
+}
+
+impl<K: Key> QuotaKey<K> {
+    /// Enforce the quota of `path` on the signatures of `key`.
+    pub fn new(key: K, path: Vec<Vec<u8>>, quotas: KeyQuotas) -> Self {
+        QuotaKey { key, path, quotas }
+    }
+
+    /// Underlying key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Derivation path whose quota applies.
+    pub fn path(&self) -> &[Vec<u8>] {
+        &self.path
+    }
+}
+
+impl<K: Key> Key for QuotaKey<K> {
+    fn sign_hash(&self, hash: [u8; 32]) -> BoxFuture<'_, Result<HashSignature, SigningError>> {
+        match self.quotas.charge(&self.path, 1, U256::zero(), crate::debug::now()) {
+            Ok(()) => self.key.sign_hash(hash),
+            Err(err) => Box::pin(future::ready(Err(SigningError::Key(err.to_string())))),
+        }
+    }
+
+    fn authorize_value(&self, value: U256) -> Result<(), SigningError> {
+        self.key.authorize_value(value)?;
+        self.quotas
+            .reserve(&self.path, value, crate::debug::now())
+            .map_err(|err| {
+                self.key.settle_value(value, false);
+                SigningError::Key(err.to_string())
+            })
+    }
+
+    fn settle_value(&self, value: U256, sent: bool) {
+        self.quotas.settle(&self.path, value, sent, crate::debug::now());
+        self.key.settle_value(value, sent);
+    }
+}
@@ -0,0 +1,190 @@
+//! Extraction and decoding of EVM revert data, and replay of reverted transactions.
+
+use crate::{
+    api::Eth,
+    error::{self, Error},
+    rpc::{self, Value},
+    transports::ic_http_client::CallOptions,
+    types::{BlockId, BlockNumber, Bytes, CallRequest, TransactionId, H256, U256},
+    Transport,
+};
+use ethabi::{ParamType, Token};
+
+/// Selector of the `Error(string)` revert reason emitted by `require` and `revert("...")`.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of the `Panic(uint256)` revert reason emitted by failed assertions and arithmetic errors.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Nested fields under which providers are known to report the revert data.
+const REVERT_DATA_FIELDS: &[&str] = &["data", "originalError", "error", "return", "result"];
+
+/// Decoded reason of a reverted call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RevertReason {
+    /// `Error(string)` revert with its message
+    Error(String),
+    /// `Panic(uint256)` revert with its panic code
+    Panic(U256),
+    /// Custom error of the contract ABI, with its decoded parameters
+    Custom {
+        /// Name of the error
+        name: String,
+        /// Decoded parameters
+        params: Vec<Token>,
+    },
+    /// Revert without data
+    Empty,
+    /// Revert data that could not be decoded
+    Raw(Bytes),
+}
+
+impl RevertReason {
+    /// Decode raw revert data.
+    pub fn decode(data: &[u8]) -> Self {
+        if data.is_empty() {
+            return RevertReason::Empty;
+        }
+        if data.len() < 4 {
+            return RevertReason::Raw(data.to_vec().into());
+        }
+        let (selector, payload) = data.split_at(4);
+        let decoded = if selector == ERROR_SELECTOR {
+            ethabi::decode(&[ParamType::String], payload)
+                .ok()
+                .and_then(|tokens| tokens.into_iter().next())
+                .and_then(Token::into_string)
+                .map(RevertReason::Error)
+        } else if selector == PANIC_SELECTOR {
+            ethabi::decode(&[ParamType::Uint(256)], payload)
+                .ok()
+                .and_then(|tokens| tokens.into_iter().next())
+                .and_then(Token::into_uint)
+                .map(RevertReason::Panic)
+        } else {
+            None
+        };
+        decoded.unwrap_or_else(|| RevertReason::Raw(data.to_vec().into()))
+    }
+
+    /// Decode raw revert data, matching custom errors against the errors of `abi`.
+    pub fn decode_with_abi(data: &[u8], abi: &ethabi::Contract) -> Self {
+        match Self::decode(data) {
+            RevertReason::Raw(raw) if raw.0.len() >= 4 => abi
+                .errors()
+                .find(|error| error.signature()[..4] == raw.0[..4])
+                .and_then(|error| {
+                    let params = error.decode(&raw.0[4..]).ok()?;
+                    Some(RevertReason::Custom {
+                        name: error.name.clone(),
+                        params,
+                    })
+                })
+                .unwrap_or(RevertReason::Raw(raw)),
+            reason => reason,
+        }
+    }
+}
+
+impl std::fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RevertReason::Error(message) => write!(f, "{}", message),
+            RevertReason::Panic(code) => write!(f, "panic 0x{:x}", code),
+            RevertReason::Custom { name, params } => {
+                write!(f, "{}(", name)?;
+                for (index, param) in params.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, ")")
+            }
+            RevertReason::Empty => write!(f, "reverted without reason"),
+            RevertReason::Raw(data) => write!(f, "0x{}", hex::encode(&data.0)),
+        }
+    }
+}
+
+/// Extract the revert data from a JSON-RPC error, whatever field the provider used for it.
+///
+/// Geth-like nodes put the hex data directly into `error.data`, others nest it (e.g. under
+/// `error.data.data` or `error.data.originalError.data`) or prefix it (`"Reverted 0x..."`).
+pub fn revert_data(error: &rpc::Error) -> Option<Bytes> {
+    error.data.as_ref().and_then(find_revert_data).map(Bytes)
+}
+
+/// Extract and decode the revert reason of a failed call, `None` if the error is not a revert.
+pub fn revert_reason(error: &Error) -> Option<RevertReason> {
+    match error {
+        Error::Rpc(error) => revert_data(error).map(|data| RevertReason::decode(&data.0)),
+        _ => None,
+    }
+}
+
+fn find_revert_data(value: &Value) -> Option<Vec<u8>> {
+    match value {
+        Value::String(s) => {
+            let s = s.trim();
+            let hex = s.strip_prefix("Reverted ").unwrap_or(s).strip_prefix("0x")?;
+            hex::decode(hex).ok()
+        }
+        Value::Object(map) => REVERT_DATA_FIELDS
+            .iter()
+            .filter_map(|field| map.get(*field))
+            .chain(map.values())
+            .find_map(find_revert_data),
+        _ => None,
+    }
+}
+
+/// Revert reason of the mined transaction `hash`, `None` if it succeeded.
+///
+/// Receipts only tell whether a transaction failed, so the transaction is re-executed with `eth_call`
+/// against the state of its inclusion block and the reason is decoded from the revert data, matching
+/// custom errors against `abi` when given. The replay runs after all the transactions of the block, so
+/// it may not reproduce a failure depending on the transactions that followed it in the block; the
+/// reason is then [`RevertReason::Empty`]. Replaying historical state requires an archive node for old
+/// blocks.
+pub async fn replay_revert_reason<T: Transport>(
+    eth: &Eth<T>,
+    hash: H256,
+    abi: Option<&ethabi::Contract>,
+    options: CallOptions,
+) -> error::Result<Option<RevertReason>> {
+    let receipt = eth
+        .transaction_receipt(hash, options.clone())
+        .await?
+        .ok_or_else(|| Error::InvalidResponse(format!("no receipt for transaction {:?}", hash)))?;
+    if receipt.status.is_none_or(|status| !status.is_zero()) {
+        return Ok(None);
+    }
+    let tx = eth
+        .transaction(TransactionId::Hash(hash), options.clone())
+        .await?
+        .ok_or_else(|| Error::InvalidResponse(format!("transaction {:?} not found", hash)))?;
+    let call = CallRequest {
+        from: tx.from,
+        to: tx.to,
+        gas: Some(tx.gas),
+        gas_price: tx.gas_price.filter(|_| tx.max_fee_per_gas.is_none()),
+        value: Some(tx.value),
+        data: Some(tx.input),
+        transaction_type: tx.transaction_type,
+        access_list: tx.access_list,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+    };
+    let block = receipt
+        .block_number
+        .map(|number| BlockId::Number(BlockNumber::Number(number)));
+    let data = match eth.call(call, block, options).await {
+        Ok(_) => return Ok(Some(RevertReason::Empty)),
+        Err(Error::Rpc(error)) => revert_data(&error).unwrap_or_default(),
+        Err(err) => return Err(err),
+    };
+    Ok(Some(match abi {
+        Some(abi) => RevertReason::decode_with_abi(&data.0, abi),
+        None => RevertReason::decode(&data.0),
+    }))
+}
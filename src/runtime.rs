@@ -0,0 +1,201 @@
+//! Lifecycle of the background subsystems.
+//!
+//! The scheduler, the event bridge and the other subsystems run from canister timers and write their
+//! state through to a [`StableStore`](crate::stable::StableStore). A [`Web3Runtime`] starts them and, on
+//! [`drain`](Web3Runtime::drain), stops their timers and persists their whole state, so that an upgrade
+//! does not interrupt them between two steps of a submission:
+//!
+//! ```ignore
+//! thread_local! {
+//!     static RUNTIME: Web3Runtime = Web3Runtime::new();
+//! }
+//!
+//! #[post_upgrade]
+//! fn post_upgrade() {
+//!     RUNTIME.with(|runtime| {
+//!         runtime.start(scheduler(), Duration::from_secs(60));
+//!         runtime.register(nonce_manager());
+//!     });
+//! }
+//!
+//! #[pre_upgrade]
+//! fn pre_upgrade() {
+//!     let report = RUNTIME.with(|runtime| runtime.drain());
+//!     // refuse to upgrade with report.busy subsystems, or let them resume after the upgrade
+//! }
+//! ```
+//!
+//! `pre_upgrade` cannot await, so work in flight is not waited for. The subsystems persist the state of
+//! a submission before issuing it, so that it is not issued twice after the upgrade: the scheduler loads a
+//! transaction which was being sent with an [unknown outcome](crate::scheduler::EntryStatus::OutcomeUnknown),
+//! and the tracker looks for the receipts of the replacements it was broadcasting. Reads in flight, e.g.
+//! of the invoice balances, are simply made again, while the event bridge delivers the events at least
+//! once: a delivery in flight is delivered again after the upgrade.
+//!
+//! To avoid unknown outcomes altogether, refuse the upgrade while [`DrainReport::busy`] is not empty,
+//! or call [`drain`](Web3Runtime::drain) from an update method before stopping the canister, which lets
+//! the running steps complete without new ones starting.
+
+use crate::debug;
+use candid::CandidType;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Background subsystem managed by a [`Web3Runtime`].
+pub trait Subsystem: Send + Sync {
+    /// Name of the subsystem in the reports.
+    fn name(&self) -> &'static str;
+
+    /// Start the timer running the subsystem every `interval`, if it runs from a timer.
+    fn start(&self, interval: Duration) -> Option<ic_cdk_timers::TimerId> {
+        let _ = interval;
+        None
+    }
+
+    /// Write the whole state of the subsystem to its store, if it has one.
+    ///
+    /// A step in flight must not be resumed as if it had not started once loaded from the store, e.g. a
+    /// transaction being sent is persisted as such before its outcall.
+    fn persist(&self);
+
+    /// Whether a step of the subsystem, e.g. a dispatch of due transactions, is in flight.
+    fn is_busy(&self) -> bool {
+        false
+    }
+}
+
+/// Flag of a subsystem step in flight, e.g. a dispatch, raised by [`enter`](Self::enter) for the lifetime
+/// of the returned guard.
+///
+/// The flag is lowered when the guard is dropped, which also happens when the step traps after an await:
+/// the CDK drops the future of a trapped callback in its cleanup, so that the subsystem is not left busy
+/// forever.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BusyFlag(Arc<AtomicBool>);
+
+impl BusyFlag {
+    /// Raise the flag, or return `None` when a step is already in flight.
+    pub(crate) fn enter(&self) -> Option<BusyGuard> {
+        self.0
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| BusyGuard(self.0.clone()))
+    }
+
+    /// Whether a step is in flight.
+    pub(crate) fn is_busy(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Guard of a step in flight, lowering its [`BusyFlag`] when dropped.
+#[derive(Debug)]
+pub(crate) struct BusyGuard(Arc<AtomicBool>);
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Outcome of [`Web3Runtime::drain`].
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Number of timers stopped
+    pub timers_stopped: u64,
+    /// Subsystems whose state was persisted
+    pub persisted: Vec<String>,
+    /// Subsystems with a step in flight, whose outcome may be unknown after the upgrade
+    pub busy: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    subsystems: Vec<Arc<dyn Subsystem>>,
+    timers: Vec<ic_cdk_timers::TimerId>,
+    draining: bool,
+}
+
+/// Registry of the background subsystems of a canister.
+///
+/// The runtime is cheap to clone; clones share the same subsystems.
+#[derive(Clone, Default)]
+pub struct Web3Runtime {
+    state: Arc<Mutex<State>>,
+}
+
+impl std::fmt::Debug for Web3Runtime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock();
+        let names: Vec<_> = state.subsystems.iter().map(|subsystem| subsystem.name()).collect();
+        f.debug_struct("Web3Runtime")
+            .field("subsystems", &names)
+            .field("timers", &state.timers.len())
+            .field("draining", &state.draining)
+            .finish()
+    }
+}
+
+impl Web3Runtime {
+    /// Create a runtime without subsystems.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Manage `subsystem` without starting it, e.g. a nonce manager used by the other subsystems.
+    pub fn register<S: Subsystem + 'static>(&self, subsystem: S) {
+        self.state.lock().subsystems.push(Arc::new(subsystem));
+    }
+
+    /// Manage `subsystem` and start it every `interval`.
+    ///
+    /// Nothing is started once the runtime is draining.
+    pub fn start<S: Subsystem + 'static>(&self, subsystem: S, interval: Duration) {
+        let mut state = self.state.lock();
+        if state.draining {
+            debug::warn(&format!("{} not started by a draining runtime", subsystem.name()));
+            return;
+        }
+        if let Some(timer) = subsystem.start(interval) {
+            state.timers.push(timer);
+        }
+        state.subsystems.push(Arc::new(subsystem));
+    }
+
+    /// Whether [`drain`](Self::drain) was called.
+    pub fn is_draining(&self) -> bool {
+        self.state.lock().draining
+    }
+
+    /// Stop the timers of the subsystems and persist their state.
+    ///
+    /// The subsystems stay registered, so that the runtime can be drained again, e.g. from `pre_upgrade`
+    /// after an update method drained it before stopping the canister.
+    pub fn drain(&self) -> DrainReport {
+        let mut state = self.state.lock();
+        state.draining = true;
+        let mut report = DrainReport::default();
+        for timer in state.timers.drain(..) {
+            ic_cdk_timers::clear_timer(timer);
+            report.timers_stopped += 1;
+        }
+        for subsystem in &state.subsystems {
+            if subsystem.is_busy() {
+                report.busy.push(subsystem.name().to_string());
+            }
+            subsystem.persist();
+            report.persisted.push(subsystem.name().to_string());
+        }
+        if !report.busy.is_empty() {
+            debug::warn(&format!("drained with steps in flight: {}", report.busy.join(", ")));
+        }
+        report
+    }
+}
@@ -0,0 +1,503 @@
+//! Transactions scheduled for a future block or time.
+//!
+//! A [`Scheduler`] keeps a calendar of transactions, e.g. vesting payouts or periodic settlements, and
+//! sends them from a [`Wallet`] once they are due. The calendar is persisted into a [`StableStore`] when
+//! one is given, so it survives upgrades, and is dispatched from a canister timer:
+//!
+//! ```ignore
+//! let scheduler = Scheduler::new(wallet).with_store(store);
+//! let id = scheduler.schedule_tx(
+//!     BlockOrTime::Time(vesting_end),
+//!     TxSpec::new(beneficiary).with_value(amount),
+//! );
+//! scheduler.start(Duration::from_secs(60));
+//! ```
+//!
+//! Timers do not survive upgrades, so `start` has to be called again from `post_upgrade`.
+
+use crate::{
+    debug, error,
+    runtime::{BusyFlag, Subsystem},
+    stable::{StableStore, StoreHandle},
+    types::{Address, Bytes, TransactionParameters, H256, U256, U64},
+    wallet::Wallet,
+    Transport,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+/// Key prefix of the scheduled transactions persisted into a [`StableStore`].
+const STORE_PREFIX: &str = "schedule/";
+
+/// Key of the next entry identifier, kept so that identifiers of removed entries are not reused.
+const NEXT_ID_KEY: &[u8] = b"next";
+
+/// When a scheduled transaction is due.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockOrTime {
+    /// Once the chain reaches the block with this number
+    Block(U64),
+    /// Once the IC time reaches this time, in nanoseconds since the epoch
+    Time(u64),
+}
+
+impl BlockOrTime {
+    fn is_due(&self, block: Option<U64>, now: u64) -> bool {
+        match *self {
+            BlockOrTime::Block(number) => block.is_some_and(|block| block >= number),
+            BlockOrTime::Time(time) => now >= time,
+        }
+    }
+
+    fn after(&self, every: u64) -> Self {
+        match *self {
+            BlockOrTime::Block(number) => BlockOrTime::Block(number + every),
+            BlockOrTime::Time(time) => BlockOrTime::Time(time.saturating_add(every)),
+        }
+    }
+}
+
+/// Transaction to send when due.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxSpec {
+    /// Recipient
+    pub to: Address,
+    /// Transferred value, in wei
+    pub value: U256,
+    /// Calldata
+    pub data: Bytes,
+    /// Gas limit, estimated when sending if `None`
+    pub gas: Option<U256>,
+    /// Period of recurring transactions, in blocks or nanoseconds like their due date
+    pub every: Option<u64>,
+}
+
+impl TxSpec {
+    /// Empty transaction to `to`.
+    pub fn new(to: Address) -> Self {
+        TxSpec {
+            to,
+            value: U256::zero(),
+            data: Bytes::default(),
+            gas: None,
+            every: None,
+        }
+    }
+
+    /// Transfer `value` wei.
+    pub fn with_value(mut self, value: U256) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Send `data` as calldata.
+    pub fn with_data(mut self, data: Bytes) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Use a fixed gas limit instead of estimating it.
+    pub fn with_gas(mut self, gas: U256) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    /// Send the transaction again every `every` blocks or nanoseconds after each due date.
+    pub fn every(mut self, every: u64) -> Self {
+        self.every = Some(every.max(1));
+        self
+    }
+}
+
+/// State of an entry of the calendar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryStatus {
+    /// Sent once due
+    #[default]
+    Scheduled,
+    /// Recurring transaction which failed the maximum number of times in a row, not sent until resumed
+    Paused,
+    /// Being sent by a dispatch; an entry loaded in this state was interrupted by an upgrade and is loaded
+    /// as [`OutcomeUnknown`](EntryStatus::OutcomeUnknown)
+    Sending,
+    /// The last attempt may have broadcast the transaction, e.g. its outcall timed out: not sent again
+    /// until the chain is checked and the entry is resumed or marked as sent
+    OutcomeUnknown,
+}
+
+/// Entry of the calendar.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledTx {
+    /// Identifier returned by [`Scheduler::schedule_tx`]
+    pub id: u64,
+    /// Next due date
+    pub at: BlockOrTime,
+    /// Transaction sent
+    pub spec: TxSpec,
+    /// Failed attempts at sending the transaction for the current due date
+    pub failures: u32,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    /// Hash of the last transaction sent, for recurring transactions
+    pub last_tx_hash: Option<H256>,
+    /// Whether the entry is sent when due
+    #[serde(default)]
+    pub status: EntryStatus,
+}
+
+/// Outcome of a due transaction in a dispatch.
+#[derive(Clone, Debug)]
+pub struct Dispatched {
+    /// Identifier of the scheduled transaction
+    pub id: u64,
+    /// Hash of the sent transaction, or the error which prevented sending it
+    pub result: error::Result<H256>,
+}
+
+/// Failure to send a due transaction.
+enum SendError {
+    /// Its gas or fees could not be determined, nothing was sent
+    Prepare(error::Error),
+    /// Signing or broadcasting it failed
+    Send(error::Error),
+}
+
+/// Calendar of scheduled transactions, sent from a wallet when due.
+///
+/// Transactions failing to be sent stay in the calendar and are retried by the next dispatches, up to
+/// the maximum number of failures, after which one-off transactions are dropped with a warning and
+/// recurring ones are [paused](EntryStatus::Paused). A failure which may have broadcast the transaction
+/// is not retried: the entry is marked [`OutcomeUnknown`](EntryStatus::OutcomeUnknown) until
+/// [`resume`](Scheduler::resume) or [`mark_sent`](Scheduler::mark_sent) is called. One-off transactions
+/// leave the calendar once sent; recurring ones are moved to their next due date.
+///
+/// The scheduler is cheap to clone; clones share the same calendar.
+#[derive(Clone, Debug)]
+pub struct Scheduler<T: Transport> {
+    wallet: Wallet<T>,
+    entries: Arc<Mutex<BTreeMap<u64, ScheduledTx>>>,
+    next_id: Arc<Mutex<u64>>,
+    dispatching: BusyFlag,
+    store: Option<StoreHandle>,
+    max_failures: u32,
+}
+
+impl<T: Transport> Scheduler<T> {
+    /// Create an empty calendar sending transactions from `wallet`.
+    pub fn new(wallet: Wallet<T>) -> Self {
+        Scheduler {
+            wallet,
+            entries: Default::default(),
+            next_id: Default::default(),
+            dispatching: Default::default(),
+            store: None,
+            max_failures: 5,
+        }
+    }
+
+    /// Persist the calendar into `store`, loading the transactions scheduled there.
+    ///
+    /// The transactions which were being sent when the calendar was persisted, e.g. by a
+    /// [drain](crate::runtime::Web3Runtime::drain) before an upgrade, may have been broadcast: they are
+    /// loaded as [`OutcomeUnknown`](EntryStatus::OutcomeUnknown) rather than sent again.
+    pub fn with_store(mut self, store: Arc<dyn StableStore>) -> Self {
+        let store = StoreHandle::new(store, STORE_PREFIX);
+        let entries: BTreeMap<u64, ScheduledTx> = store
+            .scan::<ScheduledTx>()
+            .into_iter()
+            .map(|(_, mut entry)| {
+                if entry.status == EntryStatus::Sending {
+                    debug::warn(&format!(
+                        "scheduled transaction {} was being sent before the upgrade, its outcome is unknown",
+                        entry.id
+                    ));
+                    entry.status = EntryStatus::OutcomeUnknown;
+                    store.insert(&entry.id.to_be_bytes(), &entry);
+                }
+                (entry.id, entry)
+            })
+            .collect();
+        let next_id = entries
+            .keys()
+            .next_back()
+            .map(|id| id + 1)
+            .max(store.get::<u64>(NEXT_ID_KEY))
+            .unwrap_or(0);
+        self.entries = Arc::new(Mutex::new(entries));
+        self.next_id = Arc::new(Mutex::new(next_id));
+        self.store = Some(store);
+        self
+    }
+
+    /// Set the number of failed attempts after which a one-off transaction is dropped from the calendar
+    /// and a recurring one is paused.
+    pub fn with_max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    fn persist(&self, id: u64, entry: Option<&ScheduledTx>) {
+        if let Some(store) = &self.store {
+            match entry {
+                Some(entry) => store.insert(&id.to_be_bytes(), entry),
+                None => store.remove(&id.to_be_bytes()),
+            }
+        }
+    }
+
+    /// Schedule `spec` to be sent at `at`, returning the identifier of the entry.
+    pub fn schedule_tx(&self, at: BlockOrTime, spec: TxSpec) -> u64 {
+        let mut entries = self.entries.lock();
+        let id = {
+            let mut next_id = self.next_id.lock();
+            *next_id += 1;
+            if let Some(store) = &self.store {
+                store.insert(NEXT_ID_KEY, &*next_id);
+            }
+            *next_id - 1
+        };
+        let entry = ScheduledTx {
+            id,
+            at,
+            spec,
+            failures: 0,
+            last_error: None,
+            last_tx_hash: None,
+            status: EntryStatus::Scheduled,
+        };
+        self.persist(id, Some(&entry));
+        entries.insert(id, entry);
+        id
+    }
+
+    /// Remove the entry `id` from the calendar, returning whether it was scheduled.
+    pub fn cancel(&self, id: u64) -> bool {
+        let removed = self.entries.lock().remove(&id).is_some();
+        if removed {
+            self.persist(id, None);
+        }
+        removed
+    }
+
+    /// Send the paused or outcome unknown entry `id` again at its current due date, returning whether
+    /// it was scheduled.
+    pub fn resume(&self, id: u64) -> bool {
+        let mut entries = self.entries.lock();
+        match entries.get_mut(&id) {
+            Some(entry) => {
+                entry.status = EntryStatus::Scheduled;
+                entry.failures = 0;
+                self.persist(id, Some(entry));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record that the transaction of the entry `id` was sent, e.g. once found on chain after an unknown
+    /// outcome, returning whether it was scheduled.
+    ///
+    /// A one-off entry leaves the calendar; a recurring one is moved to its next due date.
+    pub fn mark_sent(&self, id: u64, hash: Option<H256>) -> bool {
+        let mut entries = self.entries.lock();
+        let scheduled = entries.contains_key(&id);
+        if scheduled {
+            self.record_sent(&mut entries, id, hash);
+        }
+        scheduled
+    }
+
+    fn record_sent(&self, entries: &mut BTreeMap<u64, ScheduledTx>, id: u64, hash: Option<H256>) {
+        let entry = match entries.get_mut(&id) {
+            Some(entry) => entry,
+            None => return,
+        };
+        match entry.spec.every {
+            Some(every) => {
+                entry.at = entry.at.after(every);
+                entry.failures = 0;
+                entry.last_error = None;
+                entry.last_tx_hash = hash.or(entry.last_tx_hash);
+                entry.status = EntryStatus::Scheduled;
+                self.persist(id, Some(entry));
+            }
+            None => {
+                entries.remove(&id);
+                self.persist(id, None);
+            }
+        }
+    }
+
+    /// Entry `id` of the calendar.
+    pub fn get(&self, id: u64) -> Option<ScheduledTx> {
+        self.entries.lock().get(&id).cloned()
+    }
+
+    /// All the entries of the calendar, by identifier.
+    pub fn scheduled(&self) -> Vec<ScheduledTx> {
+        self.entries.lock().values().cloned().collect()
+    }
+
+    /// Send the transactions which are due, in order of identifier.
+    ///
+    /// The block number is only fetched when a transaction is scheduled at a block. A dispatch started
+    /// while another one is running returns immediately, so that timers firing during a slow dispatch do
+    /// not send the same transactions twice.
+    pub async fn dispatch(&self) -> error::Result<Vec<Dispatched>> {
+        let _guard = match self.dispatching.enter() {
+            Some(guard) => guard,
+            None => return Ok(vec![]),
+        };
+        self.dispatch_due().await
+    }
+
+    async fn dispatch_due(&self) -> error::Result<Vec<Dispatched>> {
+        let waits_for_block = self
+            .entries
+            .lock()
+            .values()
+            .any(|entry| matches!(entry.at, BlockOrTime::Block(_)));
+        let block = if waits_for_block {
+            let options = self.wallet.web3().transport().default_call_options();
+            Some(self.wallet.web3().eth().block_number(options).await?)
+        } else {
+            None
+        };
+        let now = debug::now();
+        let due: Vec<ScheduledTx> = self
+            .entries
+            .lock()
+            .values()
+            .filter(|entry| entry.status == EntryStatus::Scheduled && entry.at.is_due(block, now))
+            .cloned()
+            .collect();
+
+        let mut dispatched = Vec::with_capacity(due.len());
+        for entry in due {
+            let (result, unknown) = match self.send(entry.id, &entry.spec).await {
+                Ok(hash) => (Ok(hash), false),
+                Err(SendError::Prepare(err)) => (Err(err), false),
+                Err(SendError::Send(err)) => {
+                    let unknown = err.is_outcome_unknown();
+                    (Err(err), unknown)
+                }
+            };
+            let mut entries = self.entries.lock();
+            // The entry may have been cancelled while the transaction was being sent.
+            let current = match entries.get_mut(&entry.id) {
+                Some(current) => current,
+                None => {
+                    dispatched.push(Dispatched { id: entry.id, result });
+                    continue;
+                }
+            };
+            match &result {
+                Ok(hash) => self.record_sent(&mut entries, entry.id, Some(*hash)),
+                Err(err) if unknown => {
+                    debug::warn(&format!(
+                        "scheduled transaction {} may have been sent, not retrying it: {}",
+                        entry.id, err
+                    ));
+                    current.status = EntryStatus::OutcomeUnknown;
+                    current.last_error = Some(err.to_string());
+                    self.persist(entry.id, Some(current));
+                }
+                Err(err) => {
+                    current.status = EntryStatus::Scheduled;
+                    current.failures += 1;
+                    current.last_error = Some(err.to_string());
+                    if current.failures < self.max_failures {
+                        self.persist(entry.id, Some(current));
+                    } else if current.spec.every.is_some() {
+                        debug::warn(&format!(
+                            "pausing scheduled transaction {} after {} failures: {}",
+                            entry.id, current.failures, err
+                        ));
+                        current.status = EntryStatus::Paused;
+                        self.persist(entry.id, Some(current));
+                    } else {
+                        debug::warn(&format!(
+                            "dropping scheduled transaction {} after {} failures: {}",
+                            entry.id, current.failures, err
+                        ));
+                        entries.remove(&entry.id);
+                        self.persist(entry.id, None);
+                    }
+                }
+            }
+            dispatched.push(Dispatched { id: entry.id, result });
+        }
+        Ok(dispatched)
+    }
+
+    /// Send `spec` for the entry `id`, with its gas and fees set beforehand so that a failure of the
+    /// broadcast can be told apart: only the errors of [`send_transaction`](Wallet::send_transaction) may
+    /// leave the outcome unknown. The entry is persisted as [`Sending`](EntryStatus::Sending) before.
+    async fn send(&self, id: u64, spec: &TxSpec) -> std::result::Result<H256, SendError> {
+        let mut tx = match spec.gas {
+            Some(gas) => TransactionParameters {
+                to: Some(spec.to),
+                gas,
+                value: spec.value,
+                data: spec.data.clone(),
+                ..Default::default()
+            },
+            None => self
+                .wallet
+                .prepare(spec.to, spec.value, spec.data.clone())
+                .await
+                .map_err(SendError::Prepare)?,
+        };
+        self.wallet.fees().await.map_err(SendError::Prepare)?.apply(&mut tx);
+        if let Some(entry) = self.entries.lock().get_mut(&id) {
+            entry.status = EntryStatus::Sending;
+            self.persist(id, Some(entry));
+        }
+        self.wallet.send_transaction(tx).await.map_err(SendError::Send)
+    }
+}
+
+impl<T> Scheduler<T>
+where
+    T: Transport + 'static,
+{
+    /// Dispatch the calendar every `interval` from a canister timer, returning the timer to clear to stop.
+    pub fn start(&self, interval: Duration) -> ic_cdk_timers::TimerId {
+        let scheduler = self.clone();
+        ic_cdk_timers::set_timer_interval(interval, move || {
+            let scheduler = scheduler.clone();
+            ic_cdk::spawn(async move {
+                if let Err(err) = scheduler.dispatch().await {
+                    debug::warn(&format!("scheduled transactions dispatch failed: {}", err));
+                }
+            });
+        })
+    }
+}
+
+impl<T> Subsystem for Scheduler<T>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    fn start(&self, interval: Duration) -> Option<ic_cdk_timers::TimerId> {
+        Some(Scheduler::start(self, interval))
+    }
+
+    fn persist(&self) {
+        for (id, entry) in self.entries.lock().iter() {
+            self.persist(*id, Some(entry));
+        }
+        if let Some(store) = &self.store {
+            store.insert(NEXT_ID_KEY, &*self.next_id.lock());
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.dispatching.is_busy()
+    }
+}
@@ -1,6 +1,9 @@
 //! Signing capabilities and utilities.
 
-use crate::types::H256;
+use crate::types::{Address, H256, U256};
+use futures::future::{self, BoxFuture};
+
+pub use libsecp256k1::SecretKey;
 
 /// Error during signing.
 #[derive(Debug, derive_more::Display, PartialEq, Clone)]
@@ -8,6 +11,9 @@ pub enum SigningError {
     /// A message to sign is invalid. Has to be a non-zero 32-bytes slice.
     #[display(fmt = "Message has to be a non-zero 32-bytes slice.")]
     InvalidMessage,
+    /// The key source failed to sign, e.g. the threshold ECDSA call was rejected.
+    #[display(fmt = "{}", _0)]
+    Key(String),
 }
 impl std::error::Error for SigningError {}
 
@@ -33,6 +39,156 @@ pub struct Signature {
     pub s: H256,
 }
 
+/// A secp256k1 signature of a 32-byte hash, as returned by a [`Key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashSignature {
+    /// R component of the signature.
+    pub r: H256,
+    /// S component of the signature.
+    pub s: H256,
+    /// Standard recovery id (0 or 1), `None` if the key source does not know it, in which case it is
+    /// found by recovering the signer address.
+    pub recovery_id: Option<u8>,
+}
+
+/// A source of secp256k1 signatures: the canister threshold ECDSA key
+/// ([`KeyInfo`](crate::ic::KeyInfo)) or a local [`SecretKey`], e.g. for tests and usage outside of a
+/// canister.
+pub trait Key {
+    /// Sign a 32-byte hash.
+    fn sign_hash(&self, hash: [u8; 32]) -> BoxFuture<'_, Result<HashSignature, SigningError>>;
+
+    /// Authorize signing a transaction transferring `value` wei, called before its hash is signed.
+    ///
+    /// Keys enforcing spending limits, e.g. [`QuotaKey`](crate::quota::QuotaKey), reject it here, and
+    /// hold the value until it is [settled](Self::settle_value).
+    fn authorize_value(&self, value: U256) -> Result<(), SigningError> {
+        Ok(())
+    }
+
+    /// Settle the `value` of an authorized transaction: `sent` once it was broadcast, or signed for
+    /// callers broadcasting it themselves, and not `sent` if signing or sending it failed.
+    fn settle_value(&self, value: U256, sent: bool) {}
+}
+
+impl<K: Key + ?Sized> Key for &K {
+    fn sign_hash(&self, hash: [u8; 32]) -> BoxFuture<'_, Result<HashSignature, SigningError>> {
+        (**self).sign_hash(hash)
+    }
+
+    fn authorize_value(&self, value: U256) -> Result<(), SigningError> {
+        (**self).authorize_value(value)
+    }
+
+    fn settle_value(&self, value: U256, sent: bool) {
+        (**self).settle_value(value, sent)
+    }
+}
+
+impl Key for SecretKey {
+    fn sign_hash(&self, hash: [u8; 32]) -> BoxFuture<'_, Result<HashSignature, SigningError>> {
+        let message = libsecp256k1::Message::parse(&hash);
+        let (signature, recovery_id) = libsecp256k1::sign(&message, self);
+        let signature = signature.serialize();
+        Box::pin(future::ready(Ok(HashSignature {
+            r: H256::from_slice(&signature[..32]),
+            s: H256::from_slice(&signature[32..]),
+            recovery_id: Some(recovery_id.serialize()),
+        })))
+    }
+}
+
+/// A [`Key`] together with the address it signs for, so that transactions can be signed without the
+/// caller passing the sender.
+///
+/// Custom key management, e.g. an HSM or an MPC service, is plugged in by implementing [`Key`] and
+/// [`Signer`]; [`KeySigner`] does it for any key whose address is known.
+pub trait Signer: Key {
+    /// Address of the key.
+    fn address(&self) -> BoxFuture<'_, Result<Address, SigningError>>;
+}
+
+impl<S: Signer + ?Sized> Signer for &S {
+    fn address(&self) -> BoxFuture<'_, Result<Address, SigningError>> {
+        (**self).address()
+    }
+}
+
+impl Signer for SecretKey {
+    fn address(&self) -> BoxFuture<'_, Result<Address, SigningError>> {
+        Box::pin(future::ready(Ok(secret_key_address(self))))
+    }
+}
+
+/// A [`Key`] paired with its known address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySigner<K> {
+    key: K,
+    address: Address,
+}
+
+impl<K: Key> KeySigner<K> {
+    /// Signer of `key`, whose address is `address`.
+    pub fn new(key: K, address: Address) -> Self {
+        KeySigner { key, address }
+    }
+
+    /// Underlying key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Address of the key, without going through the [`Signer`] future.
+    pub fn known_address(&self) -> Address {
+        self.address
+    }
+}
+
+impl<K: Key> Key for KeySigner<K> {
+    fn sign_hash(&self, hash: [u8; 32]) -> BoxFuture<'_, Result<HashSignature, SigningError>> {
+        self.key.sign_hash(hash)
+    }
+
+    fn authorize_value(&self, value: U256) -> Result<(), SigningError> {
+        self.key.authorize_value(value)
+    }
+
+    fn settle_value(&self, value: U256, sent: bool) {
+        self.key.settle_value(value, sent)
+    }
+}
+
+impl<K: Key> Signer for KeySigner<K> {
+    fn address(&self) -> BoxFuture<'_, Result<Address, SigningError>> {
+        Box::pin(future::ready(Ok(self.address)))
+    }
+}
+
+/// Sign a 32-byte hash with `signer`, returning `r`, `s` and the standard recovery id (0 or 1).
+///
+/// When the key source does not return the recovery id, it is found by recovering the signer address.
+pub async fn sign_hash<S: Signer + ?Sized>(signer: &S, hash: [u8; 32]) -> Result<(H256, H256, u8), SigningError> {
+    let signature = signer.sign_hash(hash).await?;
+    let recovery_id = match signature.recovery_id {
+        Some(recovery_id) => recovery_id,
+        None => {
+            let address = signer.address().await?;
+            let raw = [signature.r.as_bytes(), signature.s.as_bytes()].concat();
+            (0..2)
+                .find(|id| recover(&hash, &raw, *id as i32).ok() == Some(address))
+                .ok_or_else(|| SigningError::Key(format!("signature does not match the address {:?}", address)))?
+        }
+    };
+    Ok((signature.r, signature.s, recovery_id))
+}
+
+/// Address of a local secret key.
+pub fn secret_key_address(key: &SecretKey) -> Address {
+    let public_key = libsecp256k1::PublicKey::from_secret_key(key).serialize();
+    let hash = keccak256(&public_key[1..]);
+    Address::from_slice(&hash[12..])
+}
+
 /// Compute the Keccak-256 hash of input bytes.
 pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
     use tiny_keccak::{Hasher, Keccak};
@@ -43,6 +199,25 @@ pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
     output
 }
 
+/// Recover the address of the signer of a 32-byte `message` hash.
+///
+/// `signature` holds the 64 bytes of `r` and `s`, and `recovery_id` is the standard recovery id (0 or 1).
+pub fn recover(message: &[u8], signature: &[u8], recovery_id: i32) -> Result<Address, RecoveryError> {
+    use libsecp256k1::{Message, RecoveryId, Signature};
+    use std::convert::TryFrom;
+
+    let message = Message::parse_slice(message).map_err(|_| RecoveryError::InvalidMessage)?;
+    let recovery_id = u8::try_from(recovery_id)
+        .ok()
+        .and_then(|id| RecoveryId::parse(id).ok())
+        .ok_or(RecoveryError::InvalidSignature)?;
+    let signature = Signature::parse_standard_slice(signature).map_err(|_| RecoveryError::InvalidSignature)?;
+    let public_key =
+        libsecp256k1::recover(&message, &signature, &recovery_id).map_err(|_| RecoveryError::InvalidSignature)?;
+    let hash = keccak256(&public_key.serialize()[1..]);
+    Ok(Address::from_slice(&hash[12..]))
+}
+
 /// Hash a message according to EIP-191.
 ///
 /// The data is a UTF-8 encoded string and will enveloped as follows:
@@ -0,0 +1,231 @@
+//! Pluggable persistence of the stateful subsystems.
+//!
+//! Nonce managers, caches and the other subsystems keeping state between calls only keep it on the heap
+//! by default, so it is lost on canister upgrades. Given a [`StableStore`] they also write it through to
+//! the store and reload it on creation. Canisters with their own storage layer implement the trait on
+//! top of it; the `stable` feature provides an implementation over an `ic-stable-structures` map.
+//!
+//! Canisters saving their state themselves in `pre_upgrade`, e.g. with `ic_cdk::storage::stable_save`,
+//! take a [`StateSnapshot`] of the nonce manager and the transaction tracker instead, and restore it in
+//! `post_upgrade`:
+//!
+//! ```ignore
+//! #[pre_upgrade]
+//! fn pre_upgrade() {
+//!     ic_cdk::storage::stable_save((nonce_manager().snapshot(), tracker().snapshot())).unwrap();
+//! }
+//!
+//! #[post_upgrade]
+//! fn post_upgrade() {
+//!     let (nonces, txs): (StateSnapshot, StateSnapshot) = ic_cdk::storage::stable_restore().unwrap();
+//!     nonce_manager().restore(nonces);
+//!     tracker().restore(txs);
+//! }
+//! ```
+
+use candid::CandidType;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+/// Key-value store the stateful subsystems persist to.
+///
+/// Every subsystem writes under its own key prefix, so a single store can be shared between them.
+pub trait StableStore: Send + Sync {
+    /// Get the value stored under `key`.
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    /// Store `value` under `key`, replacing any previous value.
+    fn insert(&self, key: &[u8], value: Vec<u8>);
+
+    /// Remove the value stored under `key`.
+    fn remove(&self, key: &[u8]);
+
+    /// All entries whose key starts with `prefix`, in key order.
+    fn scan(&self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)>;
+}
+
+/// Shared handle to a store.
+#[derive(Clone)]
+pub struct StoreHandle {
+    store: Arc<dyn StableStore>,
+    prefix: Vec<u8>,
+}
+
+impl fmt::Debug for StoreHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreHandle")
+            .field("prefix", &String::from_utf8_lossy(&self.prefix))
+            .finish()
+    }
+}
+
+impl StoreHandle {
+    /// Handle writing into `store` under the given key prefix.
+    pub fn new(store: Arc<dyn StableStore>, prefix: &str) -> Self {
+        StoreHandle {
+            store,
+            prefix: prefix.as_bytes().to_vec(),
+        }
+    }
+
+    fn key(&self, key: &[u8]) -> Vec<u8> {
+        [&self.prefix[..], key].concat()
+    }
+
+    /// Get and decode the value stored under `key`, `None` if missing or undecodable.
+    pub fn get<V: serde::de::DeserializeOwned>(&self, key: &[u8]) -> Option<V> {
+        self.store
+            .get(&self.key(key))
+            .and_then(|value| serde_json::from_slice(&value).ok())
+    }
+
+    /// Encode and store `value` under `key`.
+    pub fn insert<V: serde::Serialize>(&self, key: &[u8], value: &V) {
+        let value = serde_json::to_vec(value).expect("state types never fail to serialize");
+        self.store.insert(&self.key(key), value);
+    }
+
+    /// Remove the value stored under `key`.
+    pub fn remove(&self, key: &[u8]) {
+        self.store.remove(&self.key(key));
+    }
+
+    /// All decodable entries under this handle's prefix, keys stripped of the prefix.
+    pub fn scan<V: serde::de::DeserializeOwned>(&self) -> Vec<(Vec<u8>, V)> {
+        self.store
+            .scan(&self.prefix)
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let value = serde_json::from_slice(&value).ok()?;
+                Some((key[self.prefix.len()..].to_vec(), value))
+            })
+            .collect()
+    }
+}
+
+/// Entries of a store, serializable with candid or serde.
+///
+/// Values are encoded like in a [`StableStore`], so snapshots remain readable across versions adding
+/// fields to the state.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateSnapshot {
+    /// Keys and encoded values, in key order
+    pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Heap store, useful for tests and for canisters persisting it themselves in `pre_upgrade`.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of all the entries.
+    pub fn entries(&self) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        self.entries.lock().clone()
+    }
+
+    /// Create a store holding the entries of `snapshot`.
+    pub fn from_snapshot(snapshot: StateSnapshot) -> Self {
+        MemoryStore {
+            entries: Mutex::new(snapshot.entries.into_iter().collect()),
+        }
+    }
+
+    /// Copy of all the entries, as a snapshot.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            entries: self
+                .entries
+                .lock()
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl StableStore for MemoryStore {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.entries.lock().get(key).cloned()
+    }
+
+    fn insert(&self, key: &[u8], value: Vec<u8>) {
+        self.entries.lock().insert(key.to_vec(), value);
+    }
+
+    fn remove(&self, key: &[u8]) {
+        self.entries.lock().remove(key);
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.entries
+            .lock()
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+}
+
+#[cfg(feature = "stable")]
+pub use self::stable_structures::StableBTreeStore;
+
+#[cfg(feature = "stable")]
+mod stable_structures {
+    use super::StableStore;
+    use ic_stable_structures::{Memory, StableBTreeMap};
+    use std::{cell::RefCell, thread::LocalKey};
+
+    /// Store over an `ic-stable-structures` map kept in a thread local.
+    ///
+    /// ```ignore
+    /// thread_local! {
+    ///     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+    ///         RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+    ///     static WEB3_STATE: RefCell<StableBTreeMap<Vec<u8>, Vec<u8>, VirtualMemory<DefaultMemoryImpl>>> =
+    ///         RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0)))));
+    /// }
+    ///
+    /// let store = Arc::new(StableBTreeStore::new(&WEB3_STATE));
+    /// ```
+    pub struct StableBTreeStore<M: Memory + 'static> {
+        map: &'static LocalKey<RefCell<StableBTreeMap<Vec<u8>, Vec<u8>, M>>>,
+    }
+
+    impl<M: Memory + 'static> StableBTreeStore<M> {
+        /// Create a store over the given thread local map.
+        pub fn new(map: &'static LocalKey<RefCell<StableBTreeMap<Vec<u8>, Vec<u8>, M>>>) -> Self {
+            StableBTreeStore { map }
+        }
+    }
+
+    impl<M: Memory + 'static> StableStore for StableBTreeStore<M> {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.map.with(|map| map.borrow().get(&key.to_vec()))
+        }
+
+        fn insert(&self, key: &[u8], value: Vec<u8>) {
+            self.map.with(|map| map.borrow_mut().insert(key.to_vec(), value));
+        }
+
+        fn remove(&self, key: &[u8]) {
+            self.map.with(|map| map.borrow_mut().remove(&key.to_vec()));
+        }
+
+        fn scan(&self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+            self.map.with(|map| {
+                map.borrow()
+                    .range(prefix.to_vec()..)
+                    .take_while(|(key, _)| key.starts_with(prefix))
+                    .collect()
+            })
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Polling based streams over chain data.
+
+use crate::{
+    api::Eth,
+    error::{self, Error},
+    polling::{self, PollConfig},
+    tip::ChainTipCache,
+    transports::ic_http_client::CallOptions,
+    types::{Block, BlockId, BlockNumber, H256, U64},
+    Transport,
+};
+use futures::{stream, Stream};
+
+/// Gapless stream of new block headers.
+///
+/// Every poll reads the current head and then fetches, in ascending order, all blocks between the last
+/// delivered one and the head. When the head jumped by many blocks, at most `max_backfill` blocks are
+/// fetched per poll and the remaining ones on the following polls, so consumers always observe a
+/// contiguous sequence of block numbers while the number of outcalls per poll stays bounded.
+#[derive(Debug, Clone)]
+pub struct BlockHeaderStream<T: Transport> {
+    eth: Eth<T>,
+    options: CallOptions,
+    next: Option<U64>,
+    max_backfill: u64,
+    tip_cache: Option<ChainTipCache>,
+}
+
+impl<T: Transport> BlockHeaderStream<T> {
+    /// Create a stream starting at the head observed on the first poll.
+    pub fn new(eth: Eth<T>, options: CallOptions) -> Self {
+        BlockHeaderStream {
+            eth,
+            options,
+            next: None,
+            max_backfill: 32,
+            tip_cache: None,
+        }
+    }
+
+    /// Start delivering from block `number` instead of from the current head.
+    pub fn from_block(mut self, number: U64) -> Self {
+        self.next = Some(number);
+        self
+    }
+
+    /// Set the maximum number of blocks fetched in a single poll.
+    pub fn with_max_backfill(mut self, max_backfill: u64) -> Self {
+        self.max_backfill = max_backfill.max(1);
+        self
+    }
+
+    /// Record the last delivered block of every poll into `cache`.
+    pub fn with_tip_cache(mut self, cache: ChainTipCache) -> Self {
+        self.tip_cache = Some(cache);
+        self
+    }
+
+    /// Number of the next block to be delivered, if known.
+    pub fn next_block(&self) -> Option<U64> {
+        self.next
+    }
+
+    /// Fetch the blocks produced since the last poll, in ascending order.
+    ///
+    /// Returns an empty list if the head did not move. On error the stream position is kept, so the
+    /// same blocks are requested again on the next poll.
+    pub async fn poll(&mut self) -> error::Result<Vec<Block<H256>>> {
+        let head = self.eth.block_number(self.options.clone()).await?;
+        let from = self.next.unwrap_or(head);
+        if from > head {
+            return Ok(vec![]);
+        }
+        let to = std::cmp::min(head, from + self.max_backfill - 1);
+
+        let mut blocks = Vec::with_capacity((to - from).as_usize() + 1);
+        let mut number = from;
+        while number <= to {
+            let block = self
+                .eth
+                .block(BlockId::Number(BlockNumber::Number(number)), self.options.clone())
+                .await?
+                .ok_or_else(|| Error::InvalidResponse(format!("block {} not found", number)))?;
+            blocks.push(block);
+            number += U64::one();
+        }
+        self.next = Some(to + 1);
+        if let (Some(cache), Some(last)) = (&self.tip_cache, blocks.last()) {
+            cache.update_from_block(last);
+        }
+        Ok(blocks)
+    }
+
+    /// Turn into a `Stream` polling the chain according to `config`.
+    ///
+    /// `seed` makes the poll jitter differ between canisters, see [`PollConfig`]. The stream ends once
+    /// `config.max_attempts` polls were made or on the first error.
+    pub fn into_stream(self, config: PollConfig, seed: Vec<u8>) -> impl Stream<Item = error::Result<Block<H256>>> {
+        let state = (self, Vec::<Block<H256>>::new().into_iter(), 0u32, false);
+        stream::unfold(state, move |(mut this, mut pending, mut attempt, mut failed)| {
+            let seed = seed.clone();
+            async move {
+                loop {
+                    if let Some(block) = pending.next() {
+                        return Some((Ok(block), (this, pending, attempt, failed)));
+                    }
+                    if failed || !config.can_retry(attempt) {
+                        return None;
+                    }
+                    if attempt > 0 {
+                        polling::sleep(config.delay(attempt, &seed)).await;
+                    }
+                    attempt += 1;
+                    match this.poll().await {
+                        Ok(blocks) => pending = blocks.into_iter(),
+                        Err(err) => {
+                            failed = true;
+                            return Some((Err(err), (this, pending, attempt, failed)));
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
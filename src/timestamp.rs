@@ -0,0 +1,73 @@
+//! Deterministic timestamps and nonces for request payloads.
+
+use crate::{signing, types::H256};
+use std::time::Duration;
+
+/// Derives timestamps and nonces from the IC time rounded down to a fixed granularity.
+///
+/// Some providers and gateways require a timestamp (or a timestamp based nonce) in the request payload
+/// for replay protection. The payload of an HTTP outcall must be identical on every replica, so it can
+/// neither use the local clock nor a random nonce. The IC time is agreed on by consensus, and rounding
+/// it to `granularity` additionally keeps the payload stable when the same request is built again
+/// within the window, e.g. by a retry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timestamper {
+    granularity: Duration,
+}
+
+impl Default for Timestamper {
+    fn default() -> Self {
+        Timestamper::new(Duration::from_secs(1))
+    }
+}
+
+impl Timestamper {
+    /// Create a timestamper rounding to `granularity`, a zero granularity disables the rounding.
+    pub fn new(granularity: Duration) -> Self {
+        Timestamper { granularity }
+    }
+
+    /// Rounding granularity.
+    pub fn granularity(&self) -> Duration {
+        self.granularity
+    }
+
+    /// Round `nanos` since the epoch down to the granularity.
+    pub fn timestamp_at(&self, nanos: u64) -> Duration {
+        let granularity = self.granularity.as_nanos() as u64;
+        if granularity == 0 {
+            return Duration::from_nanos(nanos);
+        }
+        Duration::from_nanos(nanos - nanos % granularity)
+    }
+
+    /// Current IC time rounded down to the granularity.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp_at(ic_cdk::api::time())
+    }
+
+    /// Current rounded IC time in seconds since the epoch.
+    pub fn secs(&self) -> u64 {
+        self.timestamp().as_secs()
+    }
+
+    /// Current rounded IC time in milliseconds since the epoch.
+    pub fn millis(&self) -> u64 {
+        self.timestamp().as_millis() as u64
+    }
+
+    /// Nonce derived from `seed` and the time `nanos` rounded down to the granularity.
+    ///
+    /// This is `keccak256(seed || timestamp)` with the rounded timestamp big endian encoded on 8 bytes
+    /// in nanoseconds. Distinct seeds (e.g. the method name and the canister id) yield distinct nonces
+    /// within the same window.
+    pub fn nonce_at(&self, seed: &[u8], nanos: u64) -> H256 {
+        let timestamp = self.timestamp_at(nanos).as_nanos() as u64;
+        signing::keccak256(&[seed, &timestamp.to_be_bytes()].concat()).into()
+    }
+
+    /// Nonce derived from `seed` and the current rounded IC time.
+    pub fn nonce(&self, seed: &[u8]) -> H256 {
+        self.nonce_at(seed, ic_cdk::api::time())
+    }
+}
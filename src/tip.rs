@@ -0,0 +1,174 @@
+//! Cache of the last known chain tip.
+
+use crate::{
+    signing,
+    stable::{StableStore, StoreHandle},
+    types::{Block, H256},
+};
+use candid::CandidType;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc};
+
+/// Number, hash and timestamp of a block observed as the chain head.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainTip {
+    /// Block number
+    pub number: u64,
+    /// Block hash
+    pub hash: [u8; 32],
+    /// Block timestamp, in seconds since the epoch
+    pub timestamp: u64,
+}
+
+impl ChainTip {
+    /// Tip of the given block, `None` for pending blocks which have neither number nor hash.
+    pub fn from_block<TX>(block: &Block<TX>) -> Option<Self> {
+        Some(ChainTip {
+            number: block.number?.as_u64(),
+            hash: block.hash?.0,
+            timestamp: block.timestamp.low_u64(),
+        })
+    }
+
+    /// Block hash.
+    pub fn block_hash(&self) -> H256 {
+        H256(self.hash)
+    }
+
+    /// Value stored as certified data when certification is enabled.
+    ///
+    /// This is `keccak256(number || hash || timestamp)` with both integers big endian encoded on 8 bytes,
+    /// which lets a frontend check a tip returned by a query call against the canister certificate.
+    pub fn certified_data(&self) -> [u8; 32] {
+        let mut data = Vec::with_capacity(48);
+        data.extend_from_slice(&self.number.to_be_bytes());
+        data.extend_from_slice(&self.hash);
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+        signing::keccak256(&data)
+    }
+}
+
+/// A chain tip together with the certificate of the canister certified data.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CertifiedChainTip {
+    /// Cached tip
+    pub tip: ChainTip,
+    /// Certificate covering [`ChainTip::certified_data`], `None` when not called from a query
+    pub certificate: Option<Vec<u8>>,
+}
+
+type Hook = Arc<dyn Fn(&ChainTip) + Send + Sync>;
+
+/// Key prefix of the tip persisted into a [`StableStore`].
+const STORE_PREFIX: &str = "tip/";
+const STORE_KEY: &[u8] = b"current";
+
+/// Last chain tip seen by the polling subsystems.
+///
+/// Streams and trackers given a cache record every new head they observe, so a canister can serve
+/// the chain state from a query method without issuing outcalls. The cache only moves forward: a tip
+/// with a lower number than the cached one is ignored, while a tip with the same number but a
+/// different hash (a reorg of the head) replaces it.
+///
+/// When certification is enabled the cache sets the canister certified data on every update, which is
+/// only allowed from update calls and timers. Canisters using certified data for other purposes should
+/// instead register a hook and fold [`ChainTip::certified_data`] into their own certification tree.
+///
+/// The cache is cheap to clone; clones share the same state.
+#[derive(Clone, Default)]
+pub struct ChainTipCache {
+    tip: Arc<Mutex<Option<ChainTip>>>,
+    hooks: Arc<Mutex<Vec<Hook>>>,
+    certify: bool,
+    store: Option<StoreHandle>,
+}
+
+impl fmt::Debug for ChainTipCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainTipCache")
+            .field("tip", &*self.tip.lock())
+            .field("hooks", &self.hooks.lock().len())
+            .field("certify", &self.certify)
+            .finish()
+    }
+}
+
+impl ChainTipCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a cache persisting the tip into `store`, starting from the tip stored there.
+    pub fn with_store(store: Arc<dyn StableStore>) -> Self {
+        let store = StoreHandle::new(store, STORE_PREFIX);
+        ChainTipCache {
+            tip: Arc::new(Mutex::new(store.get(STORE_KEY))),
+            store: Some(store),
+            ..Default::default()
+        }
+    }
+
+    /// Set the canister certified data to the tip's [`ChainTip::certified_data`] on every update.
+    pub fn with_certified_data(mut self) -> Self {
+        self.certify = true;
+        self
+    }
+
+    /// Register a hook called with every new tip.
+    pub fn on_update<F>(&self, hook: F)
+    where
+        F: Fn(&ChainTip) + Send + Sync + 'static,
+    {
+        self.hooks.lock().push(Arc::new(hook));
+    }
+
+    /// Last known tip.
+    pub fn get(&self) -> Option<ChainTip> {
+        *self.tip.lock()
+    }
+
+    /// Last known tip with the data certificate, meant to be returned from query methods.
+    pub fn certified(&self) -> Option<CertifiedChainTip> {
+        self.get().map(|tip| CertifiedChainTip {
+            tip,
+            certificate: ic_cdk::api::data_certificate(),
+        })
+    }
+
+    /// Record `tip`, returns whether the cached tip changed.
+    pub fn update(&self, tip: ChainTip) -> bool {
+        {
+            let mut current = self.tip.lock();
+            if current.is_some_and(|current| current.number > tip.number || current == tip) {
+                return false;
+            }
+            *current = Some(tip);
+        }
+        if let Some(store) = &self.store {
+            store.insert(STORE_KEY, &tip);
+        }
+        if self.certify {
+            ic_cdk::api::set_certified_data(&tip.certified_data());
+        }
+        let hooks = self.hooks.lock().clone();
+        for hook in hooks {
+            hook(&tip);
+        }
+        true
+    }
+
+    /// Record the tip of `block`, ignored for pending blocks.
+    pub fn update_from_block<TX>(&self, block: &Block<TX>) -> bool {
+        ChainTip::from_block(block).is_some_and(|tip| self.update(tip))
+    }
+
+    /// Forget the cached tip.
+    pub fn clear(&self) {
+        *self.tip.lock() = None;
+        if let Some(store) = &self.store {
+            store.remove(STORE_KEY);
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! Follow-up of submitted transactions until they are confirmed.
+//!
+//! Transactions sent from a canister get stuck when their fees fall behind the base fee, or silently
+//! vanish when a node evicts them from its pool. A [`TxTracker`] keeps the raw transactions submitted,
+//! checks their inclusion from a canister timer, and broadcasts them again, or replaces them with
+//! bumped fees, when they stay pending for too long:
+//!
+//! ```ignore
+//! let tracker = TxTracker::new(web3)
+//!     .with_confirmations(12)
+//!     .with_policy(ResubmitPolicy::new(Duration::from_secs(120)).with_bump(BumpSchedule::new()))
+//!     .with_wallet(wallet.clone())
+//!     .with_store(store);
+//! let signed = accounts.sign_transaction_with(tx.clone(), wallet.signer(), chain_id).await?;
+//! eth.send_raw_transaction(signed.raw_transaction.clone(), options).await?;
+//! let id = tracker.track_replaceable(wallet.address(), &signed, tx)?;
+//! tracker.start(Duration::from_secs(30));
+//! // later, from the timer or a query
+//! for transition in tracker.refresh().await? { /* ... */ }
+//! ```
+//!
+//! A transaction moves from [`Pending`](TxStatus::Pending) to [`Mined`](TxStatus::Mined) once one of
+//! its submissions has a receipt, then to [`Confirmed`](TxStatus::Confirmed) with enough blocks on top
+//! of it. It is [`Dropped`](TxStatus::Dropped) when another transaction used its nonce, that is when
+//! none of its submissions has a receipt while the nonce stays used for as many blocks as the
+//! confirmations, or when it is still pending after the last resubmission allowed by the policy. A
+//! mined transaction whose receipt disappears in a reorg is pending again.
+
+use crate::{
+    api::{Accounts, Namespace},
+    debug, error,
+    gas::{BumpSchedule, Fees},
+    runtime::{BusyFlag, Subsystem},
+    signing,
+    stable::{MemoryStore, StableStore, StateSnapshot, StoreHandle},
+    transports::ic_http_client::CallOptions,
+    types::{Address, Bytes, SignedTransaction, TransactionParameters, H256, U256, U64},
+    wallet::Wallet,
+    Transport, Web3,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Key prefix of the transactions persisted into a [`StableStore`].
+const STORE_PREFIX: &str = "tracker/";
+
+/// Key of the next transaction identifier, kept so that identifiers of removed transactions are not reused.
+const NEXT_ID_KEY: &[u8] = b"next";
+
+/// State of a tracked transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxStatus {
+    /// Submitted, without receipt yet
+    Pending,
+    /// Included in a block, without enough confirmations yet
+    Mined,
+    /// Included with enough confirmations
+    Confirmed,
+    /// Replaced by another transaction with the same nonce, or given up
+    Dropped,
+}
+
+impl TxStatus {
+    /// Whether the transaction is still followed by [`TxTracker::refresh`].
+    pub fn is_final(&self) -> bool {
+        matches!(self, TxStatus::Confirmed | TxStatus::Dropped)
+    }
+}
+
+/// Transaction followed by a [`TxTracker`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrackedTx {
+    /// Identifier returned when the transaction was tracked
+    pub id: u64,
+    /// Sender
+    pub sender: Address,
+    /// Nonce
+    pub nonce: U256,
+    /// Hash of the last submission, or of the mined one
+    pub hash: H256,
+    /// Hashes of all the submissions, replacements included, oldest first
+    pub hashes: Vec<H256>,
+    /// Raw signed transaction of the last submission
+    pub raw: Bytes,
+    /// Parameters the transaction was first signed with, needed to replace it with bumped fees
+    pub params: Option<TransactionParameters>,
+    /// State
+    pub status: TxStatus,
+    /// Block the transaction was included in, once mined
+    pub block: Option<U64>,
+    /// Time of the last submission, in nanoseconds since the epoch
+    pub submitted_at: u64,
+    /// Number of resubmissions, fee bumps included
+    pub resubmissions: u32,
+    /// Number of replacements with bumped fees
+    pub bumps: u32,
+    /// Error of the last failed resubmission or receipt lookup
+    pub last_error: Option<String>,
+    /// Head block when the nonce was first seen used while none of the submissions had a receipt
+    #[serde(default)]
+    pub nonce_used_at: Option<U64>,
+}
+
+/// Status change of a tracked transaction in a [`TxTracker::refresh`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxTransition {
+    /// Identifier of the transaction
+    pub id: u64,
+    /// Previous state
+    pub from: TxStatus,
+    /// New state
+    pub to: TxStatus,
+    /// Hash of the last submission, or of the mined one
+    pub hash: H256,
+}
+
+/// When and how pending transactions are submitted again.
+#[derive(Clone, Copy, Debug)]
+pub struct ResubmitPolicy {
+    /// Time a submission stays pending before the transaction is submitted again
+    pub after: Duration,
+    /// Number of resubmissions after which a transaction still pending is dropped
+    pub max_resubmissions: u32,
+    /// Fees of the replacements, `None` to broadcast the same raw transaction again
+    pub bump: Option<BumpSchedule>,
+}
+
+impl ResubmitPolicy {
+    /// Broadcast the transactions again when pending for `after`, up to five times, without bumping
+    /// their fees.
+    pub fn new(after: Duration) -> Self {
+        ResubmitPolicy {
+            after,
+            max_resubmissions: 5,
+            bump: None,
+        }
+    }
+
+    /// Set the number of resubmissions after which a transaction still pending is dropped.
+    pub fn with_max_resubmissions(mut self, max_resubmissions: u32) -> Self {
+        self.max_resubmissions = max_resubmissions;
+        self
+    }
+
+    /// Replace the pending transactions with fees bumped along `schedule` instead of broadcasting them
+    /// again, for the transactions tracked with their parameters.
+    pub fn with_bump(mut self, schedule: BumpSchedule) -> Self {
+        self.bump = Some(schedule);
+        self
+    }
+}
+
+impl Default for ResubmitPolicy {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(180))
+    }
+}
+
+/// Submitted transactions, followed until confirmed or dropped.
+///
+/// Fee bumps need the parameters of the transaction, given to
+/// [`track_replaceable`](Self::track_replaceable), and the wallet of the sender, given to
+/// [`with_wallet`](Self::with_wallet); the other transactions are broadcast again as they are.
+///
+/// The tracker is cheap to clone; clones share the same transactions.
+#[derive(Clone, Debug)]
+pub struct TxTracker<T: Transport> {
+    web3: Web3<T>,
+    wallet: Option<Wallet<T>>,
+    entries: Arc<Mutex<BTreeMap<u64, TrackedTx>>>,
+    next_id: Arc<Mutex<u64>>,
+    refreshing: BusyFlag,
+    store: Option<StoreHandle>,
+    policy: ResubmitPolicy,
+    confirmations: u64,
+}
+
+impl<T: Transport> TxTracker<T> {
+    /// Create a tracker following the transactions through `web3`.
+    pub fn new(web3: Web3<T>) -> Self {
+        TxTracker {
+            web3,
+            wallet: None,
+            entries: Default::default(),
+            next_id: Default::default(),
+            refreshing: Default::default(),
+            store: None,
+            policy: ResubmitPolicy::default(),
+            confirmations: 1,
+        }
+    }
+
+    /// Persist the transactions into `store`, loading the transactions tracked there.
+    pub fn with_store(mut self, store: Arc<dyn StableStore>) -> Self {
+        let store = StoreHandle::new(store, STORE_PREFIX);
+        let (entries, next_id) = load(&store);
+        self.entries = Arc::new(Mutex::new(entries));
+        self.next_id = Arc::new(Mutex::new(next_id));
+        self.store = Some(store);
+        self
+    }
+
+    /// Submit the pending transactions again according to `policy`.
+    pub fn with_policy(mut self, policy: ResubmitPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sign the replacements with bumped fees with `wallet`, for the transactions it sent.
+    pub fn with_wallet(mut self, wallet: Wallet<T>) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+
+    /// Require `confirmations` blocks, the one of the transaction included, before it is confirmed.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    fn persist(&self, tx: &TrackedTx) {
+        if let Some(store) = &self.store {
+            store.insert(&tx.id.to_be_bytes(), tx);
+        }
+    }
+
+    /// Snapshot of all the transactions, e.g. to save them in `pre_upgrade` without a [`StableStore`].
+    pub fn snapshot(&self) -> StateSnapshot {
+        let snapshot = Arc::new(MemoryStore::new());
+        let store = StoreHandle::new(snapshot.clone(), STORE_PREFIX);
+        for tx in self.entries.lock().values() {
+            store.insert(&tx.id.to_be_bytes(), tx);
+        }
+        store.insert(NEXT_ID_KEY, &*self.next_id.lock());
+        snapshot.snapshot()
+    }
+
+    /// Replace all the transactions with the ones of `snapshot`, writing them through to the store if
+    /// any.
+    pub fn restore(&self, snapshot: StateSnapshot) {
+        let (restored, next_id) = load(&StoreHandle::new(
+            Arc::new(MemoryStore::from_snapshot(snapshot)),
+            STORE_PREFIX,
+        ));
+        let mut entries = self.entries.lock();
+        let mut current_next_id = self.next_id.lock();
+        if let Some(store) = &self.store {
+            for id in entries.keys().filter(|id| !restored.contains_key(id)) {
+                store.remove(&id.to_be_bytes());
+            }
+            for tx in restored.values() {
+                store.insert(&tx.id.to_be_bytes(), tx);
+            }
+            store.insert(NEXT_ID_KEY, &next_id);
+        }
+        *entries = restored;
+        *current_next_id = next_id;
+    }
+
+    /// Follow the raw transaction `raw`, already submitted, sent by `sender` with `nonce`.
+    ///
+    /// It can only be broadcast again as is; see [`track_replaceable`](Self::track_replaceable) for fee
+    /// bumps.
+    pub fn track(&self, sender: Address, nonce: U256, raw: Bytes) -> u64 {
+        let hash = H256(signing::keccak256(&raw.0));
+        self.insert(sender, nonce, hash, raw, None)
+    }
+
+    /// Follow `signed`, already submitted, signed by `sender` from `params`.
+    ///
+    /// Fails if `params` has no nonce.
+    pub fn track_replaceable(
+        &self,
+        sender: Address,
+        signed: &SignedTransaction,
+        params: TransactionParameters,
+    ) -> error::Result<u64> {
+        let nonce = params
+            .nonce
+            .ok_or_else(|| error::Error::InvalidTransaction("tracked transaction has no nonce".into()))?;
+        Ok(self.insert(
+            sender,
+            nonce,
+            signed.transaction_hash,
+            signed.raw_transaction.clone(),
+            Some(params),
+        ))
+    }
+
+    fn insert(
+        &self,
+        sender: Address,
+        nonce: U256,
+        hash: H256,
+        raw: Bytes,
+        params: Option<TransactionParameters>,
+    ) -> u64 {
+        let id = {
+            let mut next_id = self.next_id.lock();
+            *next_id += 1;
+            if let Some(store) = &self.store {
+                store.insert(NEXT_ID_KEY, &*next_id);
+            }
+            *next_id - 1
+        };
+        let tx = TrackedTx {
+            id,
+            sender,
+            nonce,
+            hash,
+            hashes: vec![hash],
+            raw,
+            params,
+            status: TxStatus::Pending,
+            block: None,
+            submitted_at: debug::now(),
+            resubmissions: 0,
+            bumps: 0,
+            last_error: None,
+            nonce_used_at: None,
+        };
+        self.persist(&tx);
+        self.entries.lock().insert(id, tx);
+        id
+    }
+
+    /// Transaction `id`.
+    pub fn get(&self, id: u64) -> Option<TrackedTx> {
+        self.entries.lock().get(&id).cloned()
+    }
+
+    /// All the tracked transactions, by identifier.
+    pub fn transactions(&self) -> Vec<TrackedTx> {
+        self.entries.lock().values().cloned().collect()
+    }
+
+    /// Forget transaction `id`, e.g. once confirmed, returning whether it was tracked.
+    pub fn remove(&self, id: u64) -> bool {
+        let removed = self.entries.lock().remove(&id).is_some();
+        if removed {
+            if let Some(store) = &self.store {
+                store.remove(&id.to_be_bytes());
+            }
+        }
+        removed
+    }
+
+    /// Check the inclusion of the transactions not confirmed or dropped yet, submitting again the ones
+    /// pending for too long, and return the status changes.
+    ///
+    /// A transaction whose lookup fails keeps its status, with the error as its `last_error`, without
+    /// stopping the refresh of the others. A refresh started while another one is running returns
+    /// immediately.
+    pub async fn refresh(&self) -> error::Result<Vec<TxTransition>> {
+        let _guard = match self.refreshing.enter() {
+            Some(guard) => guard,
+            None => return Ok(vec![]),
+        };
+        self.refresh_open().await
+    }
+
+    async fn refresh_open(&self) -> error::Result<Vec<TxTransition>> {
+        let open: Vec<TrackedTx> = self
+            .entries
+            .lock()
+            .values()
+            .filter(|tx| !tx.status.is_final())
+            .cloned()
+            .collect();
+        if open.is_empty() {
+            return Ok(vec![]);
+        }
+        let eth = self.web3.eth();
+        let options = self.web3.transport().default_call_options();
+        let head = eth.block_number(options.clone()).await?;
+        let mut nonces = BTreeMap::new();
+
+        let mut transitions = vec![];
+        for mut tx in open {
+            let from = tx.status;
+            let mined = match self.lookup(&mut tx, head, &mut nonces, &options).await {
+                Ok(mined) => mined,
+                Err(err) => {
+                    // Keep refreshing the other transactions, this one is looked up again next time.
+                    tx.last_error = Some(err.to_string());
+                    self.store_refreshed(tx, from, &mut transitions);
+                    continue;
+                }
+            };
+
+            match mined {
+                Some((hash, block)) => {
+                    tx.nonce_used_at = None;
+                    tx.hash = hash;
+                    tx.block = Some(block);
+                    tx.status = if head.saturating_sub(block) + 1 >= U64::from(self.confirmations) {
+                        TxStatus::Confirmed
+                    } else {
+                        TxStatus::Mined
+                    };
+                }
+                None => {
+                    tx.block = None;
+                    tx.status = TxStatus::Pending;
+                    if let Some(used_at) = tx.nonce_used_at {
+                        // Replaced, once the nonce has been used without a receipt for long enough that
+                        // a receipt of the transaction would have been seen by now.
+                        if head.saturating_sub(used_at) >= U64::from(self.confirmations) {
+                            tx.status = TxStatus::Dropped;
+                        }
+                    } else if debug::now().saturating_sub(tx.submitted_at) >= self.policy.after.as_nanos() as u64 {
+                        if tx.resubmissions >= self.policy.max_resubmissions {
+                            debug::warn(&format!(
+                                "dropping transaction {:?} still pending after {} resubmissions",
+                                tx.hash, tx.resubmissions
+                            ));
+                            tx.status = TxStatus::Dropped;
+                        } else {
+                            self.resubmit(&mut tx).await;
+                        }
+                    }
+                }
+            }
+
+            self.store_refreshed(tx, from, &mut transitions);
+        }
+        Ok(transitions)
+    }
+
+    /// Hash and block of the submission of `tx` which was mined, if any, recording in `tx` since when its
+    /// nonce has been used otherwise.
+    ///
+    /// `nonces` caches the next nonce of the senders, fetched at most once per refresh.
+    async fn lookup(
+        &self,
+        tx: &mut TrackedTx,
+        head: U64,
+        nonces: &mut BTreeMap<Address, U256>,
+        options: &CallOptions,
+    ) -> error::Result<Option<(H256, U64)>> {
+        let mined = self.find_mined(tx, options).await?;
+        if mined.is_some() {
+            return Ok(mined);
+        }
+        let next_nonce = match nonces.entry(tx.sender) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let count = self
+                    .web3
+                    .eth()
+                    .transaction_count(tx.sender, None, options.clone())
+                    .await?;
+                *entry.insert(count)
+            }
+        };
+        if next_nonce <= tx.nonce {
+            tx.nonce_used_at = None;
+            return Ok(None);
+        }
+        // The transaction may have been mined since its receipts were looked for.
+        let mined = self.find_mined(tx, options).await?;
+        if mined.is_none() {
+            tx.nonce_used_at = tx.nonce_used_at.or(Some(head));
+        }
+        Ok(mined)
+    }
+
+    /// Store the refreshed `tx`, which had status `from`, recording its transition if any.
+    fn store_refreshed(&self, tx: TrackedTx, from: TxStatus, transitions: &mut Vec<TxTransition>) {
+        let mut entries = self.entries.lock();
+        // The transaction may have been removed during the calls.
+        let current = match entries.get_mut(&tx.id) {
+            Some(current) => current,
+            None => return,
+        };
+        if *current != tx {
+            *current = tx;
+            self.persist(current);
+            if current.status != from {
+                transitions.push(TxTransition {
+                    id: current.id,
+                    from,
+                    to: current.status,
+                    hash: current.hash,
+                });
+            }
+        }
+    }
+
+    /// Hash and block of the submission of `tx` which was mined, if any.
+    async fn find_mined(&self, tx: &TrackedTx, options: &CallOptions) -> error::Result<Option<(H256, U64)>> {
+        let eth = self.web3.eth();
+        for hash in tx.hashes.iter().rev() {
+            let receipt = eth.transaction_receipt(*hash, options.clone()).await?;
+            if let Some(block) = receipt.and_then(|receipt| receipt.block_number) {
+                return Ok(Some((*hash, block)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Submit `tx` again, replacing it with bumped fees when possible.
+    ///
+    /// The hash of a replacement is persisted before it is broadcast, so that its receipt is looked for
+    /// even when the broadcast is interrupted, e.g. by an upgrade.
+    async fn resubmit(&self, tx: &mut TrackedTx) {
+        let options = self.web3.transport().default_call_options();
+        let raw = match self.replacement(tx).await {
+            Some(Ok(signed)) => {
+                tx.bumps += 1;
+                tx.hash = signed.transaction_hash;
+                tx.hashes.push(signed.transaction_hash);
+                tx.raw = signed.raw_transaction;
+                if let Some(current) = self.entries.lock().get_mut(&tx.id) {
+                    current.hashes.push(signed.transaction_hash);
+                    self.persist(current);
+                }
+                tx.raw.clone()
+            }
+            Some(Err(err)) => {
+                debug::warn(&format!(
+                    "failed to bump the fees of transaction {:?}: {}",
+                    tx.hash, err
+                ));
+                tx.raw.clone()
+            }
+            None => tx.raw.clone(),
+        };
+        tx.resubmissions += 1;
+        tx.submitted_at = debug::now();
+        tx.last_error = match self.web3.eth().send_raw_transaction(raw, options).await {
+            Ok(_) => None,
+            Err(err) => Some(err.to_string()),
+        };
+    }
+
+    /// Replacement of `tx` with the next bumped fees, `None` if its fees cannot be bumped.
+    async fn replacement(&self, tx: &TrackedTx) -> Option<error::Result<SignedTransaction>> {
+        let schedule = self.policy.bump?;
+        let wallet = self.wallet.as_ref().filter(|wallet| wallet.address() == tx.sender)?;
+        let mut params = tx.params.clone()?;
+        let initial = Fees::of(&params)?;
+        let current = schedule.bump_fees(initial, tx.bumps);
+        let next = schedule.bump_fees(initial, tx.bumps + 1);
+        if !next.replaces(&current) {
+            // Capped: nodes would reject the replacement as underpriced.
+            return None;
+        }
+        // The replacement is checked against the fees of the last submission, not the first one.
+        current.apply(&mut params);
+        let accounts = Accounts::new(self.web3.transport().clone());
+        Some(
+            accounts
+                .replace_transaction_with(params, next, wallet.signer(), wallet.chain().id())
+                .await,
+        )
+    }
+}
+
+/// Transactions persisted into `store`, by identifier, and the next identifier.
+fn load(store: &StoreHandle) -> (BTreeMap<u64, TrackedTx>, u64) {
+    let entries: BTreeMap<u64, TrackedTx> = store
+        .scan::<TrackedTx>()
+        .into_iter()
+        .map(|(_, tx)| (tx.id, tx))
+        .collect();
+    let next_id = entries
+        .keys()
+        .next_back()
+        .map(|id| id + 1)
+        .max(store.get::<u64>(NEXT_ID_KEY))
+        .unwrap_or(0);
+    (entries, next_id)
+}
+
+impl<T> TxTracker<T>
+where
+    T: Transport + 'static,
+{
+    /// Refresh the transactions every `interval` from a canister timer, returning the timer to clear to
+    /// stop.
+    pub fn start(&self, interval: Duration) -> ic_cdk_timers::TimerId {
+        let tracker = self.clone();
+        ic_cdk_timers::set_timer_interval(interval, move || {
+            let tracker = tracker.clone();
+            ic_cdk::spawn(async move {
+                if let Err(err) = tracker.refresh().await {
+                    debug::warn(&format!("transaction tracker refresh failed: {}", err));
+                }
+            });
+        })
+    }
+}
+
+impl<T> Subsystem for TxTracker<T>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    fn name(&self) -> &'static str {
+        "tracker"
+    }
+
+    fn start(&self, interval: Duration) -> Option<ic_cdk_timers::TimerId> {
+        Some(TxTracker::start(self, interval))
+    }
+
+    fn persist(&self) {
+        if let Some(store) = &self.store {
+            for tx in self.entries.lock().values() {
+                store.insert(&tx.id.to_be_bytes(), tx);
+            }
+            store.insert(NEXT_ID_KEY, &*self.next_id.lock());
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.refreshing.is_busy()
+    }
+}
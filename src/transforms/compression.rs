@@ -0,0 +1,54 @@
+//! Compressed responses.
+//!
+//! Outcalls are charged per byte of the response size limit, so large results like `eth_getLogs` cost
+//! less when the provider compresses them. Options built
+//! [`with_compression`](crate::transports::ic_http_client::CallOptions::with_compression) ask for a
+//! gzip or deflate response; the [`Normalizer`](super::normalize::Normalizer) transform decompresses it
+//! before normalizing the body, and [`ICHttpClient`](crate::transports::ICHttpClient) decompresses the
+//! responses still compressed, e.g. without transform.
+//!
+//! The response size limit applies to the compressed response, as sent by the provider.
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use ic_cdk::api::management_canister::http_request::{HttpHeader, HttpResponse};
+use std::io::Read;
+
+/// Value of the `Accept-Encoding` header of the requests asking for compressed responses.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+/// Name of the header giving the compression of a response.
+const CONTENT_ENCODING: &str = "content-encoding";
+
+fn read_all(mut reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body)?;
+    Ok(body)
+}
+
+/// Decompress `body`, compressed with `encoding`.
+///
+/// `deflate` bodies are zlib streams, but some servers send raw deflate data, which is accepted too.
+pub fn decompress(encoding: &str, body: &[u8]) -> Result<Vec<u8>, String> {
+    let decoded = match encoding.trim().to_ascii_lowercase().as_str() {
+        "" | "identity" => return Ok(body.to_vec()),
+        "gzip" | "x-gzip" => read_all(GzDecoder::new(body)),
+        "deflate" => read_all(ZlibDecoder::new(body)).or_else(|_| read_all(DeflateDecoder::new(body))),
+        other => return Err(format!("unsupported content encoding {}", other)),
+    };
+    decoded.map_err(|err| format!("invalid {} response body: {}", encoding.trim(), err))
+}
+
+/// Decompress the body of `response` according to its `Content-Encoding` header, which is removed.
+///
+/// Responses without the header are returned unchanged.
+pub fn decode_response(mut response: HttpResponse) -> Result<HttpResponse, String> {
+    let position = response
+        .headers
+        .iter()
+        .position(|header| header.name.eq_ignore_ascii_case(CONTENT_ENCODING));
+    if let Some(position) = position {
+        let HttpHeader { value, .. } = response.headers.remove(position);
+        response.body = decompress(&value, &response.body)?;
+    }
+    Ok(response)
+}
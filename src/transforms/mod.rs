@@ -1,2 +1,6 @@
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod normalize;
+pub mod presets;
 pub mod processors;
 pub mod transform;
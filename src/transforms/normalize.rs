@@ -0,0 +1,302 @@
+//! Normalization of JSON-RPC responses for outcall consensus.
+//!
+//! Nodes behind a load balancer don't return byte-identical responses: the JSON-RPC id of a single
+//! response follows the request, some fields depend on the node's view of the pending block, and some
+//! are only returned by some clients. A [`Normalizer`] lists per-method [`FieldRule`]s stripping or
+//! canonicalizing these fields in the `result` of a response, so that the replicas agree on it.
+//!
+//! The options built by [`CallOptions::default_for`](crate::transports::ic_http_client::CallOptions::default_for)
+//! pass the method, or the methods of a batch, as transform context to the canister query method named
+//! [`TRANSFORM_METHOD`](super::presets::TRANSFORM_METHOD), which [`export_transform!`](crate::export_transform)
+//! defines:
+//!
+//! ```ignore
+//! ic_web3_rs::export_transform!();
+//!
+//! #[init]
+//! fn init() {
+//!     // Optional, the standard normalizer is used otherwise.
+//!     ic_web3_rs::transforms::normalize::set_normalizer(
+//!         Normalizer::standard().rule("eth_getBlockByNumber", FieldRule::Strip("size".into())),
+//!     );
+//! }
+//! ```
+
+use ic_cdk::api::management_canister::http_request::HttpHeader;
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+use serde_json::Value;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
+
+/// A rule normalizing the `result` of a response.
+///
+/// Paths are dot separated field names relative to the result, `*` standing for every element of an
+/// array: `logs.*.logIndex` is the `logIndex` of every log of a receipt, `*.logIndex` the one of every
+/// log returned by `eth_getLogs`. An empty path is the result itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldRule {
+    /// Remove the field at the path
+    Strip(String),
+    /// Replace the value of the field at the path, if present
+    Canonicalize(String, Value),
+    /// Only keep the listed fields of the objects at the path
+    Keep(String, Vec<String>),
+}
+
+impl FieldRule {
+    /// Set the field at `path` to `"0x0"`, e.g. for indexes which differ between nodes.
+    pub fn zero(path: &str) -> Self {
+        FieldRule::Canonicalize(path.to_string(), Value::from("0x0"))
+    }
+
+    /// Apply the rule to a result.
+    pub fn apply(&self, result: &mut Value) {
+        match self {
+            FieldRule::Strip(path) => with_parents(result, path, &mut |object, field| {
+                object.remove(field);
+            }),
+            FieldRule::Canonicalize(path, value) => with_parents(result, path, &mut |object, field| {
+                if let Some(current) = object.get_mut(field) {
+                    *current = value.clone();
+                }
+            }),
+            FieldRule::Keep(path, fields) => for_each_at(result, &segments(path), &mut |value| {
+                if let Some(object) = value.as_object_mut() {
+                    object.retain(|field, _| fields.contains(field));
+                }
+            }),
+        }
+    }
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('.').filter(|segment| !segment.is_empty()).collect()
+}
+
+fn for_each_at(value: &mut Value, path: &[&str], f: &mut dyn FnMut(&mut Value)) {
+    match path.split_first() {
+        None => f(value),
+        Some((&"*", rest)) => {
+            if let Some(items) = value.as_array_mut() {
+                for item in items {
+                    for_each_at(item, rest, f);
+                }
+            }
+        }
+        Some((field, rest)) => {
+            if let Some(child) = value.get_mut(*field) {
+                for_each_at(child, rest, f);
+            }
+        }
+    }
+}
+
+/// Call `f` with every object holding the field at `path`, and the field name.
+fn with_parents(value: &mut Value, path: &str, f: &mut dyn FnMut(&mut serde_json::Map<String, Value>, &str)) {
+    let segments = segments(path);
+    if let Some((field, parent)) = segments.split_last() {
+        for_each_at(value, parent, &mut |value| {
+            if let Some(object) = value.as_object_mut() {
+                f(object, field);
+            }
+        });
+    }
+}
+
+/// Per-method normalization rules.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Normalizer {
+    methods: BTreeMap<String, Vec<FieldRule>>,
+    /// Lowercase names of the response headers kept
+    headers: BTreeSet<String>,
+}
+
+impl Normalizer {
+    /// Create a normalizer without rules, which only strips the JSON-RPC ids of single responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalizer with the rules needed by the common methods.
+    ///
+    /// Transaction and log indexes are zeroed, as nodes disagree on them for recent blocks, and the
+    /// deprecated `totalDifficulty` of blocks is stripped, as only some clients still return it.
+    pub fn standard() -> Self {
+        let mut normalizer = Self::new()
+            .rule("eth_getTransactionByHash", FieldRule::zero("transactionIndex"))
+            .rule("eth_getTransactionReceipt", FieldRule::zero("transactionIndex"))
+            .rule("eth_getTransactionReceipt", FieldRule::zero("logs.*.transactionIndex"))
+            .rule("eth_getTransactionReceipt", FieldRule::zero("logs.*.logIndex"));
+        for method in &["eth_getBlockByNumber", "eth_getBlockByHash"] {
+            normalizer = normalizer.rule(method, FieldRule::Strip("totalDifficulty".to_string()));
+        }
+        for method in &["eth_getLogs", "eth_getFilterChanges", "eth_getFilterLogs"] {
+            normalizer = normalizer
+                .rule(method, FieldRule::zero("*.transactionIndex"))
+                .rule(method, FieldRule::zero("*.logIndex"));
+        }
+        normalizer
+    }
+
+    /// Add a rule for `method`.
+    pub fn rule(mut self, method: &str, rule: FieldRule) -> Self {
+        self.methods.entry(method.to_string()).or_default().push(rule);
+        self
+    }
+
+    /// Remove all the rules of `method`.
+    pub fn clear(mut self, method: &str) -> Self {
+        self.methods.remove(method);
+        self
+    }
+
+    /// Keep the response header `name`, e.g. `set-cookie` for a
+    /// [`ProviderSession`](crate::transports::ProviderSession) capturing a session cookie.
+    ///
+    /// Its value must be the same for all the replicas. Only the `name=value` part of the cookies is kept,
+    /// as their attributes, e.g. the expiry date, differ between responses.
+    pub fn keep_header(mut self, name: &str) -> Self {
+        self.headers.insert(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Response headers kept from `headers`, sorted by name.
+    pub fn kept_headers(&self, headers: &[HttpHeader]) -> Vec<HttpHeader> {
+        let mut kept: Vec<_> = headers
+            .iter()
+            .filter_map(|header| {
+                let name = header.name.to_ascii_lowercase();
+                if !self.headers.contains(&name) {
+                    return None;
+                }
+                let value = if name == "set-cookie" {
+                    header.value.split(';').next().unwrap_or_default().trim()
+                } else {
+                    header.value.trim()
+                };
+                Some(HttpHeader {
+                    name,
+                    value: value.to_string(),
+                })
+            })
+            .collect();
+        kept.sort_by(|a, b| (&a.name, &a.value).cmp(&(&b.name, &b.value)));
+        kept
+    }
+
+    /// Rules of `method`.
+    pub fn rules(&self, method: &str) -> &[FieldRule] {
+        self.methods.get(method).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Normalize the body of a `method` response, single or batch.
+    ///
+    /// The rules of the method are applied to the results. The id of a single response is replaced by
+    /// `null`, as it follows the request; the ids of a batch are kept, as the transport numbers the calls
+    /// by position and matches the responses back by id. For a batch, `method` may list the methods of the
+    /// calls in order, separated by commas, each response being normalized by the rules of the method of
+    /// its id. Bodies which are not JSON are returned unchanged.
+    pub fn normalize(&self, method: &str, body: &[u8]) -> Vec<u8> {
+        let mut value: Value = match serde_json::from_slice(body) {
+            Ok(value) => value,
+            Err(_) => return body.to_vec(),
+        };
+        let apply_rules = |method: &str, response: &mut serde_json::Map<String, Value>| {
+            if let Some(result) = response.get_mut("result") {
+                for rule in self.rules(method) {
+                    rule.apply(result);
+                }
+            }
+        };
+        match &mut value {
+            Value::Array(responses) => {
+                let methods: Vec<&str> = method.split(',').collect();
+                for response in responses.iter_mut().filter_map(Value::as_object_mut) {
+                    let method = match methods[..] {
+                        [method] => Some(method),
+                        _ => response
+                            .get("id")
+                            .and_then(Value::as_u64)
+                            .and_then(|id| methods.get(id as usize).copied()),
+                    };
+                    if let Some(method) = method {
+                        apply_rules(method, response);
+                    }
+                }
+            }
+            Value::Object(response) => {
+                if response.contains_key("id") {
+                    response.insert("id".to_string(), Value::Null);
+                }
+                apply_rules(method, response);
+            }
+            _ => {}
+        }
+        serde_json::to_vec(&value).expect("JSON values never fail to serialize")
+    }
+
+    /// Transform an outcall response, the context being the JSON-RPC method.
+    ///
+    /// Headers are dropped, apart from the [kept](Self::keep_header) ones. Responses with an error status are
+    /// kept as is, apart from the headers.
+    ///
+    /// With the `compression` feature, compressed responses are decompressed first, see
+    /// [`compression`](super::compression). Those failing to decompress are kept as is.
+    pub fn transform(&self, args: TransformArgs) -> HttpResponse {
+        #[cfg(feature = "compression")]
+        let args = match super::compression::decode_response(args.response.clone()) {
+            Ok(response) => TransformArgs { response, ..args },
+            Err(_) => args,
+        };
+        let method = String::from_utf8_lossy(&args.context);
+        let headers = self.kept_headers(&args.response.headers);
+        let body = if args.response.status == 200 {
+            self.normalize(&method, &args.response.body)
+        } else {
+            args.response.body
+        };
+        HttpResponse {
+            status: args.response.status,
+            body,
+            headers,
+        }
+    }
+}
+
+thread_local! {
+    static NORMALIZER: RefCell<Normalizer> = RefCell::new(Normalizer::standard());
+}
+
+/// Replace the normalizer used by [`transform`], [`Normalizer::standard`] by default.
+pub fn set_normalizer(normalizer: Normalizer) {
+    NORMALIZER.with(|current| *current.borrow_mut() = normalizer);
+}
+
+/// Copy of the normalizer used by [`transform`].
+pub fn normalizer() -> Normalizer {
+    NORMALIZER.with(|current| current.borrow().clone())
+}
+
+/// Transform an outcall response with the normalizer set by [`set_normalizer`].
+pub fn transform(args: TransformArgs) -> HttpResponse {
+    NORMALIZER.with(|normalizer| normalizer.borrow().transform(args))
+}
+
+/// Define the `transform` canister query method the options built by
+/// [`CallOptions::default_for`](crate::transports::ic_http_client::CallOptions::default_for) use, which
+/// normalizes responses with [`transform`].
+///
+/// The canister must depend on `ic-cdk`.
+#[macro_export]
+macro_rules! export_transform {
+    () => {
+        #[$crate::ic_cdk_macros::query]
+        fn transform(
+            args: $crate::ic_cdk::api::management_canister::http_request::TransformArgs,
+        ) -> $crate::ic_cdk::api::management_canister::http_request::HttpResponse {
+            $crate::transforms::normalize::transform(args)
+        }
+    };
+}
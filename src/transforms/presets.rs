@@ -0,0 +1,59 @@
+//! Default outcall settings of the JSON-RPC methods.
+//!
+//! Every outcall needs a transform making the responses of all replicas identical and a response size
+//! limit large enough for the result. The presets give the size limit for the common methods, so that
+//! [`CallOptions::default_for`](crate::transports::ic_http_client::CallOptions::default_for) builds
+//! ready-to-use options, the transform being the canister query method named [`TRANSFORM_METHOD`]
+//! normalizing responses as described in [`normalize`](super::normalize).
+
+/// Name of the canister query method the presets use as transform.
+pub const TRANSFORM_METHOD: &str = "transform";
+
+/// Response size limit of methods without a preset.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 500_000;
+
+/// Default outcall settings of a JSON-RPC method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethodPreset {
+    /// JSON-RPC method
+    pub method: &'static str,
+    /// Estimated upper bound of the response size, in bytes
+    pub max_response_bytes: u64,
+}
+
+const fn preset(method: &'static str, max_response_bytes: u64) -> MethodPreset {
+    MethodPreset {
+        method,
+        max_response_bytes,
+    }
+}
+
+/// Presets of the supported methods.
+pub const PRESETS: &[MethodPreset] = &[
+    preset("eth_blockNumber", 1_000),
+    preset("eth_chainId", 1_000),
+    preset("eth_syncing", 2_000),
+    preset("eth_gasPrice", 1_000),
+    preset("eth_maxPriorityFeePerGas", 1_000),
+    preset("eth_estimateGas", 1_000),
+    preset("eth_getBalance", 1_000),
+    preset("eth_getTransactionCount", 1_000),
+    preset("eth_getStorageAt", 1_000),
+    preset("eth_sendRawTransaction", 1_000),
+    preset("eth_call", 100_000),
+    preset("eth_getCode", 100_000),
+    preset("eth_feeHistory", 20_000),
+    preset("eth_getTransactionByHash", 50_000),
+    preset("eth_getTransactionReceipt", 100_000),
+    preset("eth_getBlockReceipts", 2_000_000),
+    preset("eth_getBlockByNumber", 100_000),
+    preset("eth_getBlockByHash", 100_000),
+    preset("eth_getLogs", 2_000_000),
+    preset("eth_getFilterChanges", 2_000_000),
+    preset("eth_getFilterLogs", 2_000_000),
+];
+
+/// Preset of `method`, `None` for methods without one.
+pub fn for_method(method: &str) -> Option<&'static MethodPreset> {
+    PRESETS.iter().find(|preset| preset.method == method)
+}
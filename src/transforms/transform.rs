@@ -31,20 +31,19 @@ pub trait TransformProcessor {
 
 impl TransformProcessor for ArrayResultTransformProcessor {
     fn process_body(&self, body: &[u8]) -> Vec<u8> {
-        let mut body: Value = serde_json::from_slice(body).unwrap();
-        let elements = body.get_mut("result").unwrap().as_array_mut().unwrap();
-        for element in elements.iter_mut() {
-            if self.transaction_index {
-                element
-                    .as_object_mut()
-                    .unwrap()
-                    .insert("transactionIndex".to_string(), Value::from("0x0"));
-            }
-            if self.log_index {
-                element
-                    .as_object_mut()
-                    .unwrap()
-                    .insert("logIndex".to_string(), Value::from("0x0"));
+        let mut body: Value = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return body.to_vec(),
+        };
+        // Error responses have no result to normalize.
+        if let Some(elements) = body.get_mut("result").and_then(Value::as_array_mut) {
+            for element in elements.iter_mut().filter_map(Value::as_object_mut) {
+                if self.transaction_index {
+                    element.insert("transactionIndex".to_string(), Value::from("0x0"));
+                }
+                if self.log_index {
+                    element.insert("logIndex".to_string(), Value::from("0x0"));
+                }
             }
         }
         serde_json::to_vec(&body).unwrap()
@@ -53,13 +52,15 @@ impl TransformProcessor for ArrayResultTransformProcessor {
 
 impl TransformProcessor for SingleResultTransformProcessor {
     fn process_body(&self, body: &[u8]) -> Vec<u8> {
-        let mut body: Value = serde_json::from_slice(body).unwrap();
-        if self.transaction_index {
-            body.get_mut("result")
-                .unwrap()
-                .as_object_mut()
-                .unwrap()
-                .insert("transactionIndex".to_string(), Value::from("0x0"));
+        let mut body: Value = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return body.to_vec(),
+        };
+        // The result is `null` for unknown transactions and missing for error responses.
+        if let Some(result) = body.get_mut("result").and_then(Value::as_object_mut) {
+            if self.transaction_index {
+                result.insert("transactionIndex".to_string(), Value::from("0x0"));
+            }
         }
         serde_json::to_vec(&body).unwrap()
     }
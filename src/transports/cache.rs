@@ -0,0 +1,378 @@
+//! Transport caching the results of repeated reads.
+
+use crate::{error::Result, RequestId, Transport};
+use futures::future::{self, BoxFuture};
+use jsonrpc_core::types::{Call, Params, Value};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use super::{
+    ic_http_client::{is_state_changing, CallOptions},
+    layer::TransportLayer,
+    LogLimits,
+};
+
+/// Methods whose result never changes.
+const CONSTANT_METHODS: &[&str] = &["eth_chainId", "net_version"];
+
+/// Methods whose result only depends on the hash they look up.
+const HASH_METHODS: &[&str] = &[
+    "eth_getBlockByHash",
+    "eth_getBlockTransactionCountByHash",
+    "eth_getUncleCountByBlockHash",
+];
+
+/// Methods whose result depends on the filters installed on the node, which are never cached.
+const FILTER_METHODS: &[&str] = &[
+    "eth_newFilter",
+    "eth_newBlockFilter",
+    "eth_newPendingTransactionFilter",
+    "eth_getFilterChanges",
+    "eth_getFilterLogs",
+    "eth_uninstallFilter",
+];
+
+/// Position of the block parameter of the methods reading the state at a block.
+const BLOCK_PARAMS: &[(&str, usize)] = &[
+    ("eth_getBlockByNumber", 0),
+    ("eth_getBlockReceipts", 0),
+    ("eth_getBlockTransactionCountByNumber", 0),
+    ("eth_getBalance", 1),
+    ("eth_getCode", 1),
+    ("eth_getTransactionCount", 1),
+    ("eth_call", 1),
+    ("eth_getStorageAt", 2),
+    ("eth_getProof", 2),
+];
+
+type Clock = Arc<dyn Fn() -> u64 + Send + Sync>;
+
+/// Method and parameters of a call.
+type CacheKey = (String, String);
+
+struct Entry {
+    value: Value,
+    /// Expiry time in nanoseconds, `None` for immutable results
+    expires_at: Option<u64>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<CacheKey, Entry>,
+    /// Highest block number returned by `eth_blockNumber`
+    head: Option<u64>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Hit and miss counts of a [`CachingTransport`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Calls served from the cache
+    pub hits: u64,
+    /// Cacheable calls sent to the inner transport
+    pub misses: u64,
+    /// Results currently cached
+    pub entries: usize,
+}
+
+/// Transport caching the results of reads, keyed by method and parameters.
+///
+/// Immutable results are cached until evicted: the chain id, lookups by block hash, and state reads
+/// at a block number at least `finality_depth` blocks below the latest block number returned through
+/// the transport by `eth_blockNumber`. Other reads are cached for the TTL, zero by default so that only
+/// immutable results are cached. Calls changing state, filter calls, errors and `null` results are
+/// never cached. When the cache is full, the least recently used results are evicted first.
+///
+/// The transport is cheap to clone; clones share the same cache.
+#[derive(Clone)]
+pub struct CachingTransport<T> {
+    transport: T,
+    ttl: u64,
+    max_entries: usize,
+    finality_depth: u64,
+    clock: Clock,
+    state: Arc<Mutex<State>>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for CachingTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("CachingTransport")
+            .field("transport", &self.transport)
+            .field("ttl", &self.ttl)
+            .field("max_entries", &self.max_entries)
+            .field("finality_depth", &self.finality_depth)
+            .field("entries", &state.entries.len())
+            .finish()
+    }
+}
+
+impl<T: Transport> CachingTransport<T> {
+    /// Cache the immutable results of `transport`, up to 1024 of them.
+    pub fn new(transport: T) -> Self {
+        CachingTransport {
+            transport,
+            ttl: 0,
+            max_entries: 1024,
+            finality_depth: 64,
+            clock: Arc::new(crate::debug::now),
+            state: Default::default(),
+        }
+    }
+
+    /// Also cache mutable results, e.g. reads at the `latest` block, for `ttl`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl.as_nanos().min(u64::MAX as u128) as u64;
+        self
+    }
+
+    /// Keep at most `max_entries` results.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Consider the blocks `depth` blocks below the latest one final, so that reads at them are
+    /// immutable.
+    pub fn with_finality_depth(mut self, depth: u64) -> Self {
+        self.finality_depth = depth;
+        self
+    }
+
+    /// Read the time in nanoseconds from `clock` instead of the IC, e.g. outside canisters.
+    pub fn with_clock<F>(mut self, clock: F) -> Self
+    where
+        F: Fn() -> u64 + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Inner transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Hit and miss counts.
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+        }
+    }
+
+    /// Drop all the cached results.
+    pub fn clear(&self) {
+        self.state.lock().entries.clear();
+    }
+}
+
+/// Whether `block`, a block parameter, designates the same block forever given the chain `head`.
+fn is_final_block(block: &Value, head: Option<u64>, finality_depth: u64) -> bool {
+    match block {
+        Value::String(tag) => {
+            let number = match tag.strip_prefix("0x").map(|hex| u64::from_str_radix(hex, 16)) {
+                Some(Ok(number)) => number,
+                // Tags such as `latest` or `finalized` move with the chain.
+                _ => return false,
+            };
+            head.is_some_and(|head| number.saturating_add(finality_depth) <= head)
+        }
+        // EIP-1898 block by hash
+        Value::Object(block) => block.contains_key("blockHash"),
+        _ => false,
+    }
+}
+
+/// Whether the result of `method` with `params` can never change.
+fn is_immutable(method: &str, params: &[Value], head: Option<u64>, finality_depth: u64) -> bool {
+    if CONSTANT_METHODS.contains(&method) || HASH_METHODS.contains(&method) {
+        return true;
+    }
+    BLOCK_PARAMS
+        .iter()
+        .find(|(block_method, _)| *block_method == method)
+        .and_then(|(_, position)| params.get(*position))
+        .is_some_and(|block| match block {
+            // `eth_getBlockReceipts` also takes a plain block hash.
+            Value::String(hash) if hash.len() == 66 => true,
+            block => is_final_block(block, head, finality_depth),
+        })
+}
+
+fn params_of(params: &Params) -> &[Value] {
+    match params {
+        Params::Array(params) => params,
+        _ => &[],
+    }
+}
+
+impl<T> Transport for CachingTransport<T>
+where
+    T: Transport,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let call = match &request {
+            Call::MethodCall(call)
+                if !is_state_changing(&call.method) && !FILTER_METHODS.contains(&call.method.as_str()) =>
+            {
+                call
+            }
+            _ => return Box::pin(self.transport.send(id, request, options)),
+        };
+        let method = call.method.clone();
+        let params = params_of(&call.params).to_vec();
+        let key = match serde_json::to_string(&params) {
+            Ok(encoded) => (method.clone(), encoded),
+            Err(_) => return Box::pin(self.transport.send(id, request, options)),
+        };
+
+        let now = (self.clock)();
+        {
+            let mut state = self.state.lock();
+            let cached = match state.entries.get_mut(&key) {
+                Some(entry) if entry.expires_at.is_none_or(|expires_at| now < expires_at) => {
+                    entry.last_used = now;
+                    Some(entry.value.clone())
+                }
+                Some(_) => {
+                    state.entries.remove(&key);
+                    None
+                }
+                None => None,
+            };
+            match cached {
+                Some(value) => {
+                    state.hits += 1;
+                    return Box::pin(future::ready(Ok(value)));
+                }
+                None => state.misses += 1,
+            }
+        }
+
+        let pending = self.transport.send(id, request, options);
+        let state = self.state.clone();
+        let (ttl, max_entries, finality_depth) = (self.ttl, self.max_entries, self.finality_depth);
+        Box::pin(async move {
+            let result = pending.await;
+            let value = match &result {
+                Ok(value) if !value.is_null() => value,
+                _ => return result,
+            };
+            let mut state = state.lock();
+            if method == "eth_blockNumber" {
+                let head = value
+                    .as_str()
+                    .and_then(|hex| u64::from_str_radix(hex.trim_start_matches("0x"), 16).ok());
+                if head > state.head {
+                    state.head = head;
+                }
+            }
+            let expires_at = if is_immutable(&method, &params, state.head, finality_depth) {
+                None
+            } else if ttl > 0 {
+                Some(now.saturating_add(ttl))
+            } else {
+                return result;
+            };
+            if state.entries.len() >= max_entries && !state.entries.contains_key(&key) {
+                state
+                    .entries
+                    .retain(|_, entry| entry.expires_at.is_none_or(|expires_at| now < expires_at));
+                if state.entries.len() >= max_entries {
+                    let oldest = state
+                        .entries
+                        .iter()
+                        .min_by_key(|(_, entry)| entry.last_used)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        state.entries.remove(&oldest);
+                    }
+                }
+            }
+            state.entries.insert(
+                key,
+                Entry {
+                    value: value.clone(),
+                    expires_at,
+                    last_used: now,
+                },
+            );
+            result
+        })
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.transport.log_limits()
+    }
+}
+
+/// Layer caching the results of reads, see [`CachingTransport`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CachingLayer {
+    ttl: Duration,
+    max_entries: Option<usize>,
+    finality_depth: Option<u64>,
+}
+
+impl CachingLayer {
+    /// Cache the immutable results.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also cache mutable results for `ttl`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Keep at most `max_entries` results.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Consider the blocks `depth` blocks below the latest one final.
+    pub fn with_finality_depth(mut self, depth: u64) -> Self {
+        self.finality_depth = Some(depth);
+        self
+    }
+}
+
+impl<T: Transport> TransportLayer<T> for CachingLayer
+where
+    T::Out: Send + 'static,
+{
+    type Transport = CachingTransport<T>;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        let mut transport = CachingTransport::new(inner).with_ttl(self.ttl);
+        if let Some(max_entries) = self.max_entries {
+            transport = transport.with_max_entries(max_entries);
+        }
+        if let Some(depth) = self.finality_depth {
+            transport = transport.with_finality_depth(depth);
+        }
+        transport
+    }
+}
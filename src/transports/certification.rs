@@ -0,0 +1,227 @@
+//! Verification of certified responses of providers served through IC HTTP gateways.
+//!
+//! Outcall consensus only guarantees that the replicas received the same response, not that the
+//! response is genuine. Providers running as canisters behind an HTTP gateway with response
+//! certification attach an `IC-Certificate` header to their responses, signed by the subnet. An
+//! [`ICHttp`](super::ICHttp) transport with a [`ResponseVerifier`] checks every response before
+//! accepting it, which is a stronger guarantee than consensus, e.g. for queries answered by a single
+//! replica.
+//!
+//! The crate does not verify the BLS signature of the certificate nor the certification tree itself,
+//! this is left to the verifier, typically built on the `ic-certification` and `ic-http-certification`
+//! crates:
+//!
+//! ```ignore
+//! ic_web3_rs::export_certified_transform!();
+//!
+//! let mut transport = ICHttp::new(url, None)?;
+//! transport.set_response_verifier(|url: &str, response: &HttpResponse| {
+//!     let header = CertificationHeader::from_response(response)?;
+//!     verify_certified_response(url, response, &header, ROOT_KEY)
+//! });
+//! ```
+//!
+//! Certification covers the headers and the body of the response as each replica fetched it, and the
+//! certificate headers differ between fetches, so responses are verified in the outcall [`transform`]:
+//! it only returns the verified body and a fixed [marker](CERTIFIED_HEADER), on which the replicas
+//! agree, and the transport only accepts responses carrying the marker.
+
+use ic_cdk::api::management_canister::http_request::{
+    HttpHeader, HttpResponse, TransformArgs, TransformContext, TransformFunc,
+};
+use std::cell::RefCell;
+
+/// Header carrying the certificate and the certification tree of a response.
+pub const IC_CERTIFICATE_HEADER: &str = "ic-certificate";
+/// Header carrying the certification expression of a response (response verification v2).
+pub const IC_CERTIFICATE_EXPRESSION_HEADER: &str = "ic-certificateexpression";
+/// Header set by [`transform`] on the responses it verified, with the value [`VERIFIED`].
+pub const CERTIFIED_HEADER: &str = "x-ic-web3-certified";
+/// Value of [`CERTIFIED_HEADER`] on verified responses.
+pub const VERIFIED: &str = "verified";
+/// Name of the canister query method defined by [`export_certified_transform!`](crate::export_certified_transform).
+pub const CERTIFIED_TRANSFORM_METHOD: &str = "transform_certified";
+
+/// Checks a response before the transport accepts it.
+///
+/// Implemented by closures `Fn(&str, &HttpResponse) -> Result<(), String>`, called with the URL of
+/// the provider and the response.
+pub trait ResponseVerifier: Send + Sync {
+    /// Accept the `response` of the provider at `url`, or reject it with a reason.
+    fn verify(&self, url: &str, response: &HttpResponse) -> Result<(), String>;
+}
+
+impl<F> ResponseVerifier for F
+where
+    F: Fn(&str, &HttpResponse) -> Result<(), String> + Send + Sync,
+{
+    fn verify(&self, url: &str, response: &HttpResponse) -> Result<(), String> {
+        self(url, response)
+    }
+}
+
+/// Verifier only requiring a well-formed `IC-Certificate` header, without checking the certificate.
+///
+/// Useful to detect responses coming from a gateway without certification, e.g. after a
+/// misconfiguration, but no replacement for an actual verification.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequireCertificationHeader;
+
+impl ResponseVerifier for RequireCertificationHeader {
+    fn verify(&self, _url: &str, response: &HttpResponse) -> Result<(), String> {
+        CertificationHeader::from_response(response).map(|_| ())
+    }
+}
+
+thread_local! {
+    static VERIFIER: RefCell<Option<Box<dyn ResponseVerifier>>> = RefCell::new(None);
+}
+
+/// Set the verifier used by [`transform`].
+///
+/// The transform runs as a canister query method, apart from the transports, so the verifier is shared by
+/// all of them: it is called with the URL of the provider to tell them apart.
+pub fn set_verifier<V: ResponseVerifier + 'static>(verifier: V) {
+    VERIFIER.with(|current| *current.borrow_mut() = Some(Box::new(verifier)));
+}
+
+/// Parsed `IC-Certificate` header.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CertificationHeader {
+    /// CBOR encoded certificate signed by the subnet
+    pub certificate: Vec<u8>,
+    /// CBOR encoded certification tree
+    pub tree: Vec<u8>,
+    /// Response verification version, 1 when missing
+    pub version: u32,
+    /// CBOR encoded path of the certification expression in the tree (version 2)
+    pub expr_path: Option<Vec<u8>>,
+    /// Value of the `IC-CertificateExpression` header (version 2)
+    pub expression: Option<String>,
+}
+
+impl CertificationHeader {
+    /// Parse the certification headers of `response`.
+    pub fn from_response(response: &HttpResponse) -> Result<Self, String> {
+        Self::from_headers(&response.headers)
+    }
+
+    /// Parse the certification headers among `headers`.
+    pub fn from_headers(headers: &[HttpHeader]) -> Result<Self, String> {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|header| header.name.eq_ignore_ascii_case(name))
+                .map(|header| header.value.as_str())
+        };
+        let value = header(IC_CERTIFICATE_HEADER).ok_or("missing IC-Certificate header")?;
+        let mut parsed = CertificationHeader {
+            version: 1,
+            expression: header(IC_CERTIFICATE_EXPRESSION_HEADER).map(str::to_string),
+            ..Default::default()
+        };
+        let (mut certificate, mut tree) = (None, None);
+        for field in value.split(',') {
+            let (name, value) = field
+                .trim()
+                .split_once('=')
+                .ok_or_else(|| format!("malformed IC-Certificate field: {}", field))?;
+            match name {
+                "certificate" => certificate = Some(structured_bytes(value)?),
+                "tree" => tree = Some(structured_bytes(value)?),
+                "expr_path" => parsed.expr_path = Some(structured_bytes(value)?),
+                "version" => {
+                    parsed.version = value
+                        .parse()
+                        .map_err(|_| format!("invalid IC-Certificate version: {}", value))?
+                }
+                _ => {}
+            }
+        }
+        parsed.certificate = certificate.ok_or("IC-Certificate header without certificate")?;
+        parsed.tree = tree.ok_or("IC-Certificate header without tree")?;
+        Ok(parsed)
+    }
+}
+
+/// Decode a structured header byte sequence, `:<base64>:`.
+fn structured_bytes(value: &str) -> Result<Vec<u8>, String> {
+    value
+        .strip_prefix(':')
+        .and_then(|value| value.strip_suffix(':'))
+        .and_then(|value| base64::decode_config(value, base64::STANDARD).ok())
+        .ok_or_else(|| format!("invalid IC-Certificate byte sequence: {}", value))
+}
+
+/// Transform of the outcalls to the provider at `url`, the canister query method defined by
+/// [`export_certified_transform!`](crate::export_certified_transform) with the URL as context.
+pub fn transform_context(url: &str) -> TransformContext {
+    TransformContext {
+        function: TransformFunc(candid::Func {
+            principal: ic_cdk::api::id(),
+            method: CERTIFIED_TRANSFORM_METHOD.to_string(),
+        }),
+        context: url.as_bytes().to_vec(),
+    }
+}
+
+/// Outcall transform verifying the response with the verifier set by [`set_verifier`], the context being
+/// the URL of the provider.
+///
+/// Verified responses keep their status and body, their headers being replaced by the [`CERTIFIED_HEADER`]
+/// marker. The body of the others is replaced by the reason of the failure. The body is not normalized, so
+/// requests must be sent with a [fixed JSON-RPC id](super::RequestIdMode::Fixed) for the replicas to
+/// agree on the response.
+pub fn transform(args: TransformArgs) -> HttpResponse {
+    let url = String::from_utf8_lossy(&args.context);
+    let verified = VERIFIER.with(|verifier| match &*verifier.borrow() {
+        Some(verifier) => verifier.verify(&url, &args.response),
+        None => Err("no response verifier set".to_string()),
+    });
+    match verified {
+        Ok(()) => HttpResponse {
+            status: args.response.status,
+            headers: vec![HttpHeader {
+                name: CERTIFIED_HEADER.to_string(),
+                value: VERIFIED.to_string(),
+            }],
+            body: args.response.body,
+        },
+        Err(reason) => HttpResponse {
+            status: args.response.status,
+            headers: vec![],
+            body: reason.into_bytes(),
+        },
+    }
+}
+
+/// Accept the transformed `response` if [`transform`] verified it, or give the reason it did not.
+pub fn check_verified(response: &HttpResponse) -> Result<(), String> {
+    let verified = response
+        .headers
+        .iter()
+        .any(|header| header.name.eq_ignore_ascii_case(CERTIFIED_HEADER) && header.value == VERIFIED);
+    if verified {
+        Ok(())
+    } else if response.body.is_empty() {
+        Err("response not verified by the certification transform".to_string())
+    } else {
+        Err(String::from_utf8_lossy(&response.body).into_owned())
+    }
+}
+
+/// Define the canister query method named [`CERTIFIED_TRANSFORM_METHOD`], which verifies responses with
+/// [`transform`].
+///
+/// The canister must depend on `ic-cdk`.
+#[macro_export]
+macro_rules! export_certified_transform {
+    () => {
+        #[$crate::ic_cdk_macros::query]
+        fn transform_certified(
+            args: $crate::ic_cdk::api::management_canister::http_request::TransformArgs,
+        ) -> $crate::ic_cdk::api::management_canister::http_request::HttpResponse {
+            $crate::transports::certification::transform(args)
+        }
+    };
+}
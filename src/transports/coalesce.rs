@@ -0,0 +1,181 @@
+//! Transport sharing a single outcall between identical concurrent calls.
+
+use crate::{error::Result, RequestId, Transport};
+use futures::{
+    future::{self, BoxFuture, Shared},
+    FutureExt,
+};
+use jsonrpc_core::types::{Call, Value};
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use super::ic_http_client::{is_state_changing, CallOptions};
+
+/// Methods whose results depend on the previous calls, which are never coalesced.
+const STATEFUL_METHODS: &[&str] = &[
+    "eth_newFilter",
+    "eth_newBlockFilter",
+    "eth_newPendingTransactionFilter",
+    "eth_getFilterChanges",
+    "eth_uninstallFilter",
+];
+
+type Clock = Arc<dyn Fn() -> u64 + Send + Sync>;
+type SharedCall = Shared<BoxFuture<'static, Result<Value>>>;
+
+/// Method, parameters and outcall options of a call.
+type CallKey = (String, String, String);
+
+#[derive(Default)]
+struct State {
+    in_flight: HashMap<CallKey, SharedCall>,
+    completed: HashMap<CallKey, (u64, Value)>,
+    coalesced: u64,
+}
+
+/// Transport coalescing identical calls: a call made while the same call (same method, parameters and
+/// outcall options) is in flight waits for the pending outcall and gets its result instead of issuing
+/// another one.
+///
+/// With a reuse window, successful results are also served to identical calls made within the window
+/// after the outcall completed. Results may then be up to the window old, e.g. for calls at the
+/// `latest` block, so keep it short. Calls changing state and filter calls are always sent.
+///
+/// The transport is cheap to clone; clones share the pending calls and results.
+#[derive(Clone)]
+pub struct CoalescingTransport<T> {
+    transport: T,
+    window: u64,
+    clock: Clock,
+    state: Arc<Mutex<State>>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for CoalescingTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("CoalescingTransport")
+            .field("transport", &self.transport)
+            .field("window", &self.window)
+            .field("in_flight", &state.in_flight.len())
+            .field("coalesced", &state.coalesced)
+            .finish()
+    }
+}
+
+impl<T: Transport> CoalescingTransport<T> {
+    /// Coalesce the identical concurrent calls of `transport`.
+    pub fn new(transport: T) -> Self {
+        CoalescingTransport {
+            transport,
+            window: 0,
+            clock: Arc::new(crate::debug::now),
+            state: Default::default(),
+        }
+    }
+
+    /// Also serve the results of identical calls completed less than `window` ago.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window.as_nanos().min(u64::MAX as u128) as u64;
+        self
+    }
+
+    /// Read the time in nanoseconds from `clock` instead of the IC, e.g. outside canisters.
+    pub fn with_clock<F>(mut self, clock: F) -> Self
+    where
+        F: Fn() -> u64 + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Inner transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Number of calls served without an outcall of their own.
+    pub fn coalesced_calls(&self) -> u64 {
+        self.state.lock().coalesced
+    }
+}
+
+fn call_key(call: &Call, options: &CallOptions) -> Option<CallKey> {
+    match call {
+        Call::MethodCall(call)
+            if !is_state_changing(&call.method) && !STATEFUL_METHODS.contains(&call.method.as_str()) =>
+        {
+            Some((
+                call.method.clone(),
+                serde_json::to_string(&call.params).ok()?,
+                format!("{:?}", options),
+            ))
+        }
+        _ => None,
+    }
+}
+
+impl<T> Transport for CoalescingTransport<T>
+where
+    T: Transport,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let key = match call_key(&request, &options) {
+            Some(key) => key,
+            None => return Box::pin(self.transport.send(id, request, options)),
+        };
+        let now = (self.clock)();
+        let window = self.window;
+        let mut state = self.state.lock();
+        state
+            .completed
+            .retain(|_, (completed_at, _)| now.saturating_sub(*completed_at) < window);
+        if let Some((_, value)) = state.completed.get(&key) {
+            let value = value.clone();
+            state.coalesced += 1;
+            return Box::pin(future::ready(Ok(value)));
+        }
+        // A completed call whose owner was dropped before removing it is stale.
+        if let Some(pending) = state.in_flight.get(&key) {
+            if pending.peek().is_none() {
+                let pending = pending.clone();
+                state.coalesced += 1;
+                return Box::pin(pending);
+            }
+        }
+
+        let pending = self.transport.send(id, request, options).boxed().shared();
+        state.in_flight.insert(key.clone(), pending.clone());
+        drop(state);
+
+        let shared_state = self.state.clone();
+        let clock = self.clock.clone();
+        Box::pin(async move {
+            let result = pending.await;
+            let mut state = shared_state.lock();
+            state.in_flight.remove(&key);
+            if let (Ok(value), true) = (&result, window > 0) {
+                state.completed.insert(key, (clock(), value.clone()));
+            }
+            result
+        })
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> super::LogLimits {
+        self.transport.log_limits()
+    }
+}
@@ -0,0 +1,138 @@
+//! Transport refusing to issue outcalls when the canister runs low on cycles.
+
+use crate::{
+    error::{Error, Result, TransportError},
+    RequestId, Transport,
+};
+use futures::future::{self, BoxFuture};
+use jsonrpc_core::types::{Call, Value};
+use parking_lot::Mutex;
+use std::{fmt, sync::Arc};
+
+use super::ic_http_client::CallOptions;
+
+type BalanceSource = Arc<dyn Fn() -> u128 + Send + Sync>;
+type TripHook = Arc<dyn Fn(u128) + Send + Sync>;
+
+/// Circuit breaker checking the canister cycle balance before every call of the inner transport.
+///
+/// When the balance is below the threshold, calls fail with `TransportError::LowCycles` without
+/// issuing the outcall, so that a polling loop cannot drain the canister until it is frozen. The
+/// breaker closes again as soon as the balance is back above the threshold, e.g. after a top-up.
+///
+/// The transport is cheap to clone; clones share the same breaker.
+#[derive(Clone)]
+pub struct CyclesGuardTransport<T> {
+    transport: T,
+    threshold: u128,
+    tripped: Arc<Mutex<bool>>,
+    balance: BalanceSource,
+    on_trip: Option<TripHook>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for CyclesGuardTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CyclesGuardTransport")
+            .field("transport", &self.transport)
+            .field("threshold", &self.threshold)
+            .field("tripped", &*self.tripped.lock())
+            .finish()
+    }
+}
+
+impl<T: Transport> CyclesGuardTransport<T> {
+    /// Guard `transport`, refusing calls while the canister holds less than `threshold` cycles.
+    pub fn new(transport: T, threshold: u128) -> Self {
+        CyclesGuardTransport {
+            transport,
+            threshold,
+            tripped: Arc::new(Mutex::new(false)),
+            balance: Arc::new(ic_cdk::api::canister_balance128),
+            on_trip: None,
+        }
+    }
+
+    /// Read the balance from `balance` instead of the canister, e.g. to keep a reserve for other
+    /// operations.
+    pub fn with_balance_source<F>(mut self, balance: F) -> Self
+    where
+        F: Fn() -> u128 + Send + Sync + 'static,
+    {
+        self.balance = Arc::new(balance);
+        self
+    }
+
+    /// Call `hook` with the balance every time the breaker trips.
+    pub fn on_trip<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(u128) + Send + Sync + 'static,
+    {
+        self.on_trip = Some(Arc::new(hook));
+        self
+    }
+
+    /// Inner transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Minimum cycle balance required to issue calls.
+    pub fn threshold(&self) -> u128 {
+        self.threshold
+    }
+
+    /// Whether the last call was refused.
+    pub fn is_tripped(&self) -> bool {
+        *self.tripped.lock()
+    }
+
+    /// Check the balance, tripping or closing the breaker.
+    fn check(&self) -> Result<()> {
+        let balance = (self.balance)();
+        let low = balance < self.threshold;
+        let tripped = std::mem::replace(&mut *self.tripped.lock(), low);
+        if !low {
+            return Ok(());
+        }
+        if !tripped {
+            if let Some(hook) = &self.on_trip {
+                hook(balance);
+            }
+        }
+        Err(Error::Transport(TransportError::LowCycles {
+            balance,
+            threshold: self.threshold,
+        }))
+    }
+}
+
+impl<T> Transport for CyclesGuardTransport<T>
+where
+    T: Transport,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        match self.check() {
+            Ok(()) => Box::pin(self.transport.send(id, request, options)),
+            Err(err) => Box::pin(future::ready(Err(err))),
+        }
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> super::LogLimits {
+        self.transport.log_limits()
+    }
+}
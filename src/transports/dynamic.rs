@@ -0,0 +1,117 @@
+//! Type-erased transport, for transports chosen at runtime.
+
+use crate::{error::Result, RequestId, Transport};
+use futures::future::BoxFuture;
+use jsonrpc_core::types::{Call, Value};
+use std::fmt;
+
+use super::{ic_http_client::CallOptions, LogLimits};
+
+/// Object-safe counterpart of [`Transport`].
+trait ErasedTransport: fmt::Debug + Send + Sync {
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call);
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> BoxFuture<'static, Result<Value>>;
+    fn set_max_response_bytes(&mut self, bytes: u64);
+    fn default_call_options(&self) -> CallOptions;
+    fn log_limits(&self) -> LogLimits;
+    fn clone_box(&self) -> Box<dyn ErasedTransport>;
+}
+
+impl<T> ErasedTransport for T
+where
+    T: Transport + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        Transport::prepare(self, method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> BoxFuture<'static, Result<Value>> {
+        Box::pin(Transport::send(self, id, request, options))
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        Transport::set_max_response_bytes(self, bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        Transport::default_call_options(self)
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        Transport::log_limits(self)
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedTransport> {
+        Box::new(self.clone())
+    }
+}
+
+/// Transport hiding the type of the transport it wraps, so that the transport of a [`Web3`](crate::Web3)
+/// instance can be picked at runtime, e.g. from the canister configuration, without making the code using
+/// it generic over the transport.
+///
+/// ```ignore
+/// let transport = match config.quorum {
+///     Some(quorum) => DynTransport::new(QuorumTransport::new(providers, quorum)),
+///     None => DynTransport::new(ICHttp::new(&config.url, None)?),
+/// };
+/// let web3: Web3<DynTransport> = Web3::new(transport);
+/// ```
+///
+/// Batch requests are not available through the wrapper, as [`BatchTransport`](crate::BatchTransport) is
+/// not object-safe.
+pub struct DynTransport {
+    inner: Box<dyn ErasedTransport>,
+}
+
+impl DynTransport {
+    /// Erase the type of `transport`.
+    pub fn new<T>(transport: T) -> Self
+    where
+        T: Transport + Send + Sync + 'static,
+        T::Out: Send + 'static,
+    {
+        DynTransport {
+            inner: Box::new(transport),
+        }
+    }
+}
+
+impl Clone for DynTransport {
+    fn clone(&self) -> Self {
+        DynTransport {
+            inner: self.inner.clone_box(),
+        }
+    }
+}
+
+impl fmt::Debug for DynTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynTransport").field(&self.inner).finish()
+    }
+}
+
+impl Transport for DynTransport {
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.inner.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        self.inner.send(id, request, options)
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.inner.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.inner.default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.inner.log_limits()
+    }
+}
@@ -1,36 +1,63 @@
 //! IC HTTP Transport
 
-use crate::transports::ICHttpClient;
+use crate::transports::{
+    certification::{self, ResponseVerifier},
+    metrics, ICHttpClient, ProviderProfile, ProviderSession,
+};
 use crate::{
+    debug,
     error::{Error, Result, TransportError},
-    helpers, RequestId, Transport,
+    helpers, polling, BatchTransport, RequestId, Transport,
 };
 #[cfg(not(feature = "wasm"))]
 use futures::future::BoxFuture;
+use futures::Future;
 use ic_cdk::api::management_canister::http_request::TransformContext;
 use jsonrpc_core::types::{Call, Output, Request, Value};
 use serde::de::DeserializeOwned;
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
 };
 
+use super::ic_http_client::{is_state_changing, Replication};
 pub use super::ic_http_client::{CallOptions, CallOptionsBuilder};
 
+/// How the transport assigns JSON-RPC ids.
+///
+/// The id is part of the outcall request, so every replica must send the same one for the outcall to
+/// reach consensus. Sequential ids only stay in step as long as all replicas issue the same calls in the
+/// same order from a fresh transport, which does not hold e.g. for transports kept in canister state
+/// across messages. A fixed id always does. Either way [`CallOptions`] may override the id of a call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RequestIdMode {
+    /// Increasing ids, starting at zero
+    #[default]
+    Sequential,
+    /// The same id for every request
+    Fixed(RequestId),
+}
+
 /// HTTP Transport
 #[derive(Clone, Debug)]
 pub struct ICHttp {
     client: ICHttpClient,
     inner: Arc<Inner>,
+    default_options: CallOptions,
+    id_mode: RequestIdMode,
+    certified: bool,
+    session: Option<ProviderSession>,
 }
 
 #[derive(Debug)]
 struct Inner {
     url: String,
     id: AtomicUsize,
+    profile: ProviderProfile,
 }
 
 impl ICHttp {
@@ -40,45 +67,308 @@ impl ICHttp {
     /// header or enabling a proxy from the environment. You can customize it with
     /// [Http::with_client].
     pub fn new(url: &str, max_resp: Option<u64>) -> Result<Self> {
+        Self::with_profile(url, max_resp, ProviderProfile::default())
+    }
+
+    /// Create new HTTP transport whose outgoing requests are shaped by the given provider profile.
+    pub fn with_profile(url: &str, max_resp: Option<u64>, profile: ProviderProfile) -> Result<Self> {
         Ok(Self {
             client: ICHttpClient::new(max_resp),
             inner: Arc::new(Inner {
                 url: url.to_string(),
                 id: AtomicUsize::new(0),
+                profile,
             }),
+            default_options: CallOptions::default(),
+            id_mode: RequestIdMode::default(),
+            certified: false,
+            session: None,
         })
     }
 
+    /// Set how JSON-RPC ids are assigned to requests.
+    pub fn set_request_id_mode(&mut self, mode: RequestIdMode) {
+        self.id_mode = mode;
+    }
+
+    /// Set the outcall options used for calls that don't specify their own.
+    pub fn set_default_call_options(&mut self, options: CallOptions) {
+        self.default_options = options;
+    }
+
+    /// Check every response with `verifier` before accepting it, see [`certification`](super::certification).
+    ///
+    /// The outcalls are sent with the [certified transform](certification::transform), which runs the
+    /// verifier: it replaces the verifier of the other transports of the canister, see
+    /// [`set_verifier`](certification::set_verifier).
+    pub fn set_response_verifier<V: ResponseVerifier + 'static>(&mut self, verifier: V) {
+        certification::set_verifier(verifier);
+        self.certified = true;
+    }
+
+    /// Adapt the response size limits to the observed response sizes, see
+    /// [`ICHttpClient::set_adaptive_response_bytes`].
+    pub fn set_adaptive_response_bytes(&mut self, adaptive: bool) {
+        self.client.set_adaptive_response_bytes(adaptive);
+    }
+
+    /// Size in bytes of the last response to `method` observed in adaptive mode.
+    pub fn observed_response_bytes(&self, method: &str) -> Option<u64> {
+        self.client.observed_response_bytes(method)
+    }
+
+    /// Response size limit of the next call of `method` in adaptive mode.
+    pub fn adaptive_response_bytes(&self, method: &str) -> Option<u64> {
+        self.client.adaptive_response_bytes(method)
+    }
+
+    /// Price outcalls for a subnet of `subnet_size` nodes, see [`ICHttpClient::estimate_cycles`].
+    pub fn set_subnet_size(&mut self, subnet_size: u32) {
+        self.client.set_subnet_size(subnet_size);
+    }
+
+    /// Attach exactly the estimated price to the outcalls, see [`ICHttpClient::set_exact_cycles`].
+    pub fn set_exact_cycles(&mut self, exact: bool) {
+        self.client.set_exact_cycles(exact);
+    }
+
+    /// Send the header `name` with `value` with every request, e.g. the `x-api-key` of the provider, see
+    /// [`ICHttpClient::set_header`].
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.client.set_header(name, value);
+    }
+
+    /// Authenticate the requests with HTTP basic authentication.
+    pub fn set_basic_auth(&mut self, user: &str, password: &str) {
+        let credentials = base64::encode(format!("{}:{}", user, password));
+        self.set_header("Authorization", &format!("Basic {}", credentials));
+    }
+
+    /// Authenticate the requests with a bearer token.
+    pub fn set_bearer_auth(&mut self, token: &str) {
+        self.set_header("Authorization", &format!("Bearer {}", token));
+    }
+
+    /// Copy of the transport whose calls belong to `session`: they send its headers and cookies, and
+    /// the responses update them, see [`session`](super::session).
+    ///
+    /// Headers of the call options replace the session ones of the same name.
+    pub fn with_session(&self, session: ProviderSession) -> Self {
+        Self {
+            session: Some(session),
+            ..self.clone()
+        }
+    }
+
+    /// Session of the calls, see [`ICHttp::with_session`].
+    pub fn session(&self) -> Option<&ProviderSession> {
+        self.session.as_ref()
+    }
+
+    /// Provider profile used to shape outgoing requests.
+    pub fn profile(&self) -> &ProviderProfile {
+        &self.inner.profile
+    }
+
     fn next_id(&self) -> RequestId {
-        self.inner.id.fetch_add(1, Ordering::AcqRel)
+        match self.id_mode {
+            RequestIdMode::Sequential => self.inner.id.fetch_add(1, Ordering::AcqRel),
+            RequestIdMode::Fixed(id) => id,
+        }
     }
 
     fn new_request(&self) -> (ICHttpClient, String) {
         (self.client.clone(), self.inner.url.clone())
     }
+
+    /// Adds the session headers to `options`, unless they set headers of the same name, and the
+    /// certified transform if responses are verified.
+    fn session_options(&self, mut options: CallOptions) -> CallOptions {
+        if self.certified {
+            options = options.with_transform(certification::transform_context(&self.inner.url));
+        }
+        if let Some(session) = &self.session {
+            for header in session.headers() {
+                if !options
+                    .headers()
+                    .iter()
+                    .any(|current| current.name.eq_ignore_ascii_case(&header.name))
+                {
+                    options = options.with_header(&header.name, &header.value);
+                }
+            }
+        }
+        options
+    }
 }
 
-// Id is only used for logging.
-async fn execute_rpc<T: DeserializeOwned>(
+async fn post_rpc(
     client: &ICHttpClient,
     url: String,
     request: &Request,
-    id: RequestId,
     options: CallOptions,
-) -> Result<T> {
+    certified: bool,
+    session: Option<ProviderSession>,
+) -> Result<Vec<u8>> {
     let response = client
-        .post(url, request, options)
+        .post_response(url, request, options)
         .await
-        .map_err(|err| Error::Transport(TransportError::Message(err)))?;
-    helpers::arbitrary_precision_deserialize_workaround(&response).map_err(|err| {
+        .map_err(Error::Transport)?;
+    let status = u16::try_from(&response.status.0).unwrap_or(u16::MAX);
+    if !(200..300).contains(&status) {
+        // Providers answer some JSON-RPC errors, e.g. reverts or rate limits, with an error status: those
+        // are decoded as any response, so that their code and data are not lost.
+        if is_rpc_response(&response.body) {
+            return Ok(response.body);
+        }
+        return Err(Error::Transport(TransportError::Code(status)));
+    }
+    if certified {
+        certification::check_verified(&response)
+            .map_err(|reason| Error::Transport(TransportError::Uncertified(reason)))?;
+    }
+    if let Some(session) = session {
+        session.capture(&response.headers);
+    }
+    Ok(response.body)
+}
+
+/// Whether `body` is a JSON-RPC response, single or batch.
+pub(super) fn is_rpc_response(body: &[u8]) -> bool {
+    helpers::from_slice::<jsonrpc_core::Response>(body).is_ok()
+}
+
+pub(super) fn decode_response<T: DeserializeOwned>(response: &[u8]) -> Result<T> {
+    helpers::from_slice(response).map_err(|err| {
         Error::Transport(TransportError::Message(format!(
             "failed to deserialize response: {}: {}",
             err,
-            String::from_utf8_lossy(&response)
+            String::from_utf8_lossy(response)
         )))
     })
 }
 
+// Records the request in the `debug` ring buffer.
+fn trace_request(call: &Call, started: u64, response_bytes: usize, result: &Result<Value>) {
+    let (method, params) = match call {
+        Call::MethodCall(method_call) => (
+            method_call.method.clone(),
+            serde_json::to_string(&method_call.params).unwrap_or_default(),
+        ),
+        Call::Notification(notification) => (
+            notification.method.clone(),
+            serde_json::to_string(&notification.params).unwrap_or_default(),
+        ),
+        Call::Invalid { .. } => (String::new(), String::new()),
+    };
+    let status = match result {
+        Ok(_) => debug::RequestStatus::Success,
+        Err(Error::Rpc(err)) => debug::RequestStatus::Rpc(err.code.code()),
+        Err(Error::Transport(TransportError::Code(code))) => debug::RequestStatus::Http(*code),
+        Err(err) => debug::RequestStatus::Failed(debug::truncate(err.to_string())),
+    };
+    debug::record(debug::RequestRecord {
+        method,
+        params: debug::truncate(params),
+        status,
+        timestamp: started,
+        latency: debug::now().saturating_sub(started),
+        response_bytes: response_bytes as u64,
+    });
+}
+
+/// Method of `call`, empty for invalid calls.
+fn method_of(call: &Call) -> &str {
+    match call {
+        Call::MethodCall(method_call) => &method_call.method,
+        Call::Notification(notification) => &notification.method,
+        Call::Invalid { .. } => "",
+    }
+}
+
+/// Makes `attempt`s of `request` until one succeeds or fails with an error the retry policy of the
+/// options does not retry, waiting between them. State-changing calls are only attempted once.
+///
+/// The retries are accounted for in the metrics with the `cycles` attached to each attempt.
+async fn retrying<T, F, Fut>(
+    request: &Request,
+    options: &CallOptions,
+    cycles: u128,
+    mut attempt: F,
+) -> (usize, Result<T>)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = (usize, Result<T>)>,
+{
+    let calls = match request {
+        Request::Single(call) => std::slice::from_ref(call),
+        Request::Batch(calls) => calls.as_slice(),
+    };
+    let policy = options
+        .retry()
+        .filter(|_| !calls.iter().any(|call| is_state_changing(method_of(call))));
+    let mut attempts = 0;
+    loop {
+        let (response_bytes, result) = attempt().await;
+        attempts += 1;
+        match (&result, policy) {
+            (Err(err), Some(policy)) if policy.should_retry(err, attempts) => {
+                for call in calls {
+                    metrics::record_retry_outcall(method_of(call), cycles / calls.len() as u128);
+                }
+                let delay = policy.backoff().delay(attempts);
+                if !delay.is_zero() {
+                    polling::sleep(delay).await;
+                }
+            }
+            _ => return (response_bytes, result),
+        }
+    }
+}
+
+// Accounts for the call in the transport metrics, with its share of the outcall cycles and response.
+fn record_metrics(
+    call: &Call,
+    cycles: u128,
+    share: usize,
+    started: u64,
+    response_bytes: usize,
+    result: &Result<Value>,
+) {
+    metrics::record_call(
+        method_of(call),
+        cycles / share as u128,
+        (response_bytes / share) as u64,
+        debug::now().saturating_sub(started),
+        result.as_ref().err(),
+    );
+}
+
+/// Refuses state-changing calls in non-replicated outcalls, whose responses are not trustworthy.
+fn check_replication(call: &Call, options: &CallOptions) -> Result<()> {
+    match call {
+        Call::MethodCall(method_call)
+            if options.replication() == Replication::NonReplicated && is_state_changing(&method_call.method) =>
+        {
+            Err(Error::Transport(TransportError::NotSent(format!(
+                "{} refused in a non-replicated outcall",
+                method_call.method
+            ))))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Shapes the parameters of `call` according to the provider profile.
+fn shape_call(profile: &ProviderProfile, call: &mut Call) -> Result<()> {
+    if let Call::MethodCall(method_call) = call {
+        if let jsonrpc_core::Params::Array(params) = &mut method_call.params {
+            *params = profile.shape(&method_call.method, std::mem::take(params))?;
+        }
+    }
+    Ok(())
+}
+
 type RpcResult = Result<Value>;
 
 impl Transport for ICHttp {
@@ -90,26 +380,249 @@ impl Transport for ICHttp {
         (id, request)
     }
 
-    fn send(&self, id: RequestId, call: Call, options: CallOptions) -> Self::Out {
+    fn send(&self, id: RequestId, mut call: Call, options: CallOptions) -> Self::Out {
         let (client, url) = self.new_request();
+        let certified = self.certified;
+        let session = self.session.clone();
+        let options = self.session_options(options);
+        if let (Some(request_id), Call::MethodCall(method_call)) = (options.request_id(), &mut call) {
+            method_call.id = jsonrpc_core::Id::Num(request_id);
+        }
+        if let Err(err) = shape_call(&self.inner.profile, &mut call).and_then(|_| check_replication(&call, &options)) {
+            return Box::pin(futures::future::ready(Err(err)));
+        }
+        let request = Request::Single(call);
+        let cycles = match client.attached_cycles(&url, &request, &options) {
+            Ok(cycles) => cycles,
+            Err(err) => return Box::pin(futures::future::ready(Err(err))),
+        };
         Box::pin(async move {
-            let output: Output = execute_rpc(&client, url, &Request::Single(call), id, options).await?;
-            helpers::to_result_from_output(output)
+            let started = debug::now();
+            let (response_bytes, result) = retrying(&request, &options, cycles, || async {
+                let response = post_rpc(
+                    &client,
+                    url.clone(),
+                    &request,
+                    options.clone(),
+                    certified,
+                    session.clone(),
+                )
+                .await;
+                metrics::record_outcall();
+                let response_bytes = response.as_ref().map_or(0, Vec::len);
+                let result = response
+                    .and_then(|response| decode_response::<Output>(&response))
+                    .and_then(helpers::to_result_from_output);
+                (response_bytes, result)
+            })
+            .await;
+            if let Request::Single(call) = &request {
+                record_metrics(call, cycles, 1, started, response_bytes, &result);
+                trace_request(call, started, response_bytes, &result);
+            }
+            result
         })
     }
 
     fn set_max_response_bytes(&mut self, v: u64) {
         self.client.set_max_response_bytes(v);
     }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.default_options.clone()
+    }
+
+    fn log_limits(&self) -> super::LogLimits {
+        self.inner.profile.log_limits().clone()
+    }
 }
 
-fn id_of_output(output: &Output) -> Result<RequestId> {
-    let id = match output {
+impl BatchTransport for ICHttp {
+    type Batch = BoxFuture<'static, Result<Vec<RpcResult>>>;
+
+    /// Send the calls as a JSON-RPC batch in a single outcall.
+    ///
+    /// The calls are renumbered by their position in the batch, so that the batch is the same on
+    /// every replica whatever the [`RequestIdMode`], and the results are matched back by id since
+    /// providers may answer in any order.
+    fn send_batch<I>(&self, requests: I, options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let (client, url) = self.new_request();
+        let certified = self.certified;
+        let session = self.session.clone();
+        let options = self.session_options(options);
+        let calls: Result<Vec<Call>> = requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, mut call))| {
+                if let Call::MethodCall(method_call) = &mut call {
+                    method_call.id = jsonrpc_core::Id::Num(index as u64);
+                }
+                shape_call(&self.inner.profile, &mut call)?;
+                check_replication(&call, &options)?;
+                Ok(call)
+            })
+            .collect();
+        let calls = match calls {
+            Ok(calls) => calls,
+            Err(err) => return Box::pin(futures::future::ready(Err(err))),
+        };
+        let len = calls.len();
+        let request = Request::Batch(calls);
+        let cycles = match client.attached_cycles(&url, &request, &options) {
+            Ok(cycles) => cycles,
+            Err(err) => return Box::pin(futures::future::ready(Err(err))),
+        };
+        Box::pin(async move {
+            let started = debug::now();
+            let (response_bytes, result) = retrying(&request, &options, cycles, || async {
+                let response = post_rpc(
+                    &client,
+                    url.clone(),
+                    &request,
+                    options.clone(),
+                    certified,
+                    session.clone(),
+                )
+                .await;
+                metrics::record_outcall();
+                let response_bytes = response.as_ref().map_or(0, Vec::len);
+                let result = response
+                    .and_then(|response| decode_response::<Vec<Output>>(&response))
+                    .and_then(|outputs| order_outputs(outputs, len));
+                (response_bytes, result)
+            })
+            .await;
+            if let Request::Batch(calls) = &request {
+                for (index, call) in calls.iter().enumerate() {
+                    let call_result = match &result {
+                        Ok(results) => results[index].clone(),
+                        Err(err) => Err(err.clone()),
+                    };
+                    record_metrics(call, cycles, calls.len(), started, response_bytes, &call_result);
+                    trace_request(call, started, response_bytes, &call_result);
+                }
+            }
+            result
+        })
+    }
+}
+
+/// Results of a batch of `len` calls in request order, from outputs in any order.
+///
+/// Outputs whose ids were all replaced by `null`, e.g. by a custom transform, are taken in request order.
+pub(super) fn order_outputs(outputs: Vec<Output>, len: usize) -> Result<Vec<RpcResult>> {
+    if outputs.len() != len {
+        return Err(Error::InvalidResponse(format!(
+            "expected {} responses to the batch, got {}",
+            len,
+            outputs.len()
+        )));
+    }
+    if outputs
+        .iter()
+        .all(|output| *output_id(output) == jsonrpc_core::Id::Null)
+    {
+        return Ok(outputs.into_iter().map(helpers::to_result_from_output).collect());
+    }
+    let mut results: Vec<Option<RpcResult>> = vec![None; len];
+    for output in outputs {
+        let id = id_of_output(&output)?;
+        match results.get_mut(id) {
+            Some(slot @ None) => *slot = Some(helpers::to_result_from_output(output)),
+            _ => {
+                return Err(Error::InvalidResponse(format!(
+                    "unexpected response id {} in batch",
+                    id
+                )))
+            }
+        }
+    }
+    Ok(results.into_iter().flatten().collect())
+}
+
+fn output_id(output: &Output) -> &jsonrpc_core::Id {
+    match output {
         Output::Success(success) => &success.id,
         Output::Failure(failure) => &failure.id,
-    };
-    match id {
+    }
+}
+
+fn id_of_output(output: &Output) -> Result<RequestId> {
+    match output_id(output) {
         jsonrpc_core::Id::Num(num) => Ok(*num as RequestId),
         _ => Err(Error::InvalidResponse("response id is not u64".to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_replication, decode_response, order_outputs};
+    use crate::{helpers::build_request, transforms::normalize::Normalizer, transports::ic_http_client::CallOptions};
+    use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+    use jsonrpc_core::types::Output;
+    use serde_json::json;
+
+    fn transform_batch(context: &str, body: serde_json::Value) -> Vec<u8> {
+        let response = HttpResponse {
+            status: 200u64.into(),
+            headers: vec![],
+            body: serde_json::to_vec(&body).unwrap(),
+        };
+        Normalizer::standard()
+            .transform(TransformArgs {
+                response,
+                context: context.as_bytes().to_vec(),
+            })
+            .body
+    }
+
+    #[test]
+    fn batch_through_standard_transform() {
+        let body = transform_batch(
+            "eth_getBlockByNumber,eth_getTransactionReceipt,eth_chainId",
+            json!([
+                {"jsonrpc": "2.0", "id": 2, "result": "0x1"},
+                {"jsonrpc": "2.0", "id": 1, "result": {"transactionIndex": "0x5", "logs": []}},
+                {"jsonrpc": "2.0", "id": 0, "result": {"number": "0x10", "totalDifficulty": "0x0"}},
+            ]),
+        );
+        let outputs: Vec<Output> = decode_response(&body).unwrap();
+        let results: Vec<_> = order_outputs(outputs, 3)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                json!({"number": "0x10"}),
+                json!({"transactionIndex": "0x0", "logs": []}),
+                json!("0x1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn batch_without_ids_in_request_order() {
+        let body = serde_json::to_vec(&json!([
+            {"jsonrpc": "2.0", "id": null, "result": "0x1"},
+            {"jsonrpc": "2.0", "id": null, "error": {"code": -32000, "message": "header not found"}},
+        ]))
+        .unwrap();
+        let outputs: Vec<Output> = decode_response(&body).unwrap();
+        let results = order_outputs(outputs, 2).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &json!("0x1"));
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn non_replicated_transaction_is_not_sent() {
+        let call = build_request(1, "eth_sendRawTransaction", vec![json!("0x00")]);
+        let err = check_replication(&call, &CallOptions::default().non_replicated()).unwrap_err();
+        assert!(!err.is_outcome_unknown());
+        assert!(check_replication(&call, &CallOptions::default()).is_ok());
+    }
+}
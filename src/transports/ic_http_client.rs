@@ -3,19 +3,207 @@
 use candid::CandidType;
 use candid::{candid_method, Principal};
 use derive_builder::Builder;
-use ic_cdk::api::management_canister::http_request::{
-    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformContext, TransformFunc,
+use ic_cdk::api::{
+    call::RejectionCode,
+    management_canister::http_request::{
+        http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformContext,
+        TransformFunc,
+    },
 };
-use jsonrpc_core::Request;
+use jsonrpc_core::{Call, Request};
+use parking_lot::Mutex;
 use serde::{self, Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
 
-const HTTP_OUTCALL_PRICE: u128 = 400_000_000;
-const COST_PER_BYTE: u128 = 100_000;
-const BYTES: u128 = 3_200_000;
+use crate::error::{self, Error, TransportError};
 
-#[derive(Clone, Debug)]
+use super::retry::RetryPolicy;
+
+/// Fee of an outcall, in cycles per node of the subnet, on top of [`OUTCALL_FEE_PER_NODE_SQUARED`].
+const OUTCALL_BASE_FEE: u128 = 3_000_000;
+/// Fee of an outcall, in cycles per squared node count of the subnet.
+const OUTCALL_FEE_PER_NODE_SQUARED: u128 = 60_000;
+/// Fee per request byte, in cycles per node of the subnet.
+const REQUEST_BYTE_FEE: u128 = 400;
+/// Fee per byte of the response size limit, in cycles per node of the subnet.
+const RESPONSE_BYTE_FEE: u128 = 800;
+/// Subnet size assumed for the pricing of outcalls, the size of the application subnets.
+pub const DEFAULT_SUBNET_SIZE: u32 = 13;
+/// Size of the largest subnets, e.g. the fiduciary subnet, which outcalls attach cycles for by default.
+pub const LARGEST_SUBNET_SIZE: u32 = 34;
+/// Largest response size limit accepted by the IC.
+pub const MAX_RESPONSE_BYTES: u64 = 2_000_000;
+/// Headroom added to the observed response sizes in adaptive mode, in percent.
+const ADAPTIVE_HEADROOM_PERCENT: u64 = 25;
+/// Largest decrease of the limit of a method after a successful call in adaptive mode, in percent.
+const ADAPTIVE_DECAY_PERCENT: u64 = 10;
+/// Allowance for the response headers, counted in the limit but stripped by transforms.
+const RESPONSE_HEADERS_ALLOWANCE: u64 = 1_000;
+
+/// Methods changing the chain or node state, refused by non-replicated outcalls.
+pub const STATE_CHANGING_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "eth_sign",
+    "eth_signTransaction",
+    "eth_signTypedData",
+    "eth_submitWork",
+    "eth_submitHashrate",
+    "personal_sendTransaction",
+    "personal_sign",
+];
+
+/// Whether `method` changes the chain or node state, see [`STATE_CHANGING_METHODS`].
+pub fn is_state_changing(method: &str) -> bool {
+    STATE_CHANGING_METHODS.contains(&method)
+}
+
+/// How many replicas of the subnet perform an outcall.
+///
+/// Replicated outcalls are sent by every replica and the responses go through consensus, so a single
+/// malicious replica or provider response cannot make it into the canister. A non-replicated outcall is
+/// sent by a single replica and its response is trusted as is: it is cheaper and faster, but that replica
+/// can forge the response. Only use it for reads whose result is not used to move value or change state,
+/// e.g. displaying a balance; transports refuse to send state-changing methods (see
+/// [`is_state_changing`]) without consensus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Replication {
+    /// Every replica sends the outcall and the responses reach consensus
+    #[default]
+    Replicated,
+    /// A single replica sends the outcall, its response is not checked by consensus
+    NonReplicated,
+}
+
+/// `http_request` argument of the management canister with the replication flag, missing from the
+/// `ic-cdk` types.
+#[derive(CandidType, Clone, Debug)]
+struct HttpRequestArgument {
+    url: String,
+    max_response_bytes: Option<u64>,
+    method: HttpMethod,
+    headers: Vec<HttpHeader>,
+    body: Option<Vec<u8>>,
+    transform: Option<TransformContext>,
+    is_replicated: Option<bool>,
+}
+
+/// Response size limit of a method in adaptive mode.
+#[derive(Clone, Copy, Debug, Default)]
+struct AdaptiveLimit {
+    /// Size of the last response, after its transform
+    observed: u64,
+    /// Limit of the next call
+    limit: u64,
+    /// Largest limit a response was rejected for, which the size of the untransformed responses exceeds
+    rejected: u64,
+}
+
+impl AdaptiveLimit {
+    /// Limit of the calls following one which succeeded with `limit` and returned `observed` bytes.
+    ///
+    /// Only the transformed response is seen, while the limit applies to the response as received, headers
+    /// included. The limit thus decays slowly from the one which succeeded, towards the observed size with
+    /// some headroom, and never gets back to a limit a response was rejected for.
+    fn succeeded(&mut self, limit: u64, observed: u64) {
+        let sized = observed + observed * ADAPTIVE_HEADROOM_PERCENT / 100 + RESPONSE_HEADERS_ALLOWANCE;
+        let decayed = limit - limit * ADAPTIVE_DECAY_PERCENT / 100;
+        let above_rejected = self.rejected + self.rejected * ADAPTIVE_HEADROOM_PERCENT / 100;
+        self.observed = observed;
+        self.limit = sized.max(decayed).max(above_rejected).min(MAX_RESPONSE_BYTES);
+    }
+}
+
+#[derive(Clone)]
 pub struct ICHttpClient {
     pub max_response_bytes: u64,
+    /// Response size limit of each method, in adaptive mode
+    adaptive: Option<Arc<Mutex<HashMap<String, AdaptiveLimit>>>>,
+    /// Number of nodes of the subnet of the canister, which the outcall price depends on
+    subnet_size: u32,
+    /// Whether outcalls attach exactly their estimated price instead of the price on the largest subnets
+    exact_cycles: bool,
+    /// Headers sent with every request
+    headers: Vec<HttpHeader>,
+}
+
+impl std::fmt::Debug for ICHttpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The header values may hold credentials.
+        let headers: Vec<_> = self.headers.iter().map(|header| &header.name).collect();
+        f.debug_struct("ICHttpClient")
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("adaptive", &self.adaptive)
+            .field("subnet_size", &self.subnet_size)
+            .field("exact_cycles", &self.exact_cycles)
+            .field("headers", &headers)
+            .finish()
+    }
+}
+
+/// Set `header` in `headers`, replacing the header of the same name, which is case-insensitive.
+fn merge_header(headers: &mut Vec<HttpHeader>, header: HttpHeader) {
+    match headers
+        .iter_mut()
+        .find(|existing| existing.name.eq_ignore_ascii_case(&header.name))
+    {
+        Some(existing) => *existing = header,
+        None => headers.push(header),
+    }
+}
+
+/// Headers of the JSON-RPC requests.
+pub(crate) fn json_headers() -> Vec<HttpHeader> {
+    vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }]
+}
+
+/// Transform of the options, or the canister's `transform` query method.
+///
+/// The context of the [standard transform](crate::transforms::normalize) is the method of the call: for
+/// a batch, it is replaced by the methods of the calls in order, separated by commas, so that each
+/// response is normalized by the rules of its own method.
+fn transform_of(options: &CallOptions, payload: &Request) -> TransformContext {
+    let mut transform = match &options.transform {
+        Some(t) => t.clone(),
+        None => TransformContext {
+            function: TransformFunc(candid::Func {
+                principal: ic_cdk::api::id(),
+                method: "transform".to_string(),
+            }),
+            context: vec![],
+        },
+    };
+    if let Request::Batch(_) = payload {
+        if transform.function.0.method == crate::transforms::presets::TRANSFORM_METHOD {
+            transform.context = request_key(payload).into_bytes();
+        }
+    }
+    transform
+}
+
+/// Size of an outcall request as charged by the IC: URL, headers, body and transform.
+fn request_size(url: &str, headers: &[HttpHeader], body: &[u8], transform: &TransformContext) -> u64 {
+    let headers: usize = headers
+        .iter()
+        .map(|header| header.name.len() + header.value.len())
+        .sum();
+    (url.len() + headers + body.len() + transform.function.0.method.len() + transform.context.len()) as u64
+}
+
+/// Method of a single call, or the methods of a batch, whose response sizes are remembered together.
+fn request_key(payload: &Request) -> String {
+    let method = |call: &Call| match call {
+        Call::MethodCall(call) => call.method.clone(),
+        Call::Notification(notification) => notification.method.clone(),
+        Call::Invalid { .. } => String::new(),
+    };
+    match payload {
+        Request::Single(call) => method(call),
+        Request::Batch(calls) => calls.iter().map(method).collect::<Vec<_>>().join(","),
+    }
 }
 
 #[derive(Builder, Default, Clone, Debug, PartialEq, Eq)]
@@ -23,12 +211,268 @@ pub struct CallOptions {
     max_resp: Option<u64>,
     cycles: Option<u64>,
     transform: Option<TransformContext>,
+    /// JSON-RPC id sent instead of the one assigned by the transport
+    #[builder(default)]
+    request_id: Option<u64>,
+    /// Replication of the outcall, see [`Replication`]
+    #[builder(default)]
+    replication: Replication,
+    /// Headers sent on top of the ones of the transport, replacing those of the same name
+    #[builder(default)]
+    headers: Vec<HttpHeader>,
+    /// Ask for a compressed response, see [`compression`](crate::transforms::compression)
+    #[cfg(feature = "compression")]
+    #[builder(default)]
+    compression: bool,
+    /// Retries of the failed outcalls, see [`RetryPolicy`]
+    #[builder(default)]
+    retry: Option<RetryPolicy>,
+}
+
+impl CallOptions {
+    /// Response size limit, if set.
+    pub fn max_resp(&self) -> Option<u64> {
+        self.max_resp
+    }
+
+    /// JSON-RPC id sent instead of the one assigned by the transport, if any.
+    pub fn request_id(&self) -> Option<u64> {
+        self.request_id
+    }
+
+    /// Replication of the outcall.
+    pub fn replication(&self) -> Replication {
+        self.replication
+    }
+
+    /// Headers sent on top of the ones of the transport.
+    pub fn headers(&self) -> &[HttpHeader] {
+        &self.headers
+    }
+
+    /// Send the header `name` with `value`, replacing the header of the same name of the transport, e.g.
+    /// an API key specific to the call.
+    ///
+    /// Every replica sends the request, which must be identical for the outcall to reach consensus: the
+    /// value must not depend on the replica, e.g. on the time or a random nonce.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        merge_header(
+            &mut self.headers,
+            HttpHeader {
+                name: name.to_string(),
+                value: value.to_string(),
+            },
+        );
+        self
+    }
+
+    /// Whether a compressed response is asked for.
+    #[cfg(feature = "compression")]
+    pub fn compression(&self) -> bool {
+        self.compression
+    }
+
+    /// Ask the provider for a gzip or deflate response, decompressed by the transform or the client, see
+    /// [`compression`](crate::transforms::compression).
+    ///
+    /// The response size limit applies to the compressed response, so it can be lowered accordingly.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self) -> Self {
+        self.compression = true;
+        self
+    }
+
+    /// Retry policy of the call, if any.
+    pub fn retry(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
+
+    /// Retry the call as `policy` says when it fails, see [`retry`](super::retry).
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Transform the responses with `transform` instead of the canister's `transform` query method.
+    pub fn with_transform(mut self, transform: TransformContext) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Send the outcall from a single replica, without consensus on the response.
+    ///
+    /// Only for reads whose result does not drive value transfers or state changes, see
+    /// [`Replication::NonReplicated`].
+    pub fn non_replicated(mut self) -> Self {
+        self.replication = Replication::NonReplicated;
+        self
+    }
+
+    /// Ready-to-use options for `method`, following its [preset](crate::transforms::presets).
+    ///
+    /// The response size limit is the preset estimate, or the default limit for methods without a
+    /// preset, and the transform is the canister's
+    /// [`TRANSFORM_METHOD`](crate::transforms::presets::TRANSFORM_METHOD) query with the method as
+    /// context, see [`export_transform!`](crate::export_transform).
+    pub fn default_for(method: &str) -> Self {
+        use crate::transforms::presets;
+
+        let max_resp = presets::for_method(method)
+            .map(|preset| preset.max_response_bytes)
+            .unwrap_or(presets::DEFAULT_MAX_RESPONSE_BYTES);
+        CallOptions {
+            max_resp: Some(max_resp),
+            cycles: None,
+            request_id: None,
+            replication: Replication::Replicated,
+            headers: Vec::new(),
+            #[cfg(feature = "compression")]
+            compression: false,
+            retry: None,
+            transform: Some(TransformContext {
+                function: TransformFunc(candid::Func {
+                    principal: ic_cdk::api::id(),
+                    method: presets::TRANSFORM_METHOD.to_string(),
+                }),
+                context: method.as_bytes().to_vec(),
+            }),
+        }
+    }
 }
 
 impl ICHttpClient {
     pub fn new(max_resp: Option<u64>) -> Self {
         ICHttpClient {
-            max_response_bytes: if let Some(v) = max_resp { v } else { 500_000 },
+            max_response_bytes: max_resp.unwrap_or(500_000),
+            adaptive: None,
+            subnet_size: DEFAULT_SUBNET_SIZE,
+            exact_cycles: false,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Send the header `name` with `value` with every request, replacing the header of the same name.
+    ///
+    /// Every replica sends the request, which must be identical for the outcall to reach consensus: the
+    /// value must not depend on the replica, e.g. on the time or a random nonce. Note that the replicas
+    /// of the subnet see the headers, API keys included.
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        merge_header(
+            &mut self.headers,
+            HttpHeader {
+                name: name.to_string(),
+                value: value.to_string(),
+            },
+        );
+    }
+
+    /// Headers sent with every request, on top of `Content-Type`.
+    pub fn headers(&self) -> &[HttpHeader] {
+        &self.headers
+    }
+
+    /// Headers of a request with `options`: the JSON content type, the accepted encodings of compressed
+    /// responses, the headers of the client and those of the options, in that order, later ones replacing
+    /// earlier ones of the same name.
+    fn request_headers(&self, options: &CallOptions) -> Vec<HttpHeader> {
+        let mut headers = json_headers();
+        #[cfg(feature = "compression")]
+        if options.compression {
+            merge_header(
+                &mut headers,
+                HttpHeader {
+                    name: "Accept-Encoding".to_string(),
+                    value: crate::transforms::compression::ACCEPT_ENCODING.to_string(),
+                },
+            );
+        }
+        for header in self.headers.iter().chain(&options.headers) {
+            merge_header(&mut headers, header.clone());
+        }
+        headers
+    }
+
+    /// Cycles the IC charges for an outcall of `request_size` bytes with a response limited to
+    /// `max_response_bytes`, from a subnet of `subnet_size` nodes.
+    ///
+    /// The price of an outcall is `(3_000_000 + 60_000 * n) * n` cycles, plus `400 * n` cycles per request
+    /// byte and `800 * n` cycles per byte of the response limit, `n` being the subnet size. The request
+    /// size counts the URL, the headers, the body and the transform method name and context. The whole
+    /// response limit is paid for, whatever the actual size of the response.
+    pub fn estimate_cycles(request_size: u64, max_response_bytes: u64, subnet_size: u32) -> u128 {
+        let n = subnet_size as u128;
+        (OUTCALL_BASE_FEE + OUTCALL_FEE_PER_NODE_SQUARED * n) * n
+            + REQUEST_BYTE_FEE * n * request_size as u128
+            + RESPONSE_BYTE_FEE * n * max_response_bytes as u128
+    }
+
+    /// Price outcalls for a subnet of `subnet_size` nodes, [`DEFAULT_SUBNET_SIZE`] by default.
+    pub fn set_subnet_size(&mut self, subnet_size: u32) {
+        self.subnet_size = subnet_size;
+    }
+
+    /// Number of nodes of the subnet outcalls are priced for.
+    pub fn subnet_size(&self) -> u32 {
+        self.subnet_size
+    }
+
+    /// Attach exactly the [required](Self::required_cycles) cycles to the outcalls.
+    ///
+    /// By default, outcalls attach their price on a subnet of [`LARGEST_SUBNET_SIZE`] nodes, or of the
+    /// subnet size if larger, so that they do not fail when the canister runs on a larger subnet than the
+    /// one they are priced for. The cycles not charged are refunded, so the margin only has to be held in
+    /// the balance of the canister.
+    pub fn set_exact_cycles(&mut self, exact: bool) {
+        self.exact_cycles = exact;
+    }
+
+    /// Cycles attached by default to an outcall of `request_size` bytes with a response limited to
+    /// `max_response_bytes`, see [`set_exact_cycles`](Self::set_exact_cycles).
+    fn default_cycles(&self, request_size: u64, max_response_bytes: u64) -> u128 {
+        let subnet_size = if self.exact_cycles {
+            self.subnet_size
+        } else {
+            self.subnet_size.max(LARGEST_SUBNET_SIZE)
+        };
+        Self::estimate_cycles(request_size, max_response_bytes, subnet_size)
+    }
+
+    /// Cycles the IC charges for posting `payload` to `url` with `options`.
+    pub fn required_cycles(&self, url: &str, payload: &Request, options: &CallOptions) -> u128 {
+        let max_response_bytes = self.initial_max_response_bytes(&request_key(payload), options);
+        let body = crate::helpers::encode_request(payload);
+        let size = request_size(
+            url,
+            &self.request_headers(options),
+            &body,
+            &transform_of(options, payload),
+        );
+        Self::estimate_cycles(size, max_response_bytes, self.subnet_size)
+    }
+
+    /// Cycles attached to the outcall posting `payload` to `url` with `options`: those set in the options,
+    /// or else the [required](Self::required_cycles) ones with the margin of
+    /// [`set_exact_cycles`](Self::set_exact_cycles).
+    ///
+    /// Fails with [`TransportError::InsufficientCycles`] when the options set fewer cycles than required,
+    /// so that the outcall is not issued only to be rejected.
+    pub fn attached_cycles(&self, url: &str, payload: &Request, options: &CallOptions) -> error::Result<u128> {
+        let max_response_bytes = self.initial_max_response_bytes(&request_key(payload), options);
+        let body = crate::helpers::encode_request(payload);
+        let size = request_size(
+            url,
+            &self.request_headers(options),
+            &body,
+            &transform_of(options, payload),
+        );
+        let required = Self::estimate_cycles(size, max_response_bytes, self.subnet_size);
+        match options.cycles.map(u128::from) {
+            Some(attached) if attached < required => Err(Error::Transport(TransportError::InsufficientCycles {
+                attached,
+                required,
+            })),
+            Some(attached) => Ok(attached),
+            None => Ok(self.default_cycles(size, max_response_bytes)),
         }
     }
 
@@ -36,6 +480,50 @@ impl ICHttpClient {
         self.max_response_bytes = v;
     }
 
+    /// Adapt the response size limit of every call to the size of the previous responses to its method.
+    ///
+    /// The first call of a method uses the limit of its options, its [preset](crate::transforms::presets)
+    /// or the default limit, in that order. Calls rejected because the response is larger than the limit
+    /// are retried with the limit doubled, up to [`MAX_RESPONSE_BYTES`]. Later calls of the method start
+    /// from the last limit which succeeded, decaying slowly towards the size of the responses with some
+    /// headroom but never back to a limit which was rejected, so that the limit neither has to be guessed
+    /// nor wastes cycles on oversized responses. The limits are shared by the clones of the client.
+    pub fn set_adaptive_response_bytes(&mut self, adaptive: bool) {
+        self.adaptive = if adaptive { Some(Default::default()) } else { None };
+    }
+
+    /// Size in bytes of the last response to `method` observed in adaptive mode, after its transform.
+    pub fn observed_response_bytes(&self, method: &str) -> Option<u64> {
+        self.adaptive
+            .as_ref()?
+            .lock()
+            .get(method)
+            .map(|adaptive| adaptive.observed)
+    }
+
+    /// Response size limit of the next call of `method` in adaptive mode.
+    pub fn adaptive_response_bytes(&self, method: &str) -> Option<u64> {
+        self.adaptive
+            .as_ref()?
+            .lock()
+            .get(method)
+            .map(|adaptive| adaptive.limit)
+            .filter(|limit| *limit > 0)
+    }
+
+    /// Response size limit of the first attempt of a call.
+    fn initial_max_response_bytes(&self, key: &str, options: &CallOptions) -> u64 {
+        match self.adaptive_response_bytes(key) {
+            Some(limit) => limit,
+            None => options.max_resp.unwrap_or_else(|| match &self.adaptive {
+                Some(_) => crate::transforms::presets::for_method(key)
+                    .map(|preset| preset.max_response_bytes)
+                    .unwrap_or(self.max_response_bytes),
+                None => self.max_response_bytes,
+            }),
+        }
+    }
+
     async fn request(
         &self,
         url: String,
@@ -43,60 +531,169 @@ impl ICHttpClient {
         req_headers: Vec<HttpHeader>,
         payload: &Request,
         options: CallOptions,
-    ) -> Result<Vec<u8>, String> {
-        let request = CanisterHttpRequestArgument {
-            url: url.clone(),
-            max_response_bytes: if let Some(v) = options.max_resp {
-                Some(v)
-            } else {
-                Some(self.max_response_bytes)
-            },
-            method: req_type,
-            headers: req_headers,
-            body: Some(serde_json::to_vec(&payload).unwrap()),
-            // transform: Some(TransformType::Function(TransformFunc(candid::Func {
-            //     principal: ic_cdk::api::id(),
-            //     method: "transform".to_string(),
-            // }))),
-            transform: match options.transform {
-                Some(t) => Some(t),
-                None => Some(TransformContext {
-                    function: TransformFunc(candid::Func {
-                        principal: ic_cdk::api::id(),
-                        method: "transform".to_string(),
-                    }),
-                    context: vec![],
-                }),
-            },
-        };
+    ) -> Result<HttpResponse, TransportError> {
+        let key = request_key(payload);
+        let mut max_response_bytes = self.initial_max_response_bytes(&key, &options);
+        let body = crate::helpers::encode_request(payload);
+        let transform = transform_of(&options, payload);
+        let size = request_size(&url, &req_headers, &body, &transform);
+        let mut retried = false;
 
-        match http_request(request, HTTP_OUTCALL_PRICE + (BYTES * COST_PER_BYTE)).await {
-            Ok((result,)) => Ok(result.body),
-            Err((r, m)) => {
-                let message = format!("The http_request resulted into error. RejectionCode: {r:?}, Error: {m}");
-                ic_cdk::api::print(message.clone());
-                Err(message)
+        loop {
+            let required = Self::estimate_cycles(size, max_response_bytes, self.subnet_size);
+            let cycles = options
+                .cycles
+                .map_or_else(|| self.default_cycles(size, max_response_bytes), u128::from);
+            if retried {
+                // The retries with larger limits are separate outcalls, charged separately.
+                let methods: Vec<&str> = key.split(',').collect();
+                for method in &methods {
+                    super::metrics::record_retry_outcall(method, cycles / methods.len() as u128);
+                }
+                super::metrics::record_outcall();
+            }
+            if cycles < required {
+                return Err(TransportError::InsufficientCycles {
+                    attached: cycles,
+                    required,
+                });
+            }
+            let request = CanisterHttpRequestArgument {
+                url: url.clone(),
+                max_response_bytes: Some(max_response_bytes),
+                method: req_type,
+                headers: req_headers.clone(),
+                body: Some(body.clone()),
+                transform: Some(transform.clone()),
+            };
+
+            let result = match options.replication {
+                Replication::Replicated => http_request(request, cycles).await,
+                Replication::NonReplicated => {
+                    let request = HttpRequestArgument {
+                        url: request.url,
+                        max_response_bytes: request.max_response_bytes,
+                        method: request.method,
+                        headers: request.headers,
+                        body: request.body,
+                        transform: request.transform,
+                        is_replicated: Some(false),
+                    };
+                    ic_cdk::api::call::call_with_payment128(
+                        Principal::management_canister(),
+                        "http_request",
+                        (request,),
+                        cycles,
+                    )
+                    .await
+                }
+            };
+            match result {
+                Ok((result,)) => {
+                    if let Some(adaptive) = &self.adaptive {
+                        adaptive
+                            .lock()
+                            .entry(key)
+                            .or_default()
+                            .succeeded(max_response_bytes, result.body.len() as u64);
+                    }
+                    #[cfg(feature = "compression")]
+                    let result =
+                        crate::transforms::compression::decode_response(result).map_err(TransportError::Message)?;
+                    return Ok(result);
+                }
+                Err((code, message)) => match TransportError::from_reject(code, &message) {
+                    TransportError::ResponseTooLarge { .. }
+                        if self.adaptive.is_some() && max_response_bytes < MAX_RESPONSE_BYTES =>
+                    {
+                        if let Some(adaptive) = &self.adaptive {
+                            let mut adaptive = adaptive.lock();
+                            let adaptive = adaptive.entry(key.clone()).or_default();
+                            adaptive.rejected = adaptive.rejected.max(max_response_bytes);
+                        }
+                        max_response_bytes = max_response_bytes.saturating_mul(2).min(MAX_RESPONSE_BYTES);
+                        retried = true;
+                    }
+                    err => {
+                        ic_cdk::api::print(format!(
+                            "The http_request resulted into error. RejectionCode: {code:?}, Error: {message}"
+                        ));
+                        return Err(err);
+                    }
+                },
             }
         }
     }
 
     pub async fn get(&self, url: String, payload: &Request, options: CallOptions) -> Result<Vec<u8>, String> {
-        let request_headers = vec![HttpHeader {
-            name: "Content-Type".to_string(),
-            value: "application/json".to_string(),
-        }];
-
-        self.request(url, HttpMethod::GET, request_headers, payload, options)
+        let headers = self.request_headers(&options);
+        self.request(url, HttpMethod::GET, headers, payload, options)
             .await
+            .map(|response| response.body)
+            .map_err(|err| err.to_string())
     }
 
     pub async fn post(&self, url: String, payload: &Request, options: CallOptions) -> Result<Vec<u8>, String> {
-        let request_headers = vec![HttpHeader {
-            name: "Content-Type".to_string(),
-            value: "application/json".to_string(),
-        }];
-
-        self.request(url, HttpMethod::POST, request_headers, payload, options)
+        self.post_response(url, payload, options)
             .await
+            .map(|response| response.body)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Like `post`, but returns the whole response including its status, and the structured error of
+    /// rejected outcalls, see [`TransportError::from_reject`].
+    pub async fn post_response(
+        &self,
+        url: String,
+        payload: &Request,
+        options: CallOptions,
+    ) -> Result<HttpResponse, TransportError> {
+        let headers = self.request_headers(&options);
+        self.request(url, HttpMethod::POST, headers, payload, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdaptiveLimit, ICHttpClient, LARGEST_SUBNET_SIZE, MAX_RESPONSE_BYTES};
+
+    #[test]
+    fn adaptive_limit_decays_slowly() {
+        let mut adaptive = AdaptiveLimit::default();
+        adaptive.succeeded(MAX_RESPONSE_BYTES, 10_000);
+        assert_eq!(adaptive.limit, 1_800_000);
+        for _ in 0..100 {
+            adaptive.succeeded(adaptive.limit, 10_000);
+        }
+        assert_eq!(adaptive.limit, 13_500);
+    }
+
+    #[test]
+    fn adaptive_limit_stays_above_rejected_limits() {
+        // The untransformed responses exceed 100_000 bytes, the transformed ones are much smaller.
+        let mut adaptive = AdaptiveLimit {
+            rejected: 100_000,
+            ..Default::default()
+        };
+        adaptive.succeeded(200_000, 20_000);
+        for _ in 0..100 {
+            adaptive.succeeded(adaptive.limit, 20_000);
+            assert!(adaptive.limit > 100_000);
+        }
+        assert_eq!(adaptive.limit, 125_000);
+    }
+
+    #[test]
+    fn default_cycles_cover_the_largest_subnets() {
+        let mut client = ICHttpClient::new(None);
+        let largest = ICHttpClient::estimate_cycles(500, 10_000, LARGEST_SUBNET_SIZE);
+        assert_eq!(client.default_cycles(500, 10_000), largest);
+
+        client.set_exact_cycles(true);
+        assert_eq!(
+            client.default_cycles(500, 10_000),
+            ICHttpClient::estimate_cycles(500, 10_000, 13)
+        );
+        assert!(client.default_cycles(500, 10_000) < largest);
     }
 }
@@ -0,0 +1,394 @@
+//! Middleware composed around transports.
+//!
+//! A [`TransportLayer`] wraps a transport into another one, e.g. to log, guard or rewrite its calls.
+//! Layers are stacked with a [`TransportBuilder`], the first layer added being the outermost one,
+//! i.e. the first to see the calls:
+//!
+//! ```ignore
+//! let transport = TransportBuilder::new()
+//!     .layer(LoggingLayer::new())
+//!     .layer(CyclesGuardLayer::new(MIN_CYCLES))
+//!     .layer(CoalescingLayer::new())
+//!     .build(ICHttp::new(url, None)?);
+//! let web3 = Web3::new(transport);
+//! ```
+//!
+//! Ad-hoc middleware is written as a closure with [`layer_fn`], or for request rewriting, e.g.
+//! injecting a gas price into the transactions sent, with a [`MapRequestLayer`].
+
+use crate::{error::Result, RequestId, Transport};
+use futures::future::BoxFuture;
+use jsonrpc_core::types::{Call, Value};
+use std::{fmt, sync::Arc, time::Duration};
+
+use super::{ic_http_client::CallOptions, CoalescingTransport, CyclesGuardTransport, LogLimits};
+
+/// Middleware wrapping a transport of type `T` into another transport.
+pub trait TransportLayer<T: Transport> {
+    /// Wrapping transport
+    type Transport: Transport;
+
+    /// Wrap `inner`.
+    fn layer(&self, inner: T) -> Self::Transport;
+}
+
+/// Layer leaving transports untouched.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+impl<T: Transport> TransportLayer<T> for Identity {
+    type Transport = T;
+
+    fn layer(&self, inner: T) -> T {
+        inner
+    }
+}
+
+/// Two layers, `inner` wrapping the transport first and `outer` wrapping the result.
+#[derive(Clone, Debug)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+impl<T, Inner, Outer> TransportLayer<T> for Stack<Inner, Outer>
+where
+    T: Transport,
+    Inner: TransportLayer<T>,
+    Outer: TransportLayer<Inner::Transport>,
+{
+    type Transport = Outer::Transport;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        self.outer.layer(self.inner.layer(inner))
+    }
+}
+
+/// Builder of a stack of layers.
+#[derive(Clone, Debug)]
+pub struct TransportBuilder<L> {
+    layer: L,
+}
+
+impl Default for TransportBuilder<Identity> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransportBuilder<Identity> {
+    /// Create a builder without layers.
+    pub fn new() -> Self {
+        TransportBuilder { layer: Identity }
+    }
+}
+
+impl<L> TransportBuilder<L> {
+    /// Add `layer` below the layers added so far.
+    pub fn layer<N>(self, layer: N) -> TransportBuilder<Stack<N, L>> {
+        TransportBuilder {
+            layer: Stack {
+                inner: layer,
+                outer: self.layer,
+            },
+        }
+    }
+
+    /// Wrap `transport` into the layers.
+    pub fn build<T>(&self, transport: T) -> L::Transport
+    where
+        T: Transport,
+        L: TransportLayer<T>,
+    {
+        self.layer.layer(transport)
+    }
+
+    /// Stack of the layers, itself a layer.
+    pub fn into_layer(self) -> L {
+        self.layer
+    }
+}
+
+/// Layer built from a closure, see [`layer_fn`].
+#[derive(Clone, Copy)]
+pub struct LayerFn<F> {
+    f: F,
+}
+
+impl<F> fmt::Debug for LayerFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LayerFn")
+    }
+}
+
+impl<T, F, W> TransportLayer<T> for LayerFn<F>
+where
+    T: Transport,
+    F: Fn(T) -> W,
+    W: Transport,
+{
+    type Transport = W;
+
+    fn layer(&self, inner: T) -> W {
+        (self.f)(inner)
+    }
+}
+
+/// Layer wrapping transports with `f`.
+pub fn layer_fn<F>(f: F) -> LayerFn<F> {
+    LayerFn { f }
+}
+
+/// Layer coalescing identical calls, see [`CoalescingTransport`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CoalescingLayer {
+    window: Duration,
+}
+
+impl CoalescingLayer {
+    /// Coalesce the identical concurrent calls.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also serve the results of identical calls completed less than `window` ago.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+impl<T: Transport> TransportLayer<T> for CoalescingLayer
+where
+    T::Out: Send + 'static,
+{
+    type Transport = CoalescingTransport<T>;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        CoalescingTransport::new(inner).with_window(self.window)
+    }
+}
+
+/// Layer refusing calls while the canister runs low on cycles, see [`CyclesGuardTransport`].
+#[derive(Clone, Copy, Debug)]
+pub struct CyclesGuardLayer {
+    threshold: u128,
+}
+
+impl CyclesGuardLayer {
+    /// Refuse calls while the canister holds less than `threshold` cycles.
+    pub fn new(threshold: u128) -> Self {
+        CyclesGuardLayer { threshold }
+    }
+}
+
+impl<T: Transport> TransportLayer<T> for CyclesGuardLayer
+where
+    T::Out: Send + 'static,
+{
+    type Transport = CyclesGuardTransport<T>;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        CyclesGuardTransport::new(inner, self.threshold)
+    }
+}
+
+type RequestHook = Arc<dyn Fn(&mut Call) + Send + Sync>;
+
+/// Layer rewriting the requests before they are sent, see [`MapRequest`].
+#[derive(Clone)]
+pub struct MapRequestLayer {
+    map: RequestHook,
+}
+
+impl fmt::Debug for MapRequestLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MapRequestLayer")
+    }
+}
+
+impl MapRequestLayer {
+    /// Rewrite every request with `map`.
+    pub fn new<F>(map: F) -> Self
+    where
+        F: Fn(&mut Call) + Send + Sync + 'static,
+    {
+        MapRequestLayer { map: Arc::new(map) }
+    }
+}
+
+impl<T: Transport> TransportLayer<T> for MapRequestLayer {
+    type Transport = MapRequest<T>;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        MapRequest {
+            transport: inner,
+            map: self.map.clone(),
+        }
+    }
+}
+
+/// Transport rewriting the requests before sending them with the inner transport.
+#[derive(Clone)]
+pub struct MapRequest<T> {
+    transport: T,
+    map: RequestHook,
+}
+
+impl<T: fmt::Debug> fmt::Debug for MapRequest<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapRequest")
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+impl<T> MapRequest<T> {
+    /// Inner transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Transport for MapRequest<T> {
+    type Out = T::Out;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, mut request: Call, options: CallOptions) -> Self::Out {
+        (self.map)(&mut request);
+        self.transport.send(id, request, options)
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.transport.log_limits()
+    }
+}
+
+type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Layer logging the calls, see [`LoggingTransport`].
+#[derive(Clone)]
+pub struct LoggingLayer {
+    sink: LogSink,
+}
+
+impl fmt::Debug for LoggingLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LoggingLayer")
+    }
+}
+
+impl Default for LoggingLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoggingLayer {
+    /// Log the calls to the canister log.
+    pub fn new() -> Self {
+        LoggingLayer {
+            sink: Arc::new(crate::debug::print),
+        }
+    }
+
+    /// Write the log lines to `sink` instead of the canister log.
+    pub fn with_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.sink = Arc::new(sink);
+        self
+    }
+}
+
+impl<T: Transport> TransportLayer<T> for LoggingLayer
+where
+    T::Out: Send + 'static,
+{
+    type Transport = LoggingTransport<T>;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        LoggingTransport {
+            transport: inner,
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+/// Transport logging the method, latency and outcome of every call of the inner transport.
+#[derive(Clone)]
+pub struct LoggingTransport<T> {
+    transport: T,
+    sink: LogSink,
+}
+
+impl<T: fmt::Debug> fmt::Debug for LoggingTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggingTransport")
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+impl<T> LoggingTransport<T> {
+    /// Inner transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T> Transport for LoggingTransport<T>
+where
+    T: Transport,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let method = match &request {
+            Call::MethodCall(call) => call.method.clone(),
+            Call::Notification(notification) => notification.method.clone(),
+            Call::Invalid { .. } => "<invalid>".to_string(),
+        };
+        let sink = self.sink.clone();
+        let call = self.transport.send(id, request, options);
+        Box::pin(async move {
+            let start = crate::debug::now();
+            let result = call.await;
+            let latency = crate::debug::now().saturating_sub(start) / 1_000_000;
+            match &result {
+                Ok(_) => sink(&format!("{} #{}: ok in {} ms", method, id, latency)),
+                Err(err) => sink(&format!("{} #{}: failed in {} ms: {}", method, id, latency, err)),
+            }
+            result
+        })
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.transport.log_limits()
+    }
+}
@@ -0,0 +1,177 @@
+//! Aggregate metrics of the outcalls sent by the crate.
+//!
+//! Every call sent through [`ICHttp`](crate::transports::ICHttp) is accounted for per JSON-RPC method:
+//! cycles attached, response bytes, latency and failures by [`ErrorCode`]. The transports retrying calls,
+//! e.g. [`MultiProviderTransport`](crate::transports::MultiProviderTransport), count their retries. A
+//! canister can surface the totals in its own status endpoint:
+//!
+//! ```ignore
+//! #[query]
+//! fn transport_metrics() -> ic_web3_rs::transports::metrics::TransportMetrics {
+//!     ic_web3_rs::transports::metrics::snapshot()
+//! }
+//! ```
+//!
+//! The metrics live in the heap of the canister and are lost on upgrade.
+
+use crate::error::{Error, ErrorCode};
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Metrics of the calls of a JSON-RPC method.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MethodMetrics {
+    /// Calls sent, retries included
+    pub calls: u64,
+    /// Calls which failed
+    pub failures: u64,
+    /// Calls sent again after a failure or an empty result
+    pub retries: u64,
+    /// Cycles attached to the outcalls, shared evenly by the calls of a batch
+    pub cycles_attached: u128,
+    /// Size of the response bodies in bytes, shared evenly by the calls of a batch
+    pub response_bytes: u64,
+    /// Sum of the latencies, in nanoseconds
+    pub total_latency: u64,
+    /// Highest latency, in nanoseconds
+    pub max_latency: u64,
+    /// Number of failures of each kind
+    pub errors: Vec<(ErrorCode, u64)>,
+}
+
+impl MethodMetrics {
+    /// Average latency of the calls in nanoseconds, zero without calls.
+    pub fn average_latency(&self) -> u64 {
+        self.total_latency.checked_div(self.calls).unwrap_or_default()
+    }
+
+    fn add(&mut self, other: &MethodMetrics) {
+        self.calls += other.calls;
+        self.failures += other.failures;
+        self.retries += other.retries;
+        self.cycles_attached += other.cycles_attached;
+        self.response_bytes += other.response_bytes;
+        self.total_latency += other.total_latency;
+        self.max_latency = self.max_latency.max(other.max_latency);
+        for (code, count) in &other.errors {
+            self.count_error(*code, *count);
+        }
+    }
+
+    fn count_error(&mut self, code: ErrorCode, count: u64) {
+        match self.errors.iter_mut().find(|(counted, _)| *counted == code) {
+            Some((_, total)) => *total += count,
+            None => self.errors.push((code, count)),
+        }
+    }
+}
+
+/// Snapshot of the metrics of the transports.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransportMetrics {
+    /// Time the metrics started being collected, in nanoseconds since the epoch
+    pub since: u64,
+    /// Outcalls sent, a batch being a single outcall
+    pub outcalls: u64,
+    /// Metrics of all the methods together
+    pub total: MethodMetrics,
+    /// Metrics of each method
+    pub methods: BTreeMap<String, MethodMetrics>,
+}
+
+struct Registry {
+    since: Option<u64>,
+    outcalls: u64,
+    methods: BTreeMap<String, MethodMetrics>,
+}
+
+thread_local! {
+    static METRICS: RefCell<Registry> = const {
+        RefCell::new(Registry {
+            since: None,
+            outcalls: 0,
+            methods: BTreeMap::new(),
+        })
+    };
+}
+
+/// The metrics collected since the first call or the last [`reset`].
+pub fn snapshot() -> TransportMetrics {
+    METRICS.with(|metrics| {
+        let metrics = metrics.borrow();
+        let mut total = MethodMetrics::default();
+        for method in metrics.methods.values() {
+            total.add(method);
+        }
+        TransportMetrics {
+            since: metrics.since.unwrap_or_default(),
+            outcalls: metrics.outcalls,
+            total,
+            methods: metrics.methods.clone(),
+        }
+    })
+}
+
+/// Forget the collected metrics.
+pub fn reset() {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.since = None;
+        metrics.outcalls = 0;
+        metrics.methods.clear();
+    });
+}
+
+fn with_method<F: FnOnce(&mut MethodMetrics)>(method: &str, f: F) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.since.get_or_insert_with(crate::debug::now);
+        match metrics.methods.get_mut(method) {
+            Some(entry) => f(entry),
+            None => f(metrics.methods.entry(method.to_string()).or_default()),
+        }
+    });
+}
+
+/// Record an outcall, whose calls are recorded with [`record_call`].
+pub fn record_outcall() {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.since.get_or_insert_with(crate::debug::now);
+        metrics.outcalls += 1;
+    });
+}
+
+/// Record a call of `method` with its share of the outcall cycles and response bytes, its latency in
+/// nanoseconds and its error, if any.
+pub fn record_call(method: &str, cycles: u128, response_bytes: u64, latency: u64, error: Option<&Error>) {
+    with_method(method, |metrics| {
+        metrics.calls += 1;
+        metrics.cycles_attached += cycles;
+        metrics.response_bytes += response_bytes;
+        metrics.total_latency += latency;
+        metrics.max_latency = metrics.max_latency.max(latency);
+        if let Some(error) = error {
+            metrics.failures += 1;
+            metrics.count_error(error.code(), 1);
+        }
+    });
+}
+
+/// Record that a call of `method` is sent again.
+pub fn record_retry(method: &str) {
+    with_method(method, |metrics| metrics.retries += 1);
+}
+
+/// Record that a call of `method` is sent again in another outcall of the same transport, with its share
+/// of the `cycles` attached to the outcall.
+///
+/// The transports retrying through another transport, which accounts for the outcalls, use
+/// [`record_retry`] instead.
+pub fn record_retry_outcall(method: &str, cycles: u128) {
+    with_method(method, |metrics| {
+        metrics.retries += 1;
+        metrics.cycles_attached += cycles;
+    });
+}
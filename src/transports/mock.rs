@@ -0,0 +1,373 @@
+//! In-memory transport for unit tests.
+//!
+//! [`MockTransport`] answers the calls from responses registered per method and records the calls it
+//! receives, so that code written against [`Web3`](crate::Web3) can be tested without the IC runtime:
+//!
+//! ```ignore
+//! let transport = MockTransport::new();
+//! transport.respond("eth_blockNumber", json!("0x10"));
+//! let web3 = Web3::new(transport.clone());
+//! assert_eq!(web3.eth().block_number(CallOptions::default()).await?, 16.into());
+//! assert_eq!(transport.requests()[0].0, "eth_blockNumber");
+//! ```
+//!
+//! Tests checking the calls made, e.g. by contract logic, script them as [`Expectation`]s instead,
+//! optionally in order, and [`verify`](MockTransport::verify) that they were all met:
+//!
+//! ```ignore
+//! let transport = MockTransport::new().in_order();
+//! transport.expect(Expectation::new("eth_getTransactionCount").returning(json!("0x7")));
+//! transport.expect(Expectation::new("eth_sendRawTransaction").failing(MockResponse::Timeout));
+//! // ... code under test ...
+//! transport.verify().unwrap();
+//! ```
+
+use crate::{
+    error::{Error, Result, TransportError},
+    helpers,
+    rpc::error::Error as RPCError,
+    BatchTransport, RequestId, Transport,
+};
+use futures::future::{self, Ready};
+use jsonrpc_core::types::{Call, Params, Value};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
+
+use super::ic_http_client::CallOptions;
+
+/// Scripted response of a call.
+#[derive(Clone, Debug)]
+pub enum MockResponse {
+    /// The call succeeds with this result
+    Result(Value),
+    /// The node answers with this JSON-RPC error
+    RpcError(RPCError),
+    /// The call fails with this error, e.g. a transport error
+    Error(Error),
+    /// The outcall times out
+    Timeout,
+}
+
+impl MockResponse {
+    fn into_result(self) -> Result<Value> {
+        match self {
+            MockResponse::Result(value) => Ok(value),
+            MockResponse::RpcError(err) => Err(Error::Rpc(err)),
+            MockResponse::Error(err) => Err(err),
+            MockResponse::Timeout => Err(Error::Transport(TransportError::Timeout("Timeout expired".to_string()))),
+        }
+    }
+}
+
+impl From<Result<Value>> for MockResponse {
+    fn from(result: Result<Value>) -> Self {
+        match result {
+            Ok(value) => MockResponse::Result(value),
+            Err(Error::Rpc(err)) => MockResponse::RpcError(err),
+            Err(err) => MockResponse::Error(err),
+        }
+    }
+}
+
+/// Call expected by a [`MockTransport`], and its response.
+#[derive(Clone, Debug)]
+pub struct Expectation {
+    method: String,
+    params: Option<Vec<Value>>,
+    response: MockResponse,
+    times: usize,
+    calls: usize,
+}
+
+impl Expectation {
+    /// Expect a call to `method` with any parameters, answered with a `null` result.
+    pub fn new(method: &str) -> Self {
+        Expectation {
+            method: method.to_string(),
+            params: None,
+            response: MockResponse::Result(Value::Null),
+            times: 1,
+            calls: 0,
+        }
+    }
+
+    /// Only match the calls with these parameters.
+    pub fn with_params(mut self, params: Vec<Value>) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Answer with `value`.
+    pub fn returning(mut self, value: Value) -> Self {
+        self.response = MockResponse::Result(value);
+        self
+    }
+
+    /// Answer with a failure, e.g. [`MockResponse::Timeout`].
+    pub fn failing(mut self, response: MockResponse) -> Self {
+        self.response = response;
+        self
+    }
+
+    /// Expect `times` calls instead of one.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = times;
+        self
+    }
+
+    /// Method of the expected calls.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Number of calls matched so far.
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+
+    /// Whether all the expected calls were made.
+    pub fn is_met(&self) -> bool {
+        self.calls >= self.times
+    }
+
+    fn matches(&self, method: &str, params: &[Value]) -> bool {
+        self.method == method && self.params.as_deref().is_none_or(|expected| expected == params)
+    }
+}
+
+/// Method and parameters of `call`, `None` for invalid calls.
+pub(super) fn call_parts(call: &Call) -> Option<(String, Vec<Value>)> {
+    let (method, params) = match call {
+        Call::MethodCall(call) => (call.method.clone(), call.params.clone()),
+        Call::Notification(notification) => (notification.method.clone(), notification.params.clone()),
+        Call::Invalid { .. } => return None,
+    };
+    let params = match params {
+        Params::Array(params) => params,
+        Params::Map(map) => vec![Value::Object(map)],
+        Params::None => vec![],
+    };
+    Some((method, params))
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Scripted calls, in the order they were registered
+    expectations: Vec<Expectation>,
+    /// Whether the expectations must be met in order
+    in_order: bool,
+    /// Responses by method and parameters
+    mapped: BTreeMap<String, Vec<(Vec<Value>, MockResponse)>>,
+    /// Responses served once, in order, by method
+    queued: BTreeMap<String, VecDeque<Result<Value>>>,
+    /// Responses served once the queue of the method is empty, by method
+    fallback: BTreeMap<String, Result<Value>>,
+    /// Calls received, in order
+    requests: Vec<(String, Vec<Value>)>,
+    /// Calls no expectation or response matched, as method and parameters
+    unexpected: Vec<(String, Vec<Value>)>,
+    next_id: RequestId,
+}
+
+impl State {
+    fn expected(&mut self, method: &str, params: &[Value]) -> Option<MockResponse> {
+        let expectation = if self.in_order {
+            self.expectations
+                .iter_mut()
+                .find(|expectation| !expectation.is_met())
+                .filter(|expectation| expectation.matches(method, params))
+        } else {
+            self.expectations
+                .iter_mut()
+                .find(|expectation| !expectation.is_met() && expectation.matches(method, params))
+        }?;
+        expectation.calls += 1;
+        Some(expectation.response.clone())
+    }
+}
+
+/// Transport answering the calls from registered responses.
+///
+/// A call is answered by the first of:
+/// - the first unmet [expectation](Self::expect) matching it, or in [order](Self::in_order) the next
+///   unmet expectation if it matches, the calls matching a later one failing as out of order,
+/// - the response [mapped](Self::respond_to) to its method and parameters,
+/// - the responses [queued](Self::queue) for its method, in order,
+/// - the response set for its method with [`respond`](Self::respond).
+///
+/// Calls without response fail, and are reported by [`verify`](Self::verify).
+///
+/// The transport is cheap to clone; clones share the same responses and recorded calls.
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockTransport {
+    /// Create a transport without responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the expectations to be met in the order they are registered.
+    pub fn in_order(self) -> Self {
+        self.state.lock().in_order = true;
+        self
+    }
+
+    /// Expect the calls described by `expectation`.
+    pub fn expect(&self, expectation: Expectation) {
+        self.state.lock().expectations.push(expectation);
+    }
+
+    /// Answer every call to `method` with exactly `params` with `response`.
+    pub fn respond_to(&self, method: &str, params: Vec<Value>, response: impl Into<MockResponse>) {
+        let mut state = self.state.lock();
+        let mapped = state.mapped.entry(method.to_string()).or_default();
+        mapped.retain(|(existing, _)| *existing != params);
+        mapped.push((params, response.into()));
+    }
+
+    /// Answer every call to `method` with `value`, once the queued responses are served.
+    pub fn respond(&self, method: &str, value: Value) {
+        self.state.lock().fallback.insert(method.to_string(), Ok(value));
+    }
+
+    /// Answer the next call to `method` not served yet with `response`.
+    pub fn queue(&self, method: &str, response: Result<Value>) {
+        self.state
+            .lock()
+            .queued
+            .entry(method.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Calls received so far, as method and parameters.
+    pub fn requests(&self) -> Vec<(String, Vec<Value>)> {
+        self.state.lock().requests.clone()
+    }
+
+    /// Methods of the calls received so far, in order.
+    pub fn methods(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .requests
+            .iter()
+            .map(|(method, _)| method.clone())
+            .collect()
+    }
+
+    /// Forget the calls received so far.
+    pub fn clear_requests(&self) {
+        let mut state = self.state.lock();
+        state.requests.clear();
+        state.unexpected.clear();
+    }
+
+    /// Check that all the expectations were met and every call was answered, describing the failures
+    /// otherwise.
+    pub fn verify(&self) -> std::result::Result<(), String> {
+        let state = self.state.lock();
+        let mut failures: Vec<String> = state
+            .expectations
+            .iter()
+            .filter(|expectation| !expectation.is_met())
+            .map(|expectation| {
+                format!(
+                    "expected {} call(s) to {}{}, got {}",
+                    expectation.times,
+                    expectation.method,
+                    expectation
+                        .params
+                        .as_ref()
+                        .map_or_else(String::new, |params| format!(" with {:?}", params)),
+                    expectation.calls
+                )
+            })
+            .collect();
+        failures.extend(
+            state
+                .unexpected
+                .iter()
+                .map(|(method, params)| format!("unexpected call to {} with {:?}", method, params)),
+        );
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+
+    fn answer(&self, call: &Call) -> Result<Value> {
+        let (method, params) = call_parts(call).ok_or(Error::Internal)?;
+        let mut state = self.state.lock();
+        state.requests.push((method.clone(), params.clone()));
+        if let Some(response) = state.expected(&method, &params) {
+            return response.into_result();
+        }
+        let out_of_order = state.in_order
+            && state
+                .expectations
+                .iter()
+                .any(|expectation| !expectation.is_met() && expectation.matches(&method, &params));
+        if out_of_order {
+            let err = Error::Transport(TransportError::Message(format!(
+                "call to {} with {:?} out of order",
+                method, params
+            )));
+            state.unexpected.push((method, params));
+            return Err(err);
+        }
+        let mapped = state.mapped.get(&method).and_then(|mapped| {
+            mapped
+                .iter()
+                .find(|(expected, _)| *expected == params)
+                .map(|(_, response)| response.clone())
+        });
+        if let Some(response) = mapped {
+            return response.into_result();
+        }
+        if let Some(response) = state.queued.get_mut(&method).and_then(VecDeque::pop_front) {
+            return response;
+        }
+        if let Some(response) = state.fallback.get(&method) {
+            return response.clone();
+        }
+        let err = Error::Transport(TransportError::Message(format!(
+            "no mock response for {} with {:?}",
+            method, params
+        )));
+        state.unexpected.push((method, params));
+        Err(err)
+    }
+}
+
+impl Transport for MockTransport {
+    type Out = Ready<Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let mut state = self.state.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, _id: RequestId, request: Call, _options: CallOptions) -> Self::Out {
+        future::ready(self.answer(&request))
+    }
+}
+
+impl BatchTransport for MockTransport {
+    type Batch = Ready<Result<Vec<Result<Value>>>>;
+
+    fn send_batch<I>(&self, requests: I, _options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        future::ready(Ok(requests.into_iter().map(|(_, call)| self.answer(&call)).collect()))
+    }
+}
@@ -1,6 +1,49 @@
 //! Supported Ethereum JSON-RPC transports.
 
 pub mod ic_http_client;
-pub use self::ic_http_client::ICHttpClient;
+pub use self::ic_http_client::{ICHttpClient, Replication};
 pub mod ic_http;
-pub use self::ic_http::ICHttp;
+pub use self::ic_http::{ICHttp, RequestIdMode};
+pub mod provider;
+pub use self::provider::{LogLimits, ProviderProfile, ShapingRule};
+pub mod multi;
+pub use self::multi::MultiProviderTransport;
+pub mod quorum;
+pub use self::quorum::{Quorum, QuorumTransport};
+pub mod cycles;
+pub use self::cycles::CyclesGuardTransport;
+pub mod certification;
+pub use self::certification::{CertificationHeader, RequireCertificationHeader, ResponseVerifier};
+pub mod coalesce;
+pub use self::coalesce::CoalescingTransport;
+pub mod dynamic;
+pub use self::dynamic::DynTransport;
+pub mod layer;
+pub use self::layer::{
+    layer_fn, CoalescingLayer, CyclesGuardLayer, LoggingLayer, LoggingTransport, MapRequest, MapRequestLayer,
+    TransportBuilder, TransportLayer,
+};
+pub mod null_retry;
+pub use self::null_retry::{NullResultPolicy, NullRetryLayer, NullRetryTransport};
+pub mod metrics;
+pub use self::metrics::{MethodMetrics, TransportMetrics};
+pub mod read_only;
+pub use self::read_only::ReadOnlyTransport;
+pub mod cache;
+pub use self::cache::{CacheStats, CachingLayer, CachingTransport};
+pub mod retry;
+pub use self::retry::{Backoff, RetryOn, RetryPolicy};
+pub mod session;
+pub use self::session::ProviderSession;
+#[cfg(feature = "native-http")]
+pub mod native_http;
+#[cfg(feature = "native-http")]
+pub use self::native_http::NativeHttp;
+#[cfg(feature = "test")]
+pub mod mock;
+#[cfg(feature = "test")]
+pub use self::mock::{Expectation, MockResponse, MockTransport};
+#[cfg(feature = "test")]
+pub mod replay;
+#[cfg(feature = "test")]
+pub use self::replay::{Fixture, Interaction, RecordedResponse, RecordingTransport, ReplayTransport};
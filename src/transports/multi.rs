@@ -0,0 +1,184 @@
+//! Transport with retries and fallback across several RPC providers.
+
+use crate::{
+    error::{Error, Result, TransportError},
+    polling, RequestId, Transport,
+};
+use futures::future::BoxFuture;
+use jsonrpc_core::types::{Call, Value};
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
+
+use super::{ic_http_client::CallOptions, metrics, retry::RetryOn};
+
+/// JSON-RPC methods which must not be sent twice, as every call has a side effect on the node.
+const NON_IDEMPOTENT_METHODS: &[&str] = &["eth_sendTransaction", "personal_sendTransaction"];
+
+/// Health statistics of a single provider.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProviderHealth {
+    /// Number of successful calls
+    pub successes: u64,
+    /// Number of failed calls
+    pub failures: u64,
+    /// Number of failures since the last success
+    pub consecutive_failures: u32,
+    /// Description of the last failure
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct State {
+    current: usize,
+    health: Vec<ProviderHealth>,
+}
+
+/// Transport sending calls to one of several providers, moving on to the next one on failure.
+///
+/// The active provider is used until it fails with a rate limit (HTTP 429, `-32005`), a server error
+/// (HTTP 5xx), an outcall rejection (including consensus failures) or an unparsable response. The call
+/// is then retried against the next provider, and after a full round over all providers the transport
+/// waits `backoff`, doubled on every round, before trying again. Methods with side effects such as
+/// `eth_sendTransaction` are never retried.
+#[derive(Clone, Debug)]
+pub struct MultiProviderTransport<T> {
+    providers: Arc<Vec<T>>,
+    state: Arc<Mutex<State>>,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl<T: Transport> MultiProviderTransport<T> {
+    /// Create a new transport over the given providers, in order of preference.
+    ///
+    /// Panics if `providers` is empty.
+    pub fn new(providers: Vec<T>) -> Self {
+        assert!(!providers.is_empty(), "at least one provider is required");
+        let health = vec![ProviderHealth::default(); providers.len()];
+        MultiProviderTransport {
+            max_attempts: providers.len() as u32 * 2,
+            providers: Arc::new(providers),
+            state: Arc::new(Mutex::new(State { current: 0, health })),
+            backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the maximum number of attempts of a single call, over all providers.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay to wait after every provider failed once, doubled after each further round.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Underlying providers.
+    pub fn providers(&self) -> &[T] {
+        &self.providers
+    }
+
+    /// Index of the provider currently used.
+    pub fn current(&self) -> usize {
+        self.state.lock().current
+    }
+
+    /// Health statistics of every provider, in the order they were given.
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        self.state.lock().health.clone()
+    }
+}
+
+/// Whether `err` is worth retrying against another provider: the transient failures of
+/// [`RetryOn::of`], the responses the provider failed to give and the calls it could not be sent.
+pub fn is_retryable(err: &Error) -> bool {
+    match err {
+        Error::Unreachable
+        | Error::InvalidResponse(_)
+        | Error::Transport(TransportError::Message(_))
+        | Error::Transport(TransportError::NotSent(_)) => true,
+        err => RetryOn::of(err).is_some(),
+    }
+}
+
+fn method_of(call: &Call) -> Option<&str> {
+    match call {
+        Call::MethodCall(call) => Some(&call.method),
+        Call::Notification(notification) => Some(&notification.method),
+        Call::Invalid { .. } => None,
+    }
+}
+
+impl<T> Transport for MultiProviderTransport<T>
+where
+    T: Transport + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let current = self.current();
+        self.providers[current].prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let providers = self.providers.clone();
+        let state = self.state.clone();
+        let backoff = self.backoff;
+        let idempotent = method_of(&request).is_some_and(|method| !NON_IDEMPOTENT_METHODS.contains(&method));
+        let max_attempts = if idempotent { self.max_attempts } else { 1 };
+
+        Box::pin(async move {
+            let mut attempt = 0;
+            loop {
+                let index = state.lock().current;
+                let result = providers[index].send(id, request.clone(), options.clone()).await;
+                attempt += 1;
+
+                let retryable = matches!(&result, Err(err) if is_retryable(err));
+                {
+                    let mut state = state.lock();
+                    let health = &mut state.health[index];
+                    if !retryable {
+                        // the provider answered, even if the call itself failed
+                        health.successes += 1;
+                        health.consecutive_failures = 0;
+                    } else {
+                        health.failures += 1;
+                        health.consecutive_failures += 1;
+                        health.last_error = result.as_ref().err().map(ToString::to_string);
+                        // another call may already have rotated away from the failing provider
+                        if state.current == index {
+                            state.current = (index + 1) % providers.len();
+                        }
+                    }
+                }
+                if !retryable || attempt >= max_attempts {
+                    return result;
+                }
+                if let Some(method) = method_of(&request) {
+                    metrics::record_retry(method);
+                }
+
+                let round = attempt / providers.len() as u32;
+                if attempt % providers.len() as u32 == 0 && !backoff.is_zero() {
+                    polling::sleep(backoff * 2u32.saturating_pow(round - 1)).await;
+                }
+            }
+        })
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.providers[self.current()].default_call_options()
+    }
+
+    fn log_limits(&self) -> super::LogLimits {
+        self.providers
+            .iter()
+            .fold(super::LogLimits::default(), |limits, transport| {
+                limits.strictest(&transport.log_limits())
+            })
+    }
+}
@@ -0,0 +1,175 @@
+//! Plain HTTP transport for use outside the IC.
+//!
+//! [`NativeHttp`] sends the JSON-RPC requests with `reqwest` instead of outcalls, so that business logic
+//! written against [`Web3`](crate::Web3) can be run in integration tests against a local node, e.g. anvil
+//! or hardhat, from a tokio runtime:
+//!
+//! ```ignore
+//! let web3 = Web3::new(NativeHttp::new("http://127.0.0.1:8545")?);
+//! let block = web3.eth().block_number(CallOptions::default()).await?;
+//! ```
+//!
+//! The outcall-specific options, i.e. cycles, transform and replication, are ignored. The response size
+//! limit is enforced like the IC does, so that responses outgrowing their limit fail in tests too.
+
+use crate::{
+    error::{Error, Result, TransportError},
+    helpers, BatchTransport, RequestId, Transport,
+};
+use futures::future::BoxFuture;
+use jsonrpc_core::types::{Call, Output, Request, Value};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use super::ic_http::{decode_response, is_rpc_response, order_outputs};
+use super::ic_http_client::CallOptions;
+
+/// HTTP transport sending the requests from the host, with `reqwest`.
+#[derive(Clone, Debug)]
+pub struct NativeHttp {
+    client: reqwest::Client,
+    inner: Arc<Inner>,
+    max_response_bytes: Option<u64>,
+    headers: HeaderMap,
+}
+
+#[derive(Debug)]
+struct Inner {
+    url: reqwest::Url,
+    id: AtomicUsize,
+}
+
+impl NativeHttp {
+    /// Create a transport sending the requests to `url`.
+    pub fn new(url: &str) -> Result<Self> {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Create a transport sending the requests to `url` with `client`, e.g. configured with timeouts.
+    pub fn with_client(client: reqwest::Client, url: &str) -> Result<Self> {
+        let url = url
+            .parse()
+            .map_err(|err| Error::Transport(TransportError::Message(format!("invalid url {}: {}", url, err))))?;
+        Ok(NativeHttp {
+            client,
+            inner: Arc::new(Inner {
+                url,
+                id: AtomicUsize::new(0),
+            }),
+            max_response_bytes: None,
+            headers: HeaderMap::new(),
+        })
+    }
+
+    /// Send the header `name` with `value` with every request.
+    pub fn set_header(&mut self, name: &str, value: &str) -> Result<()> {
+        let invalid =
+            |err: String| Error::Transport(TransportError::Message(format!("invalid header {}: {}", name, err)));
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| invalid(err.to_string()))?;
+        let value = HeaderValue::from_str(value).map_err(|err| invalid(err.to_string()))?;
+        self.headers.insert(name, value);
+        Ok(())
+    }
+
+    fn next_id(&self) -> RequestId {
+        self.inner.id.fetch_add(1, Ordering::AcqRel)
+    }
+
+    /// Post `request` and read the response body, within the response size limit.
+    fn post(&self, request: &Request, options: &CallOptions) -> BoxFuture<'static, Result<Vec<u8>>> {
+        let mut headers = self.headers.clone();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        for header in options.headers() {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(header.name.as_bytes()),
+                HeaderValue::from_str(&header.value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        let max_response_bytes = options.max_resp().or(self.max_response_bytes);
+        let request = self
+            .client
+            .post(self.inner.url.clone())
+            .headers(headers)
+            .body(helpers::encode_request(request));
+        Box::pin(async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|err| Error::Transport(TransportError::Message(err.to_string())))?;
+            let status = response.status();
+            let body = response
+                .bytes()
+                .await
+                .map_err(|err| Error::Transport(TransportError::Message(err.to_string())))?;
+            // JSON-RPC errors answered with an error status are decoded as any response.
+            if !status.is_success() && !is_rpc_response(&body) {
+                return Err(Error::Transport(TransportError::Code(status.as_u16())));
+            }
+            if let Some(limit) = max_response_bytes {
+                if body.len() as u64 > limit {
+                    return Err(Error::Transport(TransportError::Message(format!(
+                        "response of {} bytes exceeds the limit of {} bytes",
+                        body.len(),
+                        limit
+                    ))));
+                }
+            }
+            Ok(body.to_vec())
+        })
+    }
+}
+
+impl Transport for NativeHttp {
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let id = self.next_id();
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, _id: RequestId, mut call: Call, options: CallOptions) -> Self::Out {
+        if let (Some(request_id), Call::MethodCall(method_call)) = (options.request_id(), &mut call) {
+            method_call.id = jsonrpc_core::Id::Num(request_id);
+        }
+        let response = self.post(&Request::Single(call), &options);
+        Box::pin(async move {
+            let response = response.await?;
+            decode_response::<Output>(&response).and_then(helpers::to_result_from_output)
+        })
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.max_response_bytes = Some(bytes);
+    }
+}
+
+impl BatchTransport for NativeHttp {
+    type Batch = BoxFuture<'static, Result<Vec<Result<Value>>>>;
+
+    fn send_batch<I>(&self, requests: I, options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let calls: Vec<Call> = requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, mut call))| {
+                if let Call::MethodCall(method_call) = &mut call {
+                    method_call.id = jsonrpc_core::Id::Num(index as u64);
+                }
+                call
+            })
+            .collect();
+        let len = calls.len();
+        let response = self.post(&Request::Batch(calls), &options);
+        Box::pin(async move {
+            let response = response.await?;
+            decode_response::<Vec<Output>>(&response).and_then(|outputs| order_outputs(outputs, len))
+        })
+    }
+}
@@ -0,0 +1,205 @@
+//! Transport retrying `null` results of lookups which may only be missing on a lagging node.
+
+use crate::{error::Result, polling, RequestId, Transport};
+use futures::future::BoxFuture;
+use jsonrpc_core::types::{Call, Value};
+use std::{sync::Arc, time::Duration};
+
+use super::{ic_http_client::CallOptions, layer::TransportLayer, metrics, LogLimits};
+
+/// Lookups retried by default: a node behind the chain tip answers `null` for the recent receipts,
+/// transactions and blocks it has not imported yet.
+pub const DEFAULT_NULL_RETRY_METHODS: &[&str] = &[
+    "eth_getTransactionReceipt",
+    "eth_getTransactionByHash",
+    "eth_getBlockByNumber",
+    "eth_getBlockByHash",
+    "eth_getBlockReceipts",
+];
+
+/// Which `null` results are retried, how many times and how often.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NullResultPolicy {
+    methods: Vec<String>,
+    retries: u32,
+    delay: Duration,
+}
+
+impl Default for NullResultPolicy {
+    fn default() -> Self {
+        NullResultPolicy {
+            methods: DEFAULT_NULL_RETRY_METHODS
+                .iter()
+                .map(|method| method.to_string())
+                .collect(),
+            retries: 2,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+impl NullResultPolicy {
+    /// Retry the [`DEFAULT_NULL_RETRY_METHODS`] twice, without delay.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retry `null` results up to `retries` times before returning them.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Wait `delay` before every retry, giving lagging nodes time to catch up.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Also retry the `null` results of `method`.
+    pub fn with_method(mut self, method: &str) -> Self {
+        if !self.applies_to(method) {
+            self.methods.push(method.to_string());
+        }
+        self
+    }
+
+    /// Only retry the `null` results of `methods`.
+    pub fn with_methods(mut self, methods: &[&str]) -> Self {
+        self.methods = methods.iter().map(|method| method.to_string()).collect();
+        self
+    }
+
+    /// Whether the `null` results of `method` are retried.
+    pub fn applies_to(&self, method: &str) -> bool {
+        self.methods.iter().any(|retried| retried == method)
+    }
+
+    /// Maximum number of retries of a `null` result.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+/// Transport retrying the `null` results of lookups before returning them.
+///
+/// Load-balanced providers route calls to nodes with different views of the chain tip, so a lookup
+/// of a just-mined receipt or block may come back `null` only because the serving node lags. The
+/// transport retries such results as set by its [`NullResultPolicy`], rotating over the alternate
+/// providers when some are given, so that `None` more reliably means "not found".
+#[derive(Clone, Debug)]
+pub struct NullRetryTransport<T> {
+    providers: Arc<Vec<T>>,
+    policy: NullResultPolicy,
+}
+
+impl<T: Transport> NullRetryTransport<T> {
+    /// Retry the `null` results of `transport` with the default policy.
+    pub fn new(transport: T) -> Self {
+        NullRetryTransport {
+            providers: Arc::new(vec![transport]),
+            policy: NullResultPolicy::default(),
+        }
+    }
+
+    /// Retry as set by `policy`.
+    pub fn with_policy(mut self, policy: NullResultPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Send the retries to `alternates` in turn, then to the main transport again, and so on.
+    pub fn with_alternates(mut self, alternates: Vec<T>) -> Self {
+        let mut providers = vec![self.providers[0].clone()];
+        providers.extend(alternates);
+        self.providers = Arc::new(providers);
+        self
+    }
+
+    /// Main transport.
+    pub fn transport(&self) -> &T {
+        &self.providers[0]
+    }
+
+    /// Retry policy.
+    pub fn policy(&self) -> &NullResultPolicy {
+        &self.policy
+    }
+}
+
+impl<T> Transport for NullRetryTransport<T>
+where
+    T: Transport + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.providers[0].prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let retried = matches!(&request, Call::MethodCall(call) if self.policy.applies_to(&call.method));
+        if !retried || self.policy.retries == 0 {
+            return Box::pin(self.providers[0].send(id, request, options));
+        }
+        let providers = self.providers.clone();
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let mut attempt = 0;
+            loop {
+                let provider = &providers[attempt as usize % providers.len()];
+                let result = provider.send(id, request.clone(), options.clone()).await;
+                if !matches!(result, Ok(Value::Null)) || attempt >= policy.retries {
+                    return result;
+                }
+                attempt += 1;
+                if let Call::MethodCall(call) = &request {
+                    metrics::record_retry(&call.method);
+                }
+                if !policy.delay.is_zero() {
+                    polling::sleep(policy.delay).await;
+                }
+            }
+        })
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        for provider in Arc::make_mut(&mut self.providers) {
+            provider.set_max_response_bytes(bytes);
+        }
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.providers[0].default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.providers[0].log_limits()
+    }
+}
+
+/// Layer retrying `null` results, see [`NullRetryTransport`].
+#[derive(Clone, Debug, Default)]
+pub struct NullRetryLayer {
+    policy: NullResultPolicy,
+}
+
+impl NullRetryLayer {
+    /// Retry `null` results as set by `policy`.
+    pub fn new(policy: NullResultPolicy) -> Self {
+        NullRetryLayer { policy }
+    }
+}
+
+impl<T> TransportLayer<T> for NullRetryLayer
+where
+    T: Transport + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    type Transport = NullRetryTransport<T>;
+
+    fn layer(&self, inner: T) -> Self::Transport {
+        NullRetryTransport::new(inner).with_policy(self.policy.clone())
+    }
+}
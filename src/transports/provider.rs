@@ -0,0 +1,210 @@
+//! Provider profiles describing the quirks of specific JSON-RPC endpoints.
+
+use crate::error::{Error, TransportError};
+use jsonrpc_core::types::Value;
+
+/// A rule adjusting the outgoing parameter list of a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShapingRule {
+    /// Keep at most `max` positional parameters for `method`.
+    ///
+    /// Only `null` and `"latest"`, which nodes default to, are dropped: requests with other parameters
+    /// past `max` are refused, as sending them without would change their meaning.
+    TruncateParams {
+        /// JSON-RPC method, `None` matches every method
+        method: Option<String>,
+        /// Maximum number of parameters the provider accepts
+        max: usize,
+    },
+    /// Remove trailing `null` parameters for `method`.
+    StripTrailingNulls {
+        /// JSON-RPC method, `None` matches every method
+        method: Option<String>,
+    },
+}
+
+impl ShapingRule {
+    fn matches(method: &Option<String>, name: &str) -> bool {
+        method.as_deref().is_none_or(|m| m == name)
+    }
+
+    /// Apply the rule to the parameters of a `method` call.
+    ///
+    /// Fails if the rule would drop a parameter the provider does not support.
+    pub fn apply(&self, method: &str, params: &mut Vec<Value>) -> Result<(), Error> {
+        match self {
+            ShapingRule::TruncateParams { method: m, max } if Self::matches(m, method) => {
+                if let Some((position, param)) = params
+                    .iter()
+                    .enumerate()
+                    .skip(*max)
+                    .find(|(_, param)| !is_default_param(param))
+                {
+                    return Err(Error::Transport(TransportError::NotSent(format!(
+                        "{} accepts at most {} parameters, parameter {} ({}) is not supported by the provider",
+                        method, max, position, param
+                    ))));
+                }
+                params.truncate(*max);
+            }
+            ShapingRule::StripTrailingNulls { method: m } if Self::matches(m, method) => {
+                while let Some(Value::Null) = params.last() {
+                    params.pop();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Whether `param` is one nodes use when it is missing.
+fn is_default_param(param: &Value) -> bool {
+    match param {
+        Value::Null => true,
+        Value::String(tag) => tag == "latest",
+        _ => false,
+    }
+}
+
+/// Limits a provider puts on `eth_getLogs` queries.
+///
+/// Used by [`LogPaginator`](crate::logs::LogPaginator) to size its pages and to recognize the error the
+/// provider returns when a query goes over its limits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogLimits {
+    /// Maximum number of blocks covered by a single query
+    pub max_block_range: Option<u64>,
+    /// Maximum number of logs returned by a single query
+    pub max_results: Option<u64>,
+    /// Fragments of the provider specific "range too large" error messages, lowercase
+    pub range_errors: Vec<String>,
+}
+
+impl LogLimits {
+    /// Whether `message` is one of the provider specific "range too large" errors.
+    pub fn is_range_error(&self, message: &str) -> bool {
+        let message = message.to_lowercase();
+        self.range_errors
+            .iter()
+            .any(|fragment| message.contains(fragment.as_str()))
+    }
+
+    /// Limits satisfying both `self` and `other`, used when a query may be sent to either provider.
+    pub fn strictest(mut self, other: &LogLimits) -> Self {
+        fn min(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        self.max_block_range = min(self.max_block_range, other.max_block_range);
+        self.max_results = min(self.max_results, other.max_results);
+        for fragment in &other.range_errors {
+            if !self.range_errors.contains(fragment) {
+                self.range_errors.push(fragment.clone());
+            }
+        }
+        self
+    }
+}
+
+/// Request shaping profile of an RPC provider.
+///
+/// Some providers reject parameters other nodes accept (e.g. a block parameter for `eth_estimateGas`
+/// or a `null` reward percentiles list for `eth_feeHistory`) with an opaque "invalid params" error.
+/// A profile lists the rules needed to make outgoing requests acceptable for a given provider.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProviderProfile {
+    name: String,
+    rules: Vec<ShapingRule>,
+    log_limits: LogLimits,
+}
+
+impl ProviderProfile {
+    /// Create an empty profile which leaves requests untouched.
+    pub fn new(name: &str) -> Self {
+        ProviderProfile {
+            name: name.to_string(),
+            rules: vec![],
+            log_limits: LogLimits::default(),
+        }
+    }
+
+    /// Profile for providers which only accept the minimal, spec-mandated parameter lists.
+    pub fn strict() -> Self {
+        Self::new("strict")
+            .rule(ShapingRule::StripTrailingNulls { method: None })
+            .rule(ShapingRule::TruncateParams {
+                method: Some("eth_estimateGas".to_string()),
+                max: 1,
+            })
+    }
+
+    /// Profile of Alchemy, which serves at most 2 000 blocks per `eth_getLogs` query.
+    pub fn alchemy() -> Self {
+        Self::new("alchemy").with_log_limits(LogLimits {
+            max_block_range: Some(2_000),
+            max_results: None,
+            range_errors: vec!["log response size exceeded".to_string()],
+        })
+    }
+
+    /// Profile of Infura, which returns at most 10 000 logs per `eth_getLogs` query.
+    pub fn infura() -> Self {
+        Self::new("infura").with_log_limits(LogLimits {
+            max_block_range: None,
+            max_results: Some(10_000),
+            range_errors: vec!["query returned more than".to_string()],
+        })
+    }
+
+    /// Profile of public nodes, which commonly cap `eth_getLogs` to 10 000 logs and blocks.
+    pub fn public_node() -> Self {
+        Self::new("public").with_log_limits(LogLimits {
+            max_block_range: Some(10_000),
+            max_results: Some(10_000),
+            range_errors: vec![
+                "exceed maximum block range".to_string(),
+                "block range limit".to_string(),
+            ],
+        })
+    }
+
+    /// Set the limits the provider puts on `eth_getLogs` queries.
+    pub fn with_log_limits(mut self, log_limits: LogLimits) -> Self {
+        self.log_limits = log_limits;
+        self
+    }
+
+    /// Add a shaping rule to the profile.
+    pub fn rule(mut self, rule: ShapingRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Name of the provider.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Shaping rules of the provider.
+    pub fn rules(&self) -> &[ShapingRule] {
+        &self.rules
+    }
+
+    /// Limits the provider puts on `eth_getLogs` queries.
+    pub fn log_limits(&self) -> &LogLimits {
+        &self.log_limits
+    }
+
+    /// Adjust the parameters of a `method` call according to the profile rules.
+    ///
+    /// Fails if the call has parameters the provider does not support.
+    pub fn shape(&self, method: &str, mut params: Vec<Value>) -> Result<Vec<Value>, Error> {
+        for rule in &self.rules {
+            rule.apply(method, &mut params)?;
+        }
+        Ok(params)
+    }
+}
@@ -0,0 +1,131 @@
+//! Transport requiring several providers to agree on every response.
+
+use crate::{
+    error::{Error, QuorumFailure, Result, TransportError},
+    RequestId, Transport,
+};
+use futures::future::{join_all, BoxFuture};
+use jsonrpc_core::types::{Call, Value};
+use std::sync::Arc;
+
+use super::ic_http_client::CallOptions;
+
+/// Number of matching responses a `QuorumTransport` requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quorum {
+    /// More than half of the providers
+    Majority,
+    /// Every provider
+    All,
+    /// At least the given number of providers
+    AtLeast(usize),
+}
+
+impl Quorum {
+    /// Number of matching responses required out of `providers`.
+    pub fn required(&self, providers: usize) -> usize {
+        match *self {
+            Quorum::Majority => providers / 2 + 1,
+            Quorum::All => providers,
+            Quorum::AtLeast(n) => n.clamp(1, providers),
+        }
+    }
+}
+
+/// Transport sending every call to all inner transports and only accepting a response returned by a
+/// quorum of them.
+///
+/// This removes the single provider as a trust bottleneck for security-sensitive reads. Failed calls
+/// take part in the vote as well: if a quorum of providers fails with the same error, that error is
+/// returned. Otherwise a `TransportError::Quorum` lists the diverging responses.
+#[derive(Clone, Debug)]
+pub struct QuorumTransport<T> {
+    transports: Arc<Vec<T>>,
+    quorum: Quorum,
+}
+
+impl<T: Transport> QuorumTransport<T> {
+    /// Create a new quorum transport over the given inner transports.
+    ///
+    /// Panics if `transports` is empty.
+    pub fn new(transports: Vec<T>, quorum: Quorum) -> Self {
+        assert!(!transports.is_empty(), "at least one transport is required");
+        QuorumTransport {
+            transports: Arc::new(transports),
+            quorum,
+        }
+    }
+
+    /// Inner transports.
+    pub fn transports(&self) -> &[T] {
+        &self.transports
+    }
+
+    /// Configured quorum.
+    pub fn quorum(&self) -> Quorum {
+        self.quorum
+    }
+}
+
+impl<T> Transport for QuorumTransport<T>
+where
+    T: Transport + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transports[0].prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let required = self.quorum.required(self.transports.len());
+        let calls = self
+            .transports
+            .iter()
+            .map(|transport| transport.send(id, request.clone(), options.clone()))
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            let results = join_all(calls).await;
+
+            let mut groups: Vec<(Result<Value>, Vec<usize>)> = vec![];
+            for (index, result) in results.into_iter().enumerate() {
+                let group = groups.iter_mut().find(|(other, _)| match (other, &result) {
+                    (Ok(a), Ok(b)) => a == b,
+                    (Err(a), Err(b)) => a.to_string() == b.to_string(),
+                    _ => false,
+                });
+                match group {
+                    Some((_, providers)) => providers.push(index),
+                    None => groups.push((result, vec![index])),
+                }
+            }
+
+            if let Some(position) = groups.iter().position(|(_, providers)| providers.len() >= required) {
+                return groups.swap_remove(position).0;
+            }
+
+            let responses = groups
+                .into_iter()
+                .map(|(result, providers)| (result.map_err(|err| err.to_string()), providers))
+                .collect();
+            Err(Error::Transport(TransportError::Quorum(QuorumFailure {
+                quorum: required,
+                responses,
+            })))
+        })
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transports[0].default_call_options()
+    }
+
+    fn log_limits(&self) -> super::LogLimits {
+        self.transports
+            .iter()
+            .fold(super::LogLimits::default(), |limits, transport| {
+                limits.strictest(&transport.log_limits())
+            })
+    }
+}
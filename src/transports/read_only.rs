@@ -0,0 +1,91 @@
+//! Transport refusing the calls changing the chain or node state.
+
+use crate::{
+    error::{Error, Result, TransportError},
+    BatchTransport, RequestId, Transport,
+};
+use futures::future::{self, BoxFuture};
+use jsonrpc_core::types::{Call, Value};
+
+use super::{ic_http_client::is_state_changing, ic_http_client::CallOptions, LogLimits};
+
+/// Transport failing the [state-changing](super::ic_http_client::is_state_changing) calls without sending
+/// them, e.g. transactions and signing requests.
+///
+/// It backs [`ReadOnlyWeb3`](crate::api::ReadOnlyWeb3), so that the namespaces it hands out cannot send
+/// such calls through their transport either.
+#[derive(Clone, Debug)]
+pub struct ReadOnlyTransport<T> {
+    transport: T,
+}
+
+impl<T: Transport> ReadOnlyTransport<T> {
+    /// Refuse the state-changing calls of `transport`.
+    pub fn new(transport: T) -> Self {
+        ReadOnlyTransport { transport }
+    }
+}
+
+/// Refuses `call` if it changes state.
+fn check_read_only(call: &Call) -> Result<()> {
+    match call {
+        Call::MethodCall(method_call) if is_state_changing(&method_call.method) => Err(Error::Transport(
+            TransportError::NotSent(format!("{} refused by a read-only transport", method_call.method)),
+        )),
+        Call::Notification(notification) if is_state_changing(&notification.method) => Err(Error::Transport(
+            TransportError::NotSent(format!("{} refused by a read-only transport", notification.method)),
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl<T> Transport for ReadOnlyTransport<T>
+where
+    T: Transport,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        match check_read_only(&request) {
+            Ok(()) => Box::pin(self.transport.send(id, request, options)),
+            Err(err) => Box::pin(future::ready(Err(err))),
+        }
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.transport.log_limits()
+    }
+}
+
+impl<T> BatchTransport for ReadOnlyTransport<T>
+where
+    T: BatchTransport,
+    T::Out: Send + 'static,
+    T::Batch: Send + 'static,
+{
+    type Batch = BoxFuture<'static, Result<Vec<Result<Value>>>>;
+
+    fn send_batch<I>(&self, requests: I, options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let requests: Vec<_> = requests.into_iter().collect();
+        match requests.iter().try_for_each(|(_, call)| check_read_only(call)) {
+            Ok(()) => Box::pin(self.transport.send_batch(requests, options)),
+            Err(err) => Box::pin(future::ready(Err(err))),
+        }
+    }
+}
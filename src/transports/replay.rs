@@ -0,0 +1,292 @@
+//! Recorded provider interactions, replayed as test fixtures.
+//!
+//! A [`RecordingTransport`] wraps a live transport, e.g. a `NativeHttp` transport pointed at a
+//! provider, and captures every call with its response into a [`Fixture`]. Saved as JSON, the fixture is
+//! served back by a [`ReplayTransport`], so that regression tests run against real provider behavior
+//! without network access:
+//!
+//! ```ignore
+//! // once, with network access
+//! let recording = RecordingTransport::new(NativeHttp::new(RPC_URL)?);
+//! run_workflow(Web3::new(recording.clone())).await?;
+//! std::fs::write("tests/fixtures/workflow.json", recording.fixture().to_json())?;
+//!
+//! // in the tests
+//! let fixture = Fixture::from_json(include_str!("fixtures/workflow.json"))?;
+//! run_workflow(Web3::new(ReplayTransport::new(fixture))).await?;
+//! ```
+
+use crate::{
+    error::{Error, Result, TransportError},
+    helpers,
+    rpc::error::Error as RPCError,
+    BatchTransport, RequestId, Transport,
+};
+use futures::future::{self, BoxFuture, FutureExt, Ready};
+use jsonrpc_core::types::{Call, Value};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::{ic_http_client::CallOptions, mock::call_parts, LogLimits};
+
+/// Recorded response of a call.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordedResponse {
+    /// The call succeeded with this result
+    Result(Value),
+    /// The node answered with this JSON-RPC error
+    RpcError(RPCError),
+    /// The call failed with another error, kept as its message
+    Error(String),
+}
+
+impl RecordedResponse {
+    fn of(result: &Result<Value>) -> Self {
+        match result {
+            Ok(value) => RecordedResponse::Result(value.clone()),
+            Err(Error::Rpc(err)) => RecordedResponse::RpcError(err.clone()),
+            Err(err) => RecordedResponse::Error(err.to_string()),
+        }
+    }
+
+    fn to_result(&self) -> Result<Value> {
+        match self {
+            RecordedResponse::Result(value) => Ok(value.clone()),
+            RecordedResponse::RpcError(err) => Err(Error::Rpc(err.clone())),
+            RecordedResponse::Error(message) => Err(Error::Transport(TransportError::Message(message.clone()))),
+        }
+    }
+}
+
+/// A call and its response.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Interaction {
+    /// JSON-RPC method
+    pub method: String,
+    /// Parameters of the call
+    pub params: Vec<Value>,
+    /// Response of the provider
+    pub response: RecordedResponse,
+}
+
+/// Calls recorded from a live transport, in order.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Fixture {
+    /// Recorded calls
+    pub interactions: Vec<Interaction>,
+}
+
+impl Fixture {
+    /// Pretty-printed JSON of the fixture.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("fixtures never fail to serialize")
+    }
+
+    /// Parse a fixture saved with [`Fixture::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| Error::Decoder(format!("invalid fixture: {}", err)))
+    }
+}
+
+/// Transport recording the calls of the transport it wraps, see [`replay`](self).
+///
+/// The transport is cheap to clone; clones share the same recording.
+#[derive(Clone, Debug)]
+pub struct RecordingTransport<T> {
+    transport: T,
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Record the calls of `transport`.
+    pub fn new(transport: T) -> Self {
+        RecordingTransport {
+            transport,
+            fixture: Default::default(),
+        }
+    }
+
+    /// Wrapped transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Calls recorded so far.
+    pub fn fixture(&self) -> Fixture {
+        self.fixture.lock().clone()
+    }
+
+    /// Forget the calls recorded so far.
+    pub fn clear(&self) {
+        self.fixture.lock().interactions.clear();
+    }
+}
+
+fn record(fixture: &Mutex<Fixture>, parts: Option<(String, Vec<Value>)>, result: &Result<Value>) {
+    if let Some((method, params)) = parts {
+        fixture.lock().interactions.push(Interaction {
+            method,
+            params,
+            response: RecordedResponse::of(result),
+        });
+    }
+}
+
+impl<T> Transport for RecordingTransport<T>
+where
+    T: Transport,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call, options: CallOptions) -> Self::Out {
+        let parts = call_parts(&request);
+        let fixture = self.fixture.clone();
+        self.transport
+            .send(id, request, options)
+            .map(move |result| {
+                record(&fixture, parts, &result);
+                result
+            })
+            .boxed()
+    }
+
+    fn set_max_response_bytes(&mut self, bytes: u64) {
+        self.transport.set_max_response_bytes(bytes)
+    }
+
+    fn default_call_options(&self) -> CallOptions {
+        self.transport.default_call_options()
+    }
+
+    fn log_limits(&self) -> LogLimits {
+        self.transport.log_limits()
+    }
+}
+
+impl<T> BatchTransport for RecordingTransport<T>
+where
+    T: BatchTransport,
+    T::Out: Send + 'static,
+    T::Batch: Send + 'static,
+{
+    type Batch = BoxFuture<'static, Result<Vec<Result<Value>>>>;
+
+    fn send_batch<I>(&self, requests: I, options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let requests: Vec<_> = requests.into_iter().collect();
+        let parts: Vec<_> = requests.iter().map(|(_, call)| call_parts(call)).collect();
+        let fixture = self.fixture.clone();
+        self.transport
+            .send_batch(requests, options)
+            .map(move |results| {
+                match &results {
+                    Ok(results) => {
+                        for (parts, result) in parts.into_iter().zip(results) {
+                            record(&fixture, parts, result);
+                        }
+                    }
+                    Err(err) => {
+                        let err = Err(err.clone());
+                        for parts in parts {
+                            record(&fixture, parts, &err);
+                        }
+                    }
+                }
+                results
+            })
+            .boxed()
+    }
+}
+
+/// Transport serving the calls of a [`Fixture`].
+///
+/// A call is answered with the first interaction not served yet with the same method and parameters,
+/// so that repeated calls, e.g. polling a receipt, get the responses in the recorded order. Calls not in
+/// the fixture fail.
+///
+/// The transport is cheap to clone; clones share the served interactions.
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    fixture: Arc<Fixture>,
+    served: Arc<Mutex<Vec<bool>>>,
+    next_id: Arc<Mutex<RequestId>>,
+}
+
+impl ReplayTransport {
+    /// Serve the calls of `fixture`.
+    pub fn new(fixture: Fixture) -> Self {
+        ReplayTransport {
+            served: Arc::new(Mutex::new(vec![false; fixture.interactions.len()])),
+            fixture: Arc::new(fixture),
+            next_id: Default::default(),
+        }
+    }
+
+    /// Interactions of the fixture not served yet.
+    pub fn remaining(&self) -> Vec<Interaction> {
+        let served = self.served.lock();
+        self.fixture
+            .interactions
+            .iter()
+            .zip(served.iter())
+            .filter(|(_, served)| !**served)
+            .map(|(interaction, _)| interaction.clone())
+            .collect()
+    }
+
+    fn answer(&self, call: &Call) -> Result<Value> {
+        let (method, params) = call_parts(call).ok_or(Error::Internal)?;
+        let mut served = self.served.lock();
+        let position = self
+            .fixture
+            .interactions
+            .iter()
+            .zip(served.iter())
+            .position(|(interaction, served)| !*served && interaction.method == method && interaction.params == params);
+        match position {
+            Some(position) => {
+                served[position] = true;
+                self.fixture.interactions[position].response.to_result()
+            }
+            None => Err(Error::Transport(TransportError::Message(format!(
+                "no recorded response for {} with {:?}",
+                method, params
+            )))),
+        }
+    }
+}
+
+impl Transport for ReplayTransport {
+    type Out = Ready<Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let mut next_id = self.next_id.lock();
+        let id = *next_id;
+        *next_id += 1;
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, _id: RequestId, request: Call, _options: CallOptions) -> Self::Out {
+        future::ready(self.answer(&request))
+    }
+}
+
+impl BatchTransport for ReplayTransport {
+    type Batch = Ready<Result<Vec<Result<Value>>>>;
+
+    fn send_batch<I>(&self, requests: I, _options: CallOptions) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        future::ready(Ok(requests.into_iter().map(|(_, call)| self.answer(&call)).collect()))
+    }
+}
@@ -0,0 +1,152 @@
+//! Retries of failed outcalls.
+//!
+//! Outcalls fail transiently, e.g. when the provider rate limits the canister or the replicas time out.
+//! A [`RetryPolicy`] set in the [`CallOptions`](super::ic_http_client::CallOptions) of a call makes
+//! [`ICHttp`](super::ICHttp) retry it on the failures it lists, waiting between the attempts:
+//!
+//! ```ignore
+//! let options = CallOptions::default_for("eth_getLogs").with_retry(
+//!     RetryPolicy::new(3)
+//!         .with_backoff(Backoff::exponential(Duration::from_secs(1), Duration::from_secs(8)))
+//!         .retry_on(RetryOn::ConsensusFailure),
+//! );
+//! ```
+//!
+//! Every attempt is a separate outcall, charged separately. The [state-changing](super::ic_http_client::is_state_changing)
+//! calls are never retried, as a failed attempt may still have reached the node.
+
+use crate::error::{Error, TransportError};
+use ic_cdk::api::call::RejectionCode;
+use std::time::Duration;
+
+/// JSON-RPC error code of the rate limits of Infura and other providers.
+const RPC_LIMIT_EXCEEDED: i64 = -32005;
+
+/// Kind of failure a [`RetryPolicy`] retries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RetryOn {
+    /// Outcalls rejected with `SysTransient`, e.g. when the provider cannot be reached
+    SysTransient,
+    /// Outcalls timing out
+    Timeout,
+    /// Replicas not agreeing on the response, e.g. right after a new block
+    ConsensusFailure,
+    /// Rate limits: HTTP 429 or the `-32005` JSON-RPC error
+    RateLimited,
+    /// HTTP 5xx statuses
+    ServerError,
+}
+
+impl RetryOn {
+    /// Kind of the failure `err`, `None` for failures which are not transient.
+    pub fn of(err: &Error) -> Option<RetryOn> {
+        match err {
+            Error::Transport(TransportError::Rejected { code, .. }) if *code == RejectionCode::SysTransient => {
+                Some(RetryOn::SysTransient)
+            }
+            Error::Transport(TransportError::Timeout(_)) => Some(RetryOn::Timeout),
+            Error::Transport(TransportError::ConsensusFailure(_)) => Some(RetryOn::ConsensusFailure),
+            Error::Transport(TransportError::Code(429)) => Some(RetryOn::RateLimited),
+            Error::Transport(TransportError::Code(status)) if *status >= 500 => Some(RetryOn::ServerError),
+            Error::Rpc(err) if matches!(err.code.code(), RPC_LIMIT_EXCEEDED | 429) => Some(RetryOn::RateLimited),
+            _ => None,
+        }
+    }
+
+    /// Whether `err` is a failure of this kind.
+    pub fn matches(&self, err: &Error) -> bool {
+        Self::of(err) == Some(*self)
+    }
+}
+
+/// Delay between the attempts of a call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backoff {
+    /// The same delay before every retry
+    Fixed(Duration),
+    /// A delay doubling after every retry, up to a maximum
+    Exponential {
+        /// Delay before the first retry
+        initial: Duration,
+        /// Upper bound of the delay
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Delay starting at `initial`, doubled after every retry up to `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Backoff::Exponential { initial, max }
+    }
+
+    /// Delay before retry number `retry`, starting at one.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => initial
+                .checked_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+                .unwrap_or(max)
+                .min(max),
+        }
+    }
+}
+
+/// How failed calls are retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    retry_on: Vec<RetryOn>,
+}
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts, retrying `SysTransient` rejections, timeouts and rate limits
+    /// after an exponential backoff from one second up to thirty.
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            backoff: Backoff::exponential(Duration::from_secs(1), Duration::from_secs(30)),
+            retry_on: vec![RetryOn::SysTransient, RetryOn::Timeout, RetryOn::RateLimited],
+        }
+    }
+
+    /// Wait according to `backoff` between the attempts.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Also retry the failures of `kind`.
+    pub fn retry_on(mut self, kind: RetryOn) -> Self {
+        if !self.retry_on.contains(&kind) {
+            self.retry_on.push(kind);
+        }
+        self
+    }
+
+    /// Only retry the failures of `kinds`.
+    pub fn retrying_only(mut self, kinds: Vec<RetryOn>) -> Self {
+        self.retry_on = kinds;
+        self
+    }
+
+    /// Maximum number of attempts of a call, the first one included.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Delay between the attempts.
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Kinds of failures retried.
+    pub fn retried(&self) -> &[RetryOn] {
+        &self.retry_on
+    }
+
+    /// Whether a call failing with `err` after `attempt` attempts is retried.
+    pub fn should_retry(&self, err: &Error, attempt: u32) -> bool {
+        attempt < self.max_attempts && self.retry_on.iter().any(|kind| kind.matches(err))
+    }
+}
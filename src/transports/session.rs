@@ -0,0 +1,130 @@
+//! Sticky sessions with load-balanced providers.
+//!
+//! Providers behind a load balancer may route consecutive calls to different nodes, so that e.g. the logs
+//! of a block range are read from a node that has not imported its last blocks yet. Some of them pin a
+//! client to a node with a session header or cookie. A [`ProviderSession`] captures them from the responses
+//! and sends them back with the following calls of the transports it is attached to:
+//!
+//! ```ignore
+//! // in the canister transform, keep the session cookie of the responses
+//! set_normalizer(Normalizer::standard().keep_header("set-cookie"));
+//!
+//! let session = ProviderSession::new().capture_cookie("SERVERID");
+//! let web3 = Web3::new(transport.with_session(session.clone()));
+//! // ... calls of the workflow ...
+//! session.reset();
+//! ```
+//!
+//! Outcall responses reach the canister through consensus, so a captured value must be the same for all
+//! the replicas, i.e. the provider must derive it from the request. Otherwise, pick the session id in the
+//! canister with [`ProviderSession::with_header`], for providers routing on a request header.
+
+use ic_cdk::api::management_canister::http_request::HttpHeader;
+use parking_lot::Mutex;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Name of the response header setting cookies.
+const SET_COOKIE: &str = "set-cookie";
+
+#[derive(Debug, Default)]
+struct SessionState {
+    /// Headers sent with the calls, by lowercase name
+    headers: BTreeMap<String, HttpHeader>,
+    /// Cookies sent with the calls, by name
+    cookies: BTreeMap<String, String>,
+}
+
+/// Headers and cookies kept across the calls of a logical session with a provider.
+///
+/// The session is cheap to clone; clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct ProviderSession {
+    /// Lowercase names of the response headers captured
+    headers: Vec<String>,
+    /// Names of the cookies captured
+    cookies: Vec<String>,
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl ProviderSession {
+    /// Create a session capturing nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Capture the response header `name` and send it back with the next calls.
+    pub fn capture_header(mut self, name: &str) -> Self {
+        self.headers.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Capture the cookie `name` set by the responses and send it back with the next calls.
+    pub fn capture_cookie(mut self, name: &str) -> Self {
+        self.cookies.push(name.to_string());
+        self
+    }
+
+    /// Send the header `name` with `value` with the calls of the session, e.g. a session id chosen by the
+    /// canister.
+    pub fn with_header(self, name: &str, value: &str) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    fn set_header(&self, name: &str, value: &str) {
+        self.state.lock().headers.insert(
+            name.to_ascii_lowercase(),
+            HttpHeader {
+                name: name.to_string(),
+                value: value.to_string(),
+            },
+        );
+    }
+
+    /// Capture the session headers and cookies of a response.
+    pub fn capture(&self, headers: &[HttpHeader]) {
+        for header in headers {
+            let name = header.name.to_ascii_lowercase();
+            if name == SET_COOKIE {
+                let cookie = header.value.split(';').next().unwrap_or_default();
+                if let Some((cookie, value)) = cookie.split_once('=') {
+                    let cookie = cookie.trim();
+                    if self.cookies.iter().any(|captured| captured == cookie) {
+                        self.state
+                            .lock()
+                            .cookies
+                            .insert(cookie.to_string(), value.trim().to_string());
+                    }
+                }
+            } else if self.headers.contains(&name) {
+                self.set_header(&header.name, &header.value);
+            }
+        }
+    }
+
+    /// Headers to send with the next call: the captured and set headers, and the captured cookies in a
+    /// `Cookie` header.
+    pub fn headers(&self) -> Vec<HttpHeader> {
+        let state = self.state.lock();
+        let mut headers: Vec<_> = state.headers.values().cloned().collect();
+        if !state.cookies.is_empty() {
+            let cookies: Vec<_> = state
+                .cookies
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            headers.push(HttpHeader {
+                name: "Cookie".to_string(),
+                value: cookies.join("; "),
+            });
+        }
+        headers
+    }
+
+    /// Forget the captured and set headers and cookies, ending the session.
+    pub fn reset(&self) {
+        let mut state = self.state.lock();
+        state.headers.clear();
+        state.cookies.clear();
+    }
+}
@@ -0,0 +1,105 @@
+//! Conversions between the primitive types and their `alloy-primitives` counterparts.
+//!
+//! Both sides of the hash, address and integer conversions are foreign types, so they are provided by
+//! the [`ToAlloy`] and [`ToWeb3`] traits rather than `From` impls. [`Bytes`] also converts into
+//! `alloy_primitives::Bytes` with `From`, the other direction being taken by the generic
+//! `From<T: Into<Vec<u8>>>` impl of [`Bytes`]:
+//!
+//! ```ignore
+//! use ic_web3_rs::types::{ToAlloy, ToWeb3};
+//!
+//! let address: alloy_primitives::Address = web3_address.to_alloy();
+//! let value: ic_web3_rs::types::U256 = alloy_value.to_web3();
+//! ```
+
+use super::{Bytes, H160, H256, U256};
+
+/// Conversion of a primitive type into its `alloy-primitives` counterpart.
+pub trait ToAlloy {
+    /// `alloy-primitives` type
+    type Alloy;
+
+    /// Convert into the `alloy-primitives` type.
+    fn to_alloy(self) -> Self::Alloy;
+}
+
+/// Conversion of an `alloy-primitives` type into its counterpart of this crate.
+pub trait ToWeb3 {
+    /// Type of this crate
+    type Web3;
+
+    /// Convert into the type of this crate.
+    fn to_web3(self) -> Self::Web3;
+}
+
+impl ToAlloy for H160 {
+    type Alloy = alloy_primitives::Address;
+
+    fn to_alloy(self) -> Self::Alloy {
+        alloy_primitives::Address::from(self.0)
+    }
+}
+
+impl ToWeb3 for alloy_primitives::Address {
+    type Web3 = H160;
+
+    fn to_web3(self) -> Self::Web3 {
+        H160(self.into_array())
+    }
+}
+
+impl ToAlloy for H256 {
+    type Alloy = alloy_primitives::B256;
+
+    fn to_alloy(self) -> Self::Alloy {
+        alloy_primitives::B256::from(self.0)
+    }
+}
+
+impl ToWeb3 for alloy_primitives::B256 {
+    type Web3 = H256;
+
+    fn to_web3(self) -> Self::Web3 {
+        H256(self.0)
+    }
+}
+
+impl ToAlloy for U256 {
+    type Alloy = alloy_primitives::U256;
+
+    fn to_alloy(self) -> Self::Alloy {
+        let mut bytes = [0u8; 32];
+        self.to_big_endian(&mut bytes);
+        alloy_primitives::U256::from_be_bytes(bytes)
+    }
+}
+
+impl ToWeb3 for alloy_primitives::U256 {
+    type Web3 = U256;
+
+    fn to_web3(self) -> Self::Web3 {
+        U256::from_big_endian(&self.to_be_bytes::<32>())
+    }
+}
+
+impl ToAlloy for Bytes {
+    type Alloy = alloy_primitives::Bytes;
+
+    fn to_alloy(self) -> Self::Alloy {
+        self.into()
+    }
+}
+
+impl ToWeb3 for alloy_primitives::Bytes {
+    type Web3 = Bytes;
+
+    fn to_web3(self) -> Self::Web3 {
+        Bytes(self.to_vec())
+    }
+}
+
+impl From<Bytes> for alloy_primitives::Bytes {
+    fn from(bytes: Bytes) -> Self {
+        bytes.0.into()
+    }
+}
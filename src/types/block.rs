@@ -1,5 +1,7 @@
 use crate::types::{Bytes, H160, H2048, H256, H64, U256, U64};
 use serde::{de::Error, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// The block header type returned from RPC calls.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -50,6 +52,21 @@ pub struct BlockHeader {
     pub mix_hash: Option<H256>,
     /// Nonce
     pub nonce: Option<H64>,
+    /// Root of the withdrawals trie (if past Shanghai)
+    #[serde(rename = "withdrawalsRoot", default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    /// Blob gas used by the transactions of the block (if past Cancun)
+    #[serde(rename = "blobGasUsed", default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+    /// Blob gas in excess of the target, setting the blob base fee (if past Cancun)
+    #[serde(rename = "excessBlobGas", default, skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U64>,
+    /// Root of the parent beacon block (if past Cancun)
+    #[serde(rename = "parentBeaconBlockRoot", default, skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
+    /// Fields not known to this type, e.g. added by a later fork or by an L2 node
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
 }
 
 /// The block type returned from RPC calls.
@@ -114,6 +131,50 @@ pub struct Block<TX> {
     pub mix_hash: Option<H256>,
     /// Nonce
     pub nonce: Option<H64>,
+    /// Validator withdrawals from the beacon chain (if past Shanghai)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    /// Root of the withdrawals trie (if past Shanghai)
+    #[serde(rename = "withdrawalsRoot", default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    /// Blob gas used by the transactions of the block (if past Cancun)
+    #[serde(rename = "blobGasUsed", default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+    /// Blob gas in excess of the target, setting the blob base fee (if past Cancun)
+    #[serde(rename = "excessBlobGas", default, skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U64>,
+    /// Root of the parent beacon block (if past Cancun)
+    #[serde(rename = "parentBeaconBlockRoot", default, skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
+    /// Fields not known to this type, e.g. added by a later fork or by an L2 node
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+impl<TX> Block<TX> {
+    /// Fail with the names of the fields not known to this type, for callers which would rather reject
+    /// a block than silently ignore part of it.
+    pub fn ensure_known_fields(&self) -> Result<(), Vec<String>> {
+        if self.other.is_empty() {
+            Ok(())
+        } else {
+            Err(self.other.keys().cloned().collect())
+        }
+    }
+}
+
+/// Withdrawal of a validator from the beacon chain to the execution layer.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    /// Monotonic index of the withdrawal
+    pub index: U64,
+    /// Index of the validator
+    pub validator_index: U64,
+    /// Recipient of the withdrawn ether
+    pub address: H160,
+    /// Amount withdrawn, in gwei
+    pub amount: U256,
 }
 
 fn null_to_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
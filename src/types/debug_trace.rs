@@ -0,0 +1,214 @@
+//! Types for the Geth `debug_trace*` API.
+
+use crate::types::{Address, Bytes, StateOverride, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Built-in tracer to run the transaction with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tracer {
+    /// Tracer returning the tree of internal calls, see [`CallFrame`]
+    #[serde(rename = "callTracer")]
+    CallTracer,
+    /// Tracer returning the accounts touched by the transaction, see [`PrestateFrame`]
+    #[serde(rename = "prestateTracer")]
+    PrestateTracer,
+    /// Tracer returning the 4-byte selectors of the internal calls
+    #[serde(rename = "4byteTracer")]
+    FourByteTracer,
+    /// Tracer which does nothing, useful to check a transaction can be traced at all
+    #[serde(rename = "noopTracer")]
+    NoopTracer,
+}
+
+/// Configuration of the built-in tracers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracerConfig {
+    /// `callTracer`: only trace the top-level call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only_top_call: Option<bool>,
+    /// `callTracer`: include the logs emitted by every call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub with_log: Option<bool>,
+    /// `prestateTracer`: return the state before and after the transaction instead of the state before only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_mode: Option<bool>,
+}
+
+/// Options of `debug_traceTransaction` and `debug_traceCall`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceOptions {
+    /// Tracer to use, the opcode logger when not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracer: Option<Tracer>,
+    /// Configuration of the tracer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracer_config: Option<TracerConfig>,
+    /// Timeout of the tracing on the node, e.g. `"5s"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    /// State overrides applied before tracing, only supported by `debug_traceCall`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_overrides: Option<StateOverride>,
+}
+
+impl TraceOptions {
+    /// Options running the `callTracer`.
+    pub fn call_tracer(config: TracerConfig) -> Self {
+        TraceOptions {
+            tracer: Some(Tracer::CallTracer),
+            tracer_config: Some(config),
+            ..Default::default()
+        }
+    }
+
+    /// Options running the `prestateTracer`, in diff mode if `diff_mode` is set.
+    pub fn prestate_tracer(diff_mode: bool) -> Self {
+        TraceOptions {
+            tracer: Some(Tracer::PrestateTracer),
+            tracer_config: Some(TracerConfig {
+                diff_mode: Some(diff_mode),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// A call frame produced by the `callTracer`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    /// Type of the call, e.g. `CALL`, `DELEGATECALL`, `CREATE2` or `SELFDESTRUCT`
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// Sender
+    pub from: Address,
+    /// Recipient, the created contract for `CREATE` frames
+    #[serde(default)]
+    pub to: Option<Address>,
+    /// Transferred value
+    #[serde(default)]
+    pub value: Option<U256>,
+    /// Gas available to the call
+    pub gas: U256,
+    /// Gas used by the call
+    pub gas_used: U256,
+    /// Input data
+    pub input: Bytes,
+    /// Output data
+    #[serde(default)]
+    pub output: Option<Bytes>,
+    /// Error message, if the call failed
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Decoded revert reason, if the call reverted with one
+    #[serde(default)]
+    pub revert_reason: Option<String>,
+    /// Internal calls made by this call
+    #[serde(default)]
+    pub calls: Vec<CallFrame>,
+    /// Logs emitted by this call, only set with `withLog`
+    #[serde(default)]
+    pub logs: Vec<CallLogFrame>,
+}
+
+impl CallFrame {
+    /// Whether the call failed.
+    pub fn is_error(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// All frames of the tree, depth first starting with this one.
+    pub fn frames(&self) -> Vec<&CallFrame> {
+        let mut frames = vec![self];
+        for call in &self.calls {
+            frames.extend(call.frames());
+        }
+        frames
+    }
+}
+
+/// A log emitted within a call frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallLogFrame {
+    /// Emitting contract
+    pub address: Address,
+    /// Topics
+    #[serde(default)]
+    pub topics: Vec<H256>,
+    /// Data
+    #[serde(default)]
+    pub data: Bytes,
+}
+
+/// State of an account as reported by the `prestateTracer`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrestateAccount {
+    /// Balance
+    #[serde(default)]
+    pub balance: Option<U256>,
+    /// Nonce
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    /// Code
+    #[serde(default)]
+    pub code: Option<Bytes>,
+    /// Storage slots read or written by the transaction
+    #[serde(default)]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Output of the `prestateTracer` in diff mode.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrestateDiff {
+    /// State of the modified accounts before the transaction
+    pub pre: BTreeMap<Address, PrestateAccount>,
+    /// Modified fields of the accounts after the transaction
+    pub post: BTreeMap<Address, PrestateAccount>,
+}
+
+/// Output of the `prestateTracer`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PrestateFrame {
+    /// Output in diff mode
+    Diff(PrestateDiff),
+    /// State of all the accounts touched by the transaction
+    Prestate(BTreeMap<Address, PrestateAccount>),
+}
+
+/// Result of `debug_traceTransaction` and `debug_traceCall`.
+///
+/// The shape of the result depends on the tracer; results of tracers without a typed representation
+/// (e.g. the default opcode logger) are kept as raw JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GethTrace {
+    /// Output of the `callTracer`
+    CallTracer(Box<CallFrame>),
+    /// Output of the `prestateTracer`
+    PrestateTracer(PrestateFrame),
+    /// Output of any other tracer
+    Unknown(serde_json::Value),
+}
+
+impl GethTrace {
+    /// Call frame tree, if the trace was produced by the `callTracer`.
+    pub fn call_frame(&self) -> Option<&CallFrame> {
+        match self {
+            GethTrace::CallTracer(frame) => Some(frame),
+            _ => None,
+        }
+    }
+
+    /// Prestate, if the trace was produced by the `prestateTracer`.
+    pub fn prestate(&self) -> Option<&PrestateFrame> {
+        match self {
+            GethTrace::PrestateTracer(frame) => Some(frame),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! zkSync EIP-712 transaction fields (fee abstraction through paymasters).
+
+use crate::{
+    signing,
+    types::{Address, Bytes, H256, U256},
+};
+use ethabi::Token;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Transaction type of zkSync EIP-712 transactions.
+pub const EIP712_TX_TYPE: u64 = 0x71;
+/// Default maximum gas per byte of published data of zkSync EIP-712 transactions.
+pub const DEFAULT_GAS_PER_PUBDATA_LIMIT: u64 = 50_000;
+
+/// Paymaster paying the fees of a transaction, possibly in exchange for ERC-20 tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymasterParams {
+    /// Paymaster contract
+    pub paymaster: Address,
+    /// Input passed to the paymaster, selecting the paymaster flow
+    pub paymaster_input: Bytes,
+}
+
+impl PaymasterParams {
+    /// Paymaster using the `general(bytes)` flow with the given inner input.
+    pub fn general(paymaster: Address, inner_input: Vec<u8>) -> Self {
+        PaymasterParams {
+            paymaster,
+            paymaster_input: encode_flow("general(bytes)", vec![Token::Bytes(inner_input)]),
+        }
+    }
+
+    /// Paymaster using the `approvalBased(address,uint256,bytes)` flow, paying the fees in `token`.
+    ///
+    /// The sender must allow the paymaster to spend at least `min_allowance` of `token`; the
+    /// bootloader sets the allowance before the paymaster is called.
+    pub fn approval_based(paymaster: Address, token: Address, min_allowance: U256, inner_input: Vec<u8>) -> Self {
+        PaymasterParams {
+            paymaster,
+            paymaster_input: encode_flow(
+                "approvalBased(address,uint256,bytes)",
+                vec![
+                    Token::Address(token),
+                    Token::Uint(min_allowance),
+                    Token::Bytes(inner_input),
+                ],
+            ),
+        }
+    }
+}
+
+fn encode_flow(signature: &str, tokens: Vec<Token>) -> Bytes {
+    let selector = &signing::keccak256(signature.as_bytes())[..4];
+    [selector, &ethabi::encode(&tokens)].concat().into()
+}
+
+/// zkSync specific fields of EIP-712 (type `0x71`) transactions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712Meta {
+    /// Maximum gas the sender is willing to pay per byte of published data
+    pub gas_per_pubdata: U256,
+    /// Bytecodes of the contracts deployed by the transaction
+    #[serde(default)]
+    pub factory_deps: Vec<Bytes>,
+    /// Signature replacing the ECDSA signature, for custom account abstraction accounts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_signature: Option<Bytes>,
+    /// Paymaster paying the fees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_params: Option<PaymasterParams>,
+}
+
+impl Default for Eip712Meta {
+    fn default() -> Self {
+        Eip712Meta {
+            gas_per_pubdata: DEFAULT_GAS_PER_PUBDATA_LIMIT.into(),
+            factory_deps: vec![],
+            custom_signature: None,
+            paymaster_params: None,
+        }
+    }
+}
+
+impl Eip712Meta {
+    /// Meta paying the fees with the given paymaster.
+    pub fn with_paymaster(paymaster_params: PaymasterParams) -> Self {
+        Eip712Meta {
+            paymaster_params: Some(paymaster_params),
+            ..Default::default()
+        }
+    }
+}
+
+/// Hash of a contract bytecode as expected by zkSync in `factoryDeps`.
+///
+/// The bytecode must be made of an odd number of 32-byte words, at most `2^16 - 1` of them.
+pub fn hash_bytecode(bytecode: &[u8]) -> Result<H256, String> {
+    if !bytecode.len().is_multiple_of(32) {
+        return Err("bytecode length must be a multiple of 32 bytes".into());
+    }
+    let words = bytecode.len() / 32;
+    if words >= 1 << 16 {
+        return Err(format!("bytecode is too long: {} words", words));
+    }
+    if words.is_multiple_of(2) {
+        return Err("bytecode must have an odd number of words".into());
+    }
+    let mut hash: [u8; 32] = Sha256::digest(bytecode).into();
+    hash[0] = 1;
+    hash[1] = 0;
+    hash[2..4].copy_from_slice(&(words as u16).to_be_bytes());
+    Ok(H256(hash))
+}
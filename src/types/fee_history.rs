@@ -13,4 +13,10 @@ pub struct FeeHistory {
     pub gas_used_ratio: Vec<f64>,
     /// A vector of effective priority fee per gas data points from a single block. All zeroes are returned if the block is empty. Returned only if requested.
     pub reward: Option<Vec<Vec<U256>>>,
+    /// A vector of block base fees per blob gas, including the next block like `base_fee_per_gas`. Empty for nodes without EIP-4844 support.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub base_fee_per_blob_gas: Vec<U256>,
+    /// A vector of block blob gas used ratios. These are calculated as the ratio of blob gas used and max blob gas.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blob_gas_used_ratio: Vec<f64>,
 }
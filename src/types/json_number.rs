@@ -0,0 +1,116 @@
+use crate::types::U256;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+
+/// Unsigned integer of a JSON-RPC result given as a plain JSON number, e.g. by provider-specific methods.
+///
+/// JSON numbers above 2^64 are only parsed exactly with the `arbitrary_precision` feature; without it
+/// `serde_json` turns them into lossy floating point values. Instead of silently keeping a rounded
+/// value, deserialization then fails, so a result is either exact or rejected in both modes. Decimal
+/// and `0x` prefixed hexadecimal strings are accepted too.
+///
+/// Serialized as a hexadecimal quantity, like [`U256`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JsonNumber(pub U256);
+
+impl JsonNumber {
+    fn parse(value: &str) -> Result<Self, String> {
+        let number = match value.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(value).ok(),
+        };
+        number
+            .map(JsonNumber)
+            .ok_or_else(|| format!("invalid unsigned integer: {}", value))
+    }
+}
+
+impl From<U256> for JsonNumber {
+    fn from(value: U256) -> Self {
+        JsonNumber(value)
+    }
+}
+
+impl From<JsonNumber> for U256 {
+    fn from(value: JsonNumber) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for JsonNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Number(number) if number.is_f64() => Err(D::Error::custom(format!(
+                "{} is not an unsigned integer, or needs the arbitrary_precision feature to be parsed exactly",
+                number
+            ))),
+            // With `arbitrary_precision` the number keeps its original digits.
+            Value::Number(number) => Self::parse(&number.to_string()).map_err(D::Error::custom),
+            Value::String(value) => Self::parse(&value).map_err(D::Error::custom),
+            other => Err(D::Error::custom(format!("expected a number, got {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonNumber;
+    use crate::{helpers, types::U256};
+
+    #[test]
+    fn parses_small_numbers_and_strings() {
+        let numbers: Vec<JsonNumber> = helpers::from_slice(br#"[42, "42", "0x2a"]"#).unwrap();
+        assert_eq!(numbers, vec![JsonNumber(42.into()); 3]);
+    }
+
+    #[test]
+    fn keeps_big_numbers_exact_or_rejects_them() {
+        let result: Result<JsonNumber, _> = helpers::from_slice(b"123456789012345678901234567890");
+        if cfg!(feature = "arbitrary_precision") {
+            assert_eq!(
+                result.unwrap().0,
+                U256::from_dec_str("123456789012345678901234567890").unwrap()
+            );
+        } else {
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_fractions_and_negative_numbers() {
+        assert!(helpers::from_slice::<JsonNumber>(b"1.5").is_err());
+        assert!(helpers::from_slice::<JsonNumber>(b"-1").is_err());
+        assert!(helpers::from_slice::<JsonNumber>(br#""0xzz""#).is_err());
+    }
+
+    #[test]
+    fn decodes_results_of_untagged_responses() {
+        let body = br#"{"jsonrpc":"2.0","id":1,"result":{"total":18446744073709551615}}"#;
+        let response: jsonrpc_core::Response = helpers::from_slice(body).unwrap();
+        let output = match response {
+            jsonrpc_core::Response::Single(output) => output,
+            jsonrpc_core::Response::Batch(_) => panic!("single response expected"),
+        };
+        let result = helpers::to_result_from_output(output).unwrap();
+        let total: JsonNumber = serde_json::from_value(result["total"].clone()).unwrap();
+        assert_eq!(total.0, U256::from(u64::MAX));
+    }
+
+    #[test]
+    fn serializes_as_quantity() {
+        assert_eq!(serde_json::to_string(&JsonNumber(255.into())).unwrap(), r#""0xff""#);
+    }
+}
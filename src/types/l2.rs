@@ -0,0 +1,58 @@
+//! Fee types of optimistic rollups.
+
+use crate::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// L1 and L2 gas prices returned by Optimism's `rollup_gasPrices`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollupGasPrices {
+    /// Gas price of the L1 the rollup posts its data to
+    pub l1_gas_price: U256,
+    /// Gas price of the rollup
+    pub l2_gas_price: U256,
+}
+
+/// Gas estimate of a transaction returned by Arbitrum's `NodeInterface.gasEstimateComponents`.
+///
+/// Arbitrum charges the L1 data cost of a transaction as additional L2 gas, so that `gas_estimate`,
+/// like `eth_estimateGas`, already includes `gas_estimate_for_l1` and varies with the L1 base fee.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArbitrumGasEstimate {
+    /// Total gas limit of the transaction
+    pub gas_estimate: U256,
+    /// Part of the gas limit paying for the L1 data
+    pub gas_estimate_for_l1: U256,
+    /// L2 base fee
+    pub base_fee: U256,
+    /// Estimate of the L1 base fee used to price the L1 data
+    pub l1_base_fee_estimate: U256,
+}
+
+impl ArbitrumGasEstimate {
+    /// Gas paying for the execution on L2.
+    pub fn gas_estimate_for_l2(&self) -> U256 {
+        self.gas_estimate.saturating_sub(self.gas_estimate_for_l1)
+    }
+
+    /// Total cost of the transaction in wei at the current base fee.
+    pub fn total_fee(&self) -> U256 {
+        self.gas_estimate.saturating_mul(self.base_fee)
+    }
+}
+
+/// Total cost of an OP Stack transaction: the L2 execution fee plus the fee for posting its data to L1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimismFee {
+    /// Fee of the execution on L2, gas limit times gas price
+    pub l2_fee: U256,
+    /// Fee of the L1 data, charged on top of the gas limit
+    pub l1_fee: U256,
+}
+
+impl OptimismFee {
+    /// Total cost of the transaction in wei.
+    pub fn total_fee(&self) -> U256 {
+        self.l2_fee.saturating_add(self.l1_fee)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::{BlockNumber, Bytes, Index, H160, H256, U256, U64};
+use crate::types::{BlockNumber, Bytes, Index, Topic, H160, H256, U256, U64};
 use serde::{Deserialize, Serialize, Serializer};
 
 /// A log produced by a transaction.
@@ -42,15 +42,7 @@ impl Log {
             Some(val_removed) => return val_removed,
             None => (),
         }
-        match self.log_type {
-            Some(ref val_log_type) => {
-                if val_log_type == "removed" {
-                    return true;
-                }
-            }
-            None => (),
-        }
-        false
+        matches!(self.log_type, Some(ref val_log_type) if val_log_type == "removed")
     }
 }
 
@@ -97,7 +89,7 @@ pub struct Filter {
 }
 
 /// Filter Builder
-#[derive(Default, Clone)]
+#[derive(Default, Debug, Clone)]
 pub struct FilterBuilder {
     filter: Filter,
 }
@@ -156,6 +148,29 @@ impl FilterBuilder {
         self
     }
 
+    /// Sets the accepted values of the topic at `position` (0 to 3), leaving the other positions as they are.
+    ///
+    /// Unset positions before `position` match any value.
+    pub fn topic<I>(mut self, position: usize, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Topic>,
+    {
+        assert!(position < 4, "a log has at most 4 topics");
+        let topics = self.filter.topics.get_or_insert_with(Vec::new);
+        if topics.len() <= position {
+            topics.resize(position + 1, None);
+        }
+        let values = values.into_iter().map(|value| value.into().0).collect();
+        topics[position] = Some(ValueOrArray(values));
+        self
+    }
+
+    /// Only match logs of the event with the given signature, e.g. `Transfer(address,address,uint256)`
+    pub fn event(self, signature: &str) -> Self {
+        self.topic(0, Some(Topic::from_event_signature(signature)))
+    }
+
     /// Sets the topics according to the given `ethabi` topic filter
     pub fn topic_filter(self, topic_filter: ethabi::TopicFilter) -> Self {
         self.topics(
@@ -1,28 +1,63 @@
 //! Web3 Types
 
+#[cfg(feature = "alloy-compat")]
+mod alloy_compat;
 mod block;
 mod bytes;
 mod bytes_array;
+#[cfg(feature = "debug")]
+mod debug_trace;
+mod eip712;
 mod fee_history;
+mod json_number;
+mod l2;
 mod log;
+#[cfg(feature = "parity")]
+mod parity_peers;
+#[cfg(feature = "parity")]
+mod parity_pending_transaction;
 mod proof;
 mod recovery;
+mod series;
 mod signed;
+mod simulate;
+mod snapshot;
+mod state_override;
+mod sync_state;
+mod topic;
+#[cfg(feature = "traces")]
+mod trace_filtering;
+#[cfg(feature = "traces")]
+mod traces;
 mod transaction;
 mod transaction_id;
 mod transaction_request;
 mod uint;
 mod work;
+#[cfg(feature = "zksync")]
+mod zksync;
 
 pub use self::{
-    block::{Block, BlockHeader, BlockId, BlockNumber},
+    block::{Block, BlockHeader, BlockId, BlockNumber, Withdrawal},
     bytes::Bytes,
     bytes_array::BytesArray,
+    eip712::{hash_bytecode, Eip712Meta, PaymasterParams, DEFAULT_GAS_PER_PUBDATA_LIMIT, EIP712_TX_TYPE},
     fee_history::FeeHistory,
+    json_number::JsonNumber,
+    l2::{ArbitrumGasEstimate, OptimismFee, RollupGasPrices},
     log::{Filter, FilterBuilder, Log},
     proof::Proof,
     recovery::{ParseSignatureError, Recovery, RecoveryMessage},
-    signed::{SignedData, SignedTransaction, TransactionParameters},
+    series::{BalancePoint, BlockRange, MAX_BLOCK_SAMPLES},
+    signed::{SignedData, SignedTransaction, TransactionParameters, MAX_TRANSACTION_DATA_SIZE, TX_BASE_GAS},
+    simulate::{
+        BlockOverrides, SimulateBlock, SimulateError, SimulatePayload, SimulatedBlock, SimulatedCall,
+        NATIVE_TRANSFER_ADDRESS,
+    },
+    snapshot::{AccountSnapshot, ChainSnapshot},
+    state_override::{mapping_slot, AccountOverride, StateOverride},
+    sync_state::SyncState,
+    topic::Topic,
     transaction::{AccessList, AccessListItem, RawTransaction, Receipt as TransactionReceipt, Transaction},
     transaction_id::TransactionId,
     transaction_request::{CallRequest, TransactionCondition, TransactionRequest},
@@ -30,6 +65,34 @@ pub use self::{
     work::Work,
 };
 
+#[cfg(feature = "alloy-compat")]
+pub use self::alloy_compat::{ToAlloy, ToWeb3};
+#[cfg(feature = "debug")]
+pub use self::debug_trace::{
+    CallFrame, CallLogFrame, GethTrace, PrestateAccount, PrestateDiff, PrestateFrame, TraceOptions, Tracer,
+    TracerConfig,
+};
+#[cfg(feature = "zksync")]
+pub use self::zksync::{BridgeAddresses, Eip712CallRequest, Fee, L1BatchBlockRange, L2ToL1LogProof};
+#[cfg(feature = "parity")]
+pub use self::{
+    parity_peers::{
+        EthProtocolInfo, ParityPeerInfo, ParityPeerType, PeerNetworkInfo, PeerProtocolsInfo, PipProtocolInfo,
+    },
+    parity_pending_transaction::{FilterCondition, ParityPendingTransactionFilter, ToFilter},
+};
+#[cfg(feature = "traces")]
+pub use self::{
+    trace_filtering::{
+        Action, ActionType, Call, CallResult, CallType, Create, CreateResult, Res, Reward, RewardType, Suicide, Trace,
+        TraceFilter, TraceFilterBuilder,
+    },
+    traces::{
+        AccountDiff, BlockTrace, ChangedType, Diff, MemoryDiff, StateDiff, StorageDiff, TraceType, TransactionTrace,
+        VMExecutedOperation, VMOperation, VMTrace,
+    },
+};
+
 /// Address
 pub type Address = H160;
 /// Index in block
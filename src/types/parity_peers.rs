@@ -0,0 +1,71 @@
+use crate::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// Peers of a node, returned by `parity_netPeers`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParityPeerType {
+    /// Number of active peers
+    pub active: usize,
+    /// Number of connected peers
+    pub connected: usize,
+    /// Maximum number of peers
+    pub max: u32,
+    /// Connected peers
+    pub peers: Vec<ParityPeerInfo>,
+}
+
+/// Connected peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParityPeerInfo {
+    /// Public node id
+    pub id: Option<String>,
+    /// Node client id
+    pub name: String,
+    /// Capabilities
+    pub caps: Vec<String>,
+    /// Network information
+    pub network: PeerNetworkInfo,
+    /// Protocols information
+    pub protocols: PeerProtocolsInfo,
+}
+
+/// Network addresses of a peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerNetworkInfo {
+    /// Remote endpoint address
+    pub remote_address: String,
+    /// Local endpoint address
+    pub local_address: String,
+}
+
+/// Protocols spoken with a peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerProtocolsInfo {
+    /// Ethereum protocol
+    pub eth: Option<EthProtocolInfo>,
+    /// Parity light protocol
+    pub pip: Option<PipProtocolInfo>,
+}
+
+/// Ethereum protocol state of a peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EthProtocolInfo {
+    /// Negotiated protocol version
+    pub version: u32,
+    /// Total difficulty of the best chain of the peer
+    pub difficulty: Option<U256>,
+    /// Hash of the best block of the peer
+    pub head: String,
+}
+
+/// Parity light protocol state of a peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipProtocolInfo {
+    /// Negotiated protocol version
+    pub version: u32,
+    /// Total difficulty of the best chain of the peer
+    pub difficulty: U256,
+    /// Hash of the best block of the peer
+    pub head: String,
+}
@@ -0,0 +1,109 @@
+use crate::types::{Address, U64};
+use serde::{
+    ser::{SerializeMap, Serializer},
+    Serialize,
+};
+
+/// Condition on a field of the pending transactions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum FilterCondition<T> {
+    /// Lower than the value
+    #[serde(rename = "lt")]
+    LowerThan(T),
+    /// Equal to the value
+    #[serde(rename = "eq")]
+    Equal(T),
+    /// Greater than the value
+    #[serde(rename = "gt")]
+    GreaterThan(T),
+}
+
+impl<T> From<T> for FilterCondition<T> {
+    fn from(t: T) -> Self {
+        FilterCondition::Equal(t)
+    }
+}
+
+/// Condition on the recipient of the pending transactions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToFilter {
+    /// Transactions to the address
+    Address(Address),
+    /// Contract creations
+    Action,
+}
+
+impl Serialize for ToFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            ToFilter::Address(address) => map.serialize_entry("eq", address)?,
+            ToFilter::Action => map.serialize_entry("action", "contract_creation")?,
+        }
+        map.end()
+    }
+}
+
+/// Filter of `parity_pendingTransactions`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParityPendingTransactionFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<FilterCondition<Address>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<ToFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas: Option<FilterCondition<U64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_price: Option<FilterCondition<U64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<FilterCondition<U64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<FilterCondition<U64>>,
+}
+
+impl ParityPendingTransactionFilter {
+    /// Filter matching every pending transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match transactions from senders matching `from`.
+    pub fn with_from(mut self, from: impl Into<FilterCondition<Address>>) -> Self {
+        self.from = Some(from.into());
+        self
+    }
+
+    /// Only match transactions whose recipient matches `to`.
+    pub fn with_to(mut self, to: ToFilter) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Only match transactions whose gas limit matches `gas`.
+    pub fn with_gas(mut self, gas: impl Into<FilterCondition<U64>>) -> Self {
+        self.gas = Some(gas.into());
+        self
+    }
+
+    /// Only match transactions whose gas price matches `gas_price`.
+    pub fn with_gas_price(mut self, gas_price: impl Into<FilterCondition<U64>>) -> Self {
+        self.gas_price = Some(gas_price.into());
+        self
+    }
+
+    /// Only match transactions whose value matches `value`.
+    pub fn with_value(mut self, value: impl Into<FilterCondition<U64>>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Only match transactions whose nonce matches `nonce`.
+    pub fn with_nonce(mut self, nonce: impl Into<FilterCondition<U64>>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+}
@@ -0,0 +1,53 @@
+use crate::types::{U256, U64};
+
+/// Maximum number of blocks returned by [`BlockRange::sample`].
+pub const MAX_BLOCK_SAMPLES: u64 = 1_000;
+
+/// Inclusive range of block numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRange {
+    /// First block
+    pub from: U64,
+    /// Last block, included
+    pub to: U64,
+}
+
+impl BlockRange {
+    /// Range from block `from` to block `to`, both included.
+    pub fn new(from: U64, to: U64) -> Self {
+        BlockRange { from, to }
+    }
+
+    /// Blocks of the range every `stride` blocks from the first one, the last block always included.
+    ///
+    /// The stride is widened as needed so that at most [`MAX_BLOCK_SAMPLES`] blocks are returned.
+    pub fn sample(&self, stride: u64) -> Vec<U64> {
+        if self.from > self.to {
+            return vec![];
+        }
+        let span = (self.to - self.from).as_u64();
+        let min_stride = span.div_ceil(MAX_BLOCK_SAMPLES - 1);
+        let stride = U64::from(stride.max(min_stride).max(1));
+        let mut blocks = vec![];
+        let mut block = self.from;
+        while block < self.to {
+            blocks.push(block);
+            block = match block.checked_add(stride) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        blocks.push(self.to);
+        blocks
+    }
+}
+
+/// Balance of an account at a block, returned by
+/// [`Eth::balance_series`](crate::api::Eth::balance_series).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalancePoint {
+    /// Block number
+    pub block: U64,
+    /// Balance at the end of the block
+    pub balance: U256,
+}
@@ -1,4 +1,4 @@
-use crate::types::{AccessList, Address, Bytes, CallRequest, H256, U256, U64};
+use crate::types::{eip712, AccessList, Address, Bytes, CallRequest, Eip712Meta, H256, U256, U64};
 use serde::{Deserialize, Serialize};
 
 /// Struct representing signed data returned from `Accounts::sign` method.
@@ -37,7 +37,8 @@ pub struct SignedData {
 /// interacting with complex contracts. It is recommended when interacting
 /// with contracts to use `Eth::estimate_gas` to estimate the required gas for
 /// the transaction.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TransactionParameters {
     /// Transaction nonce (None for account transaction count)
     pub nonce: Option<U256>,
@@ -61,6 +62,8 @@ pub struct TransactionParameters {
     pub max_fee_per_gas: Option<U256>,
     /// miner bribe
     pub max_priority_fee_per_gas: Option<U256>,
+    /// zkSync fields, only for EIP-712 (type `0x71`) transactions
+    pub eip712_meta: Option<Eip712Meta>,
 }
 
 /// The default fas for transactions.
@@ -71,6 +74,87 @@ pub struct TransactionParameters {
 /// value of 100_000.
 const TRANSACTION_DEFAULT_GAS: U256 = U256([100_000, 0, 0, 0]);
 
+/// Base gas cost of every transaction.
+pub const TX_BASE_GAS: u64 = 21_000;
+/// Additional gas cost of a contract creation transaction.
+pub const TX_CREATE_GAS: u64 = 32_000;
+/// Gas cost of a zero calldata byte.
+pub const TX_DATA_ZERO_GAS: u64 = 4;
+/// Gas cost of a non-zero calldata byte (EIP-2028).
+pub const TX_DATA_NON_ZERO_GAS: u64 = 16;
+/// Gas cost of an address in the access list (EIP-2930).
+pub const TX_ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+/// Gas cost of a storage key in the access list (EIP-2930).
+pub const TX_ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+/// Gas cost of every 32-byte word of contract creation code (EIP-3860).
+pub const TX_INIT_CODE_WORD_GAS: u64 = 2;
+/// Default maximum size of transaction data accepted by the nodes' transaction pools (geth's `txMaxSize`).
+pub const MAX_TRANSACTION_DATA_SIZE: usize = 128 * 1024;
+
+impl TransactionParameters {
+    /// Turn EIP-1559 fees into a legacy gas price, for networks without a base fee.
+    ///
+    /// The max fee per gas becomes the gas price unless one is set already. Transactions with an access
+    /// list become EIP-2930 (type 1) transactions, the others legacy ones.
+    pub fn downgrade_to_legacy(&mut self) {
+        self.gas_price = self.gas_price.or(self.max_fee_per_gas);
+        self.max_fee_per_gas = None;
+        self.max_priority_fee_per_gas = None;
+        self.transaction_type = match &self.access_list {
+            Some(access_list) if !access_list.is_empty() => Some(U64::from(1)),
+            _ => None,
+        };
+    }
+
+    /// Gas charged before any execution: the base cost plus calldata, access list and creation costs.
+    pub fn intrinsic_gas(&self) -> U256 {
+        let data = &self.data.0;
+        let zero_bytes = data.iter().filter(|byte| **byte == 0).count() as u64;
+        let non_zero_bytes = data.len() as u64 - zero_bytes;
+
+        let mut gas = TX_BASE_GAS + zero_bytes * TX_DATA_ZERO_GAS + non_zero_bytes * TX_DATA_NON_ZERO_GAS;
+        if self.to.is_none() {
+            let words = (data.len() as u64).div_ceil(32);
+            gas += TX_CREATE_GAS + words * TX_INIT_CODE_WORD_GAS;
+        }
+        for item in self.access_list.iter().flatten() {
+            gas += TX_ACCESS_LIST_ADDRESS_GAS + item.storage_keys.len() as u64 * TX_ACCESS_LIST_STORAGE_KEY_GAS;
+        }
+        gas.into()
+    }
+
+    /// Rejects transactions guaranteed to fail: those whose gas limit is below the intrinsic gas, whose
+    /// data exceeds `max_data_size` bytes or whose zkSync fields are inconsistent.
+    pub fn validate(&self, max_data_size: usize) -> Result<(), String> {
+        if self.data.0.len() > max_data_size {
+            return Err(format!(
+                "transaction data is {} bytes, exceeding the limit of {} bytes",
+                self.data.0.len(),
+                max_data_size
+            ));
+        }
+        let intrinsic_gas = self.intrinsic_gas();
+        if self.gas < intrinsic_gas {
+            return Err(format!(
+                "gas limit {} is below the intrinsic gas {}",
+                self.gas, intrinsic_gas
+            ));
+        }
+        let is_eip712 = self.transaction_type == Some(eip712::EIP712_TX_TYPE.into());
+        match &self.eip712_meta {
+            Some(_) if !is_eip712 => return Err("zkSync fields require transaction type 0x71".into()),
+            None if is_eip712 => return Err("transaction type 0x71 requires zkSync fields".into()),
+            Some(meta) => {
+                for dep in &meta.factory_deps {
+                    eip712::hash_bytecode(&dep.0).map_err(|err| format!("invalid factory dependency: {}", err))?;
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
 impl Default for TransactionParameters {
     fn default() -> Self {
         TransactionParameters {
@@ -85,6 +169,7 @@ impl Default for TransactionParameters {
             access_list: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            eip712_meta: None,
         }
     }
 }
@@ -103,6 +188,7 @@ impl From<CallRequest> for TransactionParameters {
             access_list: call.access_list,
             max_fee_per_gas: call.max_fee_per_gas,
             max_priority_fee_per_gas: call.max_priority_fee_per_gas,
+            eip712_meta: None,
         }
     }
 }
@@ -140,4 +226,3 @@ pub struct SignedTransaction {
     /// The transaction hash for the RLP encoded transaction.
     pub transaction_hash: H256,
 }
-
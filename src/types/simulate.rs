@@ -0,0 +1,185 @@
+//! Types for `eth_simulateV1`.
+
+use crate::types::{Address, Block, Bytes, CallRequest, Log, StateOverride, H256, U256, U64};
+use serde::{Deserialize, Serialize};
+
+/// Pseudo-address emitting the native currency transfers traced by `eth_simulateV1`.
+pub const NATIVE_TRANSFER_ADDRESS: Address = Address::repeat_byte(0xee);
+
+/// `Transfer(address,address,uint256)` topic of the traced native currency transfers.
+const TRANSFER_TOPIC: H256 = H256([
+    0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa, 0x95, 0x2b, 0xa7,
+    0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef,
+]);
+
+/// Overrides of the header fields of a simulated block.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverrides {
+    /// Block number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<U64>,
+    /// Value of `PREVRANDAO`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_randao: Option<H256>,
+    /// Timestamp, in seconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<U64>,
+    /// Gas limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<U64>,
+    /// Recipient of the priority fees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_recipient: Option<Address>,
+    /// Base fee per gas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    /// Blob base fee per gas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_base_fee: Option<U256>,
+}
+
+/// Calls simulated in a single block, with the overrides applied before the first call.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulateBlock {
+    /// Overrides of the block header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_overrides: Option<BlockOverrides>,
+    /// Overrides of the state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_overrides: Option<StateOverride>,
+    /// Calls, executed in order, each seeing the effects of the previous ones
+    pub calls: Vec<CallRequest>,
+}
+
+impl SimulateBlock {
+    /// Block simulating `calls`.
+    pub fn new(calls: Vec<CallRequest>) -> Self {
+        SimulateBlock {
+            calls,
+            ..Default::default()
+        }
+    }
+
+    /// Override the header of the block.
+    pub fn with_block_overrides(mut self, overrides: BlockOverrides) -> Self {
+        self.block_overrides = Some(overrides);
+        self
+    }
+
+    /// Override the state before the first call.
+    pub fn with_state_overrides(mut self, overrides: StateOverride) -> Self {
+        self.state_overrides = Some(overrides);
+        self
+    }
+}
+
+/// Payload of `eth_simulateV1`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatePayload {
+    /// Simulated blocks, built on top of each other
+    pub block_state_calls: Vec<SimulateBlock>,
+    /// Report the transfers of the native currency as logs, see [`SimulatedCall::native_transfers`]
+    #[serde(default)]
+    pub trace_transfers: bool,
+    /// Check the calls as transactions (nonces, balances, base fee) instead of as `eth_call`s
+    #[serde(default)]
+    pub validation: bool,
+    /// Return the simulated transactions instead of their hashes
+    #[serde(default)]
+    pub return_full_transactions: bool,
+}
+
+impl SimulatePayload {
+    /// Simulate `blocks`, without tracing transfers nor validation.
+    pub fn new(blocks: Vec<SimulateBlock>) -> Self {
+        SimulatePayload {
+            block_state_calls: blocks,
+            ..Default::default()
+        }
+    }
+
+    /// Report the transfers of the native currency, to track the asset changes of the calls.
+    pub fn with_trace_transfers(mut self) -> Self {
+        self.trace_transfers = true;
+        self
+    }
+
+    /// Check the calls as transactions.
+    pub fn with_validation(mut self) -> Self {
+        self.validation = true;
+        self
+    }
+}
+
+/// Error of a failed simulated call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulateError {
+    /// Error code, `3` for reverts
+    pub code: i64,
+    /// Error message
+    pub message: String,
+    /// Revert data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Bytes>,
+}
+
+/// Result of a simulated call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedCall {
+    /// Returned data
+    pub return_data: Bytes,
+    /// Emitted logs, including the traced transfers of the native currency
+    #[serde(default)]
+    pub logs: Vec<Log>,
+    /// Gas used
+    pub gas_used: U64,
+    /// 1 for success, 0 for failure
+    pub status: U64,
+    /// Error of a failed call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<SimulateError>,
+}
+
+impl SimulatedCall {
+    /// Whether the call succeeded.
+    pub fn is_success(&self) -> bool {
+        self.status == U64::one()
+    }
+
+    /// Transfers of the native currency made by the call, as `(from, to, value)`, when transfers are
+    /// traced.
+    pub fn native_transfers(&self) -> Vec<(Address, Address, U256)> {
+        self.logs
+            .iter()
+            .filter(|log| {
+                log.address == NATIVE_TRANSFER_ADDRESS
+                    && log.topics.len() == 3
+                    && log.topics[0] == TRANSFER_TOPIC
+                    && log.data.0.len() == 32
+            })
+            .map(|log| {
+                (
+                    Address::from(log.topics[1]),
+                    Address::from(log.topics[2]),
+                    U256::from_big_endian(&log.data.0),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Block returned by `eth_simulateV1`, with the results of its calls.
+///
+/// `TX` is `H256` unless the full transactions are requested.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedBlock<TX = H256> {
+    /// Simulated block
+    #[serde(flatten)]
+    pub block: Block<TX>,
+    /// Results of the calls, in order
+    pub calls: Vec<SimulatedCall>,
+}
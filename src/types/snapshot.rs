@@ -0,0 +1,31 @@
+use crate::types::{H256, U256, U64};
+
+/// State of the chain returned by [`Eth::chain_snapshot`](crate::api::Eth::chain_snapshot).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSnapshot {
+    /// EIP-155 chain id
+    pub chain_id: U256,
+    /// Number of the latest block
+    pub block_number: U64,
+    /// Hash of the latest block
+    pub block_hash: H256,
+    /// Timestamp of the latest block, in seconds since the epoch
+    pub timestamp: U256,
+    /// Base fee per gas of the latest block, `None` on networks without EIP-1559
+    pub base_fee_per_gas: Option<U256>,
+    /// Gas price suggested by the node
+    pub gas_price: U256,
+}
+
+/// State of an account returned by [`Eth::account_snapshot`](crate::api::Eth::account_snapshot).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSnapshot {
+    /// Balance at the latest block
+    pub balance: U256,
+    /// Number of transactions mined from the account, i.e. the nonce of its next transaction
+    pub nonce: U256,
+    /// Nonce of the next transaction, counting the ones pending in the node's pool
+    pub pending_nonce: U256,
+    /// Whether the account holds code, i.e. is a contract
+    pub is_contract: bool,
+}
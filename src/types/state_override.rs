@@ -0,0 +1,91 @@
+use crate::{
+    signing,
+    types::{Address, Bytes, H256, U256, U64},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// State override set accepted by `eth_call` and `eth_estimateGas`.
+///
+/// Keyed by account address. A `BTreeMap` is used so the serialized request is
+/// identical on every replica, which HTTP outcall consensus relies on.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
+
+/// Ephemeral overrides applied to a single account for the duration of a call.
+///
+/// `state` and `state_diff` are mutually exclusive: `state` replaces the whole
+/// storage of the account while `state_diff` only patches the given slots.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    /// Fake balance to set for the account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    /// Fake nonce to set for the account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    /// Fake EVM bytecode to inject into the account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// Fake key-value mapping to override all slots in the account storage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Fake key-value mapping to override individual slots in the account storage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+impl AccountOverride {
+    /// Override nothing, see the `with_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the balance of the account.
+    pub fn with_balance(mut self, balance: U256) -> Self {
+        self.balance = Some(balance);
+        self
+    }
+
+    /// Set the nonce of the account.
+    pub fn with_nonce(mut self, nonce: U64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Set the code of the account.
+    pub fn with_code(mut self, code: Bytes) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Replace the whole storage of the account, dropping the slots set with [`with_slot`](Self::with_slot).
+    pub fn with_state(mut self, state: BTreeMap<H256, H256>) -> Self {
+        self.state = Some(state);
+        self.state_diff = None;
+        self
+    }
+
+    /// Set the storage slot `slot`, keeping the other slots unless the whole storage is replaced.
+    pub fn with_slot(mut self, slot: H256, value: H256) -> Self {
+        match &mut self.state {
+            Some(state) => state.insert(slot, value),
+            None => self.state_diff.get_or_insert_with(Default::default).insert(slot, value),
+        };
+        self
+    }
+}
+
+/// Storage slot of the value at `key` in a Solidity mapping declared at slot `slot`, i.e.
+/// `keccak256(key . slot)`.
+///
+/// Keys are left-padded to 32 bytes, e.g. `H256::from(address)`. Nested mappings chain the calls: the
+/// ERC-20 allowance of `spender` by `owner`, with `allowance` declared at slot 1, is at
+/// `mapping_slot(mapping_slot(H256::from_low_u64_be(1), owner.into()), spender.into())`. The declaration slot depends on
+/// the layout of the contract, as reported by `solc --storage-layout`.
+pub fn mapping_slot(slot: H256, key: H256) -> H256 {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(key.as_bytes());
+    data[32..].copy_from_slice(slot.as_bytes());
+    signing::keccak256(&data).into()
+}
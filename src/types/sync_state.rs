@@ -0,0 +1,95 @@
+use crate::types::U256;
+use serde::{
+    de::{Deserializer, Error},
+    ser::Serializer,
+    Deserialize, Serialize,
+};
+
+/// Sync state of a node, returned by `eth_syncing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncState {
+    /// The node is in sync with the chain (`false`)
+    NotSyncing,
+    /// The node is catching up with the chain
+    Syncing {
+        /// Block at which the import started
+        starting_block: U256,
+        /// Latest imported block
+        current_block: U256,
+        /// Estimated highest block of the chain
+        highest_block: U256,
+    },
+}
+
+impl SyncState {
+    /// Whether the node is catching up with the chain, so that its state may be outdated.
+    pub fn is_syncing(&self) -> bool {
+        matches!(self, SyncState::Syncing { .. })
+    }
+
+    /// Number of blocks the node still has to import, zero when in sync.
+    pub fn blocks_behind(&self) -> U256 {
+        match *self {
+            SyncState::NotSyncing => U256::zero(),
+            SyncState::Syncing {
+                current_block,
+                highest_block,
+                ..
+            } => highest_block.saturating_sub(current_block),
+        }
+    }
+}
+
+/// Progress object of a syncing node; clients add their own fields, which are ignored.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncProgress {
+    starting_block: U256,
+    current_block: U256,
+    highest_block: U256,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SyncResponse {
+    Status(bool),
+    Progress(SyncProgress),
+}
+
+impl<'de> Deserialize<'de> for SyncState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match SyncResponse::deserialize(deserializer)? {
+            SyncResponse::Status(false) => Ok(SyncState::NotSyncing),
+            SyncResponse::Status(true) => Err(D::Error::custom("`eth_syncing` returned `true` without progress")),
+            SyncResponse::Progress(progress) => Ok(SyncState::Syncing {
+                starting_block: progress.starting_block,
+                current_block: progress.current_block,
+                highest_block: progress.highest_block,
+            }),
+        }
+    }
+}
+
+impl Serialize for SyncState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            SyncState::NotSyncing => serializer.serialize_bool(false),
+            SyncState::Syncing {
+                starting_block,
+                current_block,
+                highest_block,
+            } => SyncProgress {
+                starting_block,
+                current_block,
+                highest_block,
+            }
+            .serialize(serializer),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{
+    signing,
+    types::{Address, H256, U256},
+};
+use serde::{Deserialize, Serialize};
+
+/// A single log topic.
+///
+/// Topics are always 32 bytes long: event signatures are hashed, static values such as addresses and
+/// integers are left-padded with zeros and dynamic values (strings, bytes) are hashed. The constructors
+/// take care of the encoding, so filters can be built from the values emitted by the contract.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Topic(pub H256);
+
+impl Topic {
+    /// Topic of an event signature such as `Transfer(address,address,uint256)`.
+    pub fn from_event_signature(signature: &str) -> Self {
+        Topic(signing::keccak256(signature.as_bytes()).into())
+    }
+
+    /// Topic of an indexed dynamic value (`string`, `bytes`), which is stored hashed.
+    pub fn from_hashed(data: &[u8]) -> Self {
+        Topic(signing::keccak256(data).into())
+    }
+
+    /// Underlying 32 bytes.
+    pub fn as_h256(&self) -> H256 {
+        self.0
+    }
+}
+
+impl From<H256> for Topic {
+    fn from(hash: H256) -> Self {
+        Topic(hash)
+    }
+}
+
+impl From<Address> for Topic {
+    fn from(address: Address) -> Self {
+        let mut topic = H256::zero();
+        topic.as_bytes_mut()[12..].copy_from_slice(address.as_bytes());
+        Topic(topic)
+    }
+}
+
+impl From<U256> for Topic {
+    fn from(value: U256) -> Self {
+        let mut topic = H256::zero();
+        value.to_big_endian(topic.as_bytes_mut());
+        Topic(topic)
+    }
+}
+
+impl From<u64> for Topic {
+    fn from(value: u64) -> Self {
+        U256::from(value).into()
+    }
+}
+
+impl From<bool> for Topic {
+    fn from(value: bool) -> Self {
+        U256::from(value as u8).into()
+    }
+}
+
+impl From<Topic> for H256 {
+    fn from(topic: Topic) -> Self {
+        topic.0
+    }
+}
@@ -0,0 +1,253 @@
+//! Types for the Parity/OpenEthereum transaction-trace filtering API.
+
+use crate::types::{BlockNumber, Bytes, H160, H256, U256};
+use serde::{Deserialize, Serialize};
+
+/// Trace filter
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFilter {
+    /// From block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_block: Option<BlockNumber>,
+    /// To block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_block: Option<BlockNumber>,
+    /// From address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_address: Option<Vec<H160>>,
+    /// To address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_address: Option<Vec<H160>>,
+    /// Output offset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<usize>,
+    /// Output amount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+}
+
+/// Trace filter builder
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TraceFilterBuilder {
+    filter: TraceFilter,
+}
+
+impl TraceFilterBuilder {
+    /// Sets From block
+    pub fn from_block(mut self, block: BlockNumber) -> Self {
+        self.filter.from_block = Some(block);
+        self
+    }
+
+    /// Sets to block
+    pub fn to_block(mut self, block: BlockNumber) -> Self {
+        self.filter.to_block = Some(block);
+        self
+    }
+
+    /// Sets to address
+    pub fn to_address(mut self, address: Vec<H160>) -> Self {
+        self.filter.to_address = Some(address);
+        self
+    }
+
+    /// Sets from address
+    pub fn from_address(mut self, address: Vec<H160>) -> Self {
+        self.filter.from_address = Some(address);
+        self
+    }
+
+    /// Sets after offset
+    pub fn after(mut self, after: usize) -> Self {
+        self.filter.after = Some(after);
+        self
+    }
+
+    /// Sets amount of traces to display
+    pub fn count(mut self, count: usize) -> Self {
+        self.filter.count = Some(count);
+        self
+    }
+
+    /// Builds the Filter
+    pub fn build(&self) -> TraceFilter {
+        self.filter.clone()
+    }
+}
+
+/// Trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    /// Action
+    pub action: Action,
+    /// Result
+    pub result: Option<Res>,
+    /// Trace address
+    pub trace_address: Vec<usize>,
+    /// Subtraces
+    pub subtraces: usize,
+    /// Transaction position
+    pub transaction_position: Option<usize>,
+    /// Transaction hash
+    pub transaction_hash: Option<H256>,
+    /// Block Number
+    pub block_number: u64,
+    /// Block Hash
+    pub block_hash: H256,
+    /// Action Type
+    #[serde(rename = "type")]
+    pub action_type: ActionType,
+    /// Error
+    pub error: Option<String>,
+}
+
+/// Response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Res {
+    /// Call
+    Call(CallResult),
+    /// Create
+    Create(CreateResult),
+    /// None
+    #[default]
+    None,
+}
+
+/// Action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged, rename_all = "lowercase")]
+pub enum Action {
+    /// Call
+    Call(Call),
+    /// Create
+    Create(Create),
+    /// Suicide
+    Suicide(Suicide),
+    /// Reward
+    Reward(Reward),
+}
+
+/// An external action type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionType {
+    /// Contract call.
+    Call,
+    /// Contract creation.
+    Create,
+    /// Contract suicide.
+    Suicide,
+    /// A block reward.
+    Reward,
+}
+
+/// Call Result
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallResult {
+    /// Gas used
+    pub gas_used: U256,
+    /// Output bytes
+    pub output: Bytes,
+}
+
+/// Create Result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateResult {
+    /// Gas used
+    pub gas_used: U256,
+    /// Code
+    pub code: Bytes,
+    /// Assigned address
+    pub address: H160,
+}
+
+/// Call response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Call {
+    /// Sender
+    pub from: H160,
+    /// Recipient
+    pub to: H160,
+    /// Transfered Value
+    pub value: U256,
+    /// Gas
+    pub gas: U256,
+    /// Input data
+    pub input: Bytes,
+    /// The type of the call.
+    pub call_type: CallType,
+}
+
+/// Call type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallType {
+    /// None
+    #[default]
+    None,
+    /// Call
+    Call,
+    /// Call code
+    CallCode,
+    /// Delegate call
+    DelegateCall,
+    /// Static call
+    StaticCall,
+}
+
+/// Create response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Create {
+    /// Sender
+    pub from: H160,
+    /// Value
+    pub value: U256,
+    /// Gas
+    pub gas: U256,
+    /// Initialization code
+    pub init: Bytes,
+}
+
+/// Suicide
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suicide {
+    /// Address.
+    pub address: H160,
+    /// Refund address.
+    pub refund_address: H160,
+    /// Balance.
+    pub balance: U256,
+}
+
+/// Reward action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reward {
+    /// Author's address.
+    pub author: H160,
+    /// Reward amount.
+    pub value: U256,
+    /// Reward type.
+    pub reward_type: RewardType,
+}
+
+/// Reward type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RewardType {
+    /// Block
+    Block,
+    /// Uncle
+    Uncle,
+    /// EmptyStep (AuthorityRound)
+    EmptyStep,
+    /// External (attributed as part of an external protocol)
+    External,
+}
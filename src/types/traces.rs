@@ -0,0 +1,154 @@
+//! Types for the Parity/OpenEthereum ad-hoc trace API.
+
+use crate::types::{Action, ActionType, Bytes, Res, H160, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Description of the type of trace to make
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceType {
+    /// Transaction Trace
+    Trace,
+    /// Virtual Machine Execution Trace
+    VmTrace,
+    /// State Difference
+    StateDiff,
+}
+
+/// Ad-Hoc trace API type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace {
+    /// Output Bytes
+    pub output: Bytes,
+    /// Transaction Trace
+    pub trace: Option<Vec<TransactionTrace>>,
+    /// Virtual Machine Execution Trace
+    pub vm_trace: Option<VMTrace>,
+    /// State Difference
+    pub state_diff: Option<StateDiff>,
+    /// Transaction Hash, only set by `trace_replayBlockTransactions`
+    pub transaction_hash: Option<H256>,
+}
+
+//---------------- State Diff ----------------
+/// Aux type for Diff::Changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangedType<T> {
+    /// Previous value
+    pub from: T,
+    /// Current value
+    pub to: T,
+}
+
+/// Serde-friendly `Diff` shadow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Diff<T> {
+    /// No change
+    #[serde(rename = "=")]
+    Same,
+    /// A new value has been set
+    #[serde(rename = "+")]
+    Born(T),
+    /// A value has been removed
+    #[serde(rename = "-")]
+    Died(T),
+    /// Value changed
+    #[serde(rename = "*")]
+    Changed(ChangedType<T>),
+}
+
+/// Serde-friendly `AccountDiff` shadow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountDiff {
+    /// Account balance
+    pub balance: Diff<U256>,
+    /// Account nonce
+    pub nonce: Diff<U256>,
+    /// Account code
+    pub code: Diff<Bytes>,
+    /// Account storage
+    pub storage: BTreeMap<H256, Diff<H256>>,
+}
+
+/// Serde-friendly `StateDiff` shadow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDiff(pub BTreeMap<H160, AccountDiff>);
+
+// ------------------ Trace -------------
+/// Trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionTrace {
+    /// Trace address
+    pub trace_address: Vec<usize>,
+    /// Subtraces
+    pub subtraces: usize,
+    /// Action
+    pub action: Action,
+    /// Action Type
+    #[serde(rename = "type")]
+    pub action_type: ActionType,
+    /// Result
+    pub result: Option<Res>,
+    /// Error
+    pub error: Option<String>,
+}
+
+// ---------------- VmTrace ------------------------------
+/// A record of a full VM trace for a CALL/CREATE.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VMTrace {
+    /// The code to be executed.
+    pub code: Bytes,
+    /// The operations executed.
+    pub ops: Vec<VMOperation>,
+}
+
+/// A record of the execution of a single VM operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VMOperation {
+    /// The program counter.
+    pub pc: usize,
+    /// The gas cost for this instruction.
+    pub cost: u64,
+    /// Information concerning the execution of the operation.
+    pub ex: Option<VMExecutedOperation>,
+    /// Subordinate trace of the CALL/CREATE if applicable.
+    pub sub: Option<VMTrace>,
+}
+
+/// A record of an executed VM operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VMExecutedOperation {
+    /// The total gas used.
+    #[serde(rename = "used")]
+    pub used: u64,
+    /// The stack item placed, if any.
+    pub push: Vec<U256>,
+    /// If altered, the memory delta.
+    #[serde(rename = "mem")]
+    pub mem: Option<MemoryDiff>,
+    /// The altered storage value, if any.
+    #[serde(rename = "store")]
+    pub store: Option<StorageDiff>,
+}
+
+/// A diff of some chunk of memory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryDiff {
+    /// Offset into memory the change begins.
+    pub off: usize,
+    /// The changed data.
+    pub data: Bytes,
+}
+
+/// A diff of some storage value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageDiff {
+    /// Which key in storage is changed.
+    pub key: U256,
+    /// What the value has been changed to.
+    pub val: U256,
+}
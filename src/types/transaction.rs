@@ -1,5 +1,7 @@
 use crate::types::{Address, Bytes, Index, Log, H2048, H256, U256, U64};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 /// Description of a Transaction, pending or in the chain.
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -108,6 +110,45 @@ pub struct Receipt {
     /// Effective gas price
     #[serde(rename = "effectiveGasPrice")]
     pub effective_gas_price: Option<U256>,
+    /// Blob gas used by the blobs of an EIP-4844 transaction
+    #[serde(rename = "blobGasUsed", default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+    /// Price paid per unit of blob gas by an EIP-4844 transaction
+    #[serde(rename = "blobGasPrice", default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_price: Option<U256>,
+    /// Fee paid for posting the transaction data to L1, on OP Stack rollups
+    #[serde(rename = "l1Fee", default, skip_serializing_if = "Option::is_none")]
+    pub l1_fee: Option<U256>,
+    /// L1 gas used by the transaction data, on OP Stack rollups
+    #[serde(rename = "l1GasUsed", default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_used: Option<U256>,
+    /// L1 gas price the L1 fee was computed with, on OP Stack rollups
+    #[serde(rename = "l1GasPrice", default, skip_serializing_if = "Option::is_none")]
+    pub l1_gas_price: Option<U256>,
+    /// Part of the gas used paying for the L1 data, on Arbitrum
+    #[serde(rename = "gasUsedForL1", default, skip_serializing_if = "Option::is_none")]
+    pub gas_used_for_l1: Option<U256>,
+    /// Fields not known to this type, e.g. the fee scalars of OP Stack rollups
+    #[serde(flatten)]
+    pub other: BTreeMap<String, Value>,
+}
+
+impl Receipt {
+    /// Total fee paid by the transaction in wei: the execution fee, plus the blob fee of EIP-4844
+    /// transactions and the L1 data fee of OP Stack rollups.
+    ///
+    /// Returns `None` if the receipt lacks the gas used or the effective gas price.
+    pub fn total_fee(&self) -> Option<U256> {
+        let fee = self.gas_used?.saturating_mul(self.effective_gas_price?);
+        let blob_fee = match (self.blob_gas_used, self.blob_gas_price) {
+            (Some(used), Some(price)) => U256::from(used.as_u64()).saturating_mul(price),
+            _ => U256::zero(),
+        };
+        Some(
+            fee.saturating_add(blob_fee)
+                .saturating_add(self.l1_fee.unwrap_or_default()),
+        )
+    }
 }
 
 /// Raw bytes of a signed, but not yet sent transaction
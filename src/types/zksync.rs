@@ -0,0 +1,78 @@
+//! Types of the zkSync Era `zks_*` RPC methods.
+
+use crate::types::{Address, CallRequest, Eip712Meta, H256, U256, U64};
+use serde::{Deserialize, Serialize};
+
+/// Call request carrying the zkSync fields of EIP-712 transactions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712CallRequest {
+    /// Standard call fields
+    #[serde(flatten)]
+    pub request: CallRequest,
+    /// zkSync fields, e.g. the paymaster paying the fees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eip712_meta: Option<Eip712Meta>,
+}
+
+impl From<CallRequest> for Eip712CallRequest {
+    fn from(request: CallRequest) -> Self {
+        Eip712CallRequest {
+            request,
+            eip712_meta: None,
+        }
+    }
+}
+
+/// Fee estimate returned by `zks_estimateFee`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fee {
+    /// Gas limit
+    pub gas_limit: U256,
+    /// Maximum gas per byte of published data
+    pub gas_per_pubdata_limit: U256,
+    /// Max fee per gas
+    pub max_fee_per_gas: U256,
+    /// Max priority fee per gas
+    pub max_priority_fee_per_gas: U256,
+}
+
+/// Merkle proof of inclusion of an L2 to L1 message or log in an L1 batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L2ToL1LogProof {
+    /// Merkle path to the root
+    pub proof: Vec<H256>,
+    /// Position of the leaf in the tree
+    pub id: u32,
+    /// Root of the tree
+    pub root: H256,
+}
+
+/// Addresses of the default bridges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeAddresses {
+    /// L1 ERC-20 bridge
+    #[serde(default)]
+    pub l1_erc20_default_bridge: Option<Address>,
+    /// L2 ERC-20 bridge
+    #[serde(default)]
+    pub l2_erc20_default_bridge: Option<Address>,
+    /// L1 WETH bridge
+    #[serde(default)]
+    pub l1_weth_bridge: Option<Address>,
+    /// L2 WETH bridge
+    #[serde(default)]
+    pub l2_weth_bridge: Option<Address>,
+    /// L1 shared bridge
+    #[serde(default)]
+    pub l1_shared_default_bridge: Option<Address>,
+    /// L2 shared bridge
+    #[serde(default)]
+    pub l2_shared_default_bridge: Option<Address>,
+}
+
+/// Range of L2 blocks included in an L1 batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L1BatchBlockRange(pub U64, pub U64);
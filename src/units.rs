@@ -0,0 +1,241 @@
+//! Lossless conversions between wei, gwei, ether and token units.
+//!
+//! Amounts are kept as integers of the smallest unit and converted from and to their decimal
+//! representation as strings, never through floats, so that no wei is lost on the way:
+//!
+//! ```ignore
+//! let amount = parse_amount("1.5 ether")?;
+//! assert_eq!(amount, parse_units("1500000000", Unit::Gwei.decimals())?);
+//! assert_eq!(format_ether(amount), "1.5");
+//! let usdc = parse_units("12.34", 6)?;
+//! ```
+
+use crate::types::U256;
+use ethereum_types::U512;
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// Largest number of decimals representable: 10^77 is the largest power of ten below 2^256.
+const MAX_DECIMALS: u32 = 77;
+
+/// Error while parsing or converting an amount.
+#[derive(Debug, derive_more::Display, PartialEq, Eq, Clone)]
+pub enum UnitsError {
+    /// The amount is not a decimal number
+    #[display(fmt = "invalid amount: {}", _0)]
+    InvalidNumber(String),
+    /// The unit is not known
+    #[display(fmt = "unknown unit: {}", _0)]
+    UnknownUnit(String),
+    /// The amount has more decimals than its unit
+    #[display(fmt = "{} has more than {} decimals", _0, _1)]
+    TooManyDecimals(String, u32),
+    /// The amount does not fit in 256 bits
+    #[display(fmt = "amount overflows 256 bits")]
+    Overflow,
+}
+impl std::error::Error for UnitsError {}
+
+/// Unit of an amount of ether or of tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// 1 wei
+    Wei,
+    /// 10^3 wei
+    Kwei,
+    /// 10^6 wei
+    Mwei,
+    /// 10^9 wei
+    Gwei,
+    /// 10^12 wei
+    Szabo,
+    /// 10^15 wei
+    Finney,
+    /// 10^18 wei
+    Ether,
+    /// Token unit with the given number of decimals, as returned by ERC-20 `decimals()`
+    Decimals(u32),
+}
+
+impl Unit {
+    /// Number of decimals of the unit, relative to the smallest one.
+    pub fn decimals(&self) -> u32 {
+        match *self {
+            Unit::Wei => 0,
+            Unit::Kwei => 3,
+            Unit::Mwei => 6,
+            Unit::Gwei => 9,
+            Unit::Szabo => 12,
+            Unit::Finney => 15,
+            Unit::Ether => 18,
+            Unit::Decimals(decimals) => decimals,
+        }
+    }
+
+    /// Amount of the smallest unit in one unit, `None` above 77 decimals.
+    pub fn multiplier(&self) -> Option<U256> {
+        pow10(self.decimals())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = UnitsError;
+
+    fn from_str(unit: &str) -> Result<Self, Self::Err> {
+        Ok(match unit.to_ascii_lowercase().as_str() {
+            "wei" => Unit::Wei,
+            "kwei" | "babbage" => Unit::Kwei,
+            "mwei" | "lovelace" => Unit::Mwei,
+            "gwei" | "shannon" => Unit::Gwei,
+            "szabo" | "microether" => Unit::Szabo,
+            "finney" | "milliether" => Unit::Finney,
+            "ether" | "eth" => Unit::Ether,
+            _ => return Err(UnitsError::UnknownUnit(unit.to_string())),
+        })
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Wei => f.write_str("wei"),
+            Unit::Kwei => f.write_str("kwei"),
+            Unit::Mwei => f.write_str("mwei"),
+            Unit::Gwei => f.write_str("gwei"),
+            Unit::Szabo => f.write_str("szabo"),
+            Unit::Finney => f.write_str("finney"),
+            Unit::Ether => f.write_str("ether"),
+            Unit::Decimals(decimals) => write!(f, "10^{}", decimals),
+        }
+    }
+}
+
+/// 10^`exponent`, `None` if it does not fit in 256 bits.
+fn pow10(exponent: u32) -> Option<U256> {
+    if exponent > MAX_DECIMALS {
+        None
+    } else {
+        Some(U256::exp10(exponent as usize))
+    }
+}
+
+/// Parse the decimal amount `value`, e.g. `"1.5"`, of a unit with `decimals` decimals into the smallest
+/// unit.
+///
+/// Underscores between digits are ignored. Amounts with more decimals than the unit are refused rather
+/// than rounded.
+pub fn parse_units(value: &str, decimals: u32) -> Result<U256, UnitsError> {
+    let invalid = || UnitsError::InvalidNumber(value.to_string());
+    let digits: String = value.trim().chars().filter(|c| *c != '_').collect();
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (digits.as_str(), ""),
+    };
+    if integer.is_empty() && fraction.is_empty() || !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(UnitsError::TooManyDecimals(value.to_string(), decimals));
+    }
+    let multiplier = pow10(decimals).ok_or(UnitsError::Overflow)?;
+    let integer = if integer.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(integer).map_err(|_| UnitsError::Overflow)?
+    };
+    let fraction = if fraction.is_empty() {
+        U256::zero()
+    } else {
+        let scale = pow10(decimals - fraction.len() as u32).ok_or(UnitsError::Overflow)?;
+        U256::from_dec_str(fraction).map_err(|_| invalid())? * scale
+    };
+    integer
+        .checked_mul(multiplier)
+        .and_then(|integer| integer.checked_add(fraction))
+        .ok_or(UnitsError::Overflow)
+}
+
+/// Decimal representation of `value`, in the smallest unit, in a unit with `decimals` decimals, without
+/// trailing zeros.
+pub fn format_units(value: U256, decimals: u32) -> String {
+    let digits = value.to_string();
+    if decimals == 0 {
+        return digits;
+    }
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+/// Parse an amount with its unit, e.g. `"1.5 ether"` or `"30gwei"`, into wei.
+///
+/// Amounts without unit are in wei.
+pub fn parse_amount(amount: &str) -> Result<U256, UnitsError> {
+    let amount = amount.trim();
+    let split = amount.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(amount.len());
+    let (value, unit) = amount.split_at(split);
+    let unit = if unit.is_empty() { Unit::Wei } else { unit.parse()? };
+    parse_units(value, unit.decimals())
+}
+
+/// Parse an amount of ether, e.g. `"0.01"`, into wei.
+pub fn parse_ether(value: &str) -> Result<U256, UnitsError> {
+    parse_units(value, Unit::Ether.decimals())
+}
+
+/// Parse an amount of gwei, e.g. a gas price of `"1.5"`, into wei.
+pub fn parse_gwei(value: &str) -> Result<U256, UnitsError> {
+    parse_units(value, Unit::Gwei.decimals())
+}
+
+/// Decimal representation in ether of `wei`.
+pub fn format_ether(wei: U256) -> String {
+    format_units(wei, Unit::Ether.decimals())
+}
+
+/// Decimal representation in gwei of `wei`.
+pub fn format_gwei(wei: U256) -> String {
+    format_units(wei, Unit::Gwei.decimals())
+}
+
+/// Convert `value` from a unit with `from` decimals to one with `to` decimals, e.g. between tokens of
+/// different decimals.
+///
+/// Conversions to fewer decimals fail instead of rounding if they would drop a non-zero remainder.
+pub fn rescale(value: U256, from: u32, to: u32) -> Result<U256, UnitsError> {
+    if to >= from {
+        let multiplier = pow10(to - from).ok_or(UnitsError::Overflow)?;
+        value.checked_mul(multiplier).ok_or(UnitsError::Overflow)
+    } else {
+        let divisor = match pow10(from - to) {
+            Some(divisor) => divisor,
+            None if value.is_zero() => return Ok(U256::zero()),
+            None => return Err(UnitsError::TooManyDecimals(value.to_string(), to)),
+        };
+        let (quotient, remainder) = value.div_mod(divisor);
+        if remainder.is_zero() {
+            Ok(quotient)
+        } else {
+            Err(UnitsError::TooManyDecimals(format_units(value, from), to))
+        }
+    }
+}
+
+/// `value * numerator / denominator` rounded down, computed without intermediate overflow, e.g. to
+/// apply a price or a percentage.
+///
+/// Returns `None` for a zero denominator or a result not fitting in 256 bits.
+pub fn mul_div(value: U256, numerator: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let result = U512::from(value) * U512::from(numerator) / U512::from(denominator);
+    U256::try_from(result).ok()
+}
@@ -0,0 +1,213 @@
+//! A high-level client sending transactions from the canister's threshold ECDSA address.
+//!
+//! [`Wallet`] bundles what sending a transaction takes (transport, network, key, nonce manager and
+//! gas oracle) behind a handful of methods with sensible defaults:
+//!
+//! ```ignore
+//! let web3 = Web3::new(ICHttp::new(Chain::Sepolia.default_rpc_url(), None)?);
+//! let wallet = web3.wallet(key_info, Chain::Sepolia).await?;
+//! let balance = wallet.balance().await?;
+//! let tx_hash = wallet.send_eth(to, balance / 2).await?;
+//! ```
+//!
+//! The lower-level [`Eth`](crate::api::Eth), [`Accounts`] and `Contract` APIs remain available for
+//! anything the wallet does not cover.
+
+use crate::{
+    api::{Accounts, Namespace, Web3},
+    chains::Chain,
+    error,
+    gas::{Fees, GasOracle},
+    ic::{self, KeyInfo},
+    nonce::NonceManager,
+    quota::{KeyQuotas, QuotaKey},
+    signing::KeySigner,
+    transports::ic_http_client::CallOptions,
+    types::{Address, BlockNumber, Bytes, CallRequest, SignedData, TransactionParameters, H256, U256},
+    Transport,
+};
+
+/// Sends transactions and signs messages with the canister's threshold ECDSA key on a single network.
+///
+/// Nonces are reserved from a [`NonceManager`], so that transactions can be sent without waiting for
+/// the previous ones to be mined, and fees are suggested by a [`GasOracle`]. Every signature is subject
+/// to the [quotas](crate::quota) of the wallet, unlimited by default.
+#[derive(Clone, Debug)]
+pub struct Wallet<T: Transport> {
+    web3: Web3<T>,
+    chain: Chain,
+    signer: KeySigner<QuotaKey<KeyInfo>>,
+    nonce_manager: NonceManager,
+    gas_oracle: GasOracle,
+    options: CallOptions,
+}
+
+impl<T: Transport> Wallet<T> {
+    /// Create a wallet sending transactions on `chain` through `web3` with `signer`.
+    pub fn new(web3: Web3<T>, chain: Chain, signer: KeySigner<KeyInfo>) -> Self {
+        let options = web3.transport().default_call_options();
+        Self {
+            web3,
+            chain,
+            signer: quota_signer(signer.key().clone(), signer.known_address(), KeyQuotas::new()),
+            nonce_manager: NonceManager::new(),
+            gas_oracle: GasOracle::default(),
+            options,
+        }
+    }
+
+    /// Create a wallet for the canister key of `key_info`, fetching its address from the management
+    /// canister.
+    pub async fn from_key(web3: Web3<T>, chain: Chain, key_info: KeyInfo) -> error::Result<Self> {
+        let signer = ic::signer(key_info).await.map_err(error::Error::Signing)?;
+        Ok(Self::new(web3, chain, signer))
+    }
+
+    /// Reserve nonces from `nonce_manager`, e.g. one shared with other components or persisted.
+    pub fn with_nonce_manager(mut self, nonce_manager: NonceManager) -> Self {
+        self.nonce_manager = nonce_manager;
+        self
+    }
+
+    /// Enforce the quota of the key's derivation path in `quotas` on the wallet's signatures.
+    pub fn with_quotas(mut self, quotas: KeyQuotas) -> Self {
+        self.signer = quota_signer(self.signer.key().key().clone(), self.address(), quotas);
+        self
+    }
+
+    /// Suggest fees with `gas_oracle`.
+    pub fn with_gas_oracle(mut self, gas_oracle: GasOracle) -> Self {
+        self.gas_oracle = gas_oracle;
+        self
+    }
+
+    /// Send the requests with `options`.
+    pub fn with_call_options(mut self, options: CallOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Address of the wallet.
+    pub fn address(&self) -> Address {
+        self.signer.known_address()
+    }
+
+    /// Network of the wallet.
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    /// Signer of the wallet's transactions.
+    pub fn signer(&self) -> &KeySigner<QuotaKey<KeyInfo>> {
+        &self.signer
+    }
+
+    /// `Web3` the wallet sends its requests through.
+    pub fn web3(&self) -> &Web3<T> {
+        &self.web3
+    }
+
+    /// Nonce manager the wallet reserves its nonces from.
+    pub fn nonce_manager(&self) -> &NonceManager {
+        &self.nonce_manager
+    }
+
+    /// Balance of the wallet at the latest block.
+    pub async fn balance(&self) -> error::Result<U256> {
+        self.web3
+            .eth()
+            .balance(self.address(), Some(BlockNumber::Latest), self.options.clone())
+            .await
+    }
+
+    /// Fees the wallet would pay for a transaction sent now.
+    pub async fn fees(&self) -> error::Result<Fees> {
+        self.gas_oracle
+            .fees(&self.web3.eth(), self.chain.supports_eip1559(), self.options.clone())
+            .await
+    }
+
+    /// Transfer `value` wei to `to`.
+    pub async fn send_eth(&self, to: Address, value: U256) -> error::Result<H256> {
+        self.send(to, value, Bytes::default()).await
+    }
+
+    /// Call `func` of `contract` with `params` in a transaction, transferring `value` wei to it.
+    ///
+    /// `func` is resolved as in [`Contract::function`](crate::contract::Contract::function).
+    #[cfg(feature = "contract")]
+    pub async fn call_contract(
+        &self,
+        contract: &crate::contract::Contract<T>,
+        func: &str,
+        params: &[ethabi::Token],
+        value: U256,
+    ) -> error::Result<H256> {
+        let data = contract
+            .function(func, params)
+            .and_then(|function| function.encode_input(params).map_err(Into::into))
+            .map_err(|err| error::Error::Decoder(format!("{:?}", err)))?;
+        self.send(contract.address(), value, Bytes(data)).await
+    }
+
+    /// Send a transaction to `to` with calldata `data`, estimating its gas.
+    pub async fn send(&self, to: Address, value: U256, data: Bytes) -> error::Result<H256> {
+        let tx = self.prepare(to, value, data).await?;
+        self.send_transaction(tx).await
+    }
+
+    /// Transaction to `to` with calldata `data` and its estimated gas, ready for
+    /// [`send_transaction`](Self::send_transaction).
+    pub async fn prepare(&self, to: Address, value: U256, data: Bytes) -> error::Result<TransactionParameters> {
+        let eth = self.web3.eth();
+        let gas = eth
+            .estimate_gas(
+                CallRequest {
+                    from: Some(self.address()),
+                    to: Some(to),
+                    value: Some(value),
+                    data: Some(data.clone()),
+                    ..Default::default()
+                },
+                None,
+                self.options.clone(),
+            )
+            .await?;
+        Ok(TransactionParameters {
+            to: Some(to),
+            gas,
+            value,
+            data,
+            ..Default::default()
+        })
+    }
+
+    /// Sign and broadcast `tx`, filling its nonce and fees when they are not set.
+    pub async fn send_transaction(&self, mut tx: TransactionParameters) -> error::Result<H256> {
+        if tx.gas_price.is_none() && tx.max_fee_per_gas.is_none() {
+            self.fees().await?.apply(&mut tx);
+        }
+        Accounts::new(self.web3.transport().clone())
+            .sign_and_send_transaction_with(
+                tx,
+                &self.signer,
+                self.chain.id(),
+                Some(&self.nonce_manager),
+                self.options.clone(),
+            )
+            .await
+    }
+
+    /// Sign `message` according to EIP-191 (`personal_sign`).
+    pub async fn sign_message<M: AsRef<[u8]>>(&self, message: M) -> error::Result<SignedData> {
+        Accounts::new(self.web3.transport().clone())
+            .sign_message_with(message, &self.signer)
+            .await
+    }
+}
+
+/// Signer of `key_info` at `address`, subject to the quota of its derivation path in `quotas`.
+fn quota_signer(key_info: KeyInfo, address: Address, quotas: KeyQuotas) -> KeySigner<QuotaKey<KeyInfo>> {
+    let path = key_info.derivation_path.clone();
+    KeySigner::new(QuotaKey::new(key_info, path, quotas), address)
+}